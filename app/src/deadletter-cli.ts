@@ -0,0 +1,110 @@
+import * as fs from 'fs';
+import { Connection, Keypair } from '@solana/web3.js';
+import {
+  DeadletterQueue,
+  DeadletterEntry,
+  KeeperJob,
+  WebhookJob,
+  deliverWebhook,
+  sendKeeperJob,
+} from './deadletter';
+
+const RPC_URL = process.env.RPC_URL || 'https://api.devnet.solana.com';
+const KEEPER_KEYPAIR = process.env.KEEPER_KEYPAIR;
+
+const USAGE = `Usage: npm run deadletter -- <command> [args]
+
+Commands:
+  list [webhook|keeper]   List dead jobs
+  show <id>               Print a single job
+  replay <id|all>         Retry jobs; successful ones are removed
+  purge <id|all>          Drop jobs without retrying
+
+Environment:
+  DEADLETTER_PATH   Queue file (default ./deadletter.jsonl)
+  RPC_URL           RPC endpoint for keeper replays
+  KEEPER_KEYPAIR    Keypair JSON used to re-sign keeper transactions`;
+
+function loadKeeper(): Keypair {
+  if (!KEEPER_KEYPAIR) {
+    throw new Error('KEEPER_KEYPAIR is required to replay keeper jobs');
+  }
+  const secret = JSON.parse(fs.readFileSync(KEEPER_KEYPAIR, 'utf8'));
+  return Keypair.fromSecretKey(Uint8Array.from(secret));
+}
+
+function select(queue: DeadletterQueue, target: string | undefined): DeadletterEntry[] {
+  if (!target) throw new Error('Missing job id (or "all")');
+  if (target === 'all') return queue.list();
+  const entry = queue.get(target);
+  if (!entry) throw new Error(`No deadletter job ${target}`);
+  return [entry];
+}
+
+async function replay(queue: DeadletterQueue, entries: DeadletterEntry[]) {
+  let keeper: Keypair | undefined;
+  const connection = new Connection(RPC_URL, 'confirmed');
+  const replayed: string[] = [];
+
+  for (const entry of entries) {
+    try {
+      if (entry.kind === 'webhook') {
+        await deliverWebhook(entry.job as WebhookJob);
+        console.log(`[Deadletter] ${entry.id} webhook delivered`);
+      } else {
+        keeper = keeper || loadKeeper();
+        const signature = await sendKeeperJob(connection, keeper, entry.job as KeeperJob);
+        console.log(`[Deadletter] ${entry.id} keeper job sent: ${signature}`);
+      }
+      replayed.push(entry.id);
+    } catch (err: any) {
+      queue.markFailed(entry.id, err);
+      console.error(`[Deadletter] ${entry.id} replay failed: ${err.message}`);
+    }
+  }
+
+  queue.remove(replayed);
+  console.log(`Replayed ${replayed.length}/${entries.length}`);
+}
+
+async function main() {
+  const [command, arg] = process.argv.slice(2);
+  const queue = new DeadletterQueue();
+
+  switch (command) {
+    case 'list': {
+      const entries = queue.list().filter((entry) => !arg || entry.kind === arg);
+      for (const entry of entries) {
+        const target = entry.kind === 'webhook' ? (entry.job as WebhookJob).url : (entry.job as KeeperJob).instruction;
+        console.log(`${entry.id}  ${entry.kind.padEnd(7)}  ${entry.failedAt}  x${entry.attempts}  ${target}  ${entry.lastError}`);
+      }
+      console.log(`${entries.length} dead job(s)`);
+      break;
+    }
+
+    case 'show': {
+      const [entry] = select(queue, arg);
+      console.log(JSON.stringify(entry, null, 2));
+      break;
+    }
+
+    case 'replay':
+      await replay(queue, select(queue, arg));
+      break;
+
+    case 'purge': {
+      const removed = queue.remove(select(queue, arg).map((entry) => entry.id));
+      console.log(`Purged ${removed} job(s)`);
+      break;
+    }
+
+    default:
+      console.log(USAGE);
+      process.exitCode = command ? 1 : 0;
+  }
+}
+
+main().catch((err) => {
+  console.error(err.message || err);
+  process.exit(1);
+});
@@ -0,0 +1,160 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import axios from 'axios';
+import { Connection, Keypair, Transaction } from '@solana/web3.js';
+
+// Where exhausted jobs are persisted (one JSON entry per line)
+const DEADLETTER_PATH = process.env.DEADLETTER_PATH || path.join(process.cwd(), 'deadletter.jsonl');
+const DEFAULT_MAX_ATTEMPTS = 5;
+const DEFAULT_BACKOFF_MS = 1000;
+
+export type DeadletterKind = 'webhook' | 'keeper';
+
+// Webhook delivery: POST `body` to `url`
+export interface WebhookJob {
+  url: string;
+  body: any;
+  headers?: Record<string, string>;
+}
+
+// Keeper job: a crank transaction (e.g. sweep_payouts, close_intent),
+// serialized without signatures so it can be re-signed on replay
+export interface KeeperJob {
+  instruction: string;
+  transaction: string; // base64
+}
+
+export interface DeadletterEntry {
+  id: string;
+  kind: DeadletterKind;
+  job: WebhookJob | KeeperJob;
+  attempts: number;
+  lastError: string;
+  failedAt: string;
+  replayedAt?: string;
+}
+
+export class DeadletterQueue {
+  private file: string;
+
+  constructor(file: string = DEADLETTER_PATH) {
+    this.file = file;
+  }
+
+  /**
+   * Persist a job that exhausted its retries
+   */
+  push(kind: DeadletterKind, job: WebhookJob | KeeperJob, attempts: number, error: unknown): DeadletterEntry {
+    const entry: DeadletterEntry = {
+      id: crypto.randomUUID(),
+      kind,
+      job,
+      attempts,
+      lastError: error instanceof Error ? error.message : String(error),
+      failedAt: new Date().toISOString(),
+    };
+    fs.mkdirSync(path.dirname(this.file), { recursive: true });
+    fs.appendFileSync(this.file, JSON.stringify(entry) + '\n');
+    console.error(`[Deadletter] ${kind} job ${entry.id} dead after ${attempts} attempts: ${entry.lastError}`);
+    return entry;
+  }
+
+  /**
+   * All entries, oldest first
+   */
+  list(): DeadletterEntry[] {
+    if (!fs.existsSync(this.file)) return [];
+    return fs
+      .readFileSync(this.file, 'utf8')
+      .split('\n')
+      .filter((line) => line.trim().length > 0)
+      .map((line) => JSON.parse(line) as DeadletterEntry);
+  }
+
+  get(id: string): DeadletterEntry | undefined {
+    return this.list().find((entry) => entry.id === id);
+  }
+
+  /**
+   * Remove entries (after a successful replay or a manual purge)
+   */
+  remove(ids: string[]): number {
+    const entries = this.list();
+    const kept = entries.filter((entry) => !ids.includes(entry.id));
+    this.write(kept);
+    return entries.length - kept.length;
+  }
+
+  /**
+   * Record a failed replay without dropping the entry
+   */
+  markFailed(id: string, error: unknown) {
+    const entries = this.list().map((entry) =>
+      entry.id === id
+        ? {
+            ...entry,
+            attempts: entry.attempts + 1,
+            lastError: error instanceof Error ? error.message : String(error),
+            replayedAt: new Date().toISOString(),
+          }
+        : entry
+    );
+    this.write(entries);
+  }
+
+  private write(entries: DeadletterEntry[]) {
+    const tmp = `${this.file}.tmp`;
+    fs.writeFileSync(tmp, entries.map((entry) => JSON.stringify(entry) + '\n').join(''));
+    fs.renameSync(tmp, this.file);
+  }
+}
+
+/**
+ * Run a job with exponential backoff; on exhaustion the job is written to
+ * the deadletter queue instead of being dropped.
+ */
+export async function withRetries<T>(
+  queue: DeadletterQueue,
+  kind: DeadletterKind,
+  job: WebhookJob | KeeperJob,
+  run: () => Promise<T>,
+  maxAttempts = DEFAULT_MAX_ATTEMPTS,
+  backoffMs = DEFAULT_BACKOFF_MS
+): Promise<T | undefined> {
+  let lastError: unknown;
+  for (let attempt = 1; attempt <= maxAttempts; attempt++) {
+    try {
+      return await run();
+    } catch (err) {
+      lastError = err;
+      if (attempt < maxAttempts) {
+        await new Promise((resolve) => setTimeout(resolve, backoffMs * 2 ** (attempt - 1)));
+      }
+    }
+  }
+  queue.push(kind, job, maxAttempts, lastError);
+  return undefined;
+}
+
+export async function deliverWebhook(job: WebhookJob): Promise<void> {
+  await axios.post(job.url, job.body, {
+    headers: { 'Content-Type': 'application/json', ...job.headers },
+    timeout: 10_000,
+  });
+}
+
+/**
+ * Re-sign a keeper transaction with a fresh blockhash and send it
+ */
+export async function sendKeeperJob(connection: Connection, keeper: Keypair, job: KeeperJob): Promise<string> {
+  const tx = Transaction.from(Buffer.from(job.transaction, 'base64'));
+  const { blockhash, lastValidBlockHeight } = await connection.getLatestBlockhash();
+  tx.recentBlockhash = blockhash;
+  tx.feePayer = keeper.publicKey;
+  tx.signatures = [];
+  tx.sign(keeper);
+  const signature = await connection.sendRawTransaction(tx.serialize());
+  await connection.confirmTransaction({ signature, blockhash, lastValidBlockHeight }, 'confirmed');
+  return signature;
+}
@@ -5,14 +5,32 @@ import {
   Transaction,
   SystemProgram,
   LAMPORTS_PER_SOL,
+  TransactionInstruction,
 } from '@solana/web3.js';
-import { Program, AnchorProvider, Idl, BN } from '@coral-xyz/anchor';
+import { Program, AnchorProvider, Idl, BN, utils } from '@coral-xyz/anchor';
 import axios from 'axios';
 import * as crypto from 'crypto';
 
 // Program ID (update after deployment)
 const PROGRAM_ID = new PublicKey('AgentMesh111111111111111111111111111111111');
 
+// Oldest on-chain program version this controller can operate against
+export const MIN_PROGRAM_VERSION: ProgramVersion = { major: 0, minor: 2, patch: 0 };
+
+export interface ProgramVersion {
+  major: number;
+  minor: number;
+  patch: number;
+}
+
+export function compareVersions(a: ProgramVersion, b: ProgramVersion): number {
+  return a.major - b.major || a.minor - b.minor || a.patch - b.patch;
+}
+
+export function formatVersion(v: ProgramVersion): string {
+  return `${v.major}.${v.minor}.${v.patch}`;
+}
+
 // Permission flags matching on-chain
 export const Permission = {
   CAN_SWAP: 1 << 0,
@@ -22,12 +40,25 @@ export const Permission = {
   CAN_ACCEPT_INTENT: 1 << 4,
 };
 
+// Byte offsets (including the 8-byte discriminator) of fixed-position account
+// fields for getProgramAccounts memcmp filters; mirror the *_OFFSET constants
+// on the program's account types
+export const AgentOffsets = { ownerWallet: 8, agentWallet: 40, modelProfile: 72 };
+export const ModelProfileOffsets = { ownerWallet: 8, verified: 56, isPublic: 57 };
+export const IntentOffsets = { fromAgent: 8, toAgent: 40, payer: 72, nonce: 136, status: 144 };
+
+// Anchor account discriminator, for the type filter preceding field filters
+export function accountDiscriminator(name: string): Buffer {
+  return crypto.createHash('sha256').update(`account:${name}`).digest().subarray(0, 8);
+}
+
 // Intent status
 export enum IntentStatus {
   Pending = 0,
   Accepted = 1,
   Completed = 2,
   Failed = 3,
+  Cancelled = 4,
 }
 
 // Model Profile configuration
@@ -127,6 +158,51 @@ export class MeshController {
     console.log(`[Mesh] Registered intent handler for agent ${agentPubkey}`);
   }
 
+  // Get PDA for the protocol config
+  getConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from('config')], PROGRAM_ID);
+  }
+
+  // Read the deployed program version by simulating get_version
+  async getProgramVersion(): Promise<{ program: ProgramVersion; config: ProgramVersion }> {
+    const [configPDA] = this.getConfigPDA();
+    const discriminator = crypto.createHash('sha256').update('global:get_version').digest().subarray(0, 8);
+    const ix = new TransactionInstruction({
+      programId: PROGRAM_ID,
+      keys: [{ pubkey: configPDA, isSigner: false, isWritable: false }],
+      data: discriminator,
+    });
+
+    const tx = new Transaction().add(ix);
+    tx.feePayer = this.provider.wallet.publicKey;
+    tx.recentBlockhash = (await this.connection.getLatestBlockhash()).blockhash;
+
+    const { value } = await this.connection.simulateTransaction(tx);
+    if (value.err || !value.returnData) {
+      throw new Error(`get_version failed: ${JSON.stringify(value.err)}`);
+    }
+
+    // VersionInfo { program, config }, each { major, minor, patch } as u16 LE
+    const data = Buffer.from(value.returnData.data[0], 'base64');
+    const read = (offset: number): ProgramVersion => ({
+      major: data.readUInt16LE(offset),
+      minor: data.readUInt16LE(offset + 2),
+      patch: data.readUInt16LE(offset + 4),
+    });
+    return { program: read(0), config: read(6) };
+  }
+
+  // Refuse to operate against deployments older than the features we rely on
+  async assertCompatibleProgram(min: ProgramVersion = MIN_PROGRAM_VERSION) {
+    const { program } = await this.getProgramVersion();
+    if (program.major !== min.major || compareVersions(program, min) < 0) {
+      throw new Error(
+        `Program version ${formatVersion(program)} is incompatible (requires >=${formatVersion(min)} <${min.major + 1}.0.0)`
+      );
+    }
+    console.log(`[Mesh] Program version ${formatVersion(program)} is compatible`);
+  }
+
   // Get PDA for agent identity
   getAgentPDA(ownerWallet: PublicKey): [PublicKey, number] {
     return PublicKey.findProgramAddressSync(
@@ -164,13 +240,8 @@ export class MeshController {
         // Fetch program accounts filtered by to_agent
         const accounts = await this.connection.getProgramAccounts(PROGRAM_ID, {
           filters: [
-            { dataSize: 8 + 602 }, // Intent account size
-            {
-              memcmp: {
-                offset: 8 + 32, // Skip discriminator + from_agent
-                bytes: agentPubkey.toBase58(),
-              },
-            },
+            { memcmp: { offset: 0, bytes: utils.bytes.bs58.encode(accountDiscriminator('AgentIntent')) } },
+            { memcmp: { offset: IntentOffsets.toAgent, bytes: agentPubkey.toBase58() } },
           ],
         });
 
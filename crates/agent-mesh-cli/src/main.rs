@@ -0,0 +1,635 @@
+//! `agent-mesh`: manage agents, model profiles and intents from the terminal.
+
+mod signer;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use agent_mesh_client::agent_mesh::{
+    instruction, AgentName, CommitmentScheme, ContentCommitment, IntentOption, IntentStatus, Permission,
+};
+use agent_mesh_client::instructions::{self, RegisterOptions};
+use agent_mesh_client::{commitment, pda, AgentMeshClient, CreateIntentParams, IntentRole};
+use anchor_spl::associated_token::get_associated_token_address;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use signer::{load_signer, CliSigner};
+
+type CliResult<T = ()> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "agent-mesh", version, about = "Operate Solana Agent Mesh agents")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(long, short = 'u', global = true, env = "RPC_URL", default_value = "https://api.devnet.solana.com")]
+    url: String,
+
+    /// Keypair file or hardware wallet URI (e.g. usb://ledger?key=0)
+    #[arg(long, short = 'k', global = true, env = "AGENT_MESH_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Agent identities
+    #[command(subcommand)]
+    Agent(AgentCommand),
+    /// Model profiles
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Intents between agents
+    #[command(subcommand)]
+    Intent(IntentCommand),
+    /// Print the deployed program version
+    Version,
+}
+
+#[derive(Subcommand)]
+enum AgentCommand {
+    /// Register the signer as an agent owner
+    Register {
+        /// Wallet that executes actions for the agent
+        #[arg(long)]
+        wallet: Pubkey,
+        /// Model profile the agent runs on
+        #[arg(long)]
+        profile: Pubkey,
+        #[arg(long)]
+        metadata_uri: String,
+        /// Comma-separated: swap,transfer,vote,create-intent,accept-intent
+        #[arg(long, default_value = "create-intent")]
+        permissions: String,
+        /// Mint a soulbound identity NFT to the agent wallet
+        #[arg(long)]
+        badge: bool,
+        /// Update the signer's agent if it's already registered
+        #[arg(long)]
+        update_existing: bool,
+        /// Only count intents created from now on toward reputation
+        #[arg(long)]
+        reset_reputation: bool,
+    },
+    /// Update the signer's agent
+    Update {
+        #[arg(long)]
+        wallet: Option<Pubkey>,
+        #[arg(long)]
+        profile: Option<Pubkey>,
+        #[arg(long)]
+        metadata_uri: Option<String>,
+        #[arg(long)]
+        permissions: Option<String>,
+    },
+    /// Advertise free slots and queue depth (as owner or agent wallet)
+    Capacity {
+        #[arg(long)]
+        slots: u32,
+        #[arg(long, default_value_t = 0)]
+        queue: u32,
+        /// Agent owner, when signing with the agent wallet
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    /// Refuse intents paying less than `amount` base units (0 accepts any)
+    MinPayment { amount: u64 },
+    /// Close the signer's agent and reclaim its rent
+    Close,
+    /// Show an agent (defaults to the signer's)
+    Show {
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    /// Bind a .sol domain the signer holds to their agent
+    BindName {
+        /// e.g. `researcher.sol`
+        name: String,
+    },
+    /// Remove the signer's agent name binding
+    UnbindName,
+    /// Look up the agent a .sol domain addresses
+    Resolve { name: String },
+    /// Set how much of a mint the agent may swap or transfer per day from the
+    /// signer's token account (0 revokes), optionally approving an allowance
+    SpendLimit {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        daily_cap: u64,
+        /// Approve the agent as delegate on the signer's token account for this amount
+        #[arg(long)]
+        approve: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Create a model profile owned by the signer
+    Create(CreateProfileArgs),
+    /// Show a model profile
+    Show { profile: Pubkey },
+}
+
+#[derive(Args)]
+struct CreateProfileArgs {
+    /// 16-byte profile id as hex (random if omitted)
+    #[arg(long)]
+    id: Option<String>,
+    #[arg(long)]
+    label: String,
+    #[arg(long)]
+    provider_uri: String,
+    /// Micro-units per 1K tokens
+    #[arg(long)]
+    pricing: u64,
+    #[arg(long)]
+    billing_wallet: Pubkey,
+    #[arg(long, default_value_t = 1_000_000)]
+    max_tokens_per_day: u64,
+    #[arg(long, default_value_t = 60)]
+    max_requests_per_min: u64,
+}
+
+#[derive(Subcommand)]
+enum IntentCommand {
+    /// Quote and open an intent from the signer's agent
+    Create(CreateIntentArgs),
+    /// List intents for an agent
+    List {
+        #[arg(long)]
+        agent: Pubkey,
+        #[arg(long, value_enum, default_value_t = Role::Provider)]
+        role: Role,
+    },
+    /// Show an intent
+    Show { intent: Pubkey },
+    /// Complete an intent as its provider and release the escrow
+    Settle(SettleArgs),
+    /// Fetch an intent's result and check it against its commitment
+    Verify {
+        intent: Pubkey,
+        #[arg(long, default_value = commitment::DEFAULT_IPFS_GATEWAY)]
+        ipfs_gateway: String,
+    },
+}
+
+#[derive(Args)]
+struct CreateIntentArgs {
+    #[arg(long)]
+    to_agent: Pubkey,
+    /// Payment mint
+    #[arg(long)]
+    mint: Pubkey,
+    /// Tokens to budget (priced at the provider's profile)
+    #[arg(long)]
+    tokens: u64,
+    #[arg(long)]
+    payload_uri: String,
+    /// Payload hash as hex, in `--scheme`
+    #[arg(
+        long,
+        conflicts_with = "payload_file",
+        required_unless_present = "payload_file",
+        requires = "payload_length"
+    )]
+    payload_hash: Option<String>,
+    /// Payload size in bytes, with `--payload-hash`
+    #[arg(long)]
+    payload_length: Option<u64>,
+    /// Commit to this file as the payload
+    #[arg(long)]
+    payload_file: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = Scheme::Sha256)]
+    scheme: Scheme,
+    /// Defaults to the signer's associated token account
+    #[arg(long)]
+    from_token_account: Option<Pubkey>,
+    #[arg(long)]
+    insured: bool,
+    #[arg(long)]
+    auto_release: bool,
+    /// Only complete with a result attested by the provider's agent wallet
+    #[arg(long)]
+    attested_result: bool,
+    /// Priority tip paid to the provider when it accepts
+    #[arg(long, default_value_t = 0)]
+    tip: u64,
+    /// Shared context (ContextRef) the payload builds on
+    #[arg(long)]
+    context: Option<Pubkey>,
+}
+
+#[derive(Args)]
+struct SettleArgs {
+    intent: Pubkey,
+    #[arg(long)]
+    result_uri: String,
+    /// Result hash as hex, in `--scheme`
+    #[arg(
+        long,
+        conflicts_with = "result_file",
+        required_unless_present = "result_file",
+        requires = "result_length"
+    )]
+    result_hash: Option<String>,
+    /// Result size in bytes, with `--result-hash`
+    #[arg(long)]
+    result_length: Option<u64>,
+    /// Commit to this file as the result
+    #[arg(long)]
+    result_file: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = Scheme::Sha256)]
+    scheme: Scheme,
+    /// Token account receiving the payout
+    #[arg(long)]
+    billing_token_account: Pubkey,
+    /// Treasury token account, when a protocol fee applies
+    #[arg(long)]
+    fee_token_account: Option<Pubkey>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Role {
+    Requester,
+    Provider,
+}
+
+/// Content commitment scheme for payloads and results
+#[derive(Clone, Copy, ValueEnum)]
+enum Scheme {
+    Sha256,
+    Blake3,
+    IpfsCid,
+}
+
+impl From<Scheme> for CommitmentScheme {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Sha256 => CommitmentScheme::Sha256,
+            Scheme::Blake3 => CommitmentScheme::Blake3,
+            Scheme::IpfsCid => CommitmentScheme::IpfsCid,
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(cli).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> CliResult {
+    let client = AgentMeshClient::new(&cli.url, load_signer(&cli.keypair)?);
+    match cli.command {
+        Command::Agent(command) => agent(&client, command).await,
+        Command::Profile(command) => profile(&client, command).await,
+        Command::Intent(command) => intent(&client, command).await,
+        Command::Version => {
+            let version = client.version().await?;
+            println!(
+                "program {}.{}.{} (config {}.{}.{})",
+                version.program.major,
+                version.program.minor,
+                version.program.patch,
+                version.config.major,
+                version.config.minor,
+                version.config.patch
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn agent(client: &AgentMeshClient<CliSigner>, command: AgentCommand) -> CliResult {
+    let owner = client.payer();
+    match command {
+        AgentCommand::Register {
+            wallet,
+            profile,
+            metadata_uri,
+            permissions,
+            badge,
+            update_existing,
+            reset_reputation,
+        } => {
+            let options = RegisterOptions {
+                badge,
+                update_existing,
+                reset_reputation,
+            };
+            let agent = client
+                .register_agent(wallet, profile, metadata_uri, parse_permissions(&permissions)?, options)
+                .await?;
+            println!("Registered agent {agent}");
+            if badge {
+                println!("Identity badge {}", pda::badge_mint(&agent).0);
+            }
+        }
+        AgentCommand::Update {
+            wallet,
+            profile,
+            metadata_uri,
+            permissions,
+        } => {
+            let permissions = permissions.as_deref().map(parse_permissions).transpose()?;
+            let ix = instructions::update_agent(
+                &owner,
+                instruction::UpdateAgent {
+                    agent_wallet: wallet,
+                    model_profile: profile,
+                    metadata_uri,
+                    permissions,
+                },
+                None,
+            );
+            let signature = client.send(&[ix], &[]).await?;
+            println!("Updated agent {} ({signature})", pda::agent(&owner).0);
+        }
+        AgentCommand::Capacity {
+            slots,
+            queue,
+            owner: target,
+        } => {
+            let target = target.unwrap_or(owner);
+            let ix = instructions::set_capacity(&owner, &target, slots, queue);
+            let signature = client.send(&[ix], &[]).await?;
+            println!("Set capacity of {} ({signature})", pda::agent(&target).0);
+        }
+        AgentCommand::MinPayment { amount } => {
+            let signature = client.send(&[instructions::set_min_payment(&owner, amount)], &[]).await?;
+            println!("Set minimum payment of {} to {amount} ({signature})", pda::agent(&owner).0);
+        }
+        AgentCommand::Close => {
+            let signature = client.send(&[instructions::close_agent(&owner)], &[]).await?;
+            println!("Closed agent {} ({signature})", pda::agent(&owner).0);
+        }
+        AgentCommand::BindName { name } => {
+            let label = name.strip_suffix(".sol").unwrap_or(&name).to_string();
+            let signature = client.send(&[instructions::bind_name(&owner, label)], &[]).await?;
+            println!("Bound {name} to agent {} ({signature})", pda::agent(&owner).0);
+        }
+        AgentCommand::UnbindName => {
+            let binding: AgentName = client.fetch(&pda::agent_name(&pda::agent(&owner).0).0).await?;
+            let ix = instructions::unbind_name(&owner, &owner, binding.name_record);
+            let signature = client.send(&[ix], &[]).await?;
+            println!("Unbound {}.sol ({signature})", binding.name);
+        }
+        AgentCommand::Resolve { name } => {
+            println!("{}", client.resolve_name(&name).await?);
+        }
+        AgentCommand::Show { owner: target } => {
+            let target = target.unwrap_or(owner);
+            let agent = client.agent(&target).await?;
+            println!("agent:         {}", pda::agent(&target).0);
+            println!("owner:         {}", agent.owner_wallet);
+            println!("agent wallet:  {}", agent.agent_wallet);
+            println!("model profile: {}", agent.model_profile);
+            println!("metadata uri:  {}", agent.metadata_uri);
+            println!("permissions:   {}", format_permissions(agent.permissions));
+            if agent.capacity_updated_at != 0 {
+                println!(
+                    "capacity:      {} free, {} queued (at {})",
+                    agent.available_slots, agent.queue_depth, agent.capacity_updated_at
+                );
+            }
+            if agent.min_payment > 0 {
+                println!("min payment:   {}", agent.min_payment);
+            }
+        }
+        AgentCommand::SpendLimit {
+            mint,
+            daily_cap,
+            approve,
+        } => {
+            let spend_limit = pda::spend_limit(&pda::agent(&owner).0, &mint).0;
+            let exists = client.rpc().get_account(&spend_limit).await.is_ok();
+            let mut ixs = vec![match (exists, daily_cap) {
+                (false, _) => instructions::open_spend_limit(&owner, mint, daily_cap),
+                (true, 0) => instructions::close_spend_limit(&owner, mint),
+                (true, _) => instructions::set_spend_limit(&owner, mint, daily_cap),
+            }];
+            if let Some(amount) = approve {
+                let token_account = get_associated_token_address(&owner, &mint);
+                ixs.push(instructions::approve_agent(&owner, token_account, amount));
+            }
+            let signature = client.send(&ixs, &[]).await?;
+            println!("Set spend limit {spend_limit} to {daily_cap} per day ({signature})");
+        }
+    }
+    Ok(())
+}
+
+async fn profile(client: &AgentMeshClient<CliSigner>, command: ProfileCommand) -> CliResult {
+    match command {
+        ProfileCommand::Create(args) => {
+            let owner = client.payer();
+            let profile_id = match &args.id {
+                Some(id) => parse_hex::<16>(id)?,
+                None => Keypair::new().pubkey().to_bytes()[..16].try_into()?,
+            };
+            let ix = instructions::create_model_profile(
+                &owner,
+                instruction::CreateModelProfile {
+                    profile_id,
+                    label: args.label,
+                    provider_uri: args.provider_uri,
+                    pricing: args.pricing,
+                    billing_wallet: args.billing_wallet,
+                    max_tokens_per_day: args.max_tokens_per_day,
+                    max_requests_per_min: args.max_requests_per_min,
+                },
+            );
+            client.send(&[ix], &[]).await?;
+            println!(
+                "Created profile {} (id {})",
+                pda::model_profile(&owner, &profile_id).0,
+                to_hex(&profile_id)
+            );
+        }
+        ProfileCommand::Show { profile: address } => {
+            let profile = client.model_profile(&address).await?;
+            println!("profile:        {address}");
+            println!("owner:          {}", profile.owner_wallet);
+            println!("label:          {}", profile.label);
+            println!("provider uri:   {}", profile.provider_uri);
+            println!("pricing:        {} per 1K tokens", profile.pricing);
+            println!("billing wallet: {}", profile.billing_wallet);
+            println!("verified:       {}", profile.verified);
+            println!("public:         {}", profile.is_public);
+        }
+    }
+    Ok(())
+}
+
+async fn intent(client: &AgentMeshClient<CliSigner>, command: IntentCommand) -> CliResult {
+    match command {
+        IntentCommand::Create(args) => {
+            let owner = client.payer();
+            let payload = match (&args.payload_hash, args.payload_length, &args.payload_file) {
+                (Some(hex), Some(length), _) => ContentCommitment {
+                    scheme: CommitmentScheme::from(args.scheme) as u8,
+                    length,
+                    hash: parse_hex::<32>(hex)?,
+                },
+                (None, _, Some(file)) => commitment::commit(args.scheme.into(), &std::fs::read(file)?),
+                _ => return Err("--payload-hash and --payload-length, or --payload-file, are required".into()),
+            };
+            let mut options = 0;
+            if args.insured {
+                options |= IntentOption::INSURED;
+            }
+            if args.auto_release {
+                options |= IntentOption::AUTO_RELEASE;
+            }
+            if args.attested_result {
+                options |= IntentOption::ATTESTED_RESULT;
+            }
+            let intent = client
+                .create_intent(CreateIntentParams {
+                    from_agent: pda::agent(&owner).0,
+                    to_agent: args.to_agent,
+                    payload,
+                    payload_uri: args.payload_uri,
+                    payment_mint: args.mint,
+                    from_token_account: args
+                        .from_token_account
+                        .unwrap_or_else(|| get_associated_token_address(&owner, &args.mint)),
+                    tokens: args.tokens,
+                    options,
+                    tip_amount: args.tip,
+                    context: args.context,
+                })
+                .await?;
+            let nonce = client.intent(&intent).await?.nonce;
+            println!("Created intent {intent} (nonce {nonce})");
+        }
+        IntentCommand::List { agent, role } => {
+            let role = match role {
+                Role::Requester => IntentRole::Requester,
+                Role::Provider => IntentRole::Provider,
+            };
+            let intents = client.intents_for_agent(&agent, role).await?;
+            for (address, intent) in &intents {
+                println!(
+                    "{address}  {:<9}  {:>12}  nonce {}  {}",
+                    status_name(intent.status),
+                    intent.payment_amount,
+                    intent.nonce,
+                    intent.payload_uri
+                );
+            }
+            println!("{} intent(s)", intents.len());
+        }
+        IntentCommand::Show { intent: address } => {
+            let intent = client.intent(&address).await?;
+            println!("intent:     {address}");
+            println!("from agent: {}", intent.from_agent);
+            println!("to agent:   {}", intent.to_agent);
+            println!("assignee:   {}", intent.assignee());
+            println!("status:     {}", status_name(intent.status));
+            println!("payment:    {} of {}", intent.payment_amount, intent.payment_mint);
+            if intent.tip_amount > 0 {
+                println!("tip:        {}", intent.tip_amount);
+            }
+            println!("payload:    {} ({})", intent.payload_uri, to_hex(&intent.payload_hash));
+            if !intent.result_uri.is_empty() {
+                println!("result:     {} ({})", intent.result_uri, to_hex(&intent.result_hash));
+            }
+        }
+        IntentCommand::Settle(args) => {
+            let result = match (&args.result_hash, args.result_length, &args.result_file) {
+                (Some(hex), Some(length), _) => ContentCommitment {
+                    scheme: CommitmentScheme::from(args.scheme) as u8,
+                    length,
+                    hash: parse_hex::<32>(hex)?,
+                },
+                (None, _, Some(file)) => commitment::commit(args.scheme.into(), &std::fs::read(file)?),
+                _ => return Err("--result-hash and --result-length, or --result-file, are required".into()),
+            };
+            let signature = client
+                .settle(
+                    &args.intent,
+                    result,
+                    args.result_uri,
+                    args.billing_token_account,
+                    args.fee_token_account,
+                )
+                .await?;
+            println!("Settled intent {} ({signature})", args.intent);
+        }
+        IntentCommand::Verify { intent, ipfs_gateway } => {
+            let content = client.verify_result(&intent, &ipfs_gateway).await?;
+            println!("Result of {intent} matches its commitment ({} bytes)", content.len());
+        }
+    }
+    Ok(())
+}
+
+const PERMISSIONS: [(&str, u64); 5] = [
+    ("swap", Permission::CAN_SWAP),
+    ("transfer", Permission::CAN_TRANSFER),
+    ("vote", Permission::CAN_VOTE),
+    ("create-intent", Permission::CAN_CREATE_INTENT),
+    ("accept-intent", Permission::CAN_ACCEPT_INTENT),
+];
+
+/// Comma-separated permission names, or a raw bitmask
+fn parse_permissions(value: &str) -> CliResult<u64> {
+    if let Ok(mask) = value.parse() {
+        return Ok(mask);
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .try_fold(0, |mask, name| {
+            PERMISSIONS
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, flag)| mask | flag)
+                .ok_or_else(|| format!("unknown permission '{name}'").into())
+        })
+}
+
+fn format_permissions(mask: u64) -> String {
+    let names: Vec<_> = PERMISSIONS
+        .iter()
+        .filter(|(_, flag)| mask & flag != 0)
+        .map(|(name, _)| *name)
+        .collect();
+    format!("{} ({mask:#x})", names.join(","))
+}
+
+fn status_name(status: u8) -> &'static str {
+    match status {
+        s if s == IntentStatus::Pending as u8 => "pending",
+        s if s == IntentStatus::Accepted as u8 => "accepted",
+        s if s == IntentStatus::Completed as u8 => "completed",
+        s if s == IntentStatus::Failed as u8 => "failed",
+        s if s == IntentStatus::Cancelled as u8 => "cancelled",
+        _ => "unknown",
+    }
+}
+
+fn parse_hex<const N: usize>(value: &str) -> CliResult<[u8; N]> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    if value.len() != N * 2 {
+        return Err(format!("expected {N} bytes of hex").into());
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(out)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
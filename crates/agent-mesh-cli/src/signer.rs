@@ -0,0 +1,50 @@
+//! Signer loading: keypair files or `usb://ledger` hardware wallet URIs.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use solana_remote_wallet::locator::Locator;
+use solana_remote_wallet::remote_keypair::generate_remote_keypair;
+use solana_remote_wallet::remote_wallet::maybe_wallet_manager;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signature, Signer, SignerError};
+use uriparse::URIReference;
+
+/// Owned signer of either kind, usable as the client's payer
+pub struct CliSigner(Box<dyn Signer>);
+
+impl Signer for CliSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.0.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.0.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.0.is_interactive()
+    }
+}
+
+/// `path` is a keypair file (`~` is expanded) or a locator such as
+/// `usb://ledger?key=0/0`
+pub fn load_signer(path: &str) -> Result<CliSigner, Box<dyn Error>> {
+    if path.starts_with("usb://") {
+        let uri = URIReference::try_from(path)?;
+        let locator = Locator::new_from_uri(&uri)?;
+        let derivation_path = DerivationPath::from_uri_any_query(&uri)?.unwrap_or_default();
+        let wallet_manager = maybe_wallet_manager()?.ok_or("no hardware wallet found")?;
+        let keypair = generate_remote_keypair(locator, derivation_path, &wallet_manager, false, "keypair")?;
+        return Ok(CliSigner(Box::new(keypair)));
+    }
+
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME")?).join(rest),
+        None => PathBuf::from(path),
+    };
+    let keypair = read_keypair_file(&path)
+        .map_err(|e| format!("failed to read keypair {}: {e}", path.display()))?;
+    Ok(CliSigner(Box::new(keypair)))
+}
@@ -0,0 +1,571 @@
+//! Async RPC client: account fetching plus the common intent lifecycle flows.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use agent_mesh::{
+    instruction, AgentIdentity, AgentIntent, AgentName, AgentStats, ContentCommitment, IndexRole, IntentIndex,
+    IntentIndexPage, IntentStatus, ModelProfile, NonceCounter, Permission, ProgramVersion, ProtocolConfig,
+    RoyaltySchedule, VersionInfo, INTENT_INDEX_PAGE_LEN, NAME_RECORD_OWNER_OFFSET, NAME_SERVICE_PROGRAM_ID,
+};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anchor_spl::token;
+use base64::Engine;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::instructions::{self, CreateIntentBuilder, RegisterOptions, UpdateIntentStatusBuilder};
+use crate::ranking::{self, Ranked, RankingWeights, Reputation, Signals};
+use crate::{commitment, filters, pda, ClientError, Result, MIN_PROGRAM_VERSION};
+
+/// Inputs for [`AgentMeshClient::create_intent`]
+pub struct CreateIntentParams {
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    /// See [`crate::commitment::commit`]
+    pub payload: ContentCommitment,
+    pub payload_uri: String,
+    pub payment_mint: Pubkey,
+    pub from_token_account: Pubkey,
+    /// Tokens to budget; the payment is quoted from the provider's pricing
+    pub tokens: u64,
+    /// `IntentOption` flags
+    pub options: u8,
+    /// Paid to the provider when it accepts, on top of the payment
+    pub tip_amount: u64,
+    /// Shared `ContextRef` the payload builds on
+    pub context: Option<Pubkey>,
+}
+
+/// Filters and weights for [`AgentMeshClient::find_agents`]
+#[derive(Clone, Debug)]
+pub struct AgentQuery {
+    pub model_profile: Option<Pubkey>,
+    /// Only agents on attested profiles
+    pub verified_only: bool,
+    pub weights: RankingWeights,
+    pub limit: usize,
+}
+
+impl Default for AgentQuery {
+    fn default() -> Self {
+        Self {
+            model_profile: None,
+            verified_only: false,
+            weights: RankingWeights::default(),
+            limit: 20,
+        }
+    }
+}
+
+/// A provider agent found by [`AgentMeshClient::find_agents`]
+#[derive(Clone)]
+pub struct FoundAgent {
+    pub address: Pubkey,
+    pub agent: AgentIdentity,
+    pub profile: ModelProfile,
+}
+
+/// Which side of an intent an agent is on, for [`AgentMeshClient::intents_for_agent`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntentRole {
+    Requester,
+    Provider,
+}
+
+impl IntentRole {
+    /// The agent's intent index listing this side
+    pub fn index_role(self) -> IndexRole {
+        match self {
+            IntentRole::Requester => IndexRole::Sent,
+            IntentRole::Provider => IndexRole::Received,
+        }
+    }
+}
+
+/// `S` is the payer/owner signer: a `Keypair`, or a hardware wallet signer
+pub struct AgentMeshClient<S: Signer = Keypair> {
+    rpc: RpcClient,
+    payer: S,
+}
+
+impl<S: Signer> AgentMeshClient<S> {
+    pub fn new(rpc_url: impl ToString, payer: S) -> Self {
+        Self::with_rpc(
+            RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()),
+            payer,
+        )
+    }
+
+    pub fn with_rpc(rpc: RpcClient, payer: S) -> Self {
+        Self { rpc, payer }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    /// Sign with the payer (plus `signers`) and send
+    pub async fn send(&self, instructions: &[Instruction], signers: &[&dyn Signer]) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let mut all_signers: Vec<&dyn Signer> = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&tx).await?)
+    }
+
+    // === Accounts ===
+
+    pub async fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())
+            .await?
+            .value
+            .ok_or(ClientError::AccountNotFound(*address))?;
+        T::try_deserialize(&mut account.data.as_slice()).map_err(|e| ClientError::Decode(*address, e))
+    }
+
+    pub async fn config(&self) -> Result<ProtocolConfig> {
+        self.fetch(&pda::config().0).await
+    }
+
+    pub async fn agent(&self, owner: &Pubkey) -> Result<AgentIdentity> {
+        self.fetch(&pda::agent(owner).0).await
+    }
+
+    /// Running intent totals of `agent` (an agent address, not its owner)
+    pub async fn agent_stats(&self, agent: &Pubkey) -> Result<AgentStats> {
+        self.fetch(&pda::agent_stats(agent).0).await
+    }
+
+    pub async fn model_profile(&self, address: &Pubkey) -> Result<ModelProfile> {
+        self.fetch(address).await
+    }
+
+    pub async fn intent(&self, address: &Pubkey) -> Result<AgentIntent> {
+        self.fetch(address).await
+    }
+
+    /// Program accounts of type `T` matching `filters` (see [`crate::filters`];
+    /// the account-type filter is added here)
+    pub async fn program_accounts<T: AccountDeserialize + Discriminator>(
+        &self,
+        mut filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, T)>> {
+        filters.insert(0, crate::filters::discriminator::<T>());
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.rpc
+            .get_program_accounts_with_config(&crate::PROGRAM_ID, config)
+            .await?
+            .into_iter()
+            .map(|(address, account)| {
+                T::try_deserialize(&mut account.data.as_slice())
+                    .map(|decoded| (address, decoded))
+                    .map_err(|e| ClientError::Decode(address, e))
+            })
+            .collect()
+    }
+
+    /// All intents an agent requested or is providing
+    pub async fn intents_for_agent(
+        &self,
+        agent: &Pubkey,
+        role: IntentRole,
+    ) -> Result<Vec<(Pubkey, AgentIntent)>> {
+        let filter = match role {
+            IntentRole::Requester => filters::intent_from_agent(agent),
+            IntentRole::Provider => filters::intent_to_agent(agent),
+        };
+        self.program_accounts(vec![filter]).await
+    }
+
+    /// How many intents an agent's index lists for `role`
+    pub async fn indexed_intent_count(&self, agent: &Pubkey, role: IntentRole) -> Result<u64> {
+        match self.fetch::<IntentIndex>(&pda::intent_index(agent, role.index_role()).0).await {
+            Ok(index) => Ok(index.count),
+            Err(ClientError::AccountNotFound(_)) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// One page of an agent's intent index, oldest first; pages run from 0
+    /// to `(count - 1) / INTENT_INDEX_PAGE_LEN`. Unlike [`Self::intents_for_agent`]
+    /// this is a single account lookup, and closed intents stay listed.
+    pub async fn indexed_intents(&self, agent: &Pubkey, role: IntentRole, page: u32) -> Result<Vec<Pubkey>> {
+        match self.fetch::<IntentIndexPage>(&pda::intent_index_page(agent, role.index_role(), page).0).await {
+            Ok(page) => Ok(page.intents),
+            Err(ClientError::AccountNotFound(_)) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Index pages the next intent from `from_agent` to `to_agent` is
+    /// appended to, for [`CreateIntentBuilder::index_pages`]
+    pub async fn next_index_pages(&self, from_agent: &Pubkey, to_agent: &Pubkey) -> Result<(u32, u32)> {
+        let page = |count: u64| (count / INTENT_INDEX_PAGE_LEN as u64) as u32;
+        let sent = self.indexed_intent_count(from_agent, IntentRole::Requester).await?;
+        let received = self.indexed_intent_count(to_agent, IntentRole::Provider).await?;
+        Ok((page(sent), page(received)))
+    }
+
+    /// Provider agents that accept intents and have capacity now, ranked by
+    /// [`ranking::rank`]. Reputation is tallied from the program's open
+    /// intent accounts created since each agent's `reputation_since`, so
+    /// closed intents no longer count; the indexer keeps the fuller record.
+    /// Stake is the acceptance bonds still held on those accounts.
+    pub async fn find_agents(&self, query: &AgentQuery) -> Result<Vec<Ranked<FoundAgent>>> {
+        let mut agent_filters = Vec::new();
+        if let Some(model_profile) = &query.model_profile {
+            agent_filters.push(filters::agent_model_profile(model_profile));
+        }
+        let agents = self.program_accounts::<AgentIdentity>(agent_filters).await?;
+        let profiles: HashMap<Pubkey, ModelProfile> =
+            self.program_accounts::<ModelProfile>(Vec::new()).await?.into_iter().collect();
+        let reputation_since: HashMap<Pubkey, i64> =
+            agents.iter().map(|(address, agent)| (*address, agent.reputation_since)).collect();
+        let mut reputations: HashMap<Pubkey, Reputation> = HashMap::new();
+        let mut stakes: HashMap<Pubkey, u64> = HashMap::new();
+        for (_, intent) in self.program_accounts::<AgentIntent>(Vec::new()).await? {
+            let assignee = intent.assignee();
+            if intent.bond > 0 {
+                let stake = stakes.entry(assignee).or_default();
+                *stake = stake.saturating_add(intent.bond);
+            }
+            if intent.created_at >= reputation_since.get(&assignee).copied().unwrap_or_default() {
+                reputations.entry(assignee).or_default().record(&intent);
+            }
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let candidates = agents
+            .into_iter()
+            .filter(|(_, agent)| agent.permissions & Permission::CAN_ACCEPT_INTENT != 0 && agent.has_capacity(now))
+            .filter_map(|(address, agent)| {
+                let profile = profiles.get(&agent.model_profile)?.clone();
+                if query.verified_only && !profile.verified {
+                    return None;
+                }
+                let signals = Signals {
+                    reputation: reputations.get(&address).copied().unwrap_or_default(),
+                    capacity_updated_at: agent.capacity_updated_at,
+                    pricing: profile.pricing,
+                    stake: stakes.get(&address).copied().unwrap_or_default(),
+                };
+                Some((FoundAgent { address, agent, profile }, signals))
+            })
+            .collect();
+        Ok(ranking::rank(candidates, &query.weights, now, query.limit))
+    }
+
+    // === Version ===
+
+    /// Simulate `get_version` and decode its return data
+    pub async fn version(&self) -> Result<VersionInfo> {
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            &[instructions::get_version()],
+            Some(&self.payer.pubkey()),
+            &[&self.payer as &dyn Signer],
+            blockhash,
+        );
+        let result = self.rpc.simulate_transaction(&tx).await?.value;
+        if let Some(err) = result.err {
+            return Err(ClientError::Simulation(err.to_string()));
+        }
+        let data = result
+            .return_data
+            .ok_or_else(|| ClientError::Simulation("no return data".into()))?
+            .data
+            .0;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| ClientError::Simulation(e.to_string()))?;
+        VersionInfo::try_from_slice(&bytes).map_err(|e| ClientError::Simulation(e.to_string()))
+    }
+
+    /// Fail unless the deployment is at least `required` within the same major
+    pub async fn require_version(&self, required: ProgramVersion) -> Result<()> {
+        let found = self.version().await?.program;
+        if found.major != required.major || found < required {
+            return Err(ClientError::IncompatibleVersion { found, required });
+        }
+        Ok(())
+    }
+
+    /// [`Self::require_version`] against the SDK's own minimum
+    pub async fn check_compatibility(&self) -> Result<()> {
+        self.require_version(MIN_PROGRAM_VERSION).await
+    }
+
+    // === Flows ===
+
+    /// Register the payer as an agent owner, optionally minting its identity
+    /// badge to `agent_wallet`; returns the agent PDA
+    pub async fn register_agent(
+        &self,
+        agent_wallet: Pubkey,
+        model_profile: Pubkey,
+        metadata_uri: String,
+        permissions: u64,
+        options: RegisterOptions,
+    ) -> Result<Pubkey> {
+        let owner = self.payer.pubkey();
+        let ix = instructions::register_agent(
+            &owner,
+            agent_wallet,
+            model_profile,
+            metadata_uri,
+            permissions,
+            None,
+            options,
+        );
+        self.send(&[ix], &[]).await?;
+        Ok(pda::agent(&owner).0)
+    }
+
+    /// Agent addressed by `name.sol`: the domain holder's agent, as long as it
+    /// bound the name and still holds the domain
+    pub async fn resolve_name(&self, name: &str) -> Result<Pubkey> {
+        let name = name.strip_suffix(".sol").unwrap_or(name);
+        let not_bound = || ClientError::NameNotBound(name.to_string());
+        let name_record = pda::sol_name_record(name).0;
+        let record = self
+            .rpc
+            .get_account_with_commitment(&name_record, self.rpc.commitment())
+            .await?
+            .value
+            .filter(|record| record.owner == NAME_SERVICE_PROGRAM_ID)
+            .ok_or_else(not_bound)?;
+        let holder = record
+            .data
+            .get(NAME_RECORD_OWNER_OFFSET..NAME_RECORD_OWNER_OFFSET + 32)
+            .and_then(|bytes| Pubkey::try_from(bytes).ok())
+            .ok_or_else(not_bound)?;
+
+        let agent = pda::agent(&holder).0;
+        let binding: AgentName = match self.fetch(&pda::agent_name(&agent).0).await {
+            Err(ClientError::AccountNotFound(_)) => return Err(not_bound()),
+            binding => binding?,
+        };
+        if binding.name_record != name_record || binding.owner != holder {
+            return Err(not_bound());
+        }
+        Ok(agent)
+    }
+
+    /// Price `tokens` against the provider agent's current model profile
+    pub async fn quote(&self, to_agent: &Pubkey, tokens: u64) -> Result<u64> {
+        let agent: AgentIdentity = self.fetch(to_agent).await?;
+        let profile = self.model_profile(&agent.model_profile).await?;
+        crate::quote(profile.pricing, tokens).ok_or(ClientError::QuoteOverflow)
+    }
+
+    /// Nonce the next intent from `from_agent` to `to_agent` will take
+    pub async fn next_nonce(&self, from_agent: &Pubkey, to_agent: &Pubkey) -> Result<u64> {
+        match self.fetch::<NonceCounter>(&pda::nonce_counter(from_agent, to_agent).0).await {
+            Ok(counter) => Ok(counter.next),
+            Err(ClientError::AccountNotFound(_)) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Quote, create the escrow account and open the intent at the pair's
+    /// next nonce, paid by the payer; returns the intent PDA. Retried with a
+    /// fresh nonce and index pages when another process opens an intent
+    /// between the same agents first.
+    pub async fn create_intent(&self, params: CreateIntentParams) -> Result<Pubkey> {
+        let payment_amount = self.quote(&params.to_agent, params.tokens).await?;
+        let payer = self.payer.pubkey();
+        let mut attempt = 0;
+        loop {
+            let nonce = self.next_nonce(&params.from_agent, &params.to_agent).await?;
+            let (sent_page, received_page) = self.next_index_pages(&params.from_agent, &params.to_agent).await?;
+            let mut builder = CreateIntentBuilder::new(
+                &payer,
+                params.from_agent,
+                params.to_agent,
+                params.payment_mint,
+                params.from_token_account,
+                nonce,
+                instruction::CreateIntent {
+                    payload_uri: params.payload_uri.clone(),
+                    payload: params.payload,
+                    payment_amount,
+                    options: params.options,
+                    tip_amount: params.tip_amount,
+                },
+            )
+            .index_pages(sent_page, received_page);
+            if let Some(context) = params.context {
+                builder = builder.context(context);
+            }
+            let intent = builder.intent();
+            let create_escrow = create_associated_token_account_idempotent(
+                &payer,
+                &intent,
+                &params.payment_mint,
+                &token::ID,
+            );
+            match self.send(&[create_escrow, builder.instruction()], &[]).await {
+                Ok(_) => return Ok(intent),
+                Err(e) if attempt < STALE_NONCE_RETRIES && is_stale_nonce(&e) => attempt += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Poll until the intent reaches `status` (or any terminal status)
+    pub async fn wait_for_status(
+        &self,
+        intent: &Pubkey,
+        status: IntentStatus,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<AgentIntent> {
+        let wait = async {
+            loop {
+                let account = self.intent(intent).await?;
+                if account.status == status as u8 || IntentStatus::is_terminal(account.status) {
+                    return Ok(account);
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| ClientError::Timeout(*intent))?
+    }
+
+    /// Complete an intent as its assignee (the payer must own the agent) and
+    /// release the escrow to `billing_token_account`. Pass the treasury's
+    /// token account when a protocol fee applies. Declared royalties are paid
+    /// into each recipient's associated token account, and SLA penalties into
+    /// the payer's. An intent the payer accepted settles through
+    /// `submit_result` and the slim `settle_intent`.
+    pub async fn settle(
+        &self,
+        intent: &Pubkey,
+        result: ContentCommitment,
+        result_uri: String,
+        billing_token_account: Pubkey,
+        fee_token_account: Option<Pubkey>,
+    ) -> Result<Signature> {
+        let account = self.intent(intent).await?;
+        let owner = self.payer.pubkey();
+        let escrow = pda::escrow(intent, &account.payment_mint);
+        let sla_refund = (account.sla_penalty_bps > 0)
+            .then(|| get_associated_token_address(&account.payer, &account.payment_mint));
+        let royalty_recipients = if account.royalty_bps > 0 {
+            let schedule: RoyaltySchedule = self.fetch(&pda::royalties(intent).0).await?;
+            schedule
+                .splits
+                .iter()
+                .map(|split| get_associated_token_address(&split.recipient, &account.payment_mint))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if account.status == IntentStatus::Accepted as u8 && account.assignee_owner == owner {
+            let submit = instructions::submit_result(&owner, *intent, result, result_uri);
+            let mut builder =
+                instructions::SettleIntentBuilder::new(&owner, *intent).release(escrow, billing_token_account);
+            if let Some(fee_token_account) = fee_token_account {
+                builder = builder.fee_token_account(fee_token_account);
+            }
+            if let Some(refund_token_account) = sla_refund {
+                builder = builder.sla_refund(refund_token_account);
+            }
+            if !royalty_recipients.is_empty() {
+                builder = builder.royalties(&royalty_recipients);
+            }
+            return self.send(&[submit, builder.instruction()], &[]).await;
+        }
+
+        // Completing always goes through acceptance
+        let mut ixs = Vec::new();
+        if account.status == IntentStatus::Pending as u8 {
+            ixs.push(
+                UpdateIntentStatusBuilder::new(&owner, *intent, account.assignee(), IntentStatus::Accepted as u8)
+                    .release(escrow, billing_token_account)
+                    .instruction(),
+            );
+        }
+        let mut builder = UpdateIntentStatusBuilder::new(
+            &owner,
+            *intent,
+            account.assignee(),
+            IntentStatus::Completed as u8,
+        )
+        .result(result, result_uri)
+        .release(escrow, billing_token_account);
+        if let Some(fee_token_account) = fee_token_account {
+            builder = builder.fee_token_account(fee_token_account);
+        }
+        if let Some(refund_token_account) = sla_refund {
+            builder = builder.sla_refund(refund_token_account);
+        }
+        if !royalty_recipients.is_empty() {
+            builder = builder.royalties(&royalty_recipients);
+        }
+        ixs.push(builder.instruction());
+        self.send(&ixs, &[]).await
+    }
+
+    /// Fetch an intent's `result_uri` (`ipfs://` through `ipfs_gateway`) and
+    /// check it against the committed result; returns the content
+    pub async fn verify_result(&self, intent: &Pubkey, ipfs_gateway: &str) -> Result<Vec<u8>> {
+        let account = self.intent(intent).await?;
+        if account.result_uri.is_empty() {
+            return Err(ClientError::Commitment("intent has no result".to_string()));
+        }
+        let content = commitment::fetch(&account.result_uri, ipfs_gateway).await?;
+        commitment::verify(&account.result_commitment(), &account.result_uri, &content)?;
+        Ok(content)
+    }
+}
+
+/// Times [`AgentMeshClient::create_intent`] re-reads the nonce counter
+const STALE_NONCE_RETRIES: usize = 3;
+
+/// The intent PDA was derived from a nonce another intent already took
+fn is_stale_nonce(error: &ClientError) -> bool {
+    let ClientError::Rpc(error) = error else {
+        return false;
+    };
+    matches!(
+        error.get_transaction_error(),
+        Some(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code)
+        )) if code == anchor_lang::error::ErrorCode::ConstraintSeeds as u32
+    )
+}
@@ -0,0 +1,103 @@
+//! Content commitments for intent payloads and results.
+//!
+//! Every intent commits to its payload, and every completed intent to its
+//! result, as a [`ContentCommitment`]: the content's length and its hash in
+//! one of the [`CommitmentScheme`]s, so anyone can fetch the URI and check
+//! what the parties signed up to.
+//!
+//! - `Sha256`: sha256 of the content
+//! - `Blake3`: BLAKE3 of the content
+//! - `IpfsCid`: the URI is `ipfs://<cid>` for a CIDv1 with the `raw` codec
+//!   and a sha2-256 multihash (see [`ipfs_cid`]); the hash is that digest
+
+use agent_mesh::{CommitmentScheme, ContentCommitment};
+use sha2::{Digest, Sha256};
+
+use crate::{ClientError, Result};
+
+/// Gateway `ipfs://` URIs are fetched through by [`fetch`]
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io";
+
+/// CIDv1 prefix: version 1, `raw` codec, sha2-256 multihash of 32 bytes
+const RAW_SHA256_CID_PREFIX: [u8; 4] = [0x01, 0x55, 0x12, 0x20];
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Commit to `content` under `scheme`
+pub fn commit(scheme: CommitmentScheme, content: &[u8]) -> ContentCommitment {
+    ContentCommitment {
+        scheme: scheme as u8,
+        length: content.len() as u64,
+        hash: digest(scheme, content),
+    }
+}
+
+/// Check `content` fetched from `uri` against `commitment`
+pub fn verify(commitment: &ContentCommitment, uri: &str, content: &[u8]) -> Result<()> {
+    let scheme = CommitmentScheme::from_u8(commitment.scheme)
+        .ok_or_else(|| ClientError::Commitment(format!("unknown scheme {}", commitment.scheme)))?;
+    if content.len() as u64 != commitment.length {
+        return Err(ClientError::Commitment(format!(
+            "length is {}, committed {}",
+            content.len(),
+            commitment.length
+        )));
+    }
+    if digest(scheme, content) != commitment.hash {
+        return Err(ClientError::Commitment("hash mismatch".to_string()));
+    }
+    if scheme == CommitmentScheme::IpfsCid {
+        let cid = ipfs_path(uri)
+            .map(|path| path.split('/').next().unwrap_or_default())
+            .ok_or_else(|| ClientError::Commitment(format!("{uri} is not an ipfs:// URI")))?;
+        if cid != ipfs_cid(&commitment.hash) {
+            return Err(ClientError::Commitment(format!("{cid} is not the committed CID")));
+        }
+    }
+    Ok(())
+}
+
+/// The base32 CIDv1 (`raw`, sha2-256) for a sha256 digest
+pub fn ipfs_cid(digest: &[u8; 32]) -> String {
+    let mut bytes = RAW_SHA256_CID_PREFIX.to_vec();
+    bytes.extend_from_slice(digest);
+    let mut cid = String::from("b");
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            cid.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        cid.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    cid
+}
+
+/// Download the content behind an `http(s)://` or `ipfs://` URI, the latter
+/// through `ipfs_gateway`
+pub async fn fetch(uri: &str, ipfs_gateway: &str) -> Result<Vec<u8>> {
+    let url = if let Some(path) = ipfs_path(uri) {
+        format!("{}/ipfs/{path}", ipfs_gateway.trim_end_matches('/'))
+    } else if uri.starts_with("https://") || uri.starts_with("http://") {
+        uri.to_string()
+    } else {
+        return Err(ClientError::Commitment(format!("can't fetch {uri}")));
+    };
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+fn digest(scheme: CommitmentScheme, content: &[u8]) -> [u8; 32] {
+    match scheme {
+        CommitmentScheme::Sha256 | CommitmentScheme::IpfsCid => Sha256::digest(content).into(),
+        CommitmentScheme::Blake3 => blake3::hash(content).into(),
+    }
+}
+
+fn ipfs_path(uri: &str) -> Option<&str> {
+    uri.strip_prefix("ipfs://")
+}
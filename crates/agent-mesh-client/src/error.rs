@@ -0,0 +1,48 @@
+use agent_mesh::ProgramVersion;
+use solana_sdk::pubkey::Pubkey;
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+
+    #[error("websocket error: {0}")]
+    Pubsub(#[from] solana_client::nonblocking::pubsub_client::PubsubClientError),
+
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    #[error("failed to decode account {0}: {1}")]
+    Decode(Pubkey, anchor_lang::error::Error),
+
+    #[error("simulation failed: {0}")]
+    Simulation(String),
+
+    #[error("program version {}.{}.{} is incompatible (requires >={}.{}.{})",
+        .found.major, .found.minor, .found.patch,
+        .required.major, .required.minor, .required.patch)]
+    IncompatibleVersion {
+        found: ProgramVersion,
+        required: ProgramVersion,
+    },
+
+    #[error("quote overflowed")]
+    QuoteOverflow,
+
+    #[error("timed out waiting for intent {0}")]
+    Timeout(Pubkey),
+
+    #[error("{0}.sol is not bound to an agent")]
+    NameNotBound(String),
+
+    #[error("sealed payload: {0}")]
+    Seal(&'static str),
+
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("content doesn't match its commitment: {0}")]
+    Commitment(String),
+}
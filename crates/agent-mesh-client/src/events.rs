@@ -0,0 +1,467 @@
+//! Typed program events: log decoding plus [`EventStream`], a reconnecting
+//! WebSocket subscription that backfills missed transactions by slot.
+
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use agent_mesh::ID as PROGRAM_ID;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::Engine;
+use futures_util::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{
+    RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::{ClientError, Result};
+
+macro_rules! mesh_events {
+    (
+        intent: [$($intent_event:ident),* $(,)?],
+        other: [$($event:ident),* $(,)?] $(,)?
+    ) => {
+        /// Every event the program emits
+        pub enum AgentMeshEvent {
+            $($intent_event(agent_mesh::$intent_event),)*
+            $($event(agent_mesh::$event),)*
+        }
+
+        impl AgentMeshEvent {
+            /// Decode one `Program data:` payload (discriminator + borsh body)
+            pub fn decode(data: &[u8]) -> Option<Self> {
+                let (discriminator, mut body) = data.split_first_chunk::<8>()?;
+                $(if *discriminator == agent_mesh::$intent_event::DISCRIMINATOR {
+                    return agent_mesh::$intent_event::deserialize(&mut body)
+                        .ok()
+                        .map(Self::$intent_event);
+                })*
+                $(if *discriminator == agent_mesh::$event::DISCRIMINATOR {
+                    return agent_mesh::$event::deserialize(&mut body).ok().map(Self::$event);
+                })*
+                None
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$intent_event(_) => stringify!($intent_event),)*
+                    $(Self::$event(_) => stringify!($event),)*
+                }
+            }
+
+            /// The intent and its `sequence` after the change, for intent events
+            pub fn intent_sequence(&self) -> Option<(Pubkey, u64)> {
+                match self {
+                    $(Self::$intent_event(event) => Some((event.intent, event.sequence)),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+mesh_events!(
+    intent: [
+        IntentCreated,
+        IntentStatusUpdated,
+        IntentTransition,
+        EscrowReleased,
+        ReleaseScheduled,
+        EscrowRefunded,
+        IntentCancelled,
+        TipPaid,
+        ResultSubmitted,
+        BidPlaced,
+        BidSelected,
+        BondPosted,
+        IntentTimedOut,
+        BondSettled,
+        DisputeOpened,
+        DisputeResolved,
+        RoyaltiesDeclared,
+        TeamDeclared,
+        TeamResultSubmitted,
+        TeamSettled,
+        PayloadSealed,
+        SlaSet,
+        SlaPenaltyApplied,
+        IntentRedelegated,
+        IntentClosed,
+        IntentRetried,
+        PayoutSwapSet,
+        X402PaymentSettled,
+        BridgedIntentSubmitted,
+        BridgedResultAttested,
+        InsuranceClaimResolved,
+    ],
+    other: [
+        ConfigInitialized,
+        ProgramVersionSynced,
+        AttestorUpdated,
+        OperatingModeChanged,
+        PauseToggled,
+        GovernanceUpdated,
+        ParameterUpdated,
+        FeeAllowlistUpdated,
+        InsurancePoolInitialized,
+        AgentRegistered,
+        IdentityBadgeMinted,
+        AgentUpdated,
+        AgentClosed,
+        CapacityUpdated,
+        MinPaymentUpdated,
+        EncryptionKeyRotated,
+        ModelProfileCreated,
+        ModelProfileUpdated,
+        ProfileSnapshotted,
+        PayoutVaultOpened,
+        PayoutsSwept,
+        ProfileAccessGranted,
+        ProfileAccessRevoked,
+        DelegationGranted,
+        DelegationRevoked,
+        GuardianSet,
+        AgentFrozen,
+        AgentUnfrozen,
+        ForeignEmitterUpdated,
+        RemoteAgentRegistered,
+        ProfileAttested,
+        ProfileAttestationRevoked,
+        ContextRefCreated,
+        ContextRefClosed,
+        TemplatePublished,
+        TemplateClosed,
+        SchemaPublished,
+        SchemaDeprecated,
+        ChannelOpened,
+        MessagePosted,
+        ChannelClosed,
+        BidClosed,
+        ReleasesFinalized,
+        CrankTreasuryFunded,
+        CrankTipsPaid,
+        InsuranceClaimFiled,
+        BookingCreated,
+        BookingClosed,
+        TrialStarted,
+        TrialSettled,
+        ReceiptTreeInitialized,
+        ReceiptSubmitted,
+        SpendLimitUpdated,
+        TransferDestinationsUpdated,
+        TransferExecuted,
+        BudgetUpdated,
+        BudgetSpent,
+        BudgetClosed,
+        SwapExecuted,
+        GroupCreated,
+        GroupMemberUpdated,
+        EarningsRoutingSet,
+        GroupTreasurySpent,
+        BountyPosted,
+        BountyAwarded,
+        BountyCancelled,
+        VoteApproved,
+        VoteApprovalRevoked,
+        VoteCast,
+        NameBound,
+        NameUnbound,
+        AccountMigrated,
+        RentRefunded,
+    ],
+);
+
+const DATA_PREFIX: &str = "Program data: ";
+
+/// Raw event payloads logged by the program itself, in order. Data logged
+/// by other programs in the same transaction (including CPIs out of the
+/// program) is skipped by tracking the invocation stack.
+pub fn program_data(logs: &[String]) -> Vec<Vec<u8>> {
+    let program_id = PROGRAM_ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut payloads = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = line.strip_prefix(DATA_PREFIX) {
+            if stack.last() == Some(&program_id.as_str()) {
+                if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) {
+                    payloads.push(bytes);
+                }
+            }
+        } else if let Some((program, tail)) = rest.split_once(' ') {
+            if tail.starts_with("invoke [") {
+                stack.push(program);
+            } else if tail == "success" || tail.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    payloads
+}
+
+/// Decoded program events in a transaction's logs; unknown payloads (e.g.
+/// from a newer program version) are skipped
+pub fn parse_logs(logs: &[String]) -> Vec<AgentMeshEvent> {
+    program_data(logs)
+        .iter()
+        .filter_map(|data| AgentMeshEvent::decode(data))
+        .collect()
+}
+
+/// One event delivered by [`EventStream`]
+pub struct EventNotification {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Position among the transaction's program events
+    pub index: usize,
+    pub event: AgentMeshEvent,
+}
+
+/// Options for [`EventStream::subscribe`]
+#[derive(Clone, Debug)]
+pub struct EventStreamConfig {
+    pub commitment: CommitmentConfig,
+    /// Replay transactions from this slot before following live logs
+    pub start_slot: Option<u64>,
+    /// Notifications buffered before the subscription waits on the consumer
+    pub buffer: usize,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            start_slot: None,
+            buffer: 1024,
+        }
+    }
+}
+
+/// Recently delivered signatures, so backfill and live logs don't repeat
+const SEEN_CAPACITY: usize = 4096;
+
+/// Longest wait between reconnect attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Signatures fetched per `getSignaturesForAddress` page during backfill
+const BACKFILL_PAGE: usize = 1000;
+
+/// Program events pushed over the RPC WebSocket. The background task
+/// reconnects with backoff and, after every (re)connect, replays the
+/// transactions since the last slot it delivered, so consumers see each
+/// successful transaction's events once, in slot order within a backfill.
+/// Connection errors are reported through [`EventStream::next`] and the
+/// stream keeps going.
+pub struct EventStream {
+    receiver: mpsc::Receiver<Result<EventNotification>>,
+    task: JoinHandle<()>,
+}
+
+impl EventStream {
+    pub fn subscribe(rpc_url: impl ToString, ws_url: impl ToString, config: EventStreamConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.buffer.max(1));
+        let rpc = RpcClient::new_with_commitment(rpc_url.to_string(), config.commitment);
+        let task = tokio::spawn(run(rpc, ws_url.to_string(), config, sender));
+        Self { receiver, task }
+    }
+
+    /// The next event, or an error describing a dropped connection; `None`
+    /// only after [`EventStream::close`]
+    pub async fn next(&mut self) -> Option<Result<EventNotification>> {
+        self.receiver.recv().await
+    }
+
+    pub fn close(&mut self) {
+        self.task.abort();
+        self.receiver.close();
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct Delivery {
+    sender: mpsc::Sender<Result<EventNotification>>,
+    seen: HashSet<Signature>,
+    order: VecDeque<Signature>,
+    last_slot: Option<u64>,
+}
+
+impl Delivery {
+    /// False once the consumer has gone away
+    async fn deliver(&mut self, signature: Signature, slot: u64, logs: &[String]) -> bool {
+        if !self.seen.insert(signature) {
+            return true;
+        }
+        self.order.push_back(signature);
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.last_slot = Some(self.last_slot.map_or(slot, |last| last.max(slot)));
+
+        for (index, event) in parse_logs(logs).into_iter().enumerate() {
+            let notification = EventNotification {
+                signature,
+                slot,
+                index,
+                event,
+            };
+            if self.sender.send(Ok(notification)).await.is_err() {
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn report(&self, err: ClientError) -> bool {
+        self.sender.send(Err(err)).await.is_ok()
+    }
+}
+
+async fn run(
+    rpc: RpcClient,
+    ws_url: String,
+    config: EventStreamConfig,
+    sender: mpsc::Sender<Result<EventNotification>>,
+) {
+    let mut delivery = Delivery {
+        sender,
+        seen: HashSet::new(),
+        order: VecDeque::new(),
+        last_slot: config.start_slot,
+    };
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match follow(&rpc, &ws_url, config.commitment, &mut delivery).await {
+            Ok(false) => return,
+            Ok(true) => backoff = Duration::from_secs(1),
+            Err(err) => {
+                if !delivery.report(err).await {
+                    return;
+                }
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Subscribe, backfill the gap, then follow live logs. `Ok(true)` when the
+/// subscription ended and should be reopened, `Ok(false)` when the consumer
+/// is gone.
+async fn follow(
+    rpc: &RpcClient,
+    ws_url: &str,
+    commitment: CommitmentConfig,
+    delivery: &mut Delivery,
+) -> Result<bool> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut logs, unsubscribe) = client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![PROGRAM_ID.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(commitment),
+            },
+        )
+        .await?;
+
+    // Subscribed first, so nothing lands between the backfill and live logs
+    if let Some(since) = delivery.last_slot {
+        if !backfill(rpc, commitment, since, delivery).await? {
+            return Ok(false);
+        }
+    }
+
+    while let Some(response) = logs.next().await {
+        if response.value.err.is_some() {
+            continue;
+        }
+        let Ok(signature) = response.value.signature.parse::<Signature>() else {
+            continue;
+        };
+        if !delivery
+            .deliver(signature, response.context.slot, &response.value.logs)
+            .await
+        {
+            return Ok(false);
+        }
+    }
+    unsubscribe().await;
+    Ok(true)
+}
+
+/// Deliver successful program transactions from `since` onwards, oldest first
+async fn backfill(
+    rpc: &RpcClient,
+    commitment: CommitmentConfig,
+    since: u64,
+    delivery: &mut Delivery,
+) -> Result<bool> {
+    let mut pending = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc
+            .get_signatures_for_address_with_config(
+                &PROGRAM_ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(BACKFILL_PAGE),
+                    commitment: Some(commitment),
+                },
+            )
+            .await?;
+        let done = page.len() < BACKFILL_PAGE || page.last().is_some_and(|s| s.slot < since);
+        for status in &page {
+            if status.slot >= since && status.err.is_none() {
+                if let Ok(signature) = status.signature.parse::<Signature>() {
+                    pending.push((signature, status.slot));
+                }
+            }
+        }
+        before = page.last().and_then(|s| s.signature.parse().ok());
+        if done || before.is_none() {
+            break;
+        }
+    }
+
+    for (signature, slot) in pending.into_iter().rev() {
+        if delivery.seen.contains(&signature) {
+            continue;
+        }
+        let tx = rpc
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(commitment),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await?;
+        let logs = match tx.transaction.meta.map(|meta| meta.log_messages) {
+            Some(OptionSerializer::Some(logs)) => logs,
+            _ => continue,
+        };
+        if !delivery.deliver(signature, slot, &logs).await {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
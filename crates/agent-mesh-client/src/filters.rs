@@ -0,0 +1,81 @@
+//! `getProgramAccounts` filters built from the program's layout constants,
+//! so queries follow account layout changes instead of hardcoding offsets.
+//!
+//! Combine an account-type filter with any field filters for that type:
+//!
+//! ```ignore
+//! let filters = vec![filters::intents(), filters::intent_to_agent(&agent), filters::intent_status(IntentStatus::Pending)];
+//! ```
+
+use agent_mesh::{AgentIdentity, AgentIntent, IntentStatus, ModelProfile};
+use anchor_lang::Discriminator;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, bytes))
+}
+
+/// Accounts of Anchor type `T`
+pub fn discriminator<T: Discriminator>() -> RpcFilterType {
+    memcmp(0, &T::DISCRIMINATOR)
+}
+
+// === Agents ===
+
+pub fn agents() -> RpcFilterType {
+    discriminator::<AgentIdentity>()
+}
+
+pub fn agent_owner(owner: &Pubkey) -> RpcFilterType {
+    memcmp(AgentIdentity::OWNER_WALLET_OFFSET, owner.as_ref())
+}
+
+pub fn agent_wallet(wallet: &Pubkey) -> RpcFilterType {
+    memcmp(AgentIdentity::AGENT_WALLET_OFFSET, wallet.as_ref())
+}
+
+pub fn agent_model_profile(model_profile: &Pubkey) -> RpcFilterType {
+    memcmp(AgentIdentity::MODEL_PROFILE_OFFSET, model_profile.as_ref())
+}
+
+// === Model profiles ===
+
+pub fn model_profiles() -> RpcFilterType {
+    discriminator::<ModelProfile>()
+}
+
+pub fn profile_owner(owner: &Pubkey) -> RpcFilterType {
+    memcmp(ModelProfile::OWNER_WALLET_OFFSET, owner.as_ref())
+}
+
+pub fn profile_verified(verified: bool) -> RpcFilterType {
+    memcmp(ModelProfile::VERIFIED_OFFSET, &[verified as u8])
+}
+
+pub fn profile_public(is_public: bool) -> RpcFilterType {
+    memcmp(ModelProfile::IS_PUBLIC_OFFSET, &[is_public as u8])
+}
+
+// === Intents ===
+
+pub fn intents() -> RpcFilterType {
+    discriminator::<AgentIntent>()
+}
+
+pub fn intent_from_agent(agent: &Pubkey) -> RpcFilterType {
+    memcmp(AgentIntent::FROM_AGENT_OFFSET, agent.as_ref())
+}
+
+/// Matches the original provider only; re-delegated intents keep `to_agent`
+pub fn intent_to_agent(agent: &Pubkey) -> RpcFilterType {
+    memcmp(AgentIntent::TO_AGENT_OFFSET, agent.as_ref())
+}
+
+pub fn intent_payer(payer: &Pubkey) -> RpcFilterType {
+    memcmp(AgentIntent::PAYER_OFFSET, payer.as_ref())
+}
+
+pub fn intent_status(status: IntentStatus) -> RpcFilterType {
+    memcmp(AgentIntent::STATUS_OFFSET, &[status as u8])
+}
@@ -0,0 +1,2827 @@
+//! Typed instruction builders. Each function derives the PDAs an instruction
+//! needs from the caller's keys; instructions with many optional accounts
+//! get a builder instead.
+
+use agent_mesh::{
+    accounts, instruction, ContentCommitment, Deadline, GovernanceVote, IndexRole, IntentOption, OperatingMode,
+    ProtocolParameter, RegisterOption, ResultAttestation, RoyaltySplit, SwapRoute, TeamShare, TimeSpan, X402Quote, ID,
+    TOKEN_METADATA_PROGRAM_ID,
+};
+use anchor_lang::{system_program, Id, InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// === Agents ===
+
+/// Extras for [`register_agent`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RegisterOptions {
+    /// Mint the soulbound identity badge to the agent wallet (skipped by the
+    /// program if an earlier registration already did)
+    pub badge: bool,
+    /// Update the identity if it already exists instead of failing
+    pub update_existing: bool,
+    /// Only count intents created from now on toward reputation
+    pub reset_reputation: bool,
+}
+
+impl RegisterOptions {
+    /// The program's `RegisterOption` flags
+    pub fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.update_existing {
+            flags |= RegisterOption::UPDATE_EXISTING;
+        }
+        if self.reset_reputation {
+            flags |= RegisterOption::RESET_REPUTATION;
+        }
+        flags
+    }
+}
+
+pub fn register_agent(
+    owner: &Pubkey,
+    agent_wallet: Pubkey,
+    model_profile: Pubkey,
+    metadata_uri: String,
+    permissions: u64,
+    profile_authorization: Option<Pubkey>,
+    options: RegisterOptions,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    let badge_mint = pda::badge_mint(&agent).0;
+    let with_badge = |key: Pubkey| options.badge.then_some(key);
+    build(
+        accounts::RegisterAgent {
+            agent,
+            stats: pda::agent_stats(&agent).0,
+            config: pda::config().0,
+            model_profile,
+            profile_authorization,
+            owner: *owner,
+            system_program: system_program::ID,
+            agent_wallet: with_badge(agent_wallet),
+            badge_mint: with_badge(badge_mint),
+            badge_token_account: with_badge(get_associated_token_address(&agent_wallet, &badge_mint)),
+            badge_metadata: with_badge(pda::badge_metadata(&badge_mint).0),
+            badge_edition: with_badge(pda::badge_edition(&badge_mint).0),
+            token_program: with_badge(token::ID),
+            associated_token_program: with_badge(spl_associated_token_account::ID),
+            token_metadata_program: with_badge(TOKEN_METADATA_PROGRAM_ID),
+        },
+        instruction::RegisterAgent {
+            agent_wallet,
+            model_profile,
+            metadata_uri,
+            permissions,
+            options: options.flags(),
+        },
+    )
+}
+
+/// `profile_authorization` is only needed when switching to a private profile
+/// owned by someone else
+pub fn update_agent(
+    owner: &Pubkey,
+    args: instruction::UpdateAgent,
+    profile_authorization: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::UpdateAgent {
+            agent: pda::agent(owner).0,
+            config: pda::config().0,
+            owner: *owner,
+            model_profile: args.model_profile,
+            profile_authorization,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+/// Bind `name.sol`, which `owner` must hold, to their agent
+pub fn bind_name(owner: &Pubkey, name: String) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::BindName {
+            agent_name: pda::agent_name(&agent).0,
+            agent,
+            name_record: pda::sol_name_record(&name).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::BindName { name },
+    )
+}
+
+/// `authority` is the binding's owner, or anyone once `name_record` has
+/// changed hands
+pub fn unbind_name(authority: &Pubkey, owner: &Pubkey, name_record: Pubkey) -> Instruction {
+    build(
+        accounts::UnbindName {
+            agent_name: pda::agent_name(&pda::agent(owner).0).0,
+            name_record,
+            owner: *owner,
+            authority: *authority,
+        },
+        instruction::UnbindName {},
+    )
+}
+
+/// `authority` is the agent's owner or its agent wallet
+pub fn set_capacity(
+    authority: &Pubkey,
+    owner: &Pubkey,
+    available_slots: u32,
+    queue_depth: u32,
+) -> Instruction {
+    build(
+        accounts::SetCapacity {
+            agent: pda::agent(owner).0,
+            authority: *authority,
+        },
+        instruction::SetCapacity {
+            available_slots,
+            queue_depth,
+        },
+    )
+}
+
+pub fn set_min_payment(owner: &Pubkey, min_payment: u64) -> Instruction {
+    build(
+        accounts::SetMinPayment {
+            agent: pda::agent(owner).0,
+            owner: *owner,
+        },
+        instruction::SetMinPayment { min_payment },
+    )
+}
+
+pub fn rotate_encryption_key(owner: &Pubkey, new_key: [u8; 32], overlap: i64) -> Instruction {
+    build(
+        accounts::RotateEncryptionKey {
+            agent: pda::agent(owner).0,
+            owner: *owner,
+        },
+        instruction::RotateEncryptionKey { new_key, overlap },
+    )
+}
+
+pub fn close_agent(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseAgent {
+            agent: pda::agent(owner).0,
+            config: pda::config().0,
+            owner: *owner,
+        },
+        instruction::CloseAgent {},
+    )
+}
+
+/// Open the stats of an agent registered before they were kept
+/// Relay the `RegisterAgent` bridge message in `posted_vaa` for agent
+/// `sender` on `chain`; `model_profile` and `metadata_uri` must repeat the
+/// message's
+pub fn register_remote_agent(
+    relayer: &Pubkey,
+    chain: u16,
+    sender: [u8; 32],
+    model_profile: Pubkey,
+    metadata_uri: String,
+    posted_vaa: Pubkey,
+) -> Instruction {
+    let remote_agent = pda::remote_agent(chain, &sender).0;
+    let agent = pda::agent(&remote_agent).0;
+    build(
+        accounts::RegisterRemoteAgent {
+            remote_agent,
+            agent,
+            stats: pda::agent_stats(&agent).0,
+            foreign_emitter: pda::foreign_emitter(chain).0,
+            posted_vaa,
+            config: pda::config().0,
+            model_profile,
+            relayer: *relayer,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterRemoteAgent {
+            chain,
+            sender,
+            metadata_uri,
+        },
+    )
+}
+
+pub fn open_agent_stats(payer: &Pubkey, agent: Pubkey) -> Instruction {
+    build(
+        accounts::OpenAgentStats {
+            stats: pda::agent_stats(&agent).0,
+            agent,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::OpenAgentStats {},
+    )
+}
+
+/// Lend the owner's agent's `permissions` (a subset of its own) to
+/// `delegate` until `expires`
+pub fn grant_delegation(owner: &Pubkey, delegate: Pubkey, permissions: u64, expires: Deadline) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::GrantDelegation {
+            delegation: pda::delegation(&agent, &delegate).0,
+            agent,
+            delegate,
+            config: pda::config().0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::GrantDelegation { permissions, expires },
+    )
+}
+
+pub fn revoke_delegation(owner: &Pubkey, delegate: &Pubkey) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::RevokeDelegation {
+            delegation: pda::delegation(&agent, delegate).0,
+            agent,
+            owner: *owner,
+        },
+        instruction::RevokeDelegation {},
+    )
+}
+
+/// Let `guardian` freeze the owner's agent (`Pubkey::default()` removes it)
+pub fn set_guardian(owner: &Pubkey, guardian: Pubkey) -> Instruction {
+    build(
+        accounts::SetGuardian {
+            agent: pda::agent(owner).0,
+            owner: *owner,
+        },
+        instruction::SetGuardian { guardian },
+    )
+}
+
+/// `authority` is the agent's owner or its guardian
+pub fn freeze_agent(agent: Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::FreezeAgent {
+            agent,
+            authority: *authority,
+        },
+        instruction::FreezeAgent {},
+    )
+}
+
+pub fn unfreeze_agent(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::UnfreezeAgent {
+            agent: pda::agent(owner).0,
+            owner: *owner,
+        },
+        instruction::UnfreezeAgent {},
+    )
+}
+
+/// SPL `approve` letting `delegate` fund intents it creates for the owner's
+/// agent with up to `amount` out of `token_account`
+pub fn approve_delegation(owner: &Pubkey, token_account: Pubkey, delegate: &Pubkey, amount: u64) -> Instruction {
+    token::spl_token::instruction::approve(
+        &token::ID,
+        &token_account,
+        &pda::delegation(&pda::agent(owner).0, delegate).0,
+        owner,
+        &[],
+        amount,
+    )
+    .expect("approve with no multisig signers is always valid")
+}
+
+// === Config ===
+
+/// `admin` must be the program's upgrade authority
+pub fn initialize_config(admin: &Pubkey, attestor: Pubkey) -> Instruction {
+    build(
+        accounts::InitializeConfig {
+            config: pda::config().0,
+            admin: *admin,
+            program_data: pda::program_data().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeConfig { attestor },
+    )
+}
+
+fn update_config(admin: &Pubkey) -> accounts::UpdateConfig {
+    accounts::UpdateConfig {
+        config: pda::config().0,
+        admin: *admin,
+    }
+}
+
+pub fn sync_program_version(admin: &Pubkey) -> Instruction {
+    build(update_config(admin), instruction::SyncProgramVersion {})
+}
+
+/// Meant for simulation; the version comes back as return data
+pub fn get_version() -> Instruction {
+    build(
+        accounts::GetVersion {
+            config: pda::config().0,
+        },
+        instruction::GetVersion {},
+    )
+}
+
+pub fn set_attestor(admin: &Pubkey, attestor: Pubkey) -> Instruction {
+    build(update_config(admin), instruction::SetAttestor { attestor })
+}
+
+pub fn set_operating_mode(admin: &Pubkey, mode: OperatingMode) -> Instruction {
+    build(
+        update_config(admin),
+        instruction::SetOperatingMode { mode: mode as u8 },
+    )
+}
+
+pub fn set_paused(admin: &Pubkey, paused: bool) -> Instruction {
+    build(update_config(admin), instruction::SetPaused { paused })
+}
+
+fn govern_config(authority: &Pubkey) -> accounts::GovernConfig {
+    accounts::GovernConfig {
+        config: pda::config().0,
+        authority: *authority,
+    }
+}
+
+/// `governance` must be a governance account of `realm`, both owned by
+/// `governance_program`
+pub fn set_governance(authority: &Pubkey, governance: Pubkey, realm: Pubkey, governance_program: Pubkey) -> Instruction {
+    build(
+        accounts::SetGovernance {
+            config: pda::config().0,
+            authority: *authority,
+            governance,
+            realm,
+            governance_program,
+        },
+        instruction::SetGovernance {},
+    )
+}
+
+pub fn update_parameter(authority: &Pubkey, parameter: ProtocolParameter) -> Instruction {
+    build(govern_config(authority), instruction::UpdateParameter { parameter })
+}
+
+pub fn add_fee_allowlist(admin: &Pubkey, subject: Pubkey, discount_bps: u16) -> Instruction {
+    build(
+        accounts::AddFeeAllowlist {
+            fee_allowlist: pda::fee_allowlist(&subject).0,
+            config: pda::config().0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::AddFeeAllowlist {
+            subject,
+            discount_bps,
+        },
+    )
+}
+
+pub fn remove_fee_allowlist(admin: &Pubkey, subject: &Pubkey) -> Instruction {
+    build(
+        accounts::RemoveFeeAllowlist {
+            fee_allowlist: pda::fee_allowlist(subject).0,
+            config: pda::config().0,
+            admin: *admin,
+        },
+        instruction::RemoveFeeAllowlist {},
+    )
+}
+
+pub fn register_foreign_emitter(admin: &Pubkey, chain: u16, address: [u8; 32]) -> Instruction {
+    build(
+        accounts::RegisterForeignEmitter {
+            foreign_emitter: pda::foreign_emitter(chain).0,
+            config: pda::config().0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterForeignEmitter { chain, address },
+    )
+}
+
+pub fn remove_foreign_emitter(admin: &Pubkey, chain: u16) -> Instruction {
+    build(
+        accounts::RemoveForeignEmitter {
+            foreign_emitter: pda::foreign_emitter(chain).0,
+            config: pda::config().0,
+            admin: *admin,
+        },
+        instruction::RemoveForeignEmitter {},
+    )
+}
+
+pub fn initialize_insurance_pool(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeInsurancePool {
+            insurance_pool: pda::insurance_pool(mint).0,
+            vault: pda::insurance_vault(mint).0,
+            mint: *mint,
+            config: pda::config().0,
+            admin: *admin,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeInsurancePool {},
+    )
+}
+
+// === Model Profiles ===
+
+pub fn create_model_profile(owner: &Pubkey, args: instruction::CreateModelProfile) -> Instruction {
+    build(
+        accounts::CreateModelProfile {
+            model_profile: pda::model_profile(owner, &args.profile_id).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+fn update_model_profile_accounts(owner: &Pubkey, model_profile: Pubkey) -> accounts::UpdateModelProfile {
+    accounts::UpdateModelProfile {
+        model_profile,
+        owner: *owner,
+    }
+}
+
+/// Also snapshots the new contents as version `latest_version + 1`
+pub fn update_model_profile(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    latest_version: u32,
+    args: instruction::UpdateModelProfile,
+) -> Instruction {
+    build(
+        accounts::UpdateProfileContents {
+            profile_version: pda::profile_version(&model_profile, latest_version + 1).0,
+            model_profile,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+pub fn set_payout_preference(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    payout_mint: Pubkey,
+    max_slippage_bps: u16,
+) -> Instruction {
+    build(
+        update_model_profile_accounts(owner, model_profile),
+        instruction::SetPayoutPreference {
+            payout_mint,
+            max_slippage_bps,
+        },
+    )
+}
+
+/// `requests` of 0 disables trials
+pub fn configure_trial(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    requests: u16,
+    deposit: u64,
+    mint: Pubkey,
+    duration: TimeSpan,
+) -> Instruction {
+    build(
+        update_model_profile_accounts(owner, model_profile),
+        instruction::ConfigureTrial {
+            requests,
+            deposit,
+            mint,
+            duration,
+        },
+    )
+}
+
+pub fn set_profile_public(owner: &Pubkey, model_profile: Pubkey, is_public: bool) -> Instruction {
+    build(
+        update_model_profile_accounts(owner, model_profile),
+        instruction::SetProfilePublic { is_public },
+    )
+}
+
+/// `latest_version` is the profile's current `latest_version`; the snapshot
+/// is written as the next one
+pub fn snapshot_profile(payer: &Pubkey, model_profile: Pubkey, latest_version: u32) -> Instruction {
+    build(
+        accounts::SnapshotProfile {
+            profile_version: pda::profile_version(&model_profile, latest_version + 1).0,
+            model_profile,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::SnapshotProfile {},
+    )
+}
+
+pub fn open_payout_vault(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    mint: Pubkey,
+    sweep_interval: i64,
+) -> Instruction {
+    let payout_vault = pda::payout_vault(&model_profile, &mint).0;
+    build(
+        accounts::OpenPayoutVault {
+            payout_vault,
+            vault: pda::payout_vault_tokens(&payout_vault).0,
+            model_profile,
+            mint,
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::OpenPayoutVault { sweep_interval },
+    )
+}
+
+fn sweep_accounts(
+    authority: &Pubkey,
+    model_profile: Pubkey,
+    mint: &Pubkey,
+    billing_token_account: Pubkey,
+) -> accounts::SweepPayouts {
+    let payout_vault = pda::payout_vault(&model_profile, mint).0;
+    accounts::SweepPayouts {
+        payout_vault,
+        vault: pda::payout_vault_tokens(&payout_vault).0,
+        model_profile,
+        config: pda::config().0,
+        billing_token_account,
+        authority: *authority,
+        token_program: token::ID,
+    }
+}
+
+/// Scheduled sweep; any keeper may sign
+pub fn sweep_payouts(
+    keeper: &Pubkey,
+    model_profile: Pubkey,
+    mint: &Pubkey,
+    billing_token_account: Pubkey,
+) -> Instruction {
+    build(
+        sweep_accounts(keeper, model_profile, mint, billing_token_account),
+        instruction::SweepPayouts {},
+    )
+}
+
+/// Owner-triggered sweep, ignoring the schedule
+pub fn sweep_now(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    mint: &Pubkey,
+    billing_token_account: Pubkey,
+) -> Instruction {
+    build(
+        sweep_accounts(owner, model_profile, mint, billing_token_account),
+        instruction::SweepNow {},
+    )
+}
+
+pub fn grant_profile_access(owner: &Pubkey, model_profile: Pubkey, agent: Pubkey) -> Instruction {
+    build(
+        accounts::GrantProfileAccess {
+            profile_authorization: pda::profile_authorization(&model_profile, &agent).0,
+            model_profile,
+            agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::GrantProfileAccess {},
+    )
+}
+
+pub fn revoke_profile_access(owner: &Pubkey, model_profile: Pubkey, agent: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeProfileAccess {
+            profile_authorization: pda::profile_authorization(&model_profile, agent).0,
+            model_profile,
+            owner: *owner,
+        },
+        instruction::RevokeProfileAccess {},
+    )
+}
+
+fn attest_accounts(attestor: &Pubkey, model_profile: Pubkey) -> accounts::AttestProfile {
+    accounts::AttestProfile {
+        model_profile,
+        config: pda::config().0,
+        attestor: *attestor,
+    }
+}
+
+pub fn attest_profile(attestor: &Pubkey, model_profile: Pubkey, attestation: Pubkey) -> Instruction {
+    build(
+        attest_accounts(attestor, model_profile),
+        instruction::AttestProfile { attestation },
+    )
+}
+
+pub fn revoke_attestation(attestor: &Pubkey, model_profile: Pubkey) -> Instruction {
+    build(
+        attest_accounts(attestor, model_profile),
+        instruction::RevokeAttestation {},
+    )
+}
+
+// === Shared Context ===
+
+pub fn create_context_ref(owner: &Pubkey, context_hash: [u8; 32], context_uri: String) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::CreateContextRef {
+            context: pda::context_ref(&agent, &context_hash).0,
+            config: pda::config().0,
+            agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::CreateContextRef {
+            context_hash,
+            context_uri,
+        },
+    )
+}
+
+pub fn close_context_ref(owner: &Pubkey, context: Pubkey) -> Instruction {
+    build(
+        accounts::CloseContextRef {
+            context,
+            owner: *owner,
+        },
+        instruction::CloseContextRef {},
+    )
+}
+
+// === Intent Templates ===
+
+/// `publish_template` under the owner's agent; see `IntentTemplate`
+pub fn publish_template(
+    owner: &Pubkey,
+    template_id: [u8; 16],
+    schema_hash: [u8; 32],
+    default_payment: u64,
+    completion_window: TimeSpan,
+    required_permissions: u64,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::PublishTemplate {
+            template: pda::template(&agent, &template_id).0,
+            agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::PublishTemplate {
+            template_id,
+            schema_hash,
+            default_payment,
+            completion_window,
+            required_permissions,
+        },
+    )
+}
+
+pub fn close_template(owner: &Pubkey, template: Pubkey) -> Instruction {
+    build(
+        accounts::CloseTemplate {
+            template,
+            owner: *owner,
+        },
+        instruction::CloseTemplate {},
+    )
+}
+
+// === Payload Schemas ===
+
+/// Register `schema_id` under the owner's agent with its version 1; see
+/// `SchemaRegistry`
+pub fn register_schema(
+    owner: &Pubkey,
+    schema_id: [u8; 16],
+    schema_hash: [u8; 32],
+    media_type: String,
+    schema_uri: String,
+) -> Instruction {
+    build(
+        accounts::RegisterSchema {
+            schema_registry: pda::schema_registry(&schema_id).0,
+            schema: pda::payload_schema(&schema_id, 1).0,
+            config: pda::config().0,
+            agent: pda::agent(owner).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterSchema {
+            schema_id,
+            schema_hash,
+            media_type,
+            schema_uri,
+        },
+    )
+}
+
+/// Publish `version`, which must be the registry's `latest_version + 1`
+pub fn publish_schema_version(
+    owner: &Pubkey,
+    schema_id: [u8; 16],
+    version: u32,
+    schema_hash: [u8; 32],
+    media_type: String,
+    schema_uri: String,
+) -> Instruction {
+    build(
+        accounts::PublishSchemaVersion {
+            schema_registry: pda::schema_registry(&schema_id).0,
+            schema: pda::payload_schema(&schema_id, version).0,
+            config: pda::config().0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::PublishSchemaVersion {
+            schema_hash,
+            media_type,
+            schema_uri,
+        },
+    )
+}
+
+pub fn deprecate_schema(owner: &Pubkey, schema_id: [u8; 16], version: u32) -> Instruction {
+    build(
+        accounts::DeprecateSchema {
+            schema_registry: pda::schema_registry(&schema_id).0,
+            schema: pda::payload_schema(&schema_id, version).0,
+            owner: *owner,
+        },
+        instruction::DeprecateSchema {},
+    )
+}
+
+// === Message Channels ===
+
+/// Open the channel between the owner's agent and `peer`
+pub fn open_channel(owner: &Pubkey, peer: Pubkey) -> Instruction {
+    let agent = pda::agent(owner).0;
+    let (agent_a, agent_b) = if agent < peer { (agent, peer) } else { (peer, agent) };
+    build(
+        accounts::OpenChannel {
+            channel: pda::channel(&agent, &peer).0,
+            agent_a,
+            agent_b,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::OpenChannel {},
+    )
+}
+
+pub fn post_message(owner: &Pubkey, peer: &Pubkey, message_hash: [u8; 32], message_uri: String) -> Instruction {
+    let from_agent = pda::agent(owner).0;
+    build(
+        accounts::PostMessage {
+            channel: pda::channel(&from_agent, peer).0,
+            config: pda::config().0,
+            from_agent,
+            owner: *owner,
+        },
+        instruction::PostMessage {
+            message_hash,
+            message_uri,
+        },
+    )
+}
+
+/// `rent_payer` is the channel's recorded opener
+pub fn close_channel(owner: &Pubkey, peer: &Pubkey, rent_payer: Pubkey) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::CloseChannel {
+            channel: pda::channel(&agent, peer).0,
+            agent,
+            owner: *owner,
+            rent_payer,
+        },
+        instruction::CloseChannel {},
+    )
+}
+
+// === Intents ===
+
+/// Builder for `create_intent`, `submit_signed_intent` and `settle_x402_payment`
+pub struct CreateIntentBuilder {
+    accounts: accounts::CreateIntent,
+    args: instruction::CreateIntent,
+    nonce: u64,
+}
+
+impl CreateIntentBuilder {
+    /// `nonce` is the pair's next nonce (see
+    /// [`AgentMeshClient::next_nonce`](crate::AgentMeshClient::next_nonce));
+    /// the intent only lands while it is current. Escrow defaults to the
+    /// intent PDA's associated token account for `payment_mint`. `payer`
+    /// must be the from-agent's owner or agent wallet, except for
+    /// [`delegated`](Self::delegated), signed and bridged intents.
+    pub fn new(
+        payer: &Pubkey,
+        from_agent: Pubkey,
+        to_agent: Pubkey,
+        payment_mint: Pubkey,
+        from_token_account: Pubkey,
+        nonce: u64,
+        args: instruction::CreateIntent,
+    ) -> Self {
+        let intent = pda::intent(&from_agent, &to_agent, nonce).0;
+        // Open intents are addressed to the requester's own agent
+        let open = args.options & IntentOption::OPEN_BIDDING != 0;
+        Self {
+            accounts: accounts::CreateIntent {
+                nonce_counter: pda::nonce_counter(&from_agent, &to_agent).0,
+                rate_limit: pda::intent_rate_limit(&from_agent).0,
+                sent_index: pda::intent_index(&from_agent, IndexRole::Sent).0,
+                sent_index_page: pda::intent_index_page(&from_agent, IndexRole::Sent, 0).0,
+                received_index: pda::intent_index(&to_agent, IndexRole::Received).0,
+                received_index_page: pda::intent_index_page(&to_agent, IndexRole::Received, 0).0,
+                intent,
+                config: pda::config().0,
+                from_agent,
+                to_agent,
+                from_stats: pda::agent_stats(&from_agent).0,
+                to_stats: (!open).then(|| pda::agent_stats(&to_agent).0),
+                payment_mint,
+                from_token_account,
+                escrow_token_account: pda::escrow(&intent, &payment_mint),
+                profile_version: None,
+                model_profile: None,
+                insurance_pool: None,
+                insurance_vault: None,
+                booking: None,
+                booking_vault: None,
+                trial: None,
+                context: None,
+                template: None,
+                schema: None,
+                delegation: None,
+                delegate_agent: None,
+                instructions: None,
+                remote_agent: None,
+                foreign_emitter: None,
+                posted_vaa: None,
+                budget: None,
+                payer: *payer,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            args,
+            nonce,
+        }
+    }
+
+    pub fn intent(&self) -> Pubkey {
+        self.accounts.intent
+    }
+
+    pub fn escrow(&self) -> Pubkey {
+        self.accounts.escrow_token_account
+    }
+
+    pub fn escrow_token_account(mut self, escrow: Pubkey) -> Self {
+        self.accounts.escrow_token_account = escrow;
+        self
+    }
+
+    /// Append to these pages of the agents' intent indexes (see
+    /// [`AgentMeshClient::next_index_pages`](crate::AgentMeshClient::next_index_pages));
+    /// both default to 0, which only holds for their first
+    /// `INTENT_INDEX_PAGE_LEN` intents
+    pub fn index_pages(mut self, sent_page: u32, received_page: u32) -> Self {
+        let (from_agent, to_agent) = (self.accounts.from_agent, self.accounts.to_agent);
+        self.accounts.sent_index_page = pda::intent_index_page(&from_agent, IndexRole::Sent, sent_page).0;
+        self.accounts.received_index_page = pda::intent_index_page(&to_agent, IndexRole::Received, received_page).0;
+        self
+    }
+
+    /// Pin the intent to a snapshot of the provider's profile; an older
+    /// version can't be cheaper than the profile's current pricing
+    pub fn profile_version(mut self, model_profile: &Pubkey, version: u32) -> Self {
+        self.accounts.profile_version = Some(pda::profile_version(model_profile, version).0);
+        self.accounts.model_profile = Some(*model_profile);
+        self
+    }
+
+    /// Required when the intent is created with `IntentOption::INSURED`
+    pub fn insured(mut self) -> Self {
+        let mint = self.accounts.payment_mint;
+        self.accounts.insurance_pool = Some(pda::insurance_pool(&mint).0);
+        self.accounts.insurance_vault = Some(pda::insurance_vault(&mint).0);
+        self
+    }
+
+    /// Draw from a capacity booking between the same agents
+    pub fn booking(mut self, nonce: u64, booking_vault: Pubkey) -> Self {
+        let booking = pda::booking(&self.accounts.from_agent, &self.accounts.to_agent, nonce).0;
+        self.accounts.booking = Some(booking);
+        self.accounts.booking_vault = Some(booking_vault);
+        self
+    }
+
+    /// Use one of the from-agent's free trial requests on the provider's
+    /// profile (the payment amount must be 0)
+    pub fn trial(mut self, model_profile: &Pubkey) -> Self {
+        self.accounts.trial = Some(pda::trial(model_profile, &self.accounts.from_agent).0);
+        self
+    }
+
+    /// Reference shared context committed with `create_context_ref`
+    pub fn context(mut self, context: Pubkey) -> Self {
+        self.accounts.context = Some(context);
+        self
+    }
+
+    /// Stamp the intent from a published `IntentTemplate`; build it with
+    /// [`template_instruction`](Self::template_instruction)
+    pub fn template(mut self, template: Pubkey) -> Self {
+        self.accounts.template = Some(template);
+        self
+    }
+
+    /// Declare the registered schema version the payload follows
+    pub fn schema(mut self, schema: Pubkey) -> Self {
+        self.accounts.schema = Some(schema);
+        self
+    }
+
+    /// Create the intent as a delegate of the from-agent under a
+    /// `grant_delegation` to the payer's agent. `from_token_account` may then
+    /// be the grantor's, drawn through its SPL approval of the delegation PDA.
+    pub fn delegated(mut self) -> Self {
+        let delegate_agent = pda::agent(&self.accounts.payer).0;
+        self.accounts.delegation = Some(pda::delegation(&self.accounts.from_agent, &delegate_agent).0);
+        self.accounts.delegate_agent = Some(delegate_agent);
+        self
+    }
+
+    /// Pay from the from-agent's budget for the payment mint instead of
+    /// `from_token_account`; the payer must be the agent or owner wallet
+    pub fn budget(mut self) -> Self {
+        let (from_agent, mint) = (self.accounts.from_agent, self.accounts.payment_mint);
+        self.accounts.budget = Some(pda::budget(&from_agent, &mint).0);
+        self.accounts.from_token_account = pda::budget_vault(&from_agent, &mint);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        build(self.accounts, self.args)
+    }
+
+    /// `create_intent_from_template`; `payment_amount` of `None` takes the
+    /// template's default instead of the builder's payment amount
+    pub fn template_instruction(self, payment_amount: Option<u64>) -> Instruction {
+        let args = self.args;
+        build(
+            self.accounts,
+            instruction::CreateIntentFromTemplate {
+                payload_uri: args.payload_uri,
+                payload: args.payload,
+                payment_amount,
+                options: args.options,
+                tip_amount: args.tip_amount,
+            },
+        )
+    }
+
+    /// `submit_signed_intent`; the ed25519 verify instruction over
+    /// `SignedIntent::to_message` must be placed immediately before it
+    pub fn signed_instruction(mut self, expires: Deadline) -> Instruction {
+        self.accounts.instructions = Some(sysvar::instructions::ID);
+        let args = self.args;
+        build(
+            self.accounts,
+            instruction::SubmitSignedIntent {
+                payload_uri: args.payload_uri,
+                payload: args.payload,
+                payment_amount: args.payment_amount,
+                options: args.options,
+                expires,
+            },
+        )
+    }
+
+    /// `submit_bridged_intent` for a from-agent registered with
+    /// [`register_remote_agent`] as `sender` on `chain`; `posted_vaa` holds
+    /// the `CreateIntent` message, which must match the builder's arguments.
+    /// Tips are ignored.
+    pub fn bridged_instruction(mut self, chain: u16, sender: &[u8; 32], posted_vaa: Pubkey) -> Instruction {
+        self.accounts.remote_agent = Some(pda::remote_agent(chain, sender).0);
+        self.accounts.foreign_emitter = Some(pda::foreign_emitter(chain).0);
+        self.accounts.posted_vaa = Some(posted_vaa);
+        build(
+            self.accounts,
+            instruction::SubmitBridgedIntent {
+                payload_uri: self.args.payload_uri,
+            },
+        )
+    }
+
+    /// The x402 quote this intent settles: the builder's nonce, payload hash
+    /// (as the resource hash) and payment amount as the provider priced them
+    pub fn x402_quote(&self, expires: Deadline) -> X402Quote {
+        X402Quote {
+            program_id: ID,
+            from_agent: self.accounts.from_agent,
+            to_agent: self.accounts.to_agent,
+            nonce: self.nonce,
+            resource_hash: self.args.payload.hash,
+            payment_mint: self.accounts.payment_mint,
+            amount: self.args.payment_amount,
+            expires,
+        }
+    }
+
+    /// `settle_x402_payment`; the ed25519 verify instruction over the
+    /// provider's signature of [`x402_quote`](Self::x402_quote) must be
+    /// placed immediately before it. Options are ignored.
+    pub fn x402_instruction(mut self, expires: Deadline) -> Instruction {
+        self.accounts.instructions = Some(sysvar::instructions::ID);
+        let args = self.args;
+        build(
+            self.accounts,
+            instruction::SettleX402Payment {
+                payload_uri: args.payload_uri,
+                resource_hash: args.payload.hash,
+                amount: args.payment_amount,
+                expires,
+            },
+        )
+    }
+}
+
+/// Builder for `retry_intent`
+pub struct RetryIntentBuilder {
+    accounts: accounts::RetryIntent,
+    args: instruction::RetryIntent,
+}
+
+impl RetryIntentBuilder {
+    /// Retry the owner's failed `previous_intent` with `to_agent` (its
+    /// provider, or another agent) at the pair's next `nonce`. Escrow
+    /// defaults to the new intent PDA's associated token account for
+    /// `payment_mint`, which must be the failed intent's.
+    pub fn new(
+        owner: &Pubkey,
+        previous_intent: Pubkey,
+        previous_escrow: Pubkey,
+        to_agent: Pubkey,
+        payment_mint: Pubkey,
+        nonce: u64,
+        args: instruction::RetryIntent,
+    ) -> Self {
+        let from_agent = pda::agent(owner).0;
+        let intent = pda::intent(&from_agent, &to_agent, nonce).0;
+        Self {
+            accounts: accounts::RetryIntent {
+                previous_intent,
+                previous_escrow,
+                nonce_counter: pda::nonce_counter(&from_agent, &to_agent).0,
+                rate_limit: pda::intent_rate_limit(&from_agent).0,
+                sent_index: pda::intent_index(&from_agent, IndexRole::Sent).0,
+                sent_index_page: pda::intent_index_page(&from_agent, IndexRole::Sent, 0).0,
+                received_index: pda::intent_index(&to_agent, IndexRole::Received).0,
+                received_index_page: pda::intent_index_page(&to_agent, IndexRole::Received, 0).0,
+                intent,
+                escrow_token_account: pda::escrow(&intent, &payment_mint),
+                config: pda::config().0,
+                from_agent,
+                to_agent,
+                from_stats: pda::agent_stats(&from_agent).0,
+                to_stats: pda::agent_stats(&to_agent).0,
+                from_token_account: None,
+                owner: *owner,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            args,
+        }
+    }
+
+    pub fn intent(&self) -> Pubkey {
+        self.accounts.intent
+    }
+
+    pub fn escrow(&self) -> Pubkey {
+        self.accounts.escrow_token_account
+    }
+
+    /// Append to these pages of the agents' intent indexes, as for
+    /// [`CreateIntentBuilder::index_pages`]
+    pub fn index_pages(mut self, sent_page: u32, received_page: u32) -> Self {
+        let (from_agent, to_agent) = (self.accounts.from_agent, self.accounts.to_agent);
+        self.accounts.sent_index_page = pda::intent_index_page(&from_agent, IndexRole::Sent, sent_page).0;
+        self.accounts.received_index_page = pda::intent_index_page(&to_agent, IndexRole::Received, received_page).0;
+        self
+    }
+
+    /// The payer's token account, owned by the owner, topping up whatever
+    /// the carried escrow doesn't cover
+    pub fn top_up(mut self, from_token_account: Pubkey) -> Self {
+        self.accounts.from_token_account = Some(from_token_account);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        build(self.accounts, self.args)
+    }
+}
+
+/// Builder for `update_intent_status`
+pub struct UpdateIntentStatusBuilder {
+    accounts: accounts::UpdateIntentStatus,
+    args: instruction::UpdateIntentStatus,
+    royalty_accounts: Vec<AccountMeta>,
+    swap_accounts: Vec<AccountMeta>,
+}
+
+impl UpdateIntentStatusBuilder {
+    /// `to_agent` is the assignee (the delegate once re-delegated)
+    pub fn new(owner: &Pubkey, intent: Pubkey, to_agent: Pubkey, new_status: u8) -> Self {
+        Self {
+            accounts: accounts::UpdateIntentStatus {
+                intent,
+                config: pda::config().0,
+                to_agent,
+                assignee_stats: pda::agent_stats(&to_agent).0,
+                owner: *owner,
+                escrow_token_account: None,
+                billing_token_account: None,
+                delegate_billing_token_account: None,
+                model_profile: None,
+                fee_token_account: None,
+                payout_vault: None,
+                fee_allowlist: None,
+                swap_program: None,
+                royalties: None,
+                refund_token_account: None,
+                booking: None,
+                booking_vault: None,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            args: instruction::UpdateIntentStatus {
+                new_status,
+                result: None,
+                result_uri: None,
+                swap: None,
+            },
+            royalty_accounts: Vec::new(),
+            swap_accounts: Vec::new(),
+        }
+    }
+
+    /// Required when completing; see [`crate::commitment::commit`]
+    pub fn result(mut self, result: ContentCommitment, result_uri: String) -> Self {
+        self.args.result = Some(result);
+        self.args.result_uri = Some(result_uri);
+        self
+    }
+
+    /// Accounts needed to release the escrow on completion, or to pay the
+    /// tip on acceptance
+    pub fn release(mut self, escrow: Pubkey, billing_token_account: Pubkey) -> Self {
+        self.accounts.escrow_token_account = Some(escrow);
+        self.accounts.billing_token_account = Some(billing_token_account);
+        self
+    }
+
+    /// Payer's token account; required when completing past the intent's SLA
+    pub fn sla_refund(mut self, refund_token_account: Pubkey) -> Self {
+        self.accounts.refund_token_account = Some(refund_token_account);
+        self
+    }
+
+    /// Accounts needed to refund the escrow when failing the intent;
+    /// `booking` is the booking and its vault, for an intent that drew on a
+    /// booking deposit
+    pub fn refund(mut self, escrow: Pubkey, refund_token_account: Pubkey, booking: Option<(Pubkey, Pubkey)>) -> Self {
+        self.accounts.escrow_token_account = Some(escrow);
+        self.accounts.refund_token_account = Some(refund_token_account);
+        self.accounts.booking = booking.map(|(booking, _)| booking);
+        self.accounts.booking_vault = booking.map(|(_, vault)| vault);
+        self
+    }
+
+    pub fn delegate_billing(mut self, delegate_billing_token_account: Pubkey) -> Self {
+        self.accounts.delegate_billing_token_account = Some(delegate_billing_token_account);
+        self
+    }
+
+    /// Treasury token account; required when a protocol fee applies
+    pub fn fee_token_account(mut self, fee_token_account: Pubkey) -> Self {
+        self.accounts.fee_token_account = Some(fee_token_account);
+        self
+    }
+
+    /// Apply a fee allowlist entry for the given subject
+    pub fn fee_allowlist(mut self, subject: &Pubkey) -> Self {
+        self.accounts.fee_allowlist = Some(pda::fee_allowlist(subject).0);
+        self
+    }
+
+    /// Count the payout toward a payout vault's `accrued` tally
+    pub fn payout_vault(mut self, model_profile: &Pubkey, mint: &Pubkey) -> Self {
+        let payout_vault = pda::payout_vault(model_profile, mint).0;
+        self.accounts.payout_vault = Some(payout_vault);
+        self.accounts.billing_token_account = Some(pda::payout_vault_tokens(&payout_vault).0);
+        self
+    }
+
+    /// Convert the payout through an allow-listed swap program; the route's
+    /// accounts are appended as remaining accounts. `model_profile` only matters when the intent has
+    /// no payout mint of its own (see [`set_payout_swap`]).
+    pub fn swap(
+        mut self,
+        model_profile: Pubkey,
+        swap_program: Pubkey,
+        route: SwapRoute,
+        route_accounts: Vec<AccountMeta>,
+    ) -> Self {
+        self.accounts.model_profile = Some(model_profile);
+        self.accounts.swap_program = Some(swap_program);
+        self.args.swap = Some(route);
+        self.swap_accounts = route_accounts;
+        self
+    }
+
+    /// Pay the intent's declared royalties; `recipient_token_accounts` must
+    /// follow the declaration's order
+    pub fn royalties(mut self, recipient_token_accounts: &[Pubkey]) -> Self {
+        self.accounts.royalties = Some(pda::royalties(&self.accounts.intent).0);
+        self.royalty_accounts = recipient_token_accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect();
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let mut ix = build(self.accounts, self.args);
+        ix.accounts.extend(self.royalty_accounts);
+        ix.accounts.extend(self.swap_accounts);
+        ix
+    }
+}
+
+/// `accept_intent`; `tip` is the escrow and the owner's token account,
+/// needed when the intent carries a tip
+pub fn accept_intent(owner: &Pubkey, intent: Pubkey, to_agent: Pubkey, tip: Option<(Pubkey, Pubkey)>) -> Instruction {
+    accept_intent_with(owner, intent, to_agent, tip, None)
+}
+
+/// `accept_intent` that fails unless the intent references `schema`, a
+/// `PayloadSchema` version the provider can handle
+pub fn accept_intent_with_schema(
+    owner: &Pubkey,
+    intent: Pubkey,
+    to_agent: Pubkey,
+    tip: Option<(Pubkey, Pubkey)>,
+    schema: Pubkey,
+) -> Instruction {
+    accept_intent_with(owner, intent, to_agent, tip, Some(schema))
+}
+
+fn accept_intent_with(
+    owner: &Pubkey,
+    intent: Pubkey,
+    to_agent: Pubkey,
+    tip: Option<(Pubkey, Pubkey)>,
+    schema: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::AcceptIntent {
+            intent,
+            config: pda::config().0,
+            to_agent,
+            owner: *owner,
+            escrow_token_account: tip.map(|(escrow, _)| escrow),
+            billing_token_account: tip.map(|(_, billing)| billing),
+            schema,
+            token_program: token::ID,
+        },
+        instruction::AcceptIntent {},
+    )
+}
+
+/// Have the payout of an accepted intent swapped into `payout_mint` at
+/// settlement, as the assignee's owner; `Pubkey::default()` undoes it
+pub fn set_payout_swap(owner: &Pubkey, intent: Pubkey, payout_mint: Pubkey, max_slippage_bps: u16) -> Instruction {
+    build(
+        accounts::SetPayoutSwap { intent, owner: *owner },
+        instruction::SetPayoutSwap {
+            payout_mint,
+            max_slippage_bps,
+        },
+    )
+}
+
+pub fn submit_result(owner: &Pubkey, intent: Pubkey, result: ContentCommitment, result_uri: String) -> Instruction {
+    submit_result_with(owner, intent, result, result_uri, None)
+}
+
+/// `submit_result` attested by the assignee's agent wallet; the ed25519
+/// verify instruction over [`ResultAttestation::to_message`] must be placed
+/// immediately before it
+pub fn submit_attested_result(
+    owner: &Pubkey,
+    intent: Pubkey,
+    result: ContentCommitment,
+    result_uri: String,
+) -> Instruction {
+    submit_result_with(owner, intent, result, result_uri, Some(sysvar::instructions::ID))
+}
+
+fn submit_result_with(
+    owner: &Pubkey,
+    intent: Pubkey,
+    result: ContentCommitment,
+    result_uri: String,
+    instructions: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::SubmitResult {
+            intent,
+            config: pda::config().0,
+            assignee_agent: pda::agent(owner).0,
+            instructions,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitResult { result, result_uri },
+    )
+}
+
+/// The message an agent wallet signs to attest `result_hash` for `intent`
+pub fn result_attestation(intent: Pubkey, result_hash: [u8; 32]) -> ResultAttestation {
+    ResultAttestation {
+        program_id: ID,
+        intent,
+        result_hash,
+    }
+}
+
+/// Builder for `settle_intent`, the slim completion of an accepted intent
+/// whose result was submitted with [`submit_result`]
+pub struct SettleIntentBuilder {
+    accounts: accounts::SettleIntent,
+    route: Option<SwapRoute>,
+    royalty_accounts: Vec<AccountMeta>,
+    swap_accounts: Vec<AccountMeta>,
+}
+
+impl SettleIntentBuilder {
+    /// `owner` is the assignee's owner that accepted the intent
+    pub fn new(owner: &Pubkey, intent: Pubkey) -> Self {
+        let assignee_agent = pda::agent(owner).0;
+        Self {
+            accounts: accounts::SettleIntent {
+                intent,
+                config: pda::config().0,
+                assignee_agent,
+                assignee_stats: pda::agent_stats(&assignee_agent).0,
+                owner: *owner,
+                escrow_token_account: None,
+                billing_token_account: None,
+                delegate_billing_token_account: None,
+                fee_token_account: None,
+                payout_vault: None,
+                fee_allowlist: None,
+                royalties: None,
+                refund_token_account: None,
+                swap_program: None,
+                model_profile: None,
+                token_program: token::ID,
+            },
+            route: None,
+            royalty_accounts: Vec::new(),
+            swap_accounts: Vec::new(),
+        }
+    }
+
+    /// Required unless the payment is zero
+    pub fn release(mut self, escrow: Pubkey, billing_token_account: Pubkey) -> Self {
+        self.accounts.escrow_token_account = Some(escrow);
+        self.accounts.billing_token_account = Some(billing_token_account);
+        self
+    }
+
+    /// Payer's token account; required when completing past the intent's SLA
+    pub fn sla_refund(mut self, refund_token_account: Pubkey) -> Self {
+        self.accounts.refund_token_account = Some(refund_token_account);
+        self
+    }
+
+    pub fn delegate_billing(mut self, delegate_billing_token_account: Pubkey) -> Self {
+        self.accounts.delegate_billing_token_account = Some(delegate_billing_token_account);
+        self
+    }
+
+    /// Treasury token account; required when a protocol fee applies
+    pub fn fee_token_account(mut self, fee_token_account: Pubkey) -> Self {
+        self.accounts.fee_token_account = Some(fee_token_account);
+        self
+    }
+
+    /// Apply a fee allowlist entry for the given subject
+    pub fn fee_allowlist(mut self, subject: &Pubkey) -> Self {
+        self.accounts.fee_allowlist = Some(pda::fee_allowlist(subject).0);
+        self
+    }
+
+    /// Count the payout toward a payout vault's `accrued` tally
+    pub fn payout_vault(mut self, model_profile: &Pubkey, mint: &Pubkey) -> Self {
+        let payout_vault = pda::payout_vault(model_profile, mint).0;
+        self.accounts.payout_vault = Some(payout_vault);
+        self.accounts.billing_token_account = Some(pda::payout_vault_tokens(&payout_vault).0);
+        self
+    }
+
+    /// Pay the intent's declared royalties; `recipient_token_accounts` must
+    /// follow the declaration's order
+    pub fn royalties(mut self, recipient_token_accounts: &[Pubkey]) -> Self {
+        self.accounts.royalties = Some(pda::royalties(&self.accounts.intent).0);
+        self.royalty_accounts = recipient_token_accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect();
+        self
+    }
+
+    /// Settle with `settle_intent_with_swap`, converting the payout into the
+    /// payout mint of the intent or else of `model_profile`
+    /// (`billing_token_account` must hold it); the route's accounts are
+    /// appended as remaining accounts
+    pub fn swap(
+        mut self,
+        model_profile: Pubkey,
+        swap_program: Pubkey,
+        route: SwapRoute,
+        route_accounts: Vec<AccountMeta>,
+    ) -> Self {
+        self.accounts.model_profile = Some(model_profile);
+        self.accounts.swap_program = Some(swap_program);
+        self.route = Some(route);
+        self.swap_accounts = route_accounts;
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let mut ix = match self.route {
+            Some(route) => build(self.accounts, instruction::SettleIntentWithSwap { route }),
+            None => build(self.accounts, instruction::SettleIntent {}),
+        };
+        ix.accounts.extend(self.royalty_accounts);
+        ix.accounts.extend(self.swap_accounts);
+        ix
+    }
+}
+
+/// Builder for `complete_bridged_intent`
+pub struct CompleteBridgedIntentBuilder {
+    accounts: accounts::CompleteBridgedIntent,
+    royalty_accounts: Vec<AccountMeta>,
+}
+
+impl CompleteBridgedIntentBuilder {
+    /// `sender` is the assignee's address on `chain`, and `posted_vaa` holds
+    /// its `CompleteIntent` message
+    pub fn new(intent: Pubkey, chain: u16, sender: &[u8; 32], posted_vaa: Pubkey) -> Self {
+        let remote_agent = pda::remote_agent(chain, sender).0;
+        let assignee_agent = pda::agent(&remote_agent).0;
+        Self {
+            accounts: accounts::CompleteBridgedIntent {
+                intent,
+                config: pda::config().0,
+                assignee_agent,
+                assignee_stats: pda::agent_stats(&assignee_agent).0,
+                remote_agent,
+                foreign_emitter: pda::foreign_emitter(chain).0,
+                posted_vaa,
+                escrow_token_account: None,
+                billing_token_account: None,
+                fee_token_account: None,
+                payout_vault: None,
+                fee_allowlist: None,
+                royalties: None,
+                refund_token_account: None,
+                token_program: token::ID,
+            },
+            royalty_accounts: Vec::new(),
+        }
+    }
+
+    /// Required unless the payment and tip are zero; `payout` is the
+    /// message's payout account
+    pub fn release(mut self, escrow: Pubkey, payout: Pubkey) -> Self {
+        self.accounts.escrow_token_account = Some(escrow);
+        self.accounts.billing_token_account = Some(payout);
+        self
+    }
+
+    /// Payer's token account; required when completing past the intent's SLA
+    pub fn sla_refund(mut self, refund_token_account: Pubkey) -> Self {
+        self.accounts.refund_token_account = Some(refund_token_account);
+        self
+    }
+
+    /// Treasury token account; required when a protocol fee applies
+    pub fn fee_token_account(mut self, fee_token_account: Pubkey) -> Self {
+        self.accounts.fee_token_account = Some(fee_token_account);
+        self
+    }
+
+    /// Apply a fee allowlist entry for the given subject
+    pub fn fee_allowlist(mut self, subject: &Pubkey) -> Self {
+        self.accounts.fee_allowlist = Some(pda::fee_allowlist(subject).0);
+        self
+    }
+
+    /// Count the payout toward a payout vault's `accrued` tally; the
+    /// message's payout must be the vault's token account
+    pub fn payout_vault(mut self, model_profile: &Pubkey, mint: &Pubkey) -> Self {
+        self.accounts.payout_vault = Some(pda::payout_vault(model_profile, mint).0);
+        self
+    }
+
+    /// Pay the intent's declared royalties; `recipient_token_accounts` must
+    /// follow the declaration's order
+    pub fn royalties(mut self, recipient_token_accounts: &[Pubkey]) -> Self {
+        self.accounts.royalties = Some(pda::royalties(&self.accounts.intent).0);
+        self.royalty_accounts = recipient_token_accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect();
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let mut ix = build(self.accounts, instruction::CompleteBridgedIntent {});
+        ix.accounts.extend(self.royalty_accounts);
+        ix
+    }
+}
+
+/// `penalty` is the provider agent and its owner's token account, needed
+/// when the requester cancels after the cancellation window; `booking` is the
+/// booking and its vault, needed when the intent drew on a booking deposit
+pub fn cancel_intent(
+    owner: &Pubkey,
+    agent: Pubkey,
+    intent: Pubkey,
+    escrow: Option<Pubkey>,
+    refund_token_account: Option<Pubkey>,
+    penalty: Option<(Pubkey, Pubkey)>,
+    booking: Option<(Pubkey, Pubkey)>,
+) -> Instruction {
+    build(
+        accounts::CancelIntent {
+            intent,
+            config: pda::config().0,
+            agent,
+            owner: *owner,
+            escrow_token_account: escrow,
+            refund_token_account,
+            to_agent: penalty.map(|(to_agent, _)| to_agent),
+            penalty_token_account: penalty.map(|(_, account)| account),
+            booking: booking.map(|(booking, _)| booking),
+            booking_vault: booking.map(|(_, vault)| vault),
+            token_program: token::ID,
+        },
+        instruction::CancelIntent {},
+    )
+}
+
+/// Bid on an open intent, as the provider agent's owner
+pub fn place_bid(owner: &Pubkey, intent: Pubkey, agent: Pubkey, price: u64, eta: i64) -> Instruction {
+    build(
+        accounts::PlaceBid {
+            intent,
+            config: pda::config().0,
+            bid: pda::bid(&intent, &agent).0,
+            agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::PlaceBid { price, eta },
+    )
+}
+
+/// Award an open intent to `bidder`'s bid, as the requester's owner or (for
+/// the lowest bid, once bidding has closed) anyone
+pub fn select_bid(
+    authority: &Pubkey,
+    intent: Pubkey,
+    from_agent: Pubkey,
+    bidder: &Pubkey,
+    escrow: Pubkey,
+    refund_token_account: Pubkey,
+) -> Instruction {
+    build(
+        accounts::SelectBid {
+            intent,
+            config: pda::config().0,
+            bid: pda::bid(&intent, bidder).0,
+            from_agent,
+            authority: *authority,
+            escrow_token_account: escrow,
+            refund_token_account,
+            token_program: token::ID,
+        },
+        instruction::SelectBid {},
+    )
+}
+
+pub fn close_bid(owner: &Pubkey, intent: Pubkey, agent: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseBid {
+            bid: pda::bid(&intent, agent).0,
+            intent,
+            owner: *owner,
+        },
+        instruction::CloseBid {},
+    )
+}
+
+/// Post the acceptance bond on a pending intent, as the assignee's owner
+pub fn post_bond(
+    owner: &Pubkey,
+    intent: Pubkey,
+    to_agent: Pubkey,
+    payment_mint: Pubkey,
+    owner_token_account: Pubkey,
+) -> Instruction {
+    build(
+        accounts::PostBond {
+            intent,
+            config: pda::config().0,
+            to_agent,
+            bond_vault: pda::bond_vault(&intent).0,
+            payment_mint,
+            owner_token_account,
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::PostBond {},
+    )
+}
+
+/// Fail an intent past its completion deadline. `assignee` is the intent's
+/// assignee agent; `bond_payer` is needed when a bond was posted,
+/// `cranker_token_account` when the bond pays a tip, and `booking` (the
+/// booking and its vault) when the intent drew on a booking deposit.
+pub fn flag_timeout(
+    cranker: &Pubkey,
+    intent: Pubkey,
+    assignee: &Pubkey,
+    escrow: Pubkey,
+    refund_token_account: Pubkey,
+    bond_payer: Option<Pubkey>,
+    cranker_token_account: Option<Pubkey>,
+    booking: Option<(Pubkey, Pubkey)>,
+) -> Instruction {
+    build(
+        accounts::FlagTimeout {
+            intent,
+            config: pda::config().0,
+            assignee_stats: pda::agent_stats(assignee).0,
+            escrow_token_account: escrow,
+            refund_token_account,
+            bond_vault: bond_payer.map(|_| pda::bond_vault(&intent).0),
+            bond_payer,
+            cranker_token_account,
+            booking: booking.map(|(booking, _)| booking),
+            booking_vault: booking.map(|(_, vault)| vault),
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::FlagTimeout {},
+    )
+}
+
+/// Pay out a finished intent's bond to `recipient_token_account`: the
+/// poster's if the intent completed or was cancelled, the payer's if it failed
+pub fn settle_bond(intent: Pubkey, recipient_token_account: Pubkey, bond_payer: Pubkey) -> Instruction {
+    build(
+        accounts::SettleBond {
+            intent,
+            config: pda::config().0,
+            bond_vault: pda::bond_vault(&intent).0,
+            recipient_token_account,
+            bond_payer,
+            token_program: token::ID,
+        },
+        instruction::SettleBond {},
+    )
+}
+
+/// Close a finished intent and its emptied escrow. `royalty_owner` and
+/// `team_owner` close the intent's royalty and team declarations too,
+/// returning their rent to the requester that declared them
+pub fn close_intent(
+    intent: Pubkey,
+    rent_payer: Pubkey,
+    escrow: Pubkey,
+    royalty_owner: Option<Pubkey>,
+    team_owner: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::CloseIntent {
+            intent,
+            config: pda::config().0,
+            escrow_token_account: escrow,
+            rent_payer,
+            royalties: royalty_owner.map(|_| pda::royalties(&intent).0),
+            royalty_owner,
+            team: team_owner.map(|_| pda::team(&intent).0),
+            team_owner,
+            token_program: token::ID,
+        },
+        instruction::CloseIntent {},
+    )
+}
+
+/// Declare royalty splits on a pending intent, as its requester's owner
+pub fn declare_royalties(
+    owner: &Pubkey,
+    intent: Pubkey,
+    from_agent: Pubkey,
+    schema_hash: [u8; 32],
+    splits: Vec<RoyaltySplit>,
+) -> Instruction {
+    build(
+        accounts::DeclareRoyalties {
+            royalties: pda::royalties(&intent).0,
+            intent,
+            from_agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::DeclareRoyalties { schema_hash, splits },
+    )
+}
+
+/// Split a pending intent across a team of executing agents, as its
+/// requester's owner
+pub fn declare_team(
+    owner: &Pubkey,
+    intent: Pubkey,
+    from_agent: Pubkey,
+    members: Vec<TeamShare>,
+    quorum: u8,
+) -> Instruction {
+    build(
+        accounts::DeclareTeam {
+            team: pda::team(&intent).0,
+            intent,
+            from_agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::DeclareTeam { members, quorum },
+    )
+}
+
+/// Submit a team member's result, as that member's owner
+pub fn submit_team_result(
+    owner: &Pubkey,
+    intent: Pubkey,
+    agent: Pubkey,
+    payout_token_account: Pubkey,
+    result: ContentCommitment,
+    result_uri: String,
+) -> Instruction {
+    build(
+        accounts::SubmitTeamResult {
+            intent,
+            config: pda::config().0,
+            team: pda::team(&intent).0,
+            agent,
+            owner: *owner,
+            payout_token_account,
+        },
+        instruction::SubmitTeamResult { result, result_uri },
+    )
+}
+
+/// Settle a team intent that reached its quorum. `provider` is the agent
+/// that accepted for the team; `payouts` are the submitting members' payout
+/// token accounts in team order; `refund` is needed when some members didn't
+/// submit, `fee_token_account` when the protocol charges a fee.
+pub fn settle_team_intent(
+    intent: Pubkey,
+    provider: &Pubkey,
+    escrow: Pubkey,
+    payouts: &[Pubkey],
+    refund_token_account: Option<Pubkey>,
+    fee_token_account: Option<Pubkey>,
+) -> Instruction {
+    let mut ix = build(
+        accounts::SettleTeamIntent {
+            intent,
+            config: pda::config().0,
+            team: pda::team(&intent).0,
+            assignee_stats: pda::agent_stats(provider).0,
+            escrow_token_account: escrow,
+            fee_token_account,
+            refund_token_account,
+            token_program: token::ID,
+        },
+        instruction::SettleTeamIntent {},
+    );
+    ix.accounts
+        .extend(payouts.iter().map(|account| AccountMeta::new(*account, false)));
+    ix
+}
+
+/// Set a pending intent's SLA, as its requester's owner
+pub fn set_sla(owner: &Pubkey, intent: Pubkey, from_agent: Pubkey, target: i64, penalty_bps: u16) -> Instruction {
+    build(
+        accounts::SetSla {
+            intent,
+            from_agent,
+            owner: *owner,
+        },
+        instruction::SetSla { target, penalty_bps },
+    )
+}
+
+/// Record a [`SealedPayload`](crate::seal::SealedPayload) on a pending intent
+pub fn seal_payload(
+    owner: &Pubkey,
+    intent: Pubkey,
+    from_agent: Pubkey,
+    to_agent: Pubkey,
+    sealed: &crate::seal::SealedPayload,
+) -> Instruction {
+    build(
+        accounts::SealPayload {
+            intent,
+            from_agent,
+            to_agent,
+            owner: *owner,
+        },
+        instruction::SealPayload {
+            sealed_to: sealed.sealed_to,
+            ephemeral_pubkey: sealed.ephemeral_pubkey,
+            encrypted_key: sealed.encrypted_key,
+        },
+    )
+}
+
+/// One auto-release intent for `finalize_ready_releases`
+pub struct ReadyRelease {
+    pub intent: Pubkey,
+    pub escrow: Pubkey,
+    pub release_account: Pubkey,
+    /// Defaults to `release_account` when the intent has no delegate
+    pub delegate_release_account: Option<Pubkey>,
+}
+
+pub fn finalize_ready_releases(
+    keeper: &Pubkey,
+    fee_token_account: Option<Pubkey>,
+    releases: &[ReadyRelease],
+) -> Instruction {
+    let mut ix = build(
+        accounts::FinalizeReadyReleases {
+            config: pda::config().0,
+            fee_token_account,
+            keeper: *keeper,
+            token_program: token::ID,
+        },
+        instruction::FinalizeReadyReleases {},
+    );
+    for release in releases {
+        ix.accounts.extend([
+            AccountMeta::new(release.intent, false),
+            AccountMeta::new(release.escrow, false),
+            AccountMeta::new(release.release_account, false),
+            AccountMeta::new(
+                release.delegate_release_account.unwrap_or(release.release_account),
+                false,
+            ),
+        ]);
+    }
+    ix
+}
+
+/// One timed-out intent for `process_expirations`
+pub struct Expiration {
+    pub intent: Pubkey,
+    /// The intent's assignee agent, whose stats record the failure
+    pub assignee: Pubkey,
+    pub escrow: Pubkey,
+    /// The intent payer's account in the payment mint
+    pub refund_account: Pubkey,
+}
+
+/// Top up the treasury crank tips are paid from
+pub fn fund_crank_treasury(funder: &Pubkey, lamports: u64) -> Instruction {
+    build(
+        accounts::FundCrankTreasury {
+            crank_treasury: pda::crank_treasury().0,
+            config: pda::config().0,
+            funder: *funder,
+            system_program: system_program::ID,
+        },
+        instruction::FundCrankTreasury { lamports },
+    )
+}
+
+fn crank_accounts(cranker: &Pubkey, fee_token_account: Option<Pubkey>) -> accounts::ProcessCrank {
+    accounts::ProcessCrank {
+        config: pda::config().0,
+        crank_treasury: pda::crank_treasury().0,
+        fee_token_account,
+        cranker: *cranker,
+        token_program: token::ID,
+    }
+}
+
+pub fn process_expirations(cranker: &Pubkey, expirations: &[Expiration]) -> Instruction {
+    let mut ix = build(crank_accounts(cranker, None), instruction::ProcessExpirations {});
+    for expiration in expirations {
+        ix.accounts.extend([
+            AccountMeta::new(expiration.intent, false),
+            AccountMeta::new(expiration.escrow, false),
+            AccountMeta::new(expiration.refund_account, false),
+            AccountMeta::new(pda::agent_stats(&expiration.assignee).0, false),
+        ]);
+    }
+    ix
+}
+
+/// `finalize_ready_releases`, tipped from the crank treasury
+pub fn process_settlements(
+    cranker: &Pubkey,
+    fee_token_account: Option<Pubkey>,
+    releases: &[ReadyRelease],
+) -> Instruction {
+    let mut ix = build(crank_accounts(cranker, fee_token_account), instruction::ProcessSettlements {});
+    for release in releases {
+        ix.accounts.extend([
+            AccountMeta::new(release.intent, false),
+            AccountMeta::new(release.escrow, false),
+            AccountMeta::new(release.release_account, false),
+            AccountMeta::new(
+                release.delegate_release_account.unwrap_or(release.release_account),
+                false,
+            ),
+        ]);
+    }
+    ix
+}
+
+/// `escrow` must hold the intent's payment; the opener pays the dispute fee
+/// from `opener_token_account` in the payment mint
+pub fn open_dispute(
+    opener: &Pubkey,
+    from_agent: Pubkey,
+    intent: Pubkey,
+    escrow: Pubkey,
+    opener_token_account: Pubkey,
+    reason_uri: String,
+) -> Instruction {
+    build(
+        accounts::OpenDispute {
+            dispute: pda::dispute(&intent).0,
+            intent,
+            config: pda::config().0,
+            from_agent,
+            opener: *opener,
+            opener_token_account,
+            escrow_token_account: escrow,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::OpenDispute { reason_uri },
+    )
+}
+
+/// How `resolve_dispute` pays out
+pub enum DisputeRuling {
+    /// The opener prevails: payment back to the payer, fee back to the
+    /// opener. `booking` is the booking and its vault, needed when the intent
+    /// drew on a booking deposit.
+    Refund {
+        refund_token_account: Pubkey,
+        booking: Option<(Pubkey, Pubkey)>,
+    },
+    /// The fee is forfeited to the arbiter and the intent's release account
+    Forfeit {
+        arbiter_token_account: Option<Pubkey>,
+        counterparty_token_account: Option<Pubkey>,
+    },
+}
+
+/// `assignee` is the intent's assignee agent
+pub fn resolve_dispute(
+    arbiter: &Pubkey,
+    intent: Pubkey,
+    assignee: &Pubkey,
+    opener: Pubkey,
+    escrow: Pubkey,
+    opener_token_account: Pubkey,
+    ruling: DisputeRuling,
+) -> Instruction {
+    let (refund_token_account, booking, arbiter_token_account, counterparty_token_account) = match ruling {
+        DisputeRuling::Refund {
+            refund_token_account,
+            booking,
+        } => (Some(refund_token_account), booking, None, None),
+        DisputeRuling::Forfeit {
+            arbiter_token_account,
+            counterparty_token_account,
+        } => (None, None, arbiter_token_account, counterparty_token_account),
+    };
+    build(
+        accounts::ResolveDispute {
+            dispute: pda::dispute(&intent).0,
+            intent,
+            config: pda::config().0,
+            assignee_stats: pda::agent_stats(assignee).0,
+            arbiter: *arbiter,
+            opener,
+            escrow_token_account: escrow,
+            opener_token_account,
+            refund_token_account,
+            arbiter_token_account,
+            counterparty_token_account,
+            booking: booking.map(|(booking, _)| booking),
+            booking_vault: booking.map(|(_, vault)| vault),
+            token_program: token::ID,
+        },
+        instruction::ResolveDispute {
+            opener_prevails: refund_token_account.is_some(),
+        },
+    )
+}
+
+pub fn claim_insurance(
+    claimant: &Pubkey,
+    from_agent: Pubkey,
+    intent: Pubkey,
+    amount: u64,
+    reason_uri: String,
+) -> Instruction {
+    build(
+        accounts::ClaimInsurance {
+            insurance_claim: pda::insurance_claim(&intent).0,
+            intent,
+            config: pda::config().0,
+            from_agent,
+            claimant: *claimant,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimInsurance { amount, reason_uri },
+    )
+}
+
+/// `payer_token_account` receives the payout; pass `None` with a zero
+/// payout to reject the claim
+pub fn resolve_insurance_claim(
+    arbiter: &Pubkey,
+    intent: Pubkey,
+    claimant: Pubkey,
+    payment_mint: &Pubkey,
+    payer_token_account: Option<Pubkey>,
+    payout: u64,
+) -> Instruction {
+    build(
+        accounts::ResolveInsuranceClaim {
+            insurance_claim: pda::insurance_claim(&intent).0,
+            intent,
+            config: pda::config().0,
+            arbiter: *arbiter,
+            claimant,
+            insurance_pool: pda::insurance_pool(payment_mint).0,
+            insurance_vault: pda::insurance_vault(payment_mint).0,
+            payer_token_account,
+            token_program: token::ID,
+        },
+        instruction::ResolveInsuranceClaim { payout },
+    )
+}
+
+/// Hand an accepted intent to `delegate_owner`'s agent; both owners sign.
+/// The provider's share is paid into `billing_token_account` (owned by its
+/// owner, its payout group or, with `model_profile`, its profile's billing
+/// wallet) and the delegate's into `delegate_billing_token_account`.
+pub fn redelegate_intent(
+    owner: &Pubkey,
+    to_agent: Pubkey,
+    intent: Pubkey,
+    delegate_owner: &Pubkey,
+    billing_token_account: Pubkey,
+    delegate_billing_token_account: Pubkey,
+    model_profile: Option<Pubkey>,
+    delegate_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::RedelegateIntent {
+            intent,
+            config: pda::config().0,
+            to_agent,
+            owner: *owner,
+            delegate_agent: pda::agent(delegate_owner).0,
+            delegate_owner: *delegate_owner,
+            billing_token_account,
+            delegate_billing_token_account,
+            model_profile,
+        },
+        instruction::RedelegateIntent { delegate_share_bps },
+    )
+}
+
+// === Bookings ===
+
+/// Booking vault defaults to the booking PDA's associated token account
+pub fn create_booking(
+    payer: &Pubkey,
+    from_agent: Pubkey,
+    to_agent: Pubkey,
+    model_profile: Pubkey,
+    payment_mint: Pubkey,
+    from_token_account: Pubkey,
+    args: instruction::CreateBooking,
+) -> Instruction {
+    let booking = pda::booking(&from_agent, &to_agent, args.nonce).0;
+    build(
+        accounts::CreateBooking {
+            booking,
+            config: pda::config().0,
+            from_agent,
+            to_agent,
+            model_profile,
+            payment_mint,
+            from_token_account,
+            booking_vault: pda::booking_vault(&booking, &payment_mint),
+            payer: *payer,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+pub fn close_booking(
+    booking: Pubkey,
+    booking_vault: Pubkey,
+    billing_token_account: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    build(
+        accounts::CloseBooking {
+            booking,
+            config: pda::config().0,
+            booking_vault,
+            billing_token_account,
+            payer,
+            token_program: token::ID,
+        },
+        instruction::CloseBooking {},
+    )
+}
+
+// === Trials ===
+
+/// The trial vault (see [`pda::trial_vault`]) must exist before this runs
+pub fn start_trial(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    trial_mint: &Pubkey,
+    from_token_account: Pubkey,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    let trial = pda::trial(&model_profile, &agent).0;
+    build(
+        accounts::StartTrial {
+            trial,
+            config: pda::config().0,
+            model_profile,
+            agent,
+            owner: *owner,
+            from_token_account,
+            trial_vault: pda::trial_vault(&trial, trial_mint),
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StartTrial {},
+    )
+}
+
+pub fn refund_trial(trial: Pubkey, trial_vault: Pubkey, refund_token_account: Pubkey) -> Instruction {
+    build(
+        accounts::RefundTrial {
+            trial,
+            config: pda::config().0,
+            trial_vault,
+            refund_token_account,
+            token_program: token::ID,
+        },
+        instruction::RefundTrial {},
+    )
+}
+
+pub fn forfeit_trial(
+    owner: &Pubkey,
+    model_profile: Pubkey,
+    trial: Pubkey,
+    trial_vault: Pubkey,
+    billing_token_account: Pubkey,
+) -> Instruction {
+    build(
+        accounts::ForfeitTrial {
+            trial,
+            config: pda::config().0,
+            model_profile,
+            owner: *owner,
+            trial_vault,
+            billing_token_account,
+            token_program: token::ID,
+        },
+        instruction::ForfeitTrial {},
+    )
+}
+
+// === Receipts ===
+
+/// `merkle_tree` must already be allocated (owned by the compression program)
+pub fn initialize_receipt_tree(
+    owner: &Pubkey,
+    merkle_tree: Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::InitializeReceiptTree {
+            receipt_tree: pda::receipt_tree(&agent).0,
+            agent,
+            merkle_tree,
+            owner: *owner,
+            compression_program: spl_account_compression::ID,
+            noop_program: spl_account_compression::Noop::id(),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeReceiptTree {
+            max_depth,
+            max_buffer_size,
+        },
+    )
+}
+
+pub fn submit_receipt(
+    authority: &Pubkey,
+    intent: Pubkey,
+    agent: Pubkey,
+    merkle_tree: Pubkey,
+    args: instruction::SubmitReceipt,
+) -> Instruction {
+    build(
+        accounts::SubmitReceipt {
+            intent,
+            agent,
+            authority: *authority,
+            receipt_tree: pda::receipt_tree(&agent).0,
+            merkle_tree,
+            compression_program: spl_account_compression::ID,
+            noop_program: spl_account_compression::Noop::id(),
+        },
+        args,
+    )
+}
+
+// === Agent Spending ===
+
+pub fn open_spend_limit(owner: &Pubkey, mint: Pubkey, daily_cap: u64) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::OpenSpendLimit {
+            spend_limit: pda::spend_limit(&agent, &mint).0,
+            agent,
+            mint,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::OpenSpendLimit { daily_cap },
+    )
+}
+
+pub fn set_spend_limit(owner: &Pubkey, mint: Pubkey, daily_cap: u64) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::UpdateSpendLimit {
+            spend_limit: pda::spend_limit(&agent, &mint).0,
+            agent,
+            owner: *owner,
+        },
+        instruction::SetSpendLimit { daily_cap },
+    )
+}
+
+/// An empty `destinations` lets transfers go to any token account owner
+pub fn set_transfer_destinations(owner: &Pubkey, mint: Pubkey, destinations: Vec<Pubkey>) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::UpdateSpendLimit {
+            spend_limit: pda::spend_limit(&agent, &mint).0,
+            agent,
+            owner: *owner,
+        },
+        instruction::SetTransferDestinations { destinations },
+    )
+}
+
+pub fn close_spend_limit(owner: &Pubkey, mint: Pubkey) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::CloseSpendLimit {
+            spend_limit: pda::spend_limit(&agent, &mint).0,
+            agent,
+            owner: *owner,
+        },
+        instruction::CloseSpendLimit {},
+    )
+}
+
+/// Open the agent's budget for `mint` in its conventional vault (see
+/// [`pda::budget_vault`]), which must already exist; caps of 0 are
+/// unlimited
+pub fn open_budget(
+    owner: &Pubkey,
+    mint: Pubkey,
+    owner_token_account: Pubkey,
+    daily_cap: u64,
+    total_cap: u64,
+    deposit: u64,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::OpenBudget {
+            budget: pda::budget(&agent, &mint).0,
+            agent,
+            config: pda::config().0,
+            mint,
+            budget_vault: pda::budget_vault(&agent, &mint),
+            owner_token_account,
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::OpenBudget {
+            daily_cap,
+            total_cap,
+            deposit,
+        },
+    )
+}
+
+pub fn fund_budget(owner: &Pubkey, mint: Pubkey, owner_token_account: Pubkey, amount: u64) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::FundBudget {
+            budget: pda::budget(&agent, &mint).0,
+            agent,
+            config: pda::config().0,
+            budget_vault: pda::budget_vault(&agent, &mint),
+            owner_token_account,
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::FundBudget { amount },
+    )
+}
+
+pub fn set_budget_caps(owner: &Pubkey, mint: Pubkey, daily_cap: u64, total_cap: u64) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::UpdateBudget {
+            budget: pda::budget(&agent, &mint).0,
+            agent,
+            budget_vault: pda::budget_vault(&agent, &mint),
+            owner: *owner,
+        },
+        instruction::SetBudgetCaps { daily_cap, total_cap },
+    )
+}
+
+pub fn close_budget(owner: &Pubkey, mint: Pubkey, owner_token_account: Pubkey) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::CloseBudget {
+            budget: pda::budget(&agent, &mint).0,
+            agent,
+            config: pda::config().0,
+            budget_vault: pda::budget_vault(&agent, &mint),
+            owner_token_account,
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::CloseBudget {},
+    )
+}
+
+/// SPL `approve` letting the owner's agent PDA move up to `amount` out of
+/// `token_account`; the allowance bounds the agent on top of its spend limit
+pub fn approve_agent(owner: &Pubkey, token_account: Pubkey, amount: u64) -> Instruction {
+    token::spl_token::instruction::approve(
+        &token::ID,
+        &token_account,
+        &pda::agent(owner).0,
+        owner,
+        &[],
+        amount,
+    )
+    .expect("approve with no multisig signers is always valid")
+}
+
+/// `execute_transfer` out of the owner's associated token account for `mint`
+pub fn execute_transfer(
+    agent_wallet: &Pubkey,
+    owner: &Pubkey,
+    mint: Pubkey,
+    destination_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::ExecuteTransfer {
+            config: pda::config().0,
+            agent,
+            agent_wallet: *agent_wallet,
+            spend_limit: pda::spend_limit(&agent, &mint).0,
+            source_token_account: get_associated_token_address(owner, &mint),
+            destination_token_account,
+            token_program: token::ID,
+        },
+        instruction::ExecuteTransfer { amount },
+    )
+}
+
+/// `execute_swap` from the owner's associated token account for
+/// `input_mint` into the one for `output_mint`. `route_accounts` are the swap
+/// program's accounts (e.g. a Jupiter route built with the agent PDA as the
+/// user); the agent PDA signs inside the program, not the transaction.
+pub fn execute_swap(
+    agent_wallet: &Pubkey,
+    owner: &Pubkey,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    swap_program: Pubkey,
+    args: instruction::ExecuteSwap,
+    route_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    let mut ix = build(
+        accounts::ExecuteSwap {
+            config: pda::config().0,
+            agent,
+            agent_wallet: *agent_wallet,
+            spend_limit: pda::spend_limit(&agent, &input_mint).0,
+            source_token_account: get_associated_token_address(owner, &input_mint),
+            destination_token_account: get_associated_token_address(owner, &output_mint),
+            swap_program,
+        },
+        args,
+    );
+    ix.accounts.extend(route_accounts.into_iter().map(|mut meta| {
+        meta.is_signer &= meta.pubkey != agent;
+        meta
+    }));
+    ix
+}
+
+// === Agent Groups ===
+
+/// `create_group` founded by the owner's agent
+pub fn create_group(owner: &Pubkey, group_id: [u8; 16]) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::CreateGroup {
+            group: pda::group(&agent, &group_id).0,
+            agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::CreateGroup { group_id },
+    )
+}
+
+/// `set_group_member` as an admin's owner. Adding a new member needs its
+/// owner's signature too, so pass `member_owner_signs` for it.
+pub fn set_group_member(
+    admin: &Pubkey,
+    group: Pubkey,
+    member_owner: &Pubkey,
+    member_owner_signs: bool,
+    roles: u8,
+) -> Instruction {
+    build(
+        accounts::SetGroupMember {
+            group,
+            admin_agent: pda::agent(admin).0,
+            admin: *admin,
+            member_agent: pda::agent(member_owner).0,
+            member_owner: member_owner_signs.then_some(*member_owner),
+        },
+        instruction::SetGroupMember { roles },
+    )
+}
+
+/// `remove_group_member` as an admin's owner, or `member_owner` itself
+pub fn remove_group_member(owner: &Pubkey, group: Pubkey, member_owner: &Pubkey) -> Instruction {
+    build(
+        accounts::RemoveGroupMember {
+            group,
+            member_agent: pda::agent(member_owner).0,
+            signer_agent: pda::agent(owner).0,
+            owner: *owner,
+        },
+        instruction::RemoveGroupMember {},
+    )
+}
+
+pub fn set_earnings_routing(owner: &Pubkey, group: Pubkey, enabled: bool) -> Instruction {
+    build(
+        accounts::SetEarningsRouting {
+            group,
+            agent: pda::agent(owner).0,
+            owner: *owner,
+        },
+        instruction::SetEarningsRouting { enabled },
+    )
+}
+
+/// `spend_from_group` out of the group's associated token account for `mint`
+pub fn spend_from_group(
+    owner: &Pubkey,
+    group: Pubkey,
+    mint: &Pubkey,
+    destination_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::SpendFromGroup {
+            config: pda::config().0,
+            group,
+            agent: pda::agent(owner).0,
+            owner: *owner,
+            treasury_token_account: pda::group_treasury(&group, mint),
+            destination_token_account,
+            token_program: token::ID,
+        },
+        instruction::SpendFromGroup { amount },
+    )
+}
+
+// === Bounties ===
+
+/// Bounty vault defaults to the bounty PDA's associated token account, which
+/// must exist before this runs; the reward comes from the sponsor's
+/// associated token account for `mint`
+pub fn post_bounty(sponsor: &Pubkey, mint: Pubkey, args: instruction::PostBounty) -> Instruction {
+    let bounty = pda::bounty(sponsor, &args.spec_hash).0;
+    build(
+        accounts::PostBounty {
+            bounty,
+            config: pda::config().0,
+            mint,
+            sponsor_token_account: get_associated_token_address(sponsor, &mint),
+            bounty_vault: pda::bounty_vault(&bounty, &mint),
+            sponsor: *sponsor,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+/// Pays the claimant agent owner's associated token account for `mint`
+pub fn award_bounty(
+    reviewer: &Pubkey,
+    sponsor: Pubkey,
+    spec_hash: &[u8; 32],
+    mint: &Pubkey,
+    claimant_owner: &Pubkey,
+) -> Instruction {
+    let bounty = pda::bounty(&sponsor, spec_hash).0;
+    build(
+        accounts::AwardBounty {
+            bounty,
+            config: pda::config().0,
+            bounty_vault: pda::bounty_vault(&bounty, mint),
+            claimant: pda::agent(claimant_owner).0,
+            claimant_token_account: get_associated_token_address(claimant_owner, mint),
+            reviewer: *reviewer,
+            sponsor,
+            token_program: token::ID,
+        },
+        instruction::AwardBounty {},
+    )
+}
+
+/// Signed by the reviewer, or by the sponsor once the bounty has expired
+pub fn cancel_bounty(authority: &Pubkey, sponsor: Pubkey, spec_hash: &[u8; 32], mint: &Pubkey) -> Instruction {
+    let bounty = pda::bounty(&sponsor, spec_hash).0;
+    build(
+        accounts::CancelBounty {
+            bounty,
+            config: pda::config().0,
+            bounty_vault: pda::bounty_vault(&bounty, mint),
+            sponsor_token_account: get_associated_token_address(&sponsor, mint),
+            authority: *authority,
+            sponsor,
+            token_program: token::ID,
+        },
+        instruction::CancelBounty {},
+    )
+}
+
+// === Governance ===
+
+/// spl-governance `GovernanceInstruction::SetGovernanceDelegate` index
+const SPL_GOVERNANCE_SET_DELEGATE: u8 = 3;
+
+/// A proposal in an spl-governance realm, with the accounts `cast_vote`
+/// needs beyond the voter's own
+#[derive(Clone, Debug)]
+pub struct GovernanceProposal {
+    pub governance_program: Pubkey,
+    pub realm: Pubkey,
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+    pub proposal_owner_record: Pubkey,
+    pub governing_token_mint: Pubkey,
+}
+
+impl GovernanceProposal {
+    /// spl-governance token owner record of `owner` in this realm
+    pub fn token_owner_record(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"governance",
+                self.realm.as_ref(),
+                self.governing_token_mint.as_ref(),
+                owner.as_ref(),
+            ],
+            &self.governance_program,
+        )
+        .0
+    }
+
+    pub fn vote_record(&self, token_owner_record: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"governance", self.proposal.as_ref(), token_owner_record.as_ref()],
+            &self.governance_program,
+        )
+        .0
+    }
+
+    pub fn realm_config(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"realm-config", self.realm.as_ref()], &self.governance_program).0
+    }
+}
+
+/// spl-governance `SetGovernanceDelegate` making the owner's agent PDA the
+/// delegate of their token owner record, so `cast_vote` can sign for it
+pub fn delegate_votes_to_agent(owner: &Pubkey, proposal: &GovernanceProposal) -> Instruction {
+    let mut data = vec![SPL_GOVERNANCE_SET_DELEGATE, 1];
+    data.extend_from_slice(pda::agent(owner).0.as_ref());
+    Instruction {
+        program_id: proposal.governance_program,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(proposal.token_owner_record(owner), false),
+        ],
+        data,
+    }
+}
+
+pub fn approve_vote(owner: &Pubkey, proposal: &GovernanceProposal) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::ApproveVote {
+            vote_approval: pda::vote_approval(&agent, &proposal.proposal).0,
+            agent,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::ApproveVote {
+            governance_program: proposal.governance_program,
+            realm: proposal.realm,
+            proposal: proposal.proposal,
+        },
+    )
+}
+
+pub fn revoke_vote_approval(owner: &Pubkey, proposal: Pubkey) -> Instruction {
+    let agent = pda::agent(owner).0;
+    build(
+        accounts::RevokeVoteApproval {
+            vote_approval: pda::vote_approval(&agent, &proposal).0,
+            agent,
+            owner: *owner,
+        },
+        instruction::RevokeVoteApproval {},
+    )
+}
+
+/// Vote with the owner's token owner record; pass the realm's voter weight
+/// records when it uses plugins
+pub fn cast_vote(
+    agent_wallet: &Pubkey,
+    owner: &Pubkey,
+    proposal: &GovernanceProposal,
+    vote: GovernanceVote,
+    voter_weight_record: Option<Pubkey>,
+    max_voter_weight_record: Option<Pubkey>,
+) -> Instruction {
+    let agent = pda::agent(owner).0;
+    let voter_token_owner_record = proposal.token_owner_record(owner);
+    build(
+        accounts::CastVote {
+            config: pda::config().0,
+            agent,
+            agent_wallet: *agent_wallet,
+            vote_approval: pda::vote_approval(&agent, &proposal.proposal).0,
+            governance_program: proposal.governance_program,
+            realm: proposal.realm,
+            governance: proposal.governance,
+            proposal: proposal.proposal,
+            proposal_owner_record: proposal.proposal_owner_record,
+            voter_token_owner_record,
+            vote_record: proposal.vote_record(&voter_token_owner_record),
+            governing_token_mint: proposal.governing_token_mint,
+            realm_config: proposal.realm_config(),
+            voter_weight_record,
+            max_voter_weight_record,
+            system_program: system_program::ID,
+        },
+        instruction::CastVote { vote },
+    )
+}
+
+/// Which versioned account type `migrate_account` upgrades
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigratedAccount {
+    Agent,
+    ModelProfile,
+    Intent,
+}
+
+/// Upgrade an account of type `kind` to the program's current layout,
+/// `payer` covering any extra rent
+pub fn migrate_account(payer: &Pubkey, account: Pubkey, kind: MigratedAccount) -> Instruction {
+    let accounts = accounts::MigrateAccount {
+        account,
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    match kind {
+        MigratedAccount::Agent => build(accounts, instruction::MigrateAgent {}),
+        MigratedAccount::ModelProfile => build(accounts, instruction::MigrateModelProfile {}),
+        MigratedAccount::Intent => build(accounts, instruction::MigrateIntent {}),
+    }
+}
@@ -0,0 +1,45 @@
+//! Rust client SDK for the Agent Mesh program.
+//!
+//! - [`pda`]: address derivation for every program account
+//! - [`commitment`]: payload/result commitments and their verification
+//! - [`events`]: typed program events and [`EventStream`], a reconnecting
+//!   log subscription that backfills by slot
+//! - [`filters`]: `getProgramAccounts` filters by account type and field
+//! - [`instructions`]: typed instruction builders, one per program instruction
+//! - [`ranking`]: weighted provider scoring for discovery
+//! - [`seal`]: X25519 payload encryption to the provider's `encryption_key`
+//! - [`AgentMeshClient`]: account fetching and async RPC flows (register an
+//!   agent, find and rank providers, quote and create an intent, poll its
+//!   status, settle it)
+
+#![allow(clippy::result_large_err)]
+
+pub mod client;
+pub mod commitment;
+pub mod error;
+pub mod events;
+pub mod filters;
+pub mod instructions;
+pub mod pda;
+pub mod ranking;
+pub mod seal;
+
+pub use agent_mesh::{self, ID as PROGRAM_ID};
+pub use client::{AgentMeshClient, AgentQuery, CreateIntentParams, FoundAgent, IntentRole};
+pub use error::{ClientError, Result};
+pub use events::{AgentMeshEvent, EventNotification, EventStream, EventStreamConfig};
+
+use agent_mesh::{ProgramVersion, PRICING_UNIT_TOKENS};
+
+/// Oldest program version this SDK can operate against
+pub const MIN_PROGRAM_VERSION: ProgramVersion = ProgramVersion {
+    major: 0,
+    minor: 2,
+    patch: 0,
+};
+
+/// Cost of `tokens` at a model profile's `pricing` (micro-units per 1K tokens)
+pub fn quote(pricing: u64, tokens: u64) -> Option<u64> {
+    let cost = (tokens as u128) * (pricing as u128) / (PRICING_UNIT_TOKENS as u128);
+    u64::try_from(cost).ok()
+}
@@ -0,0 +1,254 @@
+//! Program-derived addresses, mirroring the seeds in the program's contexts.
+
+use agent_mesh::{
+    IndexRole, ID, NAME_SERVICE_HASH_PREFIX, NAME_SERVICE_PROGRAM_ID, SOL_TLD_AUTHORITY, TOKEN_METADATA_PROGRAM_ID,
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::hash::hashv;
+use solana_sdk::pubkey::Pubkey;
+
+pub fn config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &ID)
+}
+
+/// The program's upgradeable-loader data account, holding its upgrade authority
+pub fn program_data() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ID.as_ref()], &solana_sdk::bpf_loader_upgradeable::ID)
+}
+
+pub fn crank_treasury() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"crank_treasury"], &ID)
+}
+
+pub fn agent(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent", owner.as_ref()], &ID)
+}
+
+pub fn agent_stats(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent_stats", agent.as_ref()], &ID)
+}
+
+pub fn model_profile(owner: &Pubkey, profile_id: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"model_profile", owner.as_ref(), profile_id], &ID)
+}
+
+pub fn profile_version(model_profile: &Pubkey, version: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"profile_version", model_profile.as_ref(), &version.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn profile_authorization(model_profile: &Pubkey, agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"profile_auth", model_profile.as_ref(), agent.as_ref()], &ID)
+}
+
+pub fn delegation(grantor: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"delegation", grantor.as_ref(), delegate.as_ref()], &ID)
+}
+
+pub fn insurance_pool(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"insurance_pool", mint.as_ref()], &ID)
+}
+
+pub fn insurance_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"insurance_vault", mint.as_ref()], &ID)
+}
+
+pub fn fee_allowlist(subject: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_allowlist", subject.as_ref()], &ID)
+}
+
+pub fn foreign_emitter(chain: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"foreign_emitter", &chain.to_le_bytes()], &ID)
+}
+
+/// Owner of the identity of agent `address` on Wormhole chain `chain`; the
+/// identity itself is `agent(&remote_agent(..).0)`
+pub fn remote_agent(chain: u16, address: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"remote_agent", &chain.to_le_bytes(), address], &ID)
+}
+
+pub fn payout_vault(model_profile: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"payout_vault", model_profile.as_ref(), mint.as_ref()], &ID)
+}
+
+pub fn payout_vault_tokens(payout_vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"payout_vault_tokens", payout_vault.as_ref()], &ID)
+}
+
+pub fn booking(from_agent: &Pubkey, to_agent: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"booking", from_agent.as_ref(), to_agent.as_ref(), &nonce.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn trial(model_profile: &Pubkey, agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"trial", model_profile.as_ref(), agent.as_ref()], &ID)
+}
+
+pub fn context_ref(agent: &Pubkey, context_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"context", agent.as_ref(), context_hash], &ID)
+}
+
+pub fn template(agent: &Pubkey, template_id: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"template", agent.as_ref(), template_id], &ID)
+}
+
+pub fn schema_registry(schema_id: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"schema_registry", schema_id], &ID)
+}
+
+/// Version `version` of the schema registered as `schema_id`
+pub fn payload_schema(schema_id: &[u8; 16], version: u32) -> (Pubkey, u8) {
+    let registry = schema_registry(schema_id).0;
+    Pubkey::find_program_address(&[b"schema", registry.as_ref(), &version.to_le_bytes()], &ID)
+}
+
+/// Channel between two agents, in either order
+pub fn channel(agent: &Pubkey, peer: &Pubkey) -> (Pubkey, u8) {
+    let (agent_a, agent_b) = if agent < peer { (agent, peer) } else { (peer, agent) };
+    Pubkey::find_program_address(&[b"channel", agent_a.as_ref(), agent_b.as_ref()], &ID)
+}
+
+pub fn intent_rate_limit(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"intent_rate", agent.as_ref()], &ID)
+}
+
+pub fn nonce_counter(from_agent: &Pubkey, to_agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"nonce_counter", from_agent.as_ref(), to_agent.as_ref()], &ID)
+}
+
+pub fn intent_index(agent: &Pubkey, role: IndexRole) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"intent_index", agent.as_ref(), &[role as u8]], &ID)
+}
+
+pub fn intent_index_page(agent: &Pubkey, role: IndexRole, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"intent_index_page", agent.as_ref(), &[role as u8], &page.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn intent(from_agent: &Pubkey, to_agent: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"intent", from_agent.as_ref(), to_agent.as_ref(), &nonce.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn dispute(intent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"dispute", intent.as_ref()], &ID)
+}
+
+pub fn insurance_claim(intent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"insurance_claim", intent.as_ref()], &ID)
+}
+
+pub fn royalties(intent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"royalties", intent.as_ref()], &ID)
+}
+
+pub fn team(intent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"team", intent.as_ref()], &ID)
+}
+
+pub fn receipt_tree(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"receipt_tree", agent.as_ref()], &ID)
+}
+
+pub fn spend_limit(agent: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"spend_limit", agent.as_ref(), mint.as_ref()], &ID)
+}
+
+pub fn budget(agent: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"budget", agent.as_ref(), mint.as_ref()], &ID)
+}
+
+/// Conventional budget vault: the budget PDA's associated token account
+pub fn budget_vault(agent: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(&budget(agent, mint).0, mint)
+}
+
+/// Group founded by `founder` (an agent)
+pub fn group(founder: &Pubkey, group_id: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"group", founder.as_ref(), group_id], &ID)
+}
+
+/// Conventional group treasury: the group PDA's associated token account
+pub fn group_treasury(group: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(group, mint)
+}
+
+/// Reverse lookup from an agent to its `.sol` name
+pub fn agent_name(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent_name", agent.as_ref()], &ID)
+}
+
+/// Name Service record of `name.sol` (`name` without the suffix)
+pub fn sol_name_record(name: &str) -> (Pubkey, u8) {
+    let hashed = hashv(&[NAME_SERVICE_HASH_PREFIX.as_bytes(), name.as_bytes()]);
+    Pubkey::find_program_address(
+        &[hashed.as_ref(), Pubkey::default().as_ref(), SOL_TLD_AUTHORITY.as_ref()],
+        &NAME_SERVICE_PROGRAM_ID,
+    )
+}
+
+pub fn bounty(sponsor: &Pubkey, spec_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bounty", sponsor.as_ref(), spec_hash], &ID)
+}
+
+pub fn vote_approval(agent: &Pubkey, proposal: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vote_approval", agent.as_ref(), proposal.as_ref()], &ID)
+}
+
+pub fn bid(intent: &Pubkey, agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bid", intent.as_ref(), agent.as_ref()], &ID)
+}
+
+/// Token account holding an intent's acceptance bond
+pub fn bond_vault(intent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bond", intent.as_ref()], &ID)
+}
+
+/// Conventional escrow: the intent PDA's associated token account
+pub fn escrow(intent: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(intent, mint)
+}
+
+/// Conventional booking vault: the booking PDA's associated token account
+pub fn booking_vault(booking: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(booking, mint)
+}
+
+/// Conventional trial vault: the trial PDA's associated token account
+pub fn trial_vault(trial: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(trial, mint)
+}
+
+/// Mint of the agent's identity badge
+pub fn badge_mint(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"badge", agent.as_ref()], &ID)
+}
+
+/// Token Metadata metadata account of a badge mint
+pub fn badge_metadata(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+/// Token Metadata master edition of a badge mint
+pub fn badge_edition(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref(), b"edition"],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+/// Conventional bounty vault: the bounty PDA's associated token account
+pub fn bounty_vault(bounty: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(bounty, mint)
+}
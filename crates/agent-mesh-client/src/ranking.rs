@@ -0,0 +1,166 @@
+//! Provider selection for discovery. Candidates are scored on a weighted mix
+//! of delivery record, how recently they signalled capacity, price and the
+//! acceptance bonds they have at stake, and the top N returned best first. [`AgentMeshClient::find_agents`] ranks
+//! from chain state; the indexer ranks the same way from its store.
+//!
+//! [`AgentMeshClient::find_agents`]: crate::AgentMeshClient::find_agents
+
+use agent_mesh::{AgentIntent, IntentStatus, CAPACITY_SIGNAL_TTL};
+
+/// How much each signal counts; only the ratios matter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankingWeights {
+    pub reputation: f64,
+    pub recency: f64,
+    pub price: f64,
+    pub stake: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        Self {
+            reputation: 0.4,
+            recency: 0.2,
+            price: 0.3,
+            stake: 0.1,
+        }
+    }
+}
+
+/// Outcomes of the intents a provider was assigned
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reputation {
+    pub completed: u64,
+    pub failed: u64,
+    pub disputed: u64,
+}
+
+impl Reputation {
+    /// Count `intent` toward its assignee's record
+    pub fn record(&mut self, intent: &AgentIntent) {
+        if intent.disputed {
+            self.disputed += 1;
+        } else if intent.status == IntentStatus::Completed as u8 {
+            self.completed += 1;
+        } else if intent.status == IntentStatus::Failed as u8 {
+            self.failed += 1;
+        }
+    }
+
+    /// Share of clean completions, smoothed so a new provider starts at 0.5
+    /// rather than at either extreme
+    pub fn score(&self) -> f64 {
+        let total = self.completed + self.failed + self.disputed;
+        (self.completed as f64 + 1.0) / (total as f64 + 2.0)
+    }
+}
+
+/// What a candidate is ranked on
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Signals {
+    pub reputation: Reputation,
+    /// Last capacity signal (unix seconds), 0 if the agent never signalled
+    pub capacity_updated_at: i64,
+    /// Profile price, micro-units per 1K tokens
+    pub pricing: u64,
+    /// Acceptance bonds held on the provider's open intents, in base units
+    /// of their mints
+    pub stake: u64,
+}
+
+impl Signals {
+    /// 1 for a capacity signal sent now, falling to 0 once it is
+    /// `CAPACITY_SIGNAL_TTL` old; 0 for agents that never signal
+    pub fn recency(&self, now: i64) -> f64 {
+        if self.capacity_updated_at == 0 {
+            return 0.0;
+        }
+        let age = now.saturating_sub(self.capacity_updated_at).max(0);
+        (1.0 - age as f64 / CAPACITY_SIGNAL_TTL as f64).max(0.0)
+    }
+
+    /// Weighted score in [0, 1]; `cheapest` is the lowest price among the
+    /// candidates and `most_staked` the largest stake, each of which scores 1
+    pub fn score(&self, weights: &RankingWeights, cheapest: u64, most_staked: u64, now: i64) -> f64 {
+        let price = if self.pricing == 0 {
+            1.0
+        } else {
+            cheapest as f64 / self.pricing as f64
+        };
+        let stake = if most_staked == 0 {
+            0.0
+        } else {
+            self.stake as f64 / most_staked as f64
+        };
+        let total = weights.reputation + weights.recency + weights.price + weights.stake;
+        if total <= 0.0 {
+            return 0.0;
+        }
+        (weights.reputation * self.reputation.score()
+            + weights.recency * self.recency(now)
+            + weights.price * price
+            + weights.stake * stake)
+            / total
+    }
+}
+
+/// A candidate with its score
+#[derive(Clone, Debug)]
+pub struct Ranked<T> {
+    pub candidate: T,
+    pub signals: Signals,
+    pub score: f64,
+}
+
+/// The `limit` best candidates, best first; ties go to the cheaper one
+pub fn rank<T>(candidates: Vec<(T, Signals)>, weights: &RankingWeights, now: i64, limit: usize) -> Vec<Ranked<T>> {
+    let cheapest = candidates.iter().map(|(_, signals)| signals.pricing).min().unwrap_or(0);
+    let most_staked = candidates.iter().map(|(_, signals)| signals.stake).max().unwrap_or(0);
+    let mut ranked: Vec<Ranked<T>> = candidates
+        .into_iter()
+        .map(|(candidate, signals)| Ranked {
+            score: signals.score(weights, cheapest, most_staked, now),
+            candidate,
+            signals,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.signals.pricing.cmp(&b.signals.pricing))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stake_lifts_an_otherwise_equal_candidate() {
+        let signals = Signals {
+            pricing: 1_000,
+            ..Signals::default()
+        };
+        let staked = Signals { stake: 500, ..signals };
+        let ranked = rank(
+            vec![("unstaked", signals), ("staked", staked)],
+            &RankingWeights::default(),
+            0,
+            2,
+        );
+        assert_eq!(ranked[0].candidate, "staked");
+        assert!(ranked[0].score > ranked[1].score);
+
+        // Without any stake among the candidates the term scores nothing
+        let weights = RankingWeights {
+            reputation: 0.0,
+            recency: 0.0,
+            price: 0.0,
+            stake: 1.0,
+        };
+        assert_eq!(signals.score(&weights, 1_000, 0, 0), 0.0);
+        assert_eq!(staked.score(&weights, 1_000, 500, 0), 1.0);
+    }
+}
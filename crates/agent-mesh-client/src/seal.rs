@@ -0,0 +1,176 @@
+//! Encrypting intent payloads to the provider.
+//!
+//! The requester picks a random 32-byte content key, encrypts the payload
+//! behind `payload_uri` with AES-256-GCM-SIV under that key (all-zero nonce;
+//! every key is used once), and seals the key to the provider's
+//! `encryption_key`:
+//!
+//! 1. generate a one-time X25519 key pair (`ephemeral_pubkey`)
+//! 2. `shared = X25519(ephemeral_secret, sealed_to)`
+//! 3. `mask = sha256("agent-mesh/seal/v1" || shared || ephemeral_pubkey || sealed_to)`
+//! 4. `encrypted_key = content_key XOR mask`
+//!
+//! The three 32-byte values are recorded on the intent with `seal_payload`.
+//! The provider repeats step 2 with its secret key to unmask the content
+//! key; the payload's AEAD tag catches a wrong key or a tampered mask.
+//! The payload commitment covers the ciphertext as uploaded.
+
+use aes_gcm_siv::aead::{Aead, NewAead};
+use aes_gcm_siv::{Aes256GcmSiv, Key, Nonce};
+use curve25519_dalek::constants::X25519_BASEPOINT;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::{ClientError, Result};
+
+const MASK_DOMAIN: &[u8] = b"agent-mesh/seal/v1";
+
+/// A payload sealed to one provider key
+#[derive(Clone, Debug)]
+pub struct SealedPayload {
+    pub sealed_to: [u8; 32],
+    pub ephemeral_pubkey: [u8; 32],
+    pub encrypted_key: [u8; 32],
+    /// The encrypted payload, to upload in place of the plaintext
+    pub ciphertext: Vec<u8>,
+}
+
+/// New X25519 secret key
+pub fn generate_secret() -> [u8; 32] {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+/// X25519 public key for `secret`, as passed to `rotate_encryption_key`
+pub fn public_key(secret: &[u8; 32]) -> [u8; 32] {
+    (X25519_BASEPOINT * clamp(secret)).to_bytes()
+}
+
+/// Encrypt `plaintext` to the provider's X25519 key `sealed_to`
+pub fn seal(sealed_to: &[u8; 32], plaintext: &[u8]) -> Result<SealedPayload> {
+    let ephemeral_secret = generate_secret();
+    let ephemeral_pubkey = public_key(&ephemeral_secret);
+    let shared = shared_secret(&ephemeral_secret, sealed_to)?;
+    let mask = mask(&shared, &ephemeral_pubkey, sealed_to);
+
+    let mut content_key = [0u8; 32];
+    OsRng.fill_bytes(&mut content_key);
+    let ciphertext = cipher(&content_key)
+        .encrypt(Nonce::from_slice(&[0u8; 12]), plaintext)
+        .map_err(|_| ClientError::Seal("payload encryption failed"))?;
+
+    Ok(SealedPayload {
+        sealed_to: *sealed_to,
+        ephemeral_pubkey,
+        encrypted_key: xor(&content_key, &mask),
+        ciphertext,
+    })
+}
+
+/// Decrypt a payload sealed to the public key of `secret`
+pub fn open(
+    secret: &[u8; 32],
+    ephemeral_pubkey: &[u8; 32],
+    encrypted_key: &[u8; 32],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>> {
+    let shared = shared_secret(secret, ephemeral_pubkey)?;
+    let mask = mask(&shared, ephemeral_pubkey, &public_key(secret));
+    let content_key = xor(encrypted_key, &mask);
+    cipher(&content_key)
+        .decrypt(Nonce::from_slice(&[0u8; 12]), ciphertext)
+        .map_err(|_| ClientError::Seal("payload is not sealed to this key"))
+}
+
+fn shared_secret(secret: &[u8; 32], peer_pubkey: &[u8; 32]) -> Result<[u8; 32]> {
+    let shared = (MontgomeryPoint(*peer_pubkey) * clamp(secret)).to_bytes();
+    if shared == [0u8; 32] {
+        return Err(ClientError::Seal("low-order public key"));
+    }
+    Ok(shared)
+}
+
+fn mask(shared: &[u8; 32], ephemeral_pubkey: &[u8; 32], sealed_to: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(MASK_DOMAIN);
+    hasher.update(shared);
+    hasher.update(ephemeral_pubkey);
+    hasher.update(sealed_to);
+    hasher.finalize().into()
+}
+
+fn clamp(secret: &[u8; 32]) -> Scalar {
+    let mut bytes = *secret;
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(bytes)
+}
+
+fn cipher(content_key: &[u8; 32]) -> Aes256GcmSiv {
+    Aes256GcmSiv::new(Key::from_slice(content_key))
+}
+
+fn xor(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (out, (a, b)) in out.iter_mut().zip(a.iter().zip(b)) {
+        *out = a ^ b;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_payload_opens_with_the_recipient_key() {
+        let secret = generate_secret();
+        let sealed = seal(&public_key(&secret), b"summarize this").unwrap();
+        assert_eq!(sealed.sealed_to, public_key(&secret));
+        assert_ne!(sealed.ciphertext.as_slice(), b"summarize this".as_slice());
+
+        let plaintext = open(
+            &secret,
+            &sealed.ephemeral_pubkey,
+            &sealed.encrypted_key,
+            &sealed.ciphertext,
+        )
+        .unwrap();
+        assert_eq!(plaintext, b"summarize this");
+    }
+
+    #[test]
+    fn sealed_payload_rejects_another_key_or_a_tampered_mask() {
+        let secret = generate_secret();
+        let sealed = seal(&public_key(&secret), b"summarize this").unwrap();
+
+        let other = generate_secret();
+        assert!(open(
+            &other,
+            &sealed.ephemeral_pubkey,
+            &sealed.encrypted_key,
+            &sealed.ciphertext
+        )
+        .is_err());
+
+        let mut encrypted_key = sealed.encrypted_key;
+        encrypted_key[0] ^= 1;
+        assert!(open(
+            &secret,
+            &sealed.ephemeral_pubkey,
+            &encrypted_key,
+            &sealed.ciphertext
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn low_order_keys_are_rejected() {
+        assert!(seal(&[0u8; 32], b"summarize this").is_err());
+    }
+}
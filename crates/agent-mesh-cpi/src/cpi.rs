@@ -0,0 +1,94 @@
+//! Anchor's generated CPI interface: one function per program instruction in
+//! [`agent_mesh::cpi`], taking a `CpiContext` over the matching struct in
+//! [`accounts`] and the instruction's arguments.
+//!
+//! ```ignore
+//! let accounts = agent_mesh_cpi::cpi::CreateIntent { /* ... */ payer: vault_authority };
+//! let seeds: &[&[u8]] = &[b"vault_authority", &[bump]];
+//! agent_mesh_cpi::cpi::create_intent(
+//!     CpiContext::new_with_signer(agent_mesh_program, accounts.into(), &[seeds]),
+//!     payload_uri,
+//!     payload,
+//!     payment_amount,
+//!     0,
+//!     0,
+//! )?;
+//! ```
+
+use anchor_lang::prelude::AccountInfo;
+
+pub use agent_mesh::cpi::accounts;
+pub use agent_mesh::cpi::*;
+
+/// The accounts of a plain `create_intent`, without the optional ones
+/// (profile version, insurance, booking, trial, context, template, delegation). Convert
+/// it into [`accounts::CreateIntent`] with `.into()`.
+///
+/// PDAs come from [`crate::pda`]; the index pages are the agents'
+/// `IntentIndex::next_page`, the stats are both agents' `AgentStats`, and
+/// the escrow must be a token account owned by the intent PDA, created
+/// beforehand. `payer` funds rent and the escrow out of
+/// `from_token_account` and must be the from-agent's owner or agent wallet,
+/// so a program's PDA that owns the agent signs as `payer`.
+pub struct CreateIntent<'info> {
+    pub nonce_counter: AccountInfo<'info>,
+    pub rate_limit: AccountInfo<'info>,
+    pub sent_index: AccountInfo<'info>,
+    pub sent_index_page: AccountInfo<'info>,
+    pub received_index: AccountInfo<'info>,
+    pub received_index_page: AccountInfo<'info>,
+    pub intent: AccountInfo<'info>,
+    pub config: AccountInfo<'info>,
+    pub from_agent: AccountInfo<'info>,
+    pub to_agent: AccountInfo<'info>,
+    pub from_stats: AccountInfo<'info>,
+    pub to_stats: AccountInfo<'info>,
+    pub payment_mint: AccountInfo<'info>,
+    pub from_token_account: AccountInfo<'info>,
+    pub escrow_token_account: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> From<CreateIntent<'info>> for accounts::CreateIntent<'info> {
+    fn from(accounts: CreateIntent<'info>) -> Self {
+        Self {
+            nonce_counter: accounts.nonce_counter,
+            rate_limit: accounts.rate_limit,
+            sent_index: accounts.sent_index,
+            sent_index_page: accounts.sent_index_page,
+            received_index: accounts.received_index,
+            received_index_page: accounts.received_index_page,
+            intent: accounts.intent,
+            config: accounts.config,
+            from_agent: accounts.from_agent,
+            to_agent: accounts.to_agent,
+            from_stats: accounts.from_stats,
+            to_stats: Some(accounts.to_stats),
+            payment_mint: accounts.payment_mint,
+            from_token_account: accounts.from_token_account,
+            escrow_token_account: accounts.escrow_token_account,
+            profile_version: None,
+            model_profile: None,
+            insurance_pool: None,
+            insurance_vault: None,
+            booking: None,
+            booking_vault: None,
+            trial: None,
+            context: None,
+            template: None,
+            schema: None,
+            delegation: None,
+            delegate_agent: None,
+            instructions: None,
+            remote_agent: None,
+            foreign_emitter: None,
+            posted_vaa: None,
+            budget: None,
+            payer: accounts.payer,
+            token_program: accounts.token_program,
+            system_program: accounts.system_program,
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! CPI interface to the Agent Mesh program, for on-chain programs (DAOs,
+//! games, DeFi protocols) that create intents and pay agents themselves.
+//!
+//! - [`seeds`]: the PDA seed prefixes the program's contexts use
+//! - [`pda`]: address derivation for the accounts an intent touches
+//! - [`cpi`] (feature `cpi`): Anchor's CPI instruction builders and account
+//!   structs, plus [`cpi::CreateIntent`] for the common `create_intent`
+//!
+//! Account types ([`AgentIdentity`], [`AgentIntent`], ...) are re-exported
+//! so callers can deserialize them with `Account<'info, T>`. A calling
+//! program acts through an agent of its own: register one owned by a PDA of
+//! the caller and sign for it with `invoke_signed`.
+
+pub mod pda;
+pub mod seeds;
+
+#[cfg(feature = "cpi")]
+pub mod cpi;
+
+pub use agent_mesh::{
+    AgentIdentity, AgentIntent, AgentStats, CommitmentScheme, ContentCommitment, Deadline, Delegation, IndexRole,
+    IntentIndex, IntentIndexPage, IntentOption, IntentRateLimit, IntentStatus, ModelProfile, NonceCounter,
+    Permission, ProtocolConfig, ID, INTENT_INDEX_PAGE_LEN,
+};
@@ -0,0 +1,58 @@
+//! Program-derived addresses for the accounts `create_intent` touches.
+
+use agent_mesh::{IndexRole, ID};
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+
+use crate::seeds;
+
+pub fn config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::CONFIG], &ID)
+}
+
+pub fn agent(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::AGENT, owner.as_ref()], &ID)
+}
+
+pub fn agent_stats(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::AGENT_STATS, agent.as_ref()], &ID)
+}
+
+pub fn model_profile(owner: &Pubkey, profile_id: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::MODEL_PROFILE, owner.as_ref(), profile_id], &ID)
+}
+
+pub fn nonce_counter(from_agent: &Pubkey, to_agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::NONCE_COUNTER, from_agent.as_ref(), to_agent.as_ref()], &ID)
+}
+
+pub fn intent_rate_limit(agent: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::INTENT_RATE, agent.as_ref()], &ID)
+}
+
+pub fn intent_index(agent: &Pubkey, role: IndexRole) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::INTENT_INDEX, agent.as_ref(), &[role as u8]], &ID)
+}
+
+pub fn intent_index_page(agent: &Pubkey, role: IndexRole, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[seeds::INTENT_INDEX_PAGE, agent.as_ref(), &[role as u8], &page.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn intent(from_agent: &Pubkey, to_agent: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[seeds::INTENT, from_agent.as_ref(), to_agent.as_ref(), &nonce.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn delegation(grantor: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::DELEGATION, grantor.as_ref(), delegate.as_ref()], &ID)
+}
+
+/// Conventional escrow: the intent PDA's associated token account
+pub fn escrow(intent: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(intent, mint)
+}
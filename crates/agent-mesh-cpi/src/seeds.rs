@@ -0,0 +1,32 @@
+//! PDA seed prefixes, as used by the program's contexts. Seeds that follow
+//! the prefix are listed with each constant.
+
+/// `["config"]`
+pub const CONFIG: &[u8] = b"config";
+
+/// `["agent", owner]`
+pub const AGENT: &[u8] = b"agent";
+
+/// `["agent_stats", agent]`
+pub const AGENT_STATS: &[u8] = b"agent_stats";
+
+/// `["model_profile", owner, profile_id]`
+pub const MODEL_PROFILE: &[u8] = b"model_profile";
+
+/// `["nonce_counter", from_agent, to_agent]`
+pub const NONCE_COUNTER: &[u8] = b"nonce_counter";
+
+/// `["intent_rate", from_agent]`
+pub const INTENT_RATE: &[u8] = b"intent_rate";
+
+/// `["intent_index", agent, [role]]`
+pub const INTENT_INDEX: &[u8] = b"intent_index";
+
+/// `["intent_index_page", agent, [role], page (u32 LE)]`
+pub const INTENT_INDEX_PAGE: &[u8] = b"intent_index_page";
+
+/// `["intent", from_agent, to_agent, nonce (u64 LE)]`
+pub const INTENT: &[u8] = b"intent";
+
+/// `["delegation", grantor_agent, delegate_agent]`
+pub const DELEGATION: &[u8] = b"delegation";
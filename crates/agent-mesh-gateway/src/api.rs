@@ -0,0 +1,268 @@
+//! Gateway routes.
+//!
+//! Every read route of the indexer API (see [`agent_mesh_indexer::api`]) is
+//! served as-is from the indexer's store, plus:
+//!
+//! | Route | Query / Body | Returns |
+//! |-------|--------------|---------|
+//! | `GET /agents/:address/intents` | `role` (`requester`/`provider`), `status`, `limit`, `offset` | the agent's intents, newest first |
+//! | `GET /agents/:address/quote` | `tokens` | payment for `tokens` at the provider's current price |
+//! | `POST /intents` | [`CreateIntentRequest`] | [`UnsignedIntent`]: a create-intent transaction to sign |
+//! | `POST /transactions` | [`SubmitRequest`] | [`Submitted`]: the confirmed signature |
+//!
+//! Public keys are base58 strings and hashes are hex, as in the indexer API.
+
+use std::sync::Arc;
+
+use agent_mesh_client::agent_mesh::{instruction, CommitmentScheme, ContentCommitment};
+use agent_mesh_client::instructions::CreateIntentBuilder;
+use agent_mesh_client::{pda, AgentMeshClient};
+use agent_mesh_indexer::store::{IntentQuery, IntentRow};
+use agent_mesh_indexer::Store;
+use anchor_spl::token;
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::null_signer::NullSigner;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::{transaction, GatewayError, Result};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+/// Shared state: the indexer store for reads and an RPC client for quotes,
+/// blockhashes and submission. The client's signer is a placeholder; the
+/// gateway signs nothing.
+pub struct Gateway {
+    store: Arc<Store>,
+    client: AgentMeshClient<NullSigner>,
+}
+
+impl Gateway {
+    pub fn new(store: Arc<Store>, rpc_url: impl ToString) -> Self {
+        Self {
+            store,
+            client: AgentMeshClient::new(rpc_url, NullSigner::new(&Pubkey::default())),
+        }
+    }
+}
+
+pub fn router(gateway: Arc<Gateway>) -> Router {
+    Router::new()
+        .route("/agents/:address/intents", get(agent_intents))
+        .route("/agents/:address/quote", get(quote))
+        .route("/intents", post(create_intent))
+        .route("/transactions", post(submit))
+        .with_state(gateway.clone())
+        .merge(agent_mesh_indexer::api::router(gateway.store.clone()))
+}
+
+fn parse_pubkey(field: &str, value: &str) -> Result<Pubkey> {
+    value
+        .parse()
+        .map_err(|_| GatewayError::BadRequest(format!("{field} is not a valid public key")))
+}
+
+fn parse_hash(field: &str, value: &str) -> Result<[u8; 32]> {
+    let invalid = || GatewayError::BadRequest(format!("{field} must be 32 bytes of hex"));
+    if value.len() != 64 || !value.is_ascii() {
+        return Err(invalid());
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(value.as_bytes().chunks_exact(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(hash)
+}
+
+#[derive(Deserialize)]
+struct AgentIntentsQuery {
+    role: Option<String>,
+    status: Option<u8>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+async fn agent_intents(
+    State(gateway): State<Arc<Gateway>>,
+    Path(address): Path<String>,
+    Query(query): Query<AgentIntentsQuery>,
+) -> Result<Json<Vec<IntentRow>>> {
+    parse_pubkey("address", &address)?;
+    let (from_agent, to_agent) = match query.role.as_deref() {
+        Some("requester") => (Some(address), None),
+        Some("provider") | None => (None, Some(address)),
+        Some(_) => return Err(GatewayError::BadRequest("role must be requester or provider".to_string())),
+    };
+    Ok(Json(gateway.store.intents(&IntentQuery {
+        from_agent,
+        to_agent,
+        status: query.status,
+        limit: query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+        offset: query.offset.unwrap_or(0),
+    })?))
+}
+
+#[derive(Deserialize)]
+struct QuoteQuery {
+    tokens: u64,
+}
+
+#[derive(Serialize)]
+pub struct Quote {
+    pub to_agent: String,
+    pub tokens: u64,
+    pub payment_amount: u64,
+}
+
+async fn quote(
+    State(gateway): State<Arc<Gateway>>,
+    Path(address): Path<String>,
+    Query(query): Query<QuoteQuery>,
+) -> Result<Json<Quote>> {
+    let to_agent = parse_pubkey("address", &address)?;
+    let payment_amount = gateway.client.quote(&to_agent, query.tokens).await?;
+    Ok(Json(Quote {
+        to_agent: address,
+        tokens: query.tokens,
+        payment_amount,
+    }))
+}
+
+/// Body of `POST /intents`
+#[derive(Deserialize)]
+pub struct CreateIntentRequest {
+    /// Fee payer and escrow funder; must sign the returned transaction
+    pub payer: String,
+    /// Defaults to the payer's agent
+    pub from_agent: Option<String>,
+    pub to_agent: String,
+    pub payment_mint: String,
+    /// Defaults to the payer's associated token account for `payment_mint`
+    pub from_token_account: Option<String>,
+    /// Tokens to budget; the payment is quoted from the provider's pricing
+    pub tokens: u64,
+    pub payload_hash: String,
+    /// Bytes behind `payload_uri`
+    pub payload_length: u64,
+    /// `CommitmentScheme` of `payload_hash`; defaults to sha256
+    pub payload_scheme: Option<u8>,
+    pub payload_uri: String,
+    /// `IntentOption` flags
+    #[serde(default)]
+    pub options: u8,
+    /// Paid to the provider when it accepts, on top of the quoted payment
+    #[serde(default)]
+    pub tip_amount: u64,
+    /// Shared `ContextRef` the payload builds on
+    pub context: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UnsignedIntent {
+    pub intent: String,
+    pub escrow: String,
+    /// The pair's next nonce; if another intent between the agents lands
+    /// first the transaction fails and must be requested again
+    pub nonce: u64,
+    pub payment_amount: u64,
+    pub recent_blockhash: String,
+    /// Base64 transaction with no signatures; sign it as `payer` and send it
+    /// to `POST /transactions` before the blockhash expires
+    pub transaction: String,
+}
+
+async fn create_intent(
+    State(gateway): State<Arc<Gateway>>,
+    Json(request): Json<CreateIntentRequest>,
+) -> Result<Json<UnsignedIntent>> {
+    let payer = parse_pubkey("payer", &request.payer)?;
+    let from_agent = match &request.from_agent {
+        Some(from_agent) => parse_pubkey("from_agent", from_agent)?,
+        None => pda::agent(&payer).0,
+    };
+    let to_agent = parse_pubkey("to_agent", &request.to_agent)?;
+    let payment_mint = parse_pubkey("payment_mint", &request.payment_mint)?;
+    let from_token_account = match &request.from_token_account {
+        Some(account) => parse_pubkey("from_token_account", account)?,
+        None => get_associated_token_address(&payer, &payment_mint),
+    };
+    let payload = ContentCommitment {
+        scheme: request.payload_scheme.unwrap_or(CommitmentScheme::Sha256 as u8),
+        length: request.payload_length,
+        hash: parse_hash("payload_hash", &request.payload_hash)?,
+    };
+    if CommitmentScheme::from_u8(payload.scheme).is_none() {
+        return Err(GatewayError::BadRequest("payload_scheme is unknown".to_string()));
+    }
+    let nonce = gateway.client.next_nonce(&from_agent, &to_agent).await?;
+    let (sent_page, received_page) = gateway.client.next_index_pages(&from_agent, &to_agent).await?;
+
+    let payment_amount = gateway.client.quote(&to_agent, request.tokens).await?;
+    let mut builder = CreateIntentBuilder::new(
+        &payer,
+        from_agent,
+        to_agent,
+        payment_mint,
+        from_token_account,
+        nonce,
+        instruction::CreateIntent {
+            payload_uri: request.payload_uri,
+            payload,
+            payment_amount,
+            options: request.options,
+            tip_amount: request.tip_amount,
+        },
+    )
+    .index_pages(sent_page, received_page);
+    if let Some(context) = &request.context {
+        builder = builder.context(parse_pubkey("context", context)?);
+    }
+    let intent = builder.intent();
+    let escrow = builder.escrow();
+    let create_escrow = create_associated_token_account_idempotent(&payer, &intent, &payment_mint, &token::ID);
+
+    let blockhash = gateway.client.rpc().get_latest_blockhash().await?;
+    let message = Message::new_with_blockhash(&[create_escrow, builder.instruction()], Some(&payer), &blockhash);
+    Ok(Json(UnsignedIntent {
+        intent: intent.to_string(),
+        escrow: escrow.to_string(),
+        nonce,
+        payment_amount,
+        recent_blockhash: blockhash.to_string(),
+        transaction: transaction::encode(&Transaction::new_unsigned(message))?,
+    }))
+}
+
+/// Body of `POST /transactions`
+#[derive(Deserialize)]
+pub struct SubmitRequest {
+    /// Base64 signed transaction
+    pub transaction: String,
+}
+
+#[derive(Serialize)]
+pub struct Submitted {
+    pub signature: String,
+}
+
+async fn submit(
+    State(gateway): State<Arc<Gateway>>,
+    Json(request): Json<SubmitRequest>,
+) -> Result<Json<Submitted>> {
+    let transaction = transaction::decode(&request.transaction)?;
+    if !transaction.is_signed() {
+        return Err(GatewayError::BadRequest("transaction is not fully signed".to_string()));
+    }
+    let signature = gateway.client.rpc().send_and_confirm_transaction(&transaction).await?;
+    Ok(Json(Submitted {
+        signature: signature.to_string(),
+    }))
+}
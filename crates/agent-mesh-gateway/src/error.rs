@@ -0,0 +1,41 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+pub type Result<T> = std::result::Result<T, GatewayError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GatewayError {
+    #[error("{0}")]
+    Client(#[from] agent_mesh_client::ClientError),
+
+    #[error("{0}")]
+    Indexer(#[from] agent_mesh_indexer::IndexerError),
+
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("transaction encoding error: {0}")]
+    Encoding(#[from] bincode::Error),
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            GatewayError::BadRequest(_) | GatewayError::Encoding(_) => StatusCode::BAD_REQUEST,
+            GatewayError::Client(agent_mesh_client::ClientError::AccountNotFound(_)) => StatusCode::NOT_FOUND,
+            GatewayError::Rpc(_) | GatewayError::Client(_) => StatusCode::BAD_GATEWAY,
+            GatewayError::Indexer(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(ErrorBody { error: self.to_string() })).into_response()
+    }
+}
@@ -0,0 +1,20 @@
+//! HTTP gateway for the Agent Mesh program, for web and bot integrations
+//! that shouldn't deal with RPC, PDAs or account lists.
+//!
+//! - [`api`]: the routes; reads come from the indexer's SQLite store and
+//!   writes are built with the client SDK
+//! - [`transaction`]: base64 wire encoding for the transactions handed to
+//!   and accepted from callers
+//!
+//! The gateway never holds user keys. `POST /intents` returns an unsigned
+//! transaction for the caller's wallet to sign, and `POST /transactions`
+//! submits it once signed.
+
+#![allow(clippy::result_large_err)]
+
+pub mod api;
+pub mod error;
+pub mod transaction;
+
+pub use api::Gateway;
+pub use error::{GatewayError, Result};
@@ -0,0 +1,63 @@
+//! `agent-mesh-gateway`: HTTP API over the indexer store and the client SDK.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use agent_mesh_gateway::{api, Gateway};
+use agent_mesh_indexer::{ingest, Store};
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "agent-mesh-gateway", version, about = "HTTP gateway for Solana Agent Mesh")]
+struct Args {
+    /// RPC endpoint for quotes, blockhashes and submission
+    #[arg(long, env = "RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// WebSocket endpoint, used with --ingest
+    #[arg(long, env = "WS_URL", default_value = "wss://api.devnet.solana.com")]
+    ws_url: String,
+
+    /// Indexer SQLite database to serve reads from
+    #[arg(long, env = "INDEXER_DB", default_value = "agent-mesh-indexer.db")]
+    db: PathBuf,
+
+    /// Run ingestion in-process instead of alongside `agent-mesh-indexer`
+    #[arg(long)]
+    ingest: bool,
+
+    /// Address for the gateway API
+    #[arg(long, env = "GATEWAY_ADDR", default_value = "127.0.0.1:8090")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let store = match Store::open(&args.db) {
+        Ok(store) => Arc::new(store),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    if args.ingest {
+        tokio::spawn(ingest::run(args.rpc_url.clone(), args.ws_url, store.clone()));
+    }
+    let gateway = Arc::new(Gateway::new(store, args.rpc_url));
+
+    let listener = match tokio::net::TcpListener::bind(args.listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("error: failed to bind {}: {err}", args.listen);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("gateway: serving on http://{}", args.listen);
+    if let Err(err) = axum::serve(listener, api::router(gateway)).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
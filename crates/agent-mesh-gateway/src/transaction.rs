@@ -0,0 +1,19 @@
+//! Legacy transactions as base64 of their bincode wire format, the encoding
+//! `sendTransaction` and wallet adapters accept.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::transaction::Transaction;
+
+use crate::{GatewayError, Result};
+
+pub fn encode(transaction: &Transaction) -> Result<String> {
+    Ok(STANDARD.encode(bincode::serialize(transaction)?))
+}
+
+pub fn decode(encoded: &str) -> Result<Transaction> {
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|err| GatewayError::BadRequest(format!("transaction is not base64: {err}")))?;
+    Ok(bincode::deserialize(&bytes)?)
+}
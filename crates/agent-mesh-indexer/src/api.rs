@@ -0,0 +1,263 @@
+//! Read-only HTTP query API.
+//!
+//! | Route | Query | Returns |
+//! |-------|-------|---------|
+//! | `GET /agents` | `model_profile`, `limit`, `offset` | agents, newest first |
+//! | `GET /agents/ranked` | `model_profile`, `verified`, `limit`, `reputation_weight`, `recency_weight`, `price_weight`, `stake_weight` | agents accepting intents, best score first |
+//! | `GET /agents/:address` | | one agent |
+//! | `GET /profiles` | `verified`, `limit`, `offset` | public profiles, cheapest first |
+//! | `GET /profiles/:address` | | one profile |
+//! | `GET /intents` | `agent`, `role` (`requester`/`provider`), `status`, `limit`, `offset` | intents, newest first |
+//! | `GET /intents/:address` | | one intent |
+//! | `GET /intents/:address/events` | | the intent's events by `sequence` |
+//! | `GET /events` | `name`, `limit`, `offset` | latest events |
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use agent_mesh_client::ranking::{self, RankingWeights, Signals};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{AgentRow, EventRow, IntentQuery, IntentRow, ProfileRow};
+use crate::{IndexerError, Store};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+pub fn router(store: Arc<Store>) -> Router {
+    Router::new()
+        .route("/agents", get(agents))
+        .route("/agents/ranked", get(ranked_agents))
+        .route("/agents/:address", get(agent))
+        .route("/profiles", get(profiles))
+        .route("/profiles/:address", get(profile))
+        .route("/intents", get(intents))
+        .route("/intents/:address", get(intent))
+        .route("/intents/:address/events", get(intent_events))
+        .route("/events", get(events))
+        .with_state(store)
+}
+
+pub enum ApiError {
+    NotFound,
+    BadRequest(&'static str),
+    Internal(String),
+}
+
+impl From<IndexerError> for ApiError {
+    fn from(err: IndexerError) -> Self {
+        ApiError::Internal(err.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            ApiError::NotFound => (StatusCode::NOT_FOUND, "not found".to_string()),
+            ApiError::BadRequest(reason) => (StatusCode::BAD_REQUEST, reason.to_string()),
+            ApiError::Internal(error) => (StatusCode::INTERNAL_SERVER_ERROR, error),
+        };
+        (status, Json(ErrorBody { error })).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+fn found<T>(row: Option<T>) -> ApiResult<T> {
+    row.map(Json).ok_or(ApiError::NotFound)
+}
+
+/// `(limit, offset)` with defaults applied and the limit capped
+fn page(limit: Option<u32>, offset: Option<u32>) -> (u32, u32) {
+    (limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT), offset.unwrap_or(0))
+}
+
+#[derive(Deserialize)]
+struct AgentsQuery {
+    model_profile: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+async fn agents(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<AgentsQuery>,
+) -> ApiResult<Vec<AgentRow>> {
+    let (limit, offset) = page(query.limit, query.offset);
+    Ok(Json(store.agents(query.model_profile.as_deref(), limit, offset)?))
+}
+
+#[derive(Deserialize)]
+struct RankedAgentsQuery {
+    model_profile: Option<String>,
+    #[serde(default)]
+    verified: bool,
+    limit: Option<u32>,
+    reputation_weight: Option<f64>,
+    recency_weight: Option<f64>,
+    price_weight: Option<f64>,
+    stake_weight: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct RankedAgentRow {
+    #[serde(flatten)]
+    agent: AgentRow,
+    pricing: u64,
+    verified: bool,
+    completed: u64,
+    failed: u64,
+    disputed: u64,
+    stake: u64,
+    score: f64,
+}
+
+async fn ranked_agents(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<RankedAgentsQuery>,
+) -> ApiResult<Vec<RankedAgentRow>> {
+    let defaults = RankingWeights::default();
+    let weights = RankingWeights {
+        reputation: query.reputation_weight.unwrap_or(defaults.reputation),
+        recency: query.recency_weight.unwrap_or(defaults.recency),
+        price: query.price_weight.unwrap_or(defaults.price),
+        stake: query.stake_weight.unwrap_or(defaults.stake),
+    };
+    if [weights.reputation, weights.recency, weights.price, weights.stake]
+        .iter()
+        .any(|weight| !weight.is_finite() || *weight < 0.0)
+    {
+        return Err(ApiError::BadRequest("weights must be non-negative numbers"));
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    let candidates = store
+        .ranking_candidates(query.model_profile.as_deref(), query.verified, now)?
+        .into_iter()
+        .map(|candidate| {
+            let signals = Signals {
+                reputation: candidate.reputation,
+                capacity_updated_at: candidate.agent.capacity_updated_at,
+                pricing: candidate.pricing,
+                stake: candidate.stake,
+            };
+            (candidate, signals)
+        })
+        .collect();
+    let (limit, _) = page(query.limit, None);
+    Ok(Json(
+        ranking::rank(candidates, &weights, now, limit as usize)
+            .into_iter()
+            .map(|ranked| RankedAgentRow {
+                pricing: ranked.candidate.pricing,
+                verified: ranked.candidate.verified,
+                completed: ranked.signals.reputation.completed,
+                failed: ranked.signals.reputation.failed,
+                disputed: ranked.signals.reputation.disputed,
+                stake: ranked.signals.stake,
+                score: ranked.score,
+                agent: ranked.candidate.agent,
+            })
+            .collect(),
+    ))
+}
+
+async fn agent(
+    State(store): State<Arc<Store>>,
+    Path(address): Path<String>,
+) -> ApiResult<AgentRow> {
+    found(store.agent(&address)?)
+}
+
+#[derive(Deserialize)]
+struct ProfilesQuery {
+    #[serde(default)]
+    verified: bool,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+async fn profiles(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<ProfilesQuery>,
+) -> ApiResult<Vec<ProfileRow>> {
+    let (limit, offset) = page(query.limit, query.offset);
+    Ok(Json(store.public_profiles(query.verified, limit, offset)?))
+}
+
+async fn profile(
+    State(store): State<Arc<Store>>,
+    Path(address): Path<String>,
+) -> ApiResult<ProfileRow> {
+    found(store.profile(&address)?)
+}
+
+#[derive(Deserialize)]
+struct IntentsQuery {
+    agent: Option<String>,
+    role: Option<String>,
+    status: Option<u8>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+async fn intents(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<IntentsQuery>,
+) -> ApiResult<Vec<IntentRow>> {
+    let (from_agent, to_agent) = match (query.agent, query.role.as_deref()) {
+        (None, None) => (None, None),
+        (Some(agent), Some("requester")) => (Some(agent), None),
+        (Some(agent), Some("provider") | None) => (None, Some(agent)),
+        (None, Some(_)) => return Err(ApiError::BadRequest("role requires agent")),
+        (Some(_), Some(_)) => return Err(ApiError::BadRequest("role must be requester or provider")),
+    };
+    let (limit, offset) = page(query.limit, query.offset);
+    Ok(Json(store.intents(&IntentQuery {
+        from_agent,
+        to_agent,
+        status: query.status,
+        limit,
+        offset,
+    })?))
+}
+
+async fn intent(
+    State(store): State<Arc<Store>>,
+    Path(address): Path<String>,
+) -> ApiResult<IntentRow> {
+    found(store.intent(&address)?)
+}
+
+async fn intent_events(
+    State(store): State<Arc<Store>>,
+    Path(address): Path<String>,
+) -> ApiResult<Vec<EventRow>> {
+    Ok(Json(store.intent_events(&address)?))
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    name: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+async fn events(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<EventsQuery>,
+) -> ApiResult<Vec<EventRow>> {
+    let (limit, offset) = page(query.limit, query.offset);
+    Ok(Json(store.events(query.name.as_deref(), limit, offset)?))
+}
@@ -0,0 +1,16 @@
+pub type Result<T> = std::result::Result<T, IndexerError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+
+    #[error("websocket error: {0}")]
+    Pubsub(#[from] solana_client::nonblocking::pubsub_client::PubsubClientError),
+
+    #[error("database error: {0}")]
+    Db(#[from] rusqlite::Error),
+
+    #[error("subscription closed")]
+    SubscriptionClosed,
+}
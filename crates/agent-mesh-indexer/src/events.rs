@@ -0,0 +1,233 @@
+//! Anchor event decoding from transaction logs.
+
+use agent_mesh_client::agent_mesh;
+use agent_mesh_client::PROGRAM_ID;
+use anchor_lang::Discriminator;
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+
+macro_rules! events {
+    ($($name:ident),* $(,)?) => {
+        const EVENTS: &[([u8; 8], &str)] = &[
+            $((<agent_mesh::$name as Discriminator>::DISCRIMINATOR, stringify!($name))),*
+        ];
+    };
+}
+
+events!(
+    ConfigInitialized,
+    ProgramVersionSynced,
+    AttestorUpdated,
+    OperatingModeChanged,
+    PauseToggled,
+    GovernanceUpdated,
+    ParameterUpdated,
+    FeeAllowlistUpdated,
+    InsurancePoolInitialized,
+    AgentRegistered,
+    IdentityBadgeMinted,
+    AgentUpdated,
+    AgentClosed,
+    EncryptionKeyRotated,
+    CapacityUpdated,
+    MinPaymentUpdated,
+    ModelProfileCreated,
+    ModelProfileUpdated,
+    ProfileSnapshotted,
+    PayoutVaultOpened,
+    PayoutsSwept,
+    ProfileAccessGranted,
+    ProfileAccessRevoked,
+    DelegationGranted,
+    DelegationRevoked,
+    GuardianSet,
+    AgentFrozen,
+    AgentUnfrozen,
+    ForeignEmitterUpdated,
+    RemoteAgentRegistered,
+    ProfileAttested,
+    ProfileAttestationRevoked,
+    ContextRefCreated,
+    ContextRefClosed,
+    TemplatePublished,
+    TemplateClosed,
+    SchemaPublished,
+    SchemaDeprecated,
+    ChannelOpened,
+    MessagePosted,
+    ChannelClosed,
+    BidClosed,
+    IntentCreated,
+    IntentStatusUpdated,
+    IntentTransition,
+    EscrowReleased,
+    ReleaseScheduled,
+    ReleasesFinalized,
+    CrankTreasuryFunded,
+    CrankTipsPaid,
+    EscrowRefunded,
+    IntentCancelled,
+    TipPaid,
+    ResultSubmitted,
+    BidPlaced,
+    BidSelected,
+    BondPosted,
+    IntentTimedOut,
+    BondSettled,
+    DisputeOpened,
+    DisputeResolved,
+    InsuranceClaimFiled,
+    InsuranceClaimResolved,
+    BookingCreated,
+    BookingClosed,
+    TrialStarted,
+    TrialSettled,
+    ReceiptTreeInitialized,
+    ReceiptSubmitted,
+    RoyaltiesDeclared,
+    TeamDeclared,
+    TeamResultSubmitted,
+    TeamSettled,
+    PayloadSealed,
+    SlaSet,
+    SlaPenaltyApplied,
+    IntentRedelegated,
+    IntentClosed,
+    IntentRetried,
+    PayoutSwapSet,
+    X402PaymentSettled,
+    BridgedIntentSubmitted,
+    BridgedResultAttested,
+    SpendLimitUpdated,
+    TransferDestinationsUpdated,
+    TransferExecuted,
+    BudgetUpdated,
+    BudgetSpent,
+    BudgetClosed,
+    SwapExecuted,
+    GroupCreated,
+    GroupMemberUpdated,
+    EarningsRoutingSet,
+    GroupTreasurySpent,
+    BountyPosted,
+    BountyAwarded,
+    BountyCancelled,
+    VoteApproved,
+    VoteApprovalRevoked,
+    VoteCast,
+    NameBound,
+    NameUnbound,
+    AccountMigrated,
+    RentRefunded,
+);
+
+/// Events whose payload starts with `intent: Pubkey, sequence: u64`
+const INTENT_EVENTS: &[&str] = &[
+    "IntentCreated",
+    "IntentStatusUpdated",
+    "IntentTransition",
+    "EscrowReleased",
+    "ReleaseScheduled",
+    "EscrowRefunded",
+    "IntentCancelled",
+    "TipPaid",
+    "ResultSubmitted",
+    "BidPlaced",
+    "BidSelected",
+    "BondPosted",
+    "IntentTimedOut",
+    "BondSettled",
+    "DisputeOpened",
+    "DisputeResolved",
+    "RoyaltiesDeclared",
+    "TeamDeclared",
+    "TeamResultSubmitted",
+    "TeamSettled",
+    "PayloadSealed",
+    "SlaSet",
+    "SlaPenaltyApplied",
+    "IntentRedelegated",
+    "IntentClosed",
+    "IntentRetried",
+    "PayoutSwapSet",
+    "X402PaymentSettled",
+    "BridgedIntentSubmitted",
+    "BridgedResultAttested",
+    "InsuranceClaimResolved",
+];
+
+const DATA_PREFIX: &str = "Program data: ";
+
+/// One event from a transaction's logs
+#[derive(Clone, Debug)]
+pub struct DecodedEvent {
+    /// Position among the transaction's program events
+    pub index: u32,
+    pub name: &'static str,
+    pub intent: Option<Pubkey>,
+    pub sequence: Option<u64>,
+    /// Borsh-encoded event body (after the discriminator)
+    pub data: Vec<u8>,
+}
+
+impl DecodedEvent {
+    /// The account a `RentRefunded` event reports as closed
+    pub fn closed_account(&self) -> Option<Pubkey> {
+        if self.name != "RentRefunded" {
+            return None;
+        }
+        Pubkey::try_from(self.data.get(..32)?).ok()
+    }
+}
+
+/// Events emitted by the program itself; data logged by other programs in
+/// the same transaction (including CPIs out of the program) is ignored
+pub fn parse_logs(logs: &[String]) -> Vec<DecodedEvent> {
+    let program_id = PROGRAM_ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = line.strip_prefix(DATA_PREFIX) {
+            if stack.last() == Some(&program_id.as_str()) {
+                if let Some(event) = decode(data, events.len() as u32) {
+                    events.push(event);
+                }
+            }
+        } else if let Some((program, tail)) = rest.split_once(' ') {
+            if tail.starts_with("invoke [") {
+                stack.push(program);
+            } else if tail == "success" || tail.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    events
+}
+
+fn decode(data: &str, index: u32) -> Option<DecodedEvent> {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data).ok()?;
+    let (discriminator, body) = bytes.split_first_chunk::<8>()?;
+    let name = EVENTS
+        .iter()
+        .find(|(known, _)| known == discriminator)
+        .map(|(_, name)| *name)?;
+
+    let (intent, sequence) = match (INTENT_EVENTS.contains(&name), body.get(..40)) {
+        (true, Some(header)) => (
+            Some(Pubkey::try_from(&header[..32]).ok()?),
+            Some(u64::from_le_bytes(header[32..40].try_into().ok()?)),
+        ),
+        _ => (None, None),
+    };
+    Some(DecodedEvent {
+        index,
+        name,
+        intent,
+        sequence,
+        data: body.to_vec(),
+    })
+}
@@ -0,0 +1,146 @@
+//! Account and log ingestion over RPC and the RPC WebSocket.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use agent_mesh_client::agent_mesh::{AgentIdentity, AgentIntent, ModelProfile};
+use agent_mesh_client::PROGRAM_ID;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use futures_util::StreamExt;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionLogsConfig,
+    RpcTransactionLogsFilter,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::events::parse_logs;
+use crate::{IndexerError, Result, Store};
+
+/// Longest wait between reconnect attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Upsert or remove one program account from its raw data. Accounts of
+/// types the indexer doesn't track are ignored.
+pub fn apply_account(store: &Store, address: &Pubkey, data: &[u8], slot: u64) -> Result<()> {
+    if data.is_empty() {
+        return store.remove(address);
+    }
+    let Some(discriminator) = data.get(..8) else {
+        return Ok(());
+    };
+    let mut data = data;
+    if discriminator == AgentIntent::DISCRIMINATOR {
+        if let Ok(intent) = AgentIntent::try_deserialize(&mut data) {
+            store.upsert_intent(address, &intent, slot)?;
+        }
+    } else if discriminator == AgentIdentity::DISCRIMINATOR {
+        if let Ok(agent) = AgentIdentity::try_deserialize(&mut data) {
+            store.upsert_agent(address, &agent, slot)?;
+        }
+    } else if discriminator == ModelProfile::DISCRIMINATOR {
+        if let Ok(profile) = ModelProfile::try_deserialize(&mut data) {
+            store.upsert_profile(address, &profile, slot)?;
+        }
+    }
+    Ok(())
+}
+
+fn account_config(commitment: CommitmentConfig) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+/// Load every current program account; returns how many were read
+pub async fn backfill(rpc: &RpcClient, store: &Store) -> Result<usize> {
+    let slot = rpc.get_slot().await?;
+    let accounts = rpc
+        .get_program_accounts_with_config(&PROGRAM_ID, account_config(rpc.commitment()))
+        .await?;
+    for (address, account) in &accounts {
+        apply_account(store, address, &account.data, slot)?;
+    }
+    Ok(accounts.len())
+}
+
+/// Follow account writes until the subscription drops
+pub async fn follow_accounts(ws_url: &str, commitment: CommitmentConfig, store: &Store) -> Result<()> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut updates, unsubscribe) = client
+        .program_subscribe(&PROGRAM_ID, Some(account_config(commitment)))
+        .await?;
+    while let Some(update) = updates.next().await {
+        let Ok(address) = update.value.pubkey.parse::<Pubkey>() else {
+            continue;
+        };
+        let data = update.value.account.decode::<solana_sdk::account::Account>().map(|a| a.data);
+        apply_account(store, &address, data.as_deref().unwrap_or_default(), update.context.slot)?;
+    }
+    unsubscribe().await;
+    Err(IndexerError::SubscriptionClosed)
+}
+
+/// Follow transaction logs mentioning the program until the subscription
+/// drops. Failed transactions are skipped: their events never took effect.
+pub async fn follow_logs(ws_url: &str, commitment: CommitmentConfig, store: &Store) -> Result<()> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut logs, unsubscribe) = client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![PROGRAM_ID.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(commitment),
+            },
+        )
+        .await?;
+    while let Some(response) = logs.next().await {
+        if response.value.err.is_some() {
+            continue;
+        }
+        let events = parse_logs(&response.value.logs);
+        if !events.is_empty() {
+            store.insert_events(&response.value.signature, response.context.slot, &events)?;
+        }
+        // Closed accounts leave the program without an account notification
+        for closed in events.iter().filter_map(|event| event.closed_account()) {
+            store.remove(&closed)?;
+        }
+    }
+    unsubscribe().await;
+    Err(IndexerError::SubscriptionClosed)
+}
+
+/// Backfill, then follow accounts and logs forever, reconnecting with
+/// exponential backoff. Accounts are re-backfilled after each reconnect so
+/// writes missed while disconnected are picked up.
+pub async fn run(rpc_url: String, ws_url: String, store: Arc<Store>) {
+    let commitment = CommitmentConfig::confirmed();
+    let rpc = RpcClient::new_with_commitment(rpc_url, commitment);
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match backfill(&rpc, &store).await {
+            Ok(count) => {
+                eprintln!("indexer: backfilled {count} accounts");
+                let result = tokio::select! {
+                    result = follow_accounts(&ws_url, commitment, &store) => result,
+                    result = follow_logs(&ws_url, commitment, &store) => result,
+                };
+                if let Err(err) = result {
+                    eprintln!("indexer: stream ended: {err}");
+                }
+                backoff = Duration::from_secs(1);
+            }
+            Err(err) => eprintln!("indexer: backfill failed: {err}"),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
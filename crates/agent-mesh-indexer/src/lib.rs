@@ -0,0 +1,23 @@
+//! Indexer for the Agent Mesh program.
+//!
+//! - [`ingest`]: backfills program accounts, then follows account updates and
+//!   transaction logs over the RPC WebSocket
+//! - [`events`]: decodes the Anchor events emitted in program logs
+//! - [`store`]: SQLite tables for agents, profiles, intents, events,
+//!   per-provider outcomes and held acceptance bonds
+//! - [`api`]: read-only HTTP query API over the store
+//!
+//! Intent rows only move forward: an update is applied when its `sequence` is
+//! at least the stored one, so replays and out-of-order notifications are
+//! harmless. Agents and profiles are ordered by slot.
+
+#![allow(clippy::result_large_err)]
+
+pub mod api;
+pub mod error;
+pub mod events;
+pub mod ingest;
+pub mod store;
+
+pub use error::{IndexerError, Result};
+pub use store::Store;
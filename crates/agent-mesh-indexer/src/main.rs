@@ -0,0 +1,55 @@
+//! `agent-mesh-indexer`: ingest program state into SQLite and serve queries.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use agent_mesh_indexer::{api, ingest, Store};
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "agent-mesh-indexer", version, about = "Index Solana Agent Mesh accounts and events")]
+struct Args {
+    /// RPC endpoint used for the initial backfill
+    #[arg(long, env = "RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// WebSocket endpoint for account and log subscriptions
+    #[arg(long, env = "WS_URL", default_value = "wss://api.devnet.solana.com")]
+    ws_url: String,
+
+    /// SQLite database file
+    #[arg(long, env = "INDEXER_DB", default_value = "agent-mesh-indexer.db")]
+    db: PathBuf,
+
+    /// Address for the query API
+    #[arg(long, env = "INDEXER_ADDR", default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let store = match Store::open(&args.db) {
+        Ok(store) => Arc::new(store),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    tokio::spawn(ingest::run(args.rpc_url, args.ws_url, store.clone()));
+
+    let listener = match tokio::net::TcpListener::bind(args.listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("error: failed to bind {}: {err}", args.listen);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("indexer: serving on http://{}", args.listen);
+    if let Err(err) = axum::serve(listener, api::router(store)).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
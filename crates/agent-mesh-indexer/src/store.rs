@@ -0,0 +1,617 @@
+//! SQLite persistence and queries.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use agent_mesh_client::agent_mesh::{
+    AgentIdentity, AgentIntent, IntentStatus, ModelProfile, Permission, CAPACITY_SIGNAL_TTL,
+};
+use agent_mesh_client::ranking::Reputation;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::events::DecodedEvent;
+use crate::Result;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS agents (
+    address             TEXT PRIMARY KEY,
+    owner               TEXT NOT NULL,
+    agent_wallet        TEXT NOT NULL,
+    model_profile       TEXT NOT NULL,
+    metadata_uri        TEXT NOT NULL,
+    permissions         INTEGER NOT NULL,
+    key_version         INTEGER NOT NULL,
+    available_slots     INTEGER NOT NULL,
+    queue_depth         INTEGER NOT NULL,
+    capacity_updated_at INTEGER NOT NULL,
+    reputation_since    INTEGER NOT NULL,
+    created_at          INTEGER NOT NULL,
+    updated_at          INTEGER NOT NULL,
+    slot                INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS agents_model_profile ON agents (model_profile);
+
+CREATE TABLE IF NOT EXISTS profiles (
+    address        TEXT PRIMARY KEY,
+    owner          TEXT NOT NULL,
+    label          TEXT NOT NULL,
+    provider_uri   TEXT NOT NULL,
+    pricing        INTEGER NOT NULL,
+    billing_wallet TEXT NOT NULL,
+    verified       INTEGER NOT NULL,
+    is_public      INTEGER NOT NULL,
+    created_at     INTEGER NOT NULL,
+    updated_at     INTEGER NOT NULL,
+    slot           INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS intents (
+    address        TEXT PRIMARY KEY,
+    from_agent     TEXT NOT NULL,
+    to_agent       TEXT NOT NULL,
+    delegate_agent TEXT,
+    payer          TEXT NOT NULL,
+    nonce          INTEGER NOT NULL,
+    status         INTEGER NOT NULL,
+    payment_amount INTEGER NOT NULL,
+    payment_mint   TEXT NOT NULL,
+    payload_uri    TEXT NOT NULL,
+    result_uri     TEXT NOT NULL,
+    released       INTEGER NOT NULL,
+    disputed       INTEGER NOT NULL,
+    sequence       INTEGER NOT NULL,
+    created_at     INTEGER NOT NULL,
+    updated_at     INTEGER NOT NULL,
+    slot           INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS intents_from_agent ON intents (from_agent, created_at);
+CREATE INDEX IF NOT EXISTS intents_to_agent ON intents (to_agent, created_at);
+CREATE INDEX IF NOT EXISTS intents_status ON intents (status);
+
+CREATE TABLE IF NOT EXISTS events (
+    signature TEXT NOT NULL,
+    idx       INTEGER NOT NULL,
+    slot      INTEGER NOT NULL,
+    name      TEXT NOT NULL,
+    intent    TEXT,
+    sequence  INTEGER,
+    data      BLOB NOT NULL,
+    PRIMARY KEY (signature, idx)
+);
+CREATE INDEX IF NOT EXISTS events_intent ON events (intent, sequence);
+CREATE INDEX IF NOT EXISTS events_name ON events (name, slot);
+
+-- Settled intents per provider, kept after the intent account closes
+CREATE TABLE IF NOT EXISTS outcomes (
+    intent     TEXT PRIMARY KEY,
+    assignee   TEXT NOT NULL,
+    status     INTEGER NOT NULL,
+    disputed   INTEGER NOT NULL,
+    sequence   INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS outcomes_assignee ON outcomes (assignee);
+
+-- Acceptance bonds held on open intents, per provider
+CREATE TABLE IF NOT EXISTS bonds (
+    intent   TEXT PRIMARY KEY,
+    assignee TEXT NOT NULL,
+    amount   INTEGER NOT NULL,
+    sequence INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS bonds_assignee ON bonds (assignee);
+";
+
+#[derive(Debug, Serialize)]
+pub struct AgentRow {
+    pub address: String,
+    pub owner: String,
+    pub agent_wallet: String,
+    pub model_profile: String,
+    pub metadata_uri: String,
+    pub permissions: u64,
+    pub key_version: u32,
+    pub available_slots: u32,
+    pub queue_depth: u32,
+    pub capacity_updated_at: i64,
+    pub reputation_since: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileRow {
+    pub address: String,
+    pub owner: String,
+    pub label: String,
+    pub provider_uri: String,
+    pub pricing: u64,
+    pub billing_wallet: String,
+    pub verified: bool,
+    pub is_public: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntentRow {
+    pub address: String,
+    pub from_agent: String,
+    pub to_agent: String,
+    pub delegate_agent: Option<String>,
+    pub payer: String,
+    pub nonce: u64,
+    pub status: u8,
+    pub payment_amount: u64,
+    pub payment_mint: String,
+    pub payload_uri: String,
+    pub result_uri: String,
+    pub released: bool,
+    pub disputed: bool,
+    pub sequence: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventRow {
+    pub signature: String,
+    pub index: u32,
+    pub slot: u64,
+    pub name: String,
+    pub intent: Option<String>,
+    pub sequence: Option<u64>,
+    /// Borsh-encoded event body, base64
+    pub data: String,
+}
+
+/// An agent eligible for discovery with what it is ranked on, from
+/// [`Store::ranking_candidates`]
+#[derive(Debug)]
+pub struct RankingCandidate {
+    pub agent: AgentRow,
+    pub pricing: u64,
+    pub verified: bool,
+    pub reputation: Reputation,
+    /// Acceptance bonds held on the agent's open intents
+    pub stake: u64,
+}
+
+/// Filters for [`Store::intents`]
+#[derive(Debug, Default)]
+pub struct IntentQuery {
+    pub from_agent: Option<String>,
+    pub to_agent: Option<String>,
+    pub status: Option<u8>,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// SQLite stores integers as i64; u64 values round-trip through the cast
+pub struct Store {
+    conn: Mutex<Connection>,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // === Writes ===
+
+    pub fn upsert_agent(&self, address: &Pubkey, agent: &AgentIdentity, slot: u64) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO agents VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+             ON CONFLICT (address) DO UPDATE SET
+                agent_wallet = excluded.agent_wallet,
+                model_profile = excluded.model_profile,
+                metadata_uri = excluded.metadata_uri,
+                permissions = excluded.permissions,
+                key_version = excluded.key_version,
+                available_slots = excluded.available_slots,
+                queue_depth = excluded.queue_depth,
+                capacity_updated_at = excluded.capacity_updated_at,
+                reputation_since = excluded.reputation_since,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                slot = excluded.slot
+             WHERE excluded.slot >= agents.slot",
+            params![
+                address.to_string(),
+                agent.owner_wallet.to_string(),
+                agent.agent_wallet.to_string(),
+                agent.model_profile.to_string(),
+                agent.metadata_uri,
+                agent.permissions as i64,
+                agent.key_version,
+                agent.available_slots,
+                agent.queue_depth,
+                agent.capacity_updated_at,
+                agent.reputation_since,
+                agent.created_at,
+                agent.updated_at,
+                slot as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_profile(&self, address: &Pubkey, profile: &ModelProfile, slot: u64) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO profiles VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT (address) DO UPDATE SET
+                label = excluded.label,
+                provider_uri = excluded.provider_uri,
+                pricing = excluded.pricing,
+                billing_wallet = excluded.billing_wallet,
+                verified = excluded.verified,
+                is_public = excluded.is_public,
+                updated_at = excluded.updated_at,
+                slot = excluded.slot
+             WHERE excluded.slot >= profiles.slot",
+            params![
+                address.to_string(),
+                profile.owner_wallet.to_string(),
+                profile.label,
+                profile.provider_uri,
+                profile.pricing as i64,
+                profile.billing_wallet.to_string(),
+                profile.verified,
+                profile.is_public,
+                profile.created_at,
+                profile.updated_at,
+                slot as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Applied only if `intent.sequence` is not behind the stored row
+    pub fn upsert_intent(&self, address: &Pubkey, intent: &AgentIntent, slot: u64) -> Result<()> {
+        let delegate = (intent.delegate_agent != Pubkey::default()).then(|| intent.delegate_agent.to_string());
+        self.conn().execute(
+            "INSERT INTO intents VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+             ON CONFLICT (address) DO UPDATE SET
+                delegate_agent = excluded.delegate_agent,
+                status = excluded.status,
+                result_uri = excluded.result_uri,
+                released = excluded.released,
+                disputed = excluded.disputed,
+                sequence = excluded.sequence,
+                updated_at = excluded.updated_at,
+                slot = excluded.slot
+             WHERE excluded.sequence >= intents.sequence",
+            params![
+                address.to_string(),
+                intent.from_agent.to_string(),
+                intent.to_agent.to_string(),
+                delegate,
+                intent.payer.to_string(),
+                intent.nonce as i64,
+                intent.status,
+                intent.payment_amount as i64,
+                intent.payment_mint.to_string(),
+                intent.payload_uri,
+                intent.result_uri,
+                intent.released,
+                intent.disputed,
+                intent.sequence as i64,
+                intent.created_at,
+                intent.updated_at,
+                slot as i64,
+            ],
+        )?;
+        if intent.disputed || IntentStatus::is_terminal(intent.status) {
+            self.conn().execute(
+                "INSERT INTO outcomes VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (intent) DO UPDATE SET
+                    assignee = excluded.assignee,
+                    status = excluded.status,
+                    disputed = excluded.disputed,
+                    sequence = excluded.sequence
+                 WHERE excluded.sequence >= outcomes.sequence",
+                params![
+                    address.to_string(),
+                    intent.assignee().to_string(),
+                    intent.status,
+                    intent.disputed,
+                    intent.sequence as i64,
+                    intent.created_at,
+                ],
+            )?;
+        }
+        if intent.bond > 0 {
+            self.conn().execute(
+                "INSERT INTO bonds VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (intent) DO UPDATE SET
+                    assignee = excluded.assignee,
+                    amount = excluded.amount,
+                    sequence = excluded.sequence
+                 WHERE excluded.sequence >= bonds.sequence",
+                params![
+                    address.to_string(),
+                    intent.assignee().to_string(),
+                    intent.bond as i64,
+                    intent.sequence as i64,
+                ],
+            )?;
+        } else {
+            self.conn().execute(
+                "DELETE FROM bonds WHERE intent = ?1 AND sequence <= ?2",
+                params![address.to_string(), intent.sequence as i64],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// A closed account: drop it from whichever table holds it
+    pub fn remove(&self, address: &Pubkey) -> Result<()> {
+        let address = address.to_string();
+        let conn = self.conn();
+        for table in ["agents", "profiles", "intents"] {
+            conn.execute(&format!("DELETE FROM {table} WHERE address = ?1"), [&address])?;
+        }
+        conn.execute("DELETE FROM bonds WHERE intent = ?1", [&address])?;
+        Ok(())
+    }
+
+    pub fn insert_events(&self, signature: &str, slot: u64, events: &[DecodedEvent]) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT OR IGNORE INTO events VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for event in events {
+                insert.execute(params![
+                    signature,
+                    event.index,
+                    slot as i64,
+                    event.name,
+                    event.intent.map(|intent| intent.to_string()),
+                    event.sequence.map(|sequence| sequence as i64),
+                    event.data,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // === Queries ===
+
+    pub fn agent(&self, address: &str) -> Result<Option<AgentRow>> {
+        Ok(self
+            .conn()
+            .query_row("SELECT * FROM agents WHERE address = ?1", [address], agent_row)
+            .optional()?)
+    }
+
+    pub fn agents(&self, model_profile: Option<&str>, limit: u32, offset: u32) -> Result<Vec<AgentRow>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM agents WHERE ?1 IS NULL OR model_profile = ?1
+             ORDER BY created_at DESC LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(params![model_profile, limit, offset], agent_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Agents that accept intents and have capacity at `now`, with their
+    /// profile's price, their record since `reputation_since` and the bonds
+    /// they hold
+    pub fn ranking_candidates(
+        &self,
+        model_profile: Option<&str>,
+        verified_only: bool,
+        now: i64,
+    ) -> Result<Vec<RankingCandidate>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT agents.*, profiles.pricing AS pricing, profiles.verified AS verified,
+                    COALESCE(record.completed, 0) AS completed,
+                    COALESCE(record.failed, 0) AS failed,
+                    COALESCE(record.disputed, 0) AS disputed,
+                    COALESCE(staked.stake, 0) AS stake
+             FROM agents
+             JOIN profiles ON profiles.address = agents.model_profile
+             LEFT JOIN (
+                SELECT assignee,
+                       SUM(disputed = 0 AND status = ?1) AS completed,
+                       SUM(disputed = 0 AND status = ?2) AS failed,
+                       SUM(disputed = 1) AS disputed
+                FROM outcomes JOIN agents AS assignees ON assignees.address = outcomes.assignee
+                WHERE outcomes.created_at >= assignees.reputation_since
+                GROUP BY assignee
+             ) AS record ON record.assignee = agents.address
+             LEFT JOIN (
+                SELECT assignee, SUM(amount) AS stake FROM bonds GROUP BY assignee
+             ) AS staked ON staked.assignee = agents.address
+             WHERE (?3 IS NULL OR agents.model_profile = ?3)
+               AND (?4 = 0 OR profiles.verified = 1)
+               AND (agents.permissions & ?5) != 0
+               AND (agents.capacity_updated_at = 0 OR agents.available_slots > 0
+                    OR ?6 > agents.capacity_updated_at + ?7)",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                IntentStatus::Completed as u8,
+                IntentStatus::Failed as u8,
+                model_profile,
+                verified_only,
+                Permission::CAN_ACCEPT_INTENT as i64,
+                now,
+                CAPACITY_SIGNAL_TTL,
+            ],
+            |row| {
+                Ok(RankingCandidate {
+                    agent: agent_row(row)?,
+                    pricing: row.get::<_, i64>("pricing")? as u64,
+                    verified: row.get("verified")?,
+                    reputation: Reputation {
+                        completed: row.get::<_, i64>("completed")? as u64,
+                        failed: row.get::<_, i64>("failed")? as u64,
+                        disputed: row.get::<_, i64>("disputed")? as u64,
+                    },
+                    stake: row.get::<_, i64>("stake")? as u64,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn profile(&self, address: &str) -> Result<Option<ProfileRow>> {
+        Ok(self
+            .conn()
+            .query_row("SELECT * FROM profiles WHERE address = ?1", [address], profile_row)
+            .optional()?)
+    }
+
+    /// Public profiles, cheapest first
+    pub fn public_profiles(&self, verified_only: bool, limit: u32, offset: u32) -> Result<Vec<ProfileRow>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM profiles WHERE is_public = 1 AND (?1 = 0 OR verified = 1)
+             ORDER BY pricing ASC LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(params![verified_only, limit, offset], profile_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn intent(&self, address: &str) -> Result<Option<IntentRow>> {
+        Ok(self
+            .conn()
+            .query_row("SELECT * FROM intents WHERE address = ?1", [address], intent_row)
+            .optional()?)
+    }
+
+    pub fn intents(&self, query: &IntentQuery) -> Result<Vec<IntentRow>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM intents
+             WHERE (?1 IS NULL OR from_agent = ?1)
+               AND (?2 IS NULL OR to_agent = ?2 OR delegate_agent = ?2)
+               AND (?3 IS NULL OR status = ?3)
+             ORDER BY created_at DESC LIMIT ?4 OFFSET ?5",
+        )?;
+        let rows = stmt.query_map(
+            params![query.from_agent, query.to_agent, query.status, query.limit, query.offset],
+            intent_row,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// An intent's events in the order they were applied
+    pub fn intent_events(&self, intent: &str) -> Result<Vec<EventRow>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM events WHERE intent = ?1 ORDER BY sequence, slot, idx",
+        )?;
+        let rows = stmt.query_map([intent], event_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Latest events, optionally of one type
+    pub fn events(&self, name: Option<&str>, limit: u32, offset: u32) -> Result<Vec<EventRow>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM events WHERE ?1 IS NULL OR name = ?1
+             ORDER BY slot DESC, idx DESC LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(params![name, limit, offset], event_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn agent_row(row: &Row) -> rusqlite::Result<AgentRow> {
+    Ok(AgentRow {
+        address: row.get("address")?,
+        owner: row.get("owner")?,
+        agent_wallet: row.get("agent_wallet")?,
+        model_profile: row.get("model_profile")?,
+        metadata_uri: row.get("metadata_uri")?,
+        permissions: row.get::<_, i64>("permissions")? as u64,
+        key_version: row.get("key_version")?,
+        available_slots: row.get("available_slots")?,
+        queue_depth: row.get("queue_depth")?,
+        capacity_updated_at: row.get("capacity_updated_at")?,
+        reputation_since: row.get("reputation_since")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        slot: row.get::<_, i64>("slot")? as u64,
+    })
+}
+
+fn profile_row(row: &Row) -> rusqlite::Result<ProfileRow> {
+    Ok(ProfileRow {
+        address: row.get("address")?,
+        owner: row.get("owner")?,
+        label: row.get("label")?,
+        provider_uri: row.get("provider_uri")?,
+        pricing: row.get::<_, i64>("pricing")? as u64,
+        billing_wallet: row.get("billing_wallet")?,
+        verified: row.get("verified")?,
+        is_public: row.get("is_public")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        slot: row.get::<_, i64>("slot")? as u64,
+    })
+}
+
+fn intent_row(row: &Row) -> rusqlite::Result<IntentRow> {
+    Ok(IntentRow {
+        address: row.get("address")?,
+        from_agent: row.get("from_agent")?,
+        to_agent: row.get("to_agent")?,
+        delegate_agent: row.get("delegate_agent")?,
+        payer: row.get("payer")?,
+        nonce: row.get::<_, i64>("nonce")? as u64,
+        status: row.get("status")?,
+        payment_amount: row.get::<_, i64>("payment_amount")? as u64,
+        payment_mint: row.get("payment_mint")?,
+        payload_uri: row.get("payload_uri")?,
+        result_uri: row.get("result_uri")?,
+        released: row.get("released")?,
+        disputed: row.get("disputed")?,
+        sequence: row.get::<_, i64>("sequence")? as u64,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        slot: row.get::<_, i64>("slot")? as u64,
+    })
+}
+
+fn event_row(row: &Row) -> rusqlite::Result<EventRow> {
+    use base64::Engine;
+    Ok(EventRow {
+        signature: row.get("signature")?,
+        index: row.get("idx")?,
+        slot: row.get::<_, i64>("slot")? as u64,
+        name: row.get("name")?,
+        intent: row.get("intent")?,
+        sequence: row.get::<_, Option<i64>>("sequence")?.map(|sequence| sequence as u64),
+        data: base64::engine::general_purpose::STANDARD.encode(row.get::<_, Vec<u8>>("data")?),
+    })
+}
@@ -0,0 +1,19 @@
+pub type Result<T> = std::result::Result<T, McpError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum McpError {
+    #[error(transparent)]
+    Client(#[from] agent_mesh_client::ClientError),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid arguments: {0}")]
+    InvalidArguments(String),
+
+    #[error("unknown tool {0:?}")]
+    UnknownTool(String),
+}
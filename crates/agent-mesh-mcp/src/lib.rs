@@ -0,0 +1,22 @@
+//! Model Context Protocol server for the Agent Mesh program.
+//!
+//! - [`protocol`]: JSON-RPC 2.0 messages as MCP frames them over stdio
+//! - [`tools`]: the mesh operations offered as tools (discover agents,
+//!   quote, create, accept, inspect and settle intents), backed by the SDK
+//! - [`server`]: the stdio loop answering `initialize`, `tools/list` and
+//!   `tools/call`
+//!
+//! Tools sign with one wallet: the owner of the host's own agent. Tool
+//! failures come back as `isError` results so the model can read them;
+//! malformed requests get JSON-RPC errors.
+
+#![allow(clippy::result_large_err)]
+
+pub mod error;
+pub mod protocol;
+pub mod server;
+pub mod tools;
+
+pub use error::{McpError, Result};
+pub use server::Server;
+pub use tools::Tools;
@@ -0,0 +1,43 @@
+//! `agent-mesh-mcp`: serve the mesh as MCP tools over stdio.
+
+use std::path::PathBuf;
+
+use agent_mesh_client::AgentMeshClient;
+use agent_mesh_mcp::{Server, Tools};
+use clap::Parser;
+use solana_sdk::signature::read_keypair_file;
+
+#[derive(Parser)]
+#[command(name = "agent-mesh-mcp", version, about = "Model Context Protocol server for Solana Agent Mesh")]
+struct Args {
+    /// RPC endpoint
+    #[arg(long, env = "RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// Keypair of the agent owner the tools act as
+    #[arg(long, env = "AGENT_MESH_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = Args::parse();
+    let path = match args.keypair.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(&args.keypair),
+    };
+    let keypair = match read_keypair_file(&path) {
+        Ok(keypair) => keypair,
+        Err(err) => {
+            eprintln!("error: failed to read keypair {}: {err}", path.display());
+            std::process::exit(1);
+        }
+    };
+
+    // stdout carries the protocol; logs go to stderr
+    let server = Server::new(Tools::new(AgentMeshClient::new(args.rpc_url, keypair)));
+    if let Err(err) = server.run().await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,99 @@
+//! stdio transport: one JSON-RPC message per line in, one per line out.
+//! Requests are handled in order; notifications get no reply.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::protocol::{
+    Request, Response, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR, PROTOCOL_VERSION,
+};
+use crate::{McpError, Result, Tools};
+
+pub struct Server {
+    tools: Tools,
+}
+
+#[derive(Deserialize)]
+struct CallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+impl Server {
+    pub fn new(tools: Tools) -> Self {
+        Self { tools }
+    }
+
+    /// Serve stdin/stdout until the host closes stdin
+    pub async fn run(&self) -> Result<()> {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some(response) = self.handle(&line).await else {
+                continue;
+            };
+            let mut out = serde_json::to_string(&response)?;
+            out.push('\n');
+            stdout.write_all(out.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Reply to one line, or `None` for a notification
+    pub async fn handle(&self, line: &str) -> Option<Response> {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => return Some(Response::error(Value::Null, PARSE_ERROR, err)),
+        };
+        let id = request.id?;
+        if request.jsonrpc != "2.0" {
+            return Some(Response::error(id, INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+        }
+
+        Some(match request.method.as_str() {
+            "initialize" => Response::result(
+                id,
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            ),
+            "ping" => Response::result(id, json!({})),
+            "tools/list" => Response::result(id, json!({ "tools": Tools::definitions() })),
+            "tools/call" => self.call(id, request.params).await,
+            method => Response::error(id, METHOD_NOT_FOUND, format!("unknown method {method:?}")),
+        })
+    }
+
+    async fn call(&self, id: Value, params: Value) -> Response {
+        let params: CallParams = match serde_json::from_value(params) {
+            Ok(params) => params,
+            Err(err) => return Response::error(id, INVALID_PARAMS, err),
+        };
+        match self.tools.call(&params.name, params.arguments).await {
+            Ok(output) => {
+                let text = serde_json::to_string_pretty(&output).unwrap_or_else(|_| output.to_string());
+                Response::result(id, tool_result(text, false))
+            }
+            Err(err @ McpError::UnknownTool(_)) => Response::error(id, INVALID_PARAMS, err),
+            Err(err) => Response::result(id, tool_result(err.to_string(), true)),
+        }
+    }
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
@@ -0,0 +1,455 @@
+//! Mesh operations as MCP tools. Arguments are JSON objects; public keys are
+//! base58 strings and hashes are hex, as in the gateway API. Results are
+//! JSON, returned to the model as text.
+
+use std::time::Duration;
+
+use agent_mesh_client::agent_mesh::{
+    AgentIdentity, AgentIntent, CommitmentScheme, ContentCommitment, IntentOption, IntentStatus,
+};
+use agent_mesh_client::instructions::UpdateIntentStatusBuilder;
+use agent_mesh_client::{commitment, pda, AgentMeshClient, AgentQuery, CreateIntentParams, FoundAgent, IntentRole};
+use anchor_spl::associated_token::get_associated_token_address;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+
+use crate::{McpError, Result};
+
+const DEFAULT_DISCOVER_LIMIT: usize = 20;
+const DEFAULT_WAIT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Tool handlers, signing as the host's agent owner
+pub struct Tools {
+    client: AgentMeshClient<Keypair>,
+}
+
+impl Tools {
+    pub fn new(client: AgentMeshClient<Keypair>) -> Self {
+        Self { client }
+    }
+
+    /// `tools/list` entries: name, description and JSON Schema for the arguments
+    pub fn definitions() -> Vec<Value> {
+        vec![
+            json!({
+                "name": "discover_agents",
+                "description": "Find provider agents that accept intents and have capacity, best first by delivery record, capacity heartbeat and price (micro-units per 1K tokens).",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "model_profile": { "type": "string", "description": "Only agents on this model profile" },
+                        "verified_only": { "type": "boolean", "description": "Only attested model profiles" },
+                        "limit": { "type": "integer", "minimum": 1 }
+                    }
+                }
+            }),
+            json!({
+                "name": "quote",
+                "description": "Price a request of `tokens` at a provider agent's current pricing.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "to_agent": { "type": "string" },
+                        "tokens": { "type": "integer", "minimum": 0 }
+                    },
+                    "required": ["to_agent", "tokens"]
+                }
+            }),
+            json!({
+                "name": "create_intent",
+                "description": "Open an intent from your agent to a provider, escrowing the quoted payment from your associated token account.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "to_agent": { "type": "string" },
+                        "payment_mint": { "type": "string" },
+                        "tokens": { "type": "integer", "minimum": 0, "description": "Token budget, priced at the provider's profile" },
+                        "payload_uri": { "type": "string", "description": "Where the provider fetches the task" },
+                        "payload": { "type": "string", "description": "Task contents; committed to as sha256 and length" },
+                        "payload_hash": { "type": "string", "description": "sha256 of the payload as hex, instead of `payload`" },
+                        "payload_length": { "type": "integer", "minimum": 0, "description": "Payload size in bytes, with `payload_hash`" },
+                        "auto_release": { "type": "boolean", "description": "Release payment after the challenge window instead of on completion" },
+                        "tip_amount": { "type": "integer", "minimum": 0, "description": "Priority tip paid to the provider when it accepts" }
+                    },
+                    "required": ["to_agent", "payment_mint", "tokens", "payload_uri"]
+                }
+            }),
+            json!({
+                "name": "get_intent",
+                "description": "Show an intent's status, payment, payload and result. Optionally wait until it reaches a status.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "intent": { "type": "string" },
+                        "wait_for": { "type": "string", "enum": ["accepted", "completed"] },
+                        "timeout_secs": { "type": "integer", "minimum": 1 }
+                    },
+                    "required": ["intent"]
+                }
+            }),
+            json!({
+                "name": "list_intents",
+                "description": "List intents an agent requested or is providing (defaults to your agent's requests).",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "agent": { "type": "string" },
+                        "role": { "type": "string", "enum": ["requester", "provider"] }
+                    }
+                }
+            }),
+            json!({
+                "name": "accept_intent",
+                "description": "Accept an intent addressed to your agent.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "intent": { "type": "string" } },
+                    "required": ["intent"]
+                }
+            }),
+            json!({
+                "name": "settle_intent",
+                "description": "Complete an intent as its provider with a result, releasing the escrow to the profile's billing wallet.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "intent": { "type": "string" },
+                        "result_uri": { "type": "string" },
+                        "result": { "type": "string", "description": "Result contents; committed to as sha256 and length" },
+                        "result_hash": { "type": "string", "description": "sha256 of the result as hex, instead of `result`" },
+                        "result_length": { "type": "integer", "minimum": 0, "description": "Result size in bytes, with `result_hash`" },
+                        "billing_token_account": { "type": "string", "description": "Defaults to the billing wallet's associated token account" }
+                    },
+                    "required": ["intent", "result_uri"]
+                }
+            }),
+        ]
+    }
+
+    pub async fn call(&self, name: &str, arguments: Value) -> Result<Value> {
+        match name {
+            "discover_agents" => self.discover_agents(args(arguments)?).await,
+            "quote" => self.quote(args(arguments)?).await,
+            "create_intent" => self.create_intent(args(arguments)?).await,
+            "get_intent" => self.get_intent(args(arguments)?).await,
+            "list_intents" => self.list_intents(args(arguments)?).await,
+            "accept_intent" => self.accept_intent(args(arguments)?).await,
+            "settle_intent" => self.settle_intent(args(arguments)?).await,
+            _ => Err(McpError::UnknownTool(name.to_string())),
+        }
+    }
+
+    async fn discover_agents(&self, args: DiscoverArgs) -> Result<Value> {
+        let query = AgentQuery {
+            model_profile: args
+                .model_profile
+                .as_deref()
+                .map(|profile| parse_pubkey("model_profile", profile))
+                .transpose()?,
+            verified_only: args.verified_only,
+            limit: args.limit.unwrap_or(DEFAULT_DISCOVER_LIMIT),
+            ..AgentQuery::default()
+        };
+        let found = self.client.find_agents(&query).await?;
+
+        Ok(Value::Array(
+            found
+                .into_iter()
+                .map(|ranked| {
+                    let FoundAgent { address, agent, profile } = ranked.candidate;
+                    let reputation = ranked.signals.reputation;
+                    json!({
+                        "agent": address.to_string(),
+                        "owner": agent.owner_wallet.to_string(),
+                        "metadata_uri": agent.metadata_uri,
+                        "model_profile": agent.model_profile.to_string(),
+                        "label": profile.label,
+                        "provider_uri": profile.provider_uri,
+                        "pricing": profile.pricing,
+                        "verified": profile.verified,
+                        "available_slots": agent.available_slots,
+                        "queue_depth": agent.queue_depth,
+                        "completed": reputation.completed,
+                        "failed": reputation.failed,
+                        "disputed": reputation.disputed,
+                        "score": ranked.score,
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    async fn quote(&self, args: QuoteArgs) -> Result<Value> {
+        let to_agent = parse_pubkey("to_agent", &args.to_agent)?;
+        let payment_amount = self.client.quote(&to_agent, args.tokens).await?;
+        Ok(json!({
+            "to_agent": args.to_agent,
+            "tokens": args.tokens,
+            "payment_amount": payment_amount,
+        }))
+    }
+
+    async fn create_intent(&self, args: CreateIntentArgs) -> Result<Value> {
+        let owner = self.client.payer();
+        let payment_mint = parse_pubkey("payment_mint", &args.payment_mint)?;
+        let payload = sha256_commitment("payload", &args.payload, &args.payload_hash, args.payload_length)?;
+
+        let address = self
+            .client
+            .create_intent(CreateIntentParams {
+                from_agent: pda::agent(&owner).0,
+                to_agent: parse_pubkey("to_agent", &args.to_agent)?,
+                payload,
+                payload_uri: args.payload_uri,
+                payment_mint,
+                from_token_account: get_associated_token_address(&owner, &payment_mint),
+                tokens: args.tokens,
+                options: if args.auto_release { IntentOption::AUTO_RELEASE } else { 0 },
+                tip_amount: args.tip_amount,
+                context: None,
+            })
+            .await?;
+        let intent = self.client.intent(&address).await?;
+        Ok(intent_summary(&address, &intent))
+    }
+
+    async fn get_intent(&self, args: GetIntentArgs) -> Result<Value> {
+        let address = parse_pubkey("intent", &args.intent)?;
+        let status = match args.wait_for.as_deref() {
+            None => None,
+            Some("accepted") => Some(IntentStatus::Accepted),
+            Some("completed") => Some(IntentStatus::Completed),
+            Some(_) => {
+                return Err(McpError::InvalidArguments("wait_for must be accepted or completed".to_string()))
+            }
+        };
+        let intent = match status {
+            Some(status) => {
+                let timeout = args.timeout_secs.map_or(DEFAULT_WAIT, Duration::from_secs);
+                self.client
+                    .wait_for_status(&address, status, POLL_INTERVAL, timeout)
+                    .await?
+            }
+            None => self.client.intent(&address).await?,
+        };
+        Ok(intent_summary(&address, &intent))
+    }
+
+    async fn list_intents(&self, args: ListIntentsArgs) -> Result<Value> {
+        let agent = match &args.agent {
+            Some(agent) => parse_pubkey("agent", agent)?,
+            None => pda::agent(&self.client.payer()).0,
+        };
+        let role = match args.role.as_deref() {
+            Some("requester") | None => IntentRole::Requester,
+            Some("provider") => IntentRole::Provider,
+            Some(_) => return Err(McpError::InvalidArguments("role must be requester or provider".to_string())),
+        };
+        let intents = self.client.intents_for_agent(&agent, role).await?;
+        Ok(Value::Array(
+            intents
+                .iter()
+                .map(|(address, intent)| intent_summary(address, intent))
+                .collect(),
+        ))
+    }
+
+    async fn accept_intent(&self, args: IntentArgs) -> Result<Value> {
+        let address = parse_pubkey("intent", &args.intent)?;
+        let intent = self.client.intent(&address).await?;
+        let mut builder = UpdateIntentStatusBuilder::new(
+            &self.client.payer(),
+            address,
+            intent.assignee(),
+            IntentStatus::Accepted as u8,
+        );
+        // The tip is paid out on acceptance, to the profile's billing wallet
+        if intent.tip_amount > 0 {
+            let agent: AgentIdentity = self.client.fetch(&intent.assignee()).await?;
+            builder = builder.release(
+                pda::escrow(&address, &intent.payment_mint),
+                self.payout_account(&agent, agent.group, &intent.payment_mint).await?,
+            );
+        }
+        let ix = builder.instruction();
+        let signature = self.client.send(&[ix], &[]).await?;
+        Ok(json!({ "intent": args.intent, "signature": signature.to_string() }))
+    }
+
+    async fn settle_intent(&self, args: SettleArgs) -> Result<Value> {
+        let address = parse_pubkey("intent", &args.intent)?;
+        let result = sha256_commitment("result", &args.result, &args.result_hash, args.result_length)?;
+
+        let intent = self.client.intent(&address).await?;
+        let billing_token_account = match &args.billing_token_account {
+            Some(account) => parse_pubkey("billing_token_account", account)?,
+            None => {
+                let agent: AgentIdentity = self.client.fetch(&intent.assignee()).await?;
+                self.payout_account(&agent, intent.payout_group, &intent.payment_mint).await?
+            }
+        };
+        let config = self.client.config().await?;
+        let fee_token_account = (config.protocol_fee_bps > 0)
+            .then(|| get_associated_token_address(&config.fee_treasury, &intent.payment_mint));
+
+        let signature = self
+            .client
+            .settle(&address, result, args.result_uri, billing_token_account, fee_token_account)
+            .await?;
+        Ok(json!({ "intent": args.intent, "signature": signature.to_string() }))
+    }
+
+    /// Where `agent` is paid in `mint`: the treasury of the group it routes
+    /// its earnings to, otherwise its profile's billing wallet
+    async fn payout_account(&self, agent: &AgentIdentity, group: Pubkey, mint: &Pubkey) -> Result<Pubkey> {
+        if group != Pubkey::default() {
+            return Ok(pda::group_treasury(&group, mint));
+        }
+        let profile = self.client.model_profile(&agent.model_profile).await?;
+        Ok(get_associated_token_address(&profile.billing_wallet, mint))
+    }
+}
+
+#[derive(Deserialize)]
+struct DiscoverArgs {
+    model_profile: Option<String>,
+    #[serde(default)]
+    verified_only: bool,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct QuoteArgs {
+    to_agent: String,
+    tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct CreateIntentArgs {
+    to_agent: String,
+    payment_mint: String,
+    tokens: u64,
+    payload_uri: String,
+    payload: Option<String>,
+    payload_hash: Option<String>,
+    payload_length: Option<u64>,
+    #[serde(default)]
+    auto_release: bool,
+    #[serde(default)]
+    tip_amount: u64,
+}
+
+#[derive(Deserialize)]
+struct GetIntentArgs {
+    intent: String,
+    wait_for: Option<String>,
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ListIntentsArgs {
+    agent: Option<String>,
+    role: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IntentArgs {
+    intent: String,
+}
+
+#[derive(Deserialize)]
+struct SettleArgs {
+    intent: String,
+    result_uri: String,
+    result: Option<String>,
+    result_hash: Option<String>,
+    result_length: Option<u64>,
+    billing_token_account: Option<String>,
+}
+
+/// Missing arguments are read as an empty object, so tools without required
+/// arguments can be called bare
+fn args<T: DeserializeOwned>(arguments: Value) -> Result<T> {
+    let arguments = if arguments.is_null() { json!({}) } else { arguments };
+    serde_json::from_value(arguments).map_err(|err| McpError::InvalidArguments(err.to_string()))
+}
+
+fn intent_summary(address: &Pubkey, intent: &AgentIntent) -> Value {
+    json!({
+        "intent": address.to_string(),
+        "status": status_name(intent.status),
+        "from_agent": intent.from_agent.to_string(),
+        "to_agent": intent.to_agent.to_string(),
+        "assignee": intent.assignee().to_string(),
+        "nonce": intent.nonce,
+        "payment_amount": intent.payment_amount,
+        "payment_mint": intent.payment_mint.to_string(),
+        "tip_amount": intent.tip_amount,
+        "payload_uri": intent.payload_uri,
+        "payload_hash": to_hex(&intent.payload_hash),
+        "payload_length": intent.payload_length,
+        "result_uri": (!intent.result_uri.is_empty()).then_some(&intent.result_uri),
+        "result_hash": (!intent.result_uri.is_empty()).then(|| to_hex(&intent.result_hash)),
+        "result_length": (!intent.result_uri.is_empty()).then_some(intent.result_length),
+        "released": intent.released,
+    })
+}
+
+fn status_name(status: u8) -> &'static str {
+    match status {
+        s if s == IntentStatus::Pending as u8 => "pending",
+        s if s == IntentStatus::Accepted as u8 => "accepted",
+        s if s == IntentStatus::Completed as u8 => "completed",
+        s if s == IntentStatus::Failed as u8 => "failed",
+        s if s == IntentStatus::Cancelled as u8 => "cancelled",
+        _ => "unknown",
+    }
+}
+
+fn parse_pubkey(field: &str, value: &str) -> Result<Pubkey> {
+    value
+        .parse()
+        .map_err(|_| McpError::InvalidArguments(format!("{field} is not a valid public key")))
+}
+
+/// sha256 commitment to `content`, or to a hash and length given as hex
+/// `<field>_hash` and `<field>_length`
+fn sha256_commitment(
+    field: &str,
+    content: &Option<String>,
+    hash: &Option<String>,
+    length: Option<u64>,
+) -> Result<ContentCommitment> {
+    match (hash, length, content) {
+        (Some(hex), Some(length), _) => Ok(ContentCommitment {
+            scheme: CommitmentScheme::Sha256 as u8,
+            length,
+            hash: parse_hash(&format!("{field}_hash"), hex)?,
+        }),
+        (Some(_), None, _) => Err(McpError::InvalidArguments(format!("{field}_length is required with {field}_hash"))),
+        (None, _, Some(content)) => Ok(commitment::commit(CommitmentScheme::Sha256, content.as_bytes())),
+        (None, _, None) => Err(McpError::InvalidArguments(format!("{field} or {field}_hash is required"))),
+    }
+}
+
+fn parse_hash(field: &str, value: &str) -> Result<[u8; 32]> {
+    let invalid = || McpError::InvalidArguments(format!("{field} must be 32 bytes of hex"));
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    if value.len() != 64 || !value.is_ascii() {
+        return Err(invalid());
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(value.as_bytes().chunks_exact(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(hash)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
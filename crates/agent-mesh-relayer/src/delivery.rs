@@ -0,0 +1,247 @@
+//! HTTP delivery with retries, receipts and the deadletter queue.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{RelayerError, Result};
+
+/// Matches the app's `DEFAULT_MAX_ATTEMPTS`
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; doubled after each failed attempt
+pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One webhook POST, in the app's `WebhookJob` shape
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebhookJob {
+    pub url: String,
+    pub body: serde_json::Value,
+    pub headers: BTreeMap<String, String>,
+}
+
+/// A job that exhausted its retries, in the app's `DeadletterEntry` shape
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadletterEntry {
+    pub id: String,
+    pub kind: String,
+    pub job: WebhookJob,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: String,
+}
+
+/// Outcome of one delivery, appended to the receipt log
+#[derive(Serialize, Deserialize)]
+pub struct Receipt {
+    pub id: String,
+    pub intent: String,
+    pub slot: u64,
+    pub url: String,
+    pub delivered: bool,
+    /// Last HTTP status, absent if no response was received
+    pub status: Option<u16>,
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub at: String,
+}
+
+/// A delivery handed to a task, logged before it is sent so a restart can
+/// find deliveries that never got a receipt
+#[derive(Serialize, Deserialize)]
+pub struct Claim {
+    pub id: String,
+    pub slot: u64,
+}
+
+/// Append-only JSON-lines file
+struct JsonLines(Mutex<File>);
+
+impl JsonLines {
+    fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self(Mutex::new(file)))
+    }
+
+    fn append<T: Serialize>(&self, entry: &T) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Delivery receipts, claims and the deadletter queue. Receipts already on
+/// disk mark their deliveries as done, so restarts and backfills don't
+/// resend; claims without a receipt were cut off mid-delivery and are sent
+/// again.
+pub struct Ledger {
+    receipts: JsonLines,
+    claims: JsonLines,
+    deadletter: JsonLines,
+    done: Mutex<HashSet<String>>,
+    resume_slot: Option<u64>,
+}
+
+impl Ledger {
+    pub fn open(receipts: &Path, claims: &Path, deadletter: &Path) -> Result<Self> {
+        let mut done = HashSet::new();
+        let mut last_slot = None;
+        for receipt in read_lines::<Receipt>(receipts)? {
+            last_slot = Some(last_slot.map_or(receipt.slot, |slot: u64| slot.max(receipt.slot)));
+            done.insert(receipt.id);
+        }
+        let mut unfinished = HashMap::new();
+        for claim in read_lines::<Claim>(claims)? {
+            if !done.contains(&claim.id) {
+                unfinished.insert(claim.id, claim.slot);
+            }
+        }
+        Ok(Self {
+            receipts: JsonLines::open(receipts)?,
+            claims: JsonLines::open(claims)?,
+            deadletter: JsonLines::open(deadletter)?,
+            done: Mutex::new(done),
+            resume_slot: unfinished.into_values().min().or(last_slot),
+        })
+    }
+
+    /// Slot to resume from after a restart: the oldest claimed delivery
+    /// without a receipt, else the highest slot with a recorded delivery
+    pub fn resume_slot(&self) -> Option<u64> {
+        self.resume_slot
+    }
+
+    /// Claim a delivery and log the claim; false if it was already
+    /// delivered or claimed
+    pub fn claim(&self, id: &str, slot: u64) -> Result<bool> {
+        let mut done = self.done.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !done.insert(id.to_string()) {
+            return Ok(false);
+        }
+        self.claims.append(&Claim {
+            id: id.to_string(),
+            slot,
+        })?;
+        Ok(true)
+    }
+
+    pub fn record(&self, receipt: &Receipt) -> Result<()> {
+        self.receipts.append(receipt)
+    }
+
+    pub fn bury(&self, entry: &DeadletterEntry) -> Result<()> {
+        self.deadletter.append(entry)
+    }
+}
+
+/// Entries of a JSON-lines log, skipping lines that don't parse; empty if
+/// the log doesn't exist yet
+fn read_lines<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// POST the job until it gets a 2xx response or `max_attempts` run out.
+/// Returns the attempts made and the last status or error.
+pub async fn deliver(
+    http: &reqwest::Client,
+    job: &WebhookJob,
+    max_attempts: u32,
+    backoff: Duration,
+) -> (u32, Option<u16>, Result<()>) {
+    let mut last_status = None;
+    let mut last_error = None;
+    for attempt in 1..=max_attempts.max(1) {
+        let mut request = http
+            .post(&job.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(job.body.to_string());
+        for (name, value) in &job.headers {
+            request = request.header(name, value);
+        }
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                return (attempt, Some(response.status().as_u16()), Ok(()));
+            }
+            Ok(response) => {
+                last_status = Some(response.status().as_u16());
+                last_error = Some(RelayerError::Status(response.status()));
+            }
+            Err(err) => last_error = Some(err.into()),
+        }
+        if attempt < max_attempts {
+            tokio::time::sleep(backoff * 2u32.saturating_pow(attempt - 1)).await;
+        }
+    }
+    let error = last_error.expect("at least one attempt was made");
+    (max_attempts.max(1), last_status, Err(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(id: &str, slot: u64) -> Receipt {
+        Receipt {
+            id: id.to_string(),
+            intent: "intent".to_string(),
+            slot,
+            url: "https://agent.example.com/hooks/intent".to_string(),
+            delivered: true,
+            status: Some(200),
+            attempts: 1,
+            error: None,
+            at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn restart_resumes_from_the_oldest_claim_without_a_receipt() {
+        let dir = std::env::temp_dir().join(format!("agent-mesh-relayer-ledger-{}", std::process::id()));
+        let receipts = dir.join("receipts.jsonl");
+        let claims = dir.join("claims.jsonl");
+        let deadletter = dir.join("deadletter.jsonl");
+
+        let ledger = Ledger::open(&receipts, &claims, &deadletter).unwrap();
+        assert_eq!(ledger.resume_slot(), None);
+        // The slot-10 delivery is still retrying when the slot-20 one lands
+        assert!(ledger.claim("a", 10).unwrap());
+        assert!(ledger.claim("b", 20).unwrap());
+        assert!(!ledger.claim("b", 20).unwrap());
+        ledger.record(&receipt("b", 20)).unwrap();
+        drop(ledger);
+
+        let ledger = Ledger::open(&receipts, &claims, &deadletter).unwrap();
+        assert_eq!(ledger.resume_slot(), Some(10));
+        assert!(ledger.claim("a", 10).unwrap());
+        assert!(!ledger.claim("b", 20).unwrap());
+        ledger.record(&receipt("a", 10)).unwrap();
+        drop(ledger);
+
+        let ledger = Ledger::open(&receipts, &claims, &deadletter).unwrap();
+        assert_eq!(ledger.resume_slot(), Some(20));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
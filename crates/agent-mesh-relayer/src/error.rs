@@ -0,0 +1,19 @@
+pub type Result<T> = std::result::Result<T, RelayerError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RelayerError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("endpoint returned {0}")]
+    Status(reqwest::StatusCode),
+
+    #[error("invalid route {0:?} (expected <AGENT>=<URL>)")]
+    InvalidRoute(String),
+}
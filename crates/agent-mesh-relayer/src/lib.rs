@@ -0,0 +1,22 @@
+//! Webhook relayer for the Agent Mesh program.
+//!
+//! - [`payload`]: the JSON body posted for each `IntentCreated` and its
+//!   HMAC-SHA256 signature
+//! - [`delivery`]: POSTs with exponential backoff, the delivery receipt and
+//!   claim logs, and the deadletter queue for deliveries that exhaust their
+//!   retries
+//! - [`relay`]: follows program events and routes intents to the endpoint
+//!   configured for their provider agent
+//!
+//! Receivers verify `X-Agent-Mesh-Signature` as
+//! `sha256=hex(HMAC-SHA256(secret, "{X-Agent-Mesh-Timestamp}.{body}"))`.
+//! Dead deliveries use the app's deadletter format, so
+//! `npm run deadletter -- replay` can retry them.
+
+pub mod delivery;
+pub mod error;
+pub mod payload;
+pub mod relay;
+
+pub use error::{RelayerError, Result};
+pub use relay::{Relayer, RelayerConfig, Route};
@@ -0,0 +1,73 @@
+//! `agent-mesh-relayer`: deliver intents to agents as signed webhooks.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use agent_mesh_relayer::delivery::{DEFAULT_BACKOFF, DEFAULT_MAX_ATTEMPTS};
+use agent_mesh_relayer::{Relayer, RelayerConfig, Route};
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "agent-mesh-relayer", version, about = "POST Solana Agent Mesh intents to agent webhooks")]
+struct Args {
+    /// RPC endpoint used to backfill missed transactions
+    #[arg(long, env = "RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// WebSocket endpoint for the log subscription
+    #[arg(long, env = "WS_URL", default_value = "wss://api.devnet.solana.com")]
+    ws_url: String,
+
+    /// `<AGENT>=<URL>`: POST intents addressed to AGENT to URL (repeatable)
+    #[arg(long = "route", required = true)]
+    routes: Vec<Route>,
+
+    /// Shared secret for the HMAC signature header
+    #[arg(long, env = "WEBHOOK_SECRET", hide_env_values = true)]
+    secret: String,
+
+    /// Delivery receipt log (JSON lines)
+    #[arg(long, env = "RELAYER_RECEIPTS", default_value = "relayer-receipts.jsonl")]
+    receipts: PathBuf,
+
+    /// Claim log of deliveries in flight (JSON lines)
+    #[arg(long, env = "RELAYER_CLAIMS", default_value = "relayer-claims.jsonl")]
+    claims: PathBuf,
+
+    /// Deadletter queue shared with `npm run deadletter`
+    #[arg(long, env = "DEADLETTER_PATH", default_value = "deadletter.jsonl")]
+    deadletter: PathBuf,
+
+    /// Replay from this slot (defaults to the oldest claim without a receipt,
+    /// else the last slot in the receipt log)
+    #[arg(long)]
+    start_slot: Option<u64>,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_ATTEMPTS)]
+    max_attempts: u32,
+
+    /// Initial retry delay in milliseconds, doubled after each attempt
+    #[arg(long, default_value_t = DEFAULT_BACKOFF.as_millis() as u64)]
+    backoff_ms: u64,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let config = RelayerConfig {
+        rpc_url: args.rpc_url,
+        ws_url: args.ws_url,
+        routes: args.routes,
+        secret: args.secret.into_bytes(),
+        receipts: args.receipts,
+        claims: args.claims,
+        deadletter: args.deadletter,
+        start_slot: args.start_slot,
+        max_attempts: args.max_attempts,
+        backoff: Duration::from_millis(args.backoff_ms),
+    };
+    if let Err(err) = Relayer::run(config).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,84 @@
+//! Webhook body and signature.
+
+use agent_mesh_client::agent_mesh::IntentCreated;
+use agent_mesh_client::EventNotification;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+pub const SIGNATURE_HEADER: &str = "X-Agent-Mesh-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Agent-Mesh-Timestamp";
+pub const DELIVERY_HEADER: &str = "X-Agent-Mesh-Delivery";
+
+/// Body posted for an `IntentCreated`. Token amounts and the nonce are
+/// decimal strings so JavaScript receivers don't lose precision.
+#[derive(Serialize)]
+pub struct IntentCreatedPayload {
+    /// Stable per event (`<signature>:<index>`); receivers dedupe on it
+    pub id: String,
+    pub event: &'static str,
+    pub signature: String,
+    pub slot: u64,
+    pub intent: String,
+    pub sequence: u64,
+    pub from_agent: String,
+    pub to_agent: String,
+    pub payer: String,
+    pub nonce: String,
+    pub payload_hash: String,
+    pub payload_uri: String,
+    pub payment_amount: String,
+    pub payment_mint: String,
+    pub tip_amount: String,
+    pub options: u8,
+    pub context: Option<String>,
+    /// `IntentTemplate` the intent was stamped from, whose schema the payload follows
+    pub template: Option<String>,
+    /// `PayloadSchema` version the requester declared for the payload
+    pub schema: Option<String>,
+    pub created_at: i64,
+}
+
+impl IntentCreatedPayload {
+    pub fn new(notification: &EventNotification, event: &IntentCreated) -> Self {
+        Self {
+            id: delivery_id(notification),
+            event: "IntentCreated",
+            signature: notification.signature.to_string(),
+            slot: notification.slot,
+            intent: event.intent.to_string(),
+            sequence: event.sequence,
+            from_agent: event.from_agent.to_string(),
+            to_agent: event.to_agent.to_string(),
+            payer: event.payer.to_string(),
+            nonce: event.nonce.to_string(),
+            payload_hash: to_hex(&event.payload_hash),
+            payload_uri: event.payload_uri.clone(),
+            payment_amount: event.payment_amount.to_string(),
+            payment_mint: event.payment_mint.to_string(),
+            tip_amount: event.tip_amount.to_string(),
+            options: event.options,
+            context: (event.context != Default::default()).then(|| event.context.to_string()),
+            template: (event.template != Default::default()).then(|| event.template.to_string()),
+            schema: (event.schema != Default::default()).then(|| event.schema.to_string()),
+            created_at: event.created_at,
+        }
+    }
+}
+
+pub fn delivery_id(notification: &EventNotification) -> String {
+    format!("{}:{}", notification.signature, notification.index)
+}
+
+/// `sha256=<hex>` over `"{timestamp}.{body}"`
+pub fn sign(secret: &[u8], timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,191 @@
+//! Event routing: provider agent to webhook endpoint.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agent_mesh_client::agent_mesh::IntentCreated;
+use agent_mesh_client::{AgentMeshEvent, EventNotification, EventStream, EventStreamConfig};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::delivery::{self, DeadletterEntry, Ledger, Receipt, WebhookJob};
+use crate::payload::{self, IntentCreatedPayload};
+use crate::{RelayerError, Result};
+
+/// Deliver intents for `agent` to `url`
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub agent: Pubkey,
+    pub url: String,
+}
+
+impl FromStr for Route {
+    type Err = RelayerError;
+
+    /// `<AGENT>=<URL>`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || RelayerError::InvalidRoute(s.to_string());
+        let (agent, url) = s.split_once('=').ok_or_else(invalid)?;
+        let agent = agent.trim().parse().map_err(|_| invalid())?;
+        let url = url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            agent,
+            url: url.to_string(),
+        })
+    }
+}
+
+pub struct RelayerConfig {
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub routes: Vec<Route>,
+    /// Shared HMAC secret for `X-Agent-Mesh-Signature`
+    pub secret: Vec<u8>,
+    pub receipts: PathBuf,
+    /// Deliveries handed to a task, logged before they are sent
+    pub claims: PathBuf,
+    pub deadletter: PathBuf,
+    /// Replay from this slot; defaults to [`Ledger::resume_slot`]
+    pub start_slot: Option<u64>,
+    pub max_attempts: u32,
+    pub backoff: Duration,
+}
+
+/// Follows program events and delivers each matching `IntentCreated` on
+/// its own task, so one slow endpoint doesn't hold up the others
+pub struct Relayer {
+    routes: HashMap<Pubkey, String>,
+    secret: Vec<u8>,
+    ledger: Ledger,
+    http: reqwest::Client,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl Relayer {
+    /// Open the receipt log, claim log and deadletter queue and relay until
+    /// the process exits; stream errors are logged and the stream reconnects
+    pub async fn run(config: RelayerConfig) -> Result<()> {
+        let ledger = Ledger::open(&config.receipts, &config.claims, &config.deadletter)?;
+        let start_slot = config.start_slot.or(ledger.resume_slot());
+        let relayer = Arc::new(Self {
+            routes: config
+                .routes
+                .into_iter()
+                .map(|route| (route.agent, route.url))
+                .collect(),
+            secret: config.secret,
+            ledger,
+            http: reqwest::Client::new(),
+            max_attempts: config.max_attempts,
+            backoff: config.backoff,
+        });
+
+        let mut stream = EventStream::subscribe(
+            config.rpc_url,
+            config.ws_url,
+            EventStreamConfig {
+                start_slot,
+                ..EventStreamConfig::default()
+            },
+        );
+        eprintln!(
+            "relayer: routing {} agent(s), resuming from slot {}",
+            relayer.routes.len(),
+            start_slot.map_or("latest".to_string(), |slot| slot.to_string())
+        );
+        while let Some(next) = stream.next().await {
+            match next {
+                Ok(notification) => relayer.dispatch(notification),
+                Err(err) => eprintln!("relayer: stream error: {err}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn dispatch(self: &Arc<Self>, notification: EventNotification) {
+        let AgentMeshEvent::IntentCreated(event) = &notification.event else {
+            return;
+        };
+        let Some(url) = self.routes.get(&event.to_agent) else {
+            return;
+        };
+        let id = payload::delivery_id(&notification);
+        let job = match self.job(url, &notification, event) {
+            Ok(job) => job,
+            Err(err) => {
+                eprintln!("relayer: failed to build delivery {id}: {err}");
+                return;
+            }
+        };
+        // Logged before the task starts, so a crash mid-retry leaves the
+        // claim behind and the next run replays from its slot
+        let slot = notification.slot;
+        match self.ledger.claim(&id, slot) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => eprintln!("relayer: failed to log claim {id}: {err}"),
+        }
+        let relayer = self.clone();
+        let intent = event.intent.to_string();
+        tokio::spawn(async move { relayer.send(id, intent, slot, job).await });
+    }
+
+    fn job(&self, url: &str, notification: &EventNotification, event: &IntentCreated) -> Result<WebhookJob> {
+        let payload = IntentCreatedPayload::new(notification, event);
+        let body = serde_json::to_value(&payload)?;
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = payload::sign(&self.secret, timestamp, &body.to_string());
+        Ok(WebhookJob {
+            url: url.to_string(),
+            body,
+            headers: BTreeMap::from([
+                (payload::SIGNATURE_HEADER.to_string(), signature),
+                (payload::TIMESTAMP_HEADER.to_string(), timestamp.to_string()),
+                (payload::DELIVERY_HEADER.to_string(), payload.id),
+            ]),
+        })
+    }
+
+    async fn send(&self, id: String, intent: String, slot: u64, job: WebhookJob) {
+        let (attempts, status, result) =
+            delivery::deliver(&self.http, &job, self.max_attempts, self.backoff).await;
+        let now = chrono::Utc::now().to_rfc3339();
+        let error = result.err().map(|err| err.to_string());
+
+        if let Some(error) = &error {
+            eprintln!("relayer: delivery {id} to {} dead after {attempts} attempts: {error}", job.url);
+            let entry = DeadletterEntry {
+                id: id.clone(),
+                kind: "webhook".to_string(),
+                job: job.clone(),
+                attempts,
+                last_error: error.clone(),
+                failed_at: now.clone(),
+            };
+            if let Err(err) = self.ledger.bury(&entry) {
+                eprintln!("relayer: failed to write deadletter entry {id}: {err}");
+            }
+        }
+
+        let receipt = Receipt {
+            id,
+            intent,
+            slot,
+            url: job.url,
+            delivered: error.is_none(),
+            status,
+            attempts,
+            error,
+            at: now,
+        };
+        if let Err(err) = self.ledger.record(&receipt) {
+            eprintln!("relayer: failed to write receipt {}: {err}", receipt.id);
+        }
+    }
+}
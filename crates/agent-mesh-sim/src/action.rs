@@ -0,0 +1,137 @@
+//! Randomly drawn simulation steps
+
+use rand::Rng;
+
+/// Agent indexes of an intent's requester and provider
+pub type Parties = (usize, usize);
+
+/// Clock jumps for [`Action::Warp`]: inside and past the windows
+/// [`SimConfig`](crate::SimConfig) sets
+pub const WARP_SECONDS: [i64; 4] = [60, 400, 700, 4_000];
+
+/// Smallest and largest token budget of a created intent
+pub const TOKEN_RANGE: (u64, u64) = (1_000, 100_000);
+
+/// One step of a run. Agents and intents are indexes into the run's
+/// registered agents and created intents.
+#[derive(Clone, Debug)]
+pub enum Action {
+    /// Spawn and register another agent
+    Register,
+    /// `from` opens an intent to `to` budgeting `tokens` at `to`'s pricing
+    Create {
+        from: usize,
+        to: usize,
+        tokens: u64,
+        auto_release: bool,
+    },
+    /// `actor` accepts the intent as its provider
+    Accept { actor: usize, intent: usize },
+    /// `actor` completes the intent through `update_intent_status`
+    Complete { actor: usize, intent: usize },
+    /// `actor` submits a result and completes through `settle_intent`
+    Settle { actor: usize, intent: usize },
+    /// `actor` cancels the intent
+    Cancel { actor: usize, intent: usize },
+    /// Fail the intent past its completion deadline through `flag_timeout`,
+    /// or the `process_expirations` crank
+    Expire { intent: usize, crank: bool },
+    /// `actor` disputes the intent's pending release
+    Dispute { actor: usize, intent: usize },
+    /// The arbiter rules on the intent's dispute
+    Resolve { intent: usize, opener_prevails: bool },
+    /// The keeper pays out the intent's release once its challenge window
+    /// has passed
+    Finalize { intent: usize },
+    /// Move the clock forward
+    Warp { seconds: i64 },
+}
+
+impl Action {
+    /// Draw a step over `agents` agents and the intents between `parties`.
+    /// Most steps are taken by the party entitled to them; the rest by any
+    /// agent, which the program should reject.
+    pub fn generate(rng: &mut impl Rng, agents: usize, parties: &[Parties]) -> Self {
+        if agents < 2 {
+            return Action::Register;
+        }
+        if parties.is_empty() {
+            return Self::create(rng, agents);
+        }
+
+        let intent = rng.gen_range(0..parties.len());
+        let (requester, provider) = parties[intent];
+        match rng.gen_range(0..100) {
+            0..=4 => Action::Register,
+            5..=24 => Self::create(rng, agents),
+            25..=37 => Action::Accept {
+                actor: actor(rng, provider, agents),
+                intent,
+            },
+            38..=47 => Action::Complete {
+                actor: actor(rng, provider, agents),
+                intent,
+            },
+            48..=57 => Action::Settle {
+                actor: actor(rng, provider, agents),
+                intent,
+            },
+            58..=65 => {
+                let entitled = if rng.gen_bool(0.5) { requester } else { provider };
+                Action::Cancel {
+                    actor: actor(rng, entitled, agents),
+                    intent,
+                }
+            }
+            66..=72 => Action::Expire {
+                intent,
+                crank: rng.gen_bool(0.5),
+            },
+            73..=79 => Action::Dispute {
+                actor: actor(rng, requester, agents),
+                intent,
+            },
+            80..=85 => Action::Resolve {
+                intent,
+                opener_prevails: rng.gen_bool(0.5),
+            },
+            86..=91 => Action::Finalize { intent },
+            _ => Action::Warp {
+                seconds: WARP_SECONDS[rng.gen_range(0..WARP_SECONDS.len())],
+            },
+        }
+    }
+
+    fn create(rng: &mut impl Rng, agents: usize) -> Self {
+        Action::Create {
+            from: rng.gen_range(0..agents),
+            to: rng.gen_range(0..agents),
+            tokens: rng.gen_range(TOKEN_RANGE.0..=TOKEN_RANGE.1),
+            auto_release: rng.gen_bool(0.5),
+        }
+    }
+
+    /// The existing intent the step acts on, if any
+    pub fn intent(&self) -> Option<usize> {
+        match *self {
+            Action::Accept { intent, .. }
+            | Action::Complete { intent, .. }
+            | Action::Settle { intent, .. }
+            | Action::Cancel { intent, .. }
+            | Action::Expire { intent, .. }
+            | Action::Dispute { intent, .. }
+            | Action::Resolve { intent, .. }
+            | Action::Finalize { intent } => Some(intent),
+            Action::Register | Action::Create { .. } | Action::Warp { .. } => None,
+        }
+    }
+}
+
+/// `entitled` most of the time, otherwise any agent
+fn actor(rng: &mut impl Rng, entitled: usize, agents: usize) -> usize {
+    if rng.gen_bool(0.8) {
+        entitled
+    } else {
+        rng.gen_range(0..agents)
+    }
+}
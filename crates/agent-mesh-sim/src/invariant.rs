@@ -0,0 +1,185 @@
+//! What every step must preserve, checked on the on-chain state observed
+//! before and after it
+
+use std::collections::BTreeMap;
+
+use agent_mesh::IntentStatus;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::action::Parties;
+
+const PENDING: u8 = IntentStatus::Pending as u8;
+const ACCEPTED: u8 = IntentStatus::Accepted as u8;
+const COMPLETED: u8 = IntentStatus::Completed as u8;
+const FAILED: u8 = IntentStatus::Failed as u8;
+const CANCELLED: u8 = IntentStatus::Cancelled as u8;
+
+/// Observed state of one intent
+#[derive(Clone, Debug)]
+pub struct IntentView {
+    pub status: u8,
+    pub released: bool,
+    pub release_pending: bool,
+    pub payment_amount: u64,
+    pub escrow_balance: u64,
+}
+
+impl IntentView {
+    /// Nothing left to pay out: terminal and not held for a challenge window
+    pub fn settled(&self) -> bool {
+        IntentStatus::is_terminal(self.status) && !self.release_pending
+    }
+}
+
+/// On-chain state of a run between two steps
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    /// In the order the run created them
+    pub intents: Vec<IntentView>,
+    /// Every token account of the run's mint the run knows of
+    pub balances: BTreeMap<Pubkey, u64>,
+    /// Total supply of the run's mint
+    pub supply: u64,
+    /// `(completed, failed)` from each agent's `AgentStats`, in registration order
+    pub stats: Vec<(u64, u64)>,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Violation {
+    #[error("{held} of {supply} minted tokens are in known accounts")]
+    TokensLeaked { supply: u64, held: u64 },
+
+    #[error("intent {intent}: escrow holds {balance}, short of its outstanding payment {payment}")]
+    EscrowShort { intent: usize, balance: u64, payment: u64 },
+
+    #[error("intent {intent}: settled with {balance} left in escrow")]
+    EscrowNotDrained { intent: usize, balance: u64 },
+
+    #[error("intent {intent}: paid out again after its release")]
+    DoubleRelease { intent: usize },
+
+    #[error("intent {intent}: status {from} -> {to} is not a lifecycle transition")]
+    IllegalTransition { intent: usize, from: u8, to: u8 },
+
+    #[error("intent {intent}: status changed by a step that doesn't target it")]
+    UntargetedChange { intent: usize },
+
+    #[error("agent {agent}: stats record {recorded} {outcome} intents, {observed} observed")]
+    StatsDrift {
+        agent: usize,
+        outcome: &'static str,
+        recorded: u64,
+        observed: u64,
+    },
+}
+
+/// Whether an intent may move from `from` to `to` in one step. A dispute
+/// the opener wins fails a completed intent whose release is still held.
+pub fn is_transition(from: u8, to: u8) -> bool {
+    matches!(
+        (from, to),
+        (PENDING, ACCEPTED)
+            | (PENDING, FAILED)
+            | (PENDING, CANCELLED)
+            | (ACCEPTED, COMPLETED)
+            | (ACCEPTED, FAILED)
+            | (ACCEPTED, CANCELLED)
+            | (COMPLETED, FAILED)
+    )
+}
+
+/// Check the step that took the run from `before` to `after`. `target` is
+/// the existing intent it acted on and `parties` covers every intent in
+/// `after`.
+pub fn check(before: &Snapshot, after: &Snapshot, target: Option<usize>, parties: &[Parties]) -> Result<(), Violation> {
+    let held = after.balances.values().sum::<u64>();
+    if held != after.supply {
+        return Err(Violation::TokensLeaked {
+            supply: after.supply,
+            held,
+        });
+    }
+
+    for (intent, view) in after.intents.iter().enumerate() {
+        if let Some(previous) = before.intents.get(intent) {
+            check_step(intent, previous, view, target == Some(intent), before, after)?;
+        }
+        if view.settled() {
+            if view.escrow_balance > 0 {
+                return Err(Violation::EscrowNotDrained {
+                    intent,
+                    balance: view.escrow_balance,
+                });
+            }
+        } else if view.escrow_balance < view.payment_amount {
+            return Err(Violation::EscrowShort {
+                intent,
+                balance: view.escrow_balance,
+                payment: view.payment_amount,
+            });
+        }
+    }
+
+    check_stats(after, parties)
+}
+
+fn check_step(
+    intent: usize,
+    previous: &IntentView,
+    view: &IntentView,
+    targeted: bool,
+    before: &Snapshot,
+    after: &Snapshot,
+) -> Result<(), Violation> {
+    if previous.status != view.status {
+        if !targeted {
+            return Err(Violation::UntargetedChange { intent });
+        }
+        if !is_transition(previous.status, view.status) {
+            return Err(Violation::IllegalTransition {
+                intent,
+                from: previous.status,
+                to: view.status,
+            });
+        }
+    }
+
+    // Once released, the intent must stay released and moving no tokens
+    if previous.released {
+        let moved = targeted
+            && before
+                .balances
+                .iter()
+                .any(|(account, balance)| after.balances.get(account) != Some(balance));
+        if !view.released || moved {
+            return Err(Violation::DoubleRelease { intent });
+        }
+    }
+    Ok(())
+}
+
+/// Each agent's stats count the completed and failed intents it provided
+fn check_stats(after: &Snapshot, parties: &[Parties]) -> Result<(), Violation> {
+    for (agent, &(completed, failed)) in after.stats.iter().enumerate() {
+        let observed = |status: u8| {
+            after
+                .intents
+                .iter()
+                .zip(parties)
+                .filter(|(view, &(_, provider))| provider == agent && view.status == status)
+                .count() as u64
+        };
+        for (outcome, recorded, status) in [("completed", completed, COMPLETED), ("failed", failed, FAILED)] {
+            let observed = observed(status);
+            if recorded != observed {
+                return Err(Violation::StatsDrift {
+                    agent,
+                    outcome,
+                    recorded,
+                    observed,
+                });
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+//! Deterministic simulation of the Agent Mesh intent lifecycle.
+//!
+//! A run starts a [`MeshTest`](agent_mesh_test_utils::MeshTest) validator,
+//! registers a few agents and then takes randomized [`Action`]s: register,
+//! create, accept, complete, settle, cancel, expire, dispute, resolve,
+//! finalize and clock warps. Actors and target intents are drawn without
+//! regard to the intent's state, so many steps are meant to be rejected.
+//! After every step the [`invariant`]s are checked against the observed
+//! on-chain state:
+//!
+//! - escrow conservation: every token of the run's mint sits in an account
+//!   the run knows of, a settled intent's escrow is drained and an open one
+//!   covers its outstanding payment
+//! - no double release: a released intent stays released and never pays out
+//!   again
+//! - status monotonicity: statuses only move along lifecycle transitions, and
+//!   only for the intent a step targets
+//! - each agent's `AgentStats` agrees with the outcomes of its intents
+//!
+//! Everything a run does follows from its seed, so a failing seed replays
+//! the same steps.
+
+#![allow(clippy::result_large_err)]
+
+pub mod action;
+pub mod invariant;
+pub mod sim;
+
+pub use action::{Action, Parties};
+pub use invariant::{IntentView, Snapshot, Violation};
+pub use sim::{Failure, Report, SimConfig, Simulation, StepRecord};
+
+use solana_program_test::BanksClientError;
+
+pub type Result<T> = std::result::Result<T, SimError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimError {
+    #[error("validator error: {0}")]
+    Banks(#[from] BanksClientError),
+
+    #[error("{0}")]
+    Violation(Box<Failure>),
+}
+
+/// Start a run for `seed` and take its steps
+pub async fn run(seed: u64, config: SimConfig) -> Result<Report> {
+    Simulation::start(seed, config).await?.run().await
+}
@@ -0,0 +1,47 @@
+//! `agent-mesh-sim`: run seeded simulations of the intent lifecycle and
+//! report the first invariant a seed breaks.
+
+use agent_mesh_sim::SimConfig;
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "agent-mesh-sim", version, about = "Randomized invariant checks for the Solana Agent Mesh program")]
+struct Args {
+    /// First seed; each run uses the next one
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of seeds to run
+    #[arg(long, default_value_t = 20)]
+    runs: u64,
+
+    /// Steps per run
+    #[arg(long, default_value_t = SimConfig::default().steps)]
+    steps: usize,
+
+    /// Agents registered before the first step
+    #[arg(long, default_value_t = SimConfig::default().agents)]
+    agents: usize,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let config = SimConfig {
+        agents: args.agents,
+        steps: args.steps,
+        ..SimConfig::default()
+    };
+    for seed in args.seed..args.seed.saturating_add(args.runs) {
+        match agent_mesh_sim::run(seed, config.clone()).await {
+            Ok(report) => println!(
+                "seed {seed}: {} of {} steps accepted, {} intents between {} agents",
+                report.accepted, report.steps, report.intents, report.agents
+            ),
+            Err(err) => {
+                eprintln!("error: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
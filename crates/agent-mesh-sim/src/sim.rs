@@ -0,0 +1,459 @@
+//! A simulation run: the validator, the agents and intents the run created
+//! and the seeded RNG that drives it
+
+use std::fmt;
+
+use agent_mesh::{AgentIntent, AgentStats, CommitmentScheme, Dispute, IntentOption, IntentStatus, ProtocolParameter, TimeSpan};
+use agent_mesh_client::instructions::{
+    self, DisputeRuling, Expiration, ReadyRelease, SettleIntentBuilder, UpdateIntentStatusBuilder,
+};
+use agent_mesh_client::{commitment, pda};
+use agent_mesh_test_utils::{MeshTest, TestAgent};
+use anchor_spl::token::spl_token;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use solana_program_test::BanksClientError;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::signer::keypair::keypair_from_seed;
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::action::{Action, Parties};
+use crate::invariant::{self, IntentView, Snapshot, Violation};
+use crate::{Result, SimError};
+
+/// Protocol fee the run's config charges, so settlements pay the treasury
+pub const PROTOCOL_FEE_BPS: u16 = 100;
+pub const CANCELLATION_PENALTY_BPS: u16 = 1_000;
+pub const DISPUTE_FEE_BPS: u16 = 500;
+pub const CANCELLATION_WINDOW: i64 = 300;
+pub const CHALLENGE_WINDOW: i64 = 600;
+pub const COMPLETION_WINDOW: i64 = 3_600;
+
+/// Lamports put in the crank treasury for `process_expirations` tips
+const CRANK_TREASURY_FUNDING: u64 = 1_000_000_000;
+
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    /// Agents registered before the first step
+    pub agents: usize,
+    pub steps: usize,
+    /// Tokens each agent is spawned with
+    pub tokens_per_agent: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            agents: 4,
+            steps: 200,
+            tokens_per_agent: 1_000_000_000,
+        }
+    }
+}
+
+/// A step and whether the program accepted it
+#[derive(Clone, Debug)]
+pub struct StepRecord {
+    pub action: Action,
+    pub accepted: bool,
+}
+
+/// Summary of a run that kept every invariant
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub seed: u64,
+    pub steps: usize,
+    /// Steps the program accepted
+    pub accepted: usize,
+    pub agents: usize,
+    pub intents: usize,
+}
+
+/// A run that broke an invariant, with the steps that led there
+#[derive(Debug)]
+pub struct Failure {
+    pub seed: u64,
+    pub trace: Vec<StepRecord>,
+    pub violation: Violation,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {} broke an invariant at step {}: {}", self.seed, self.trace.len(), self.violation)?;
+        for (step, record) in self.trace.iter().enumerate() {
+            let outcome = if record.accepted { "ok" } else { "rejected" };
+            writeln!(f, "  {:>4} {outcome:>8} {:?}", step + 1, record.action)?;
+        }
+        write!(f, "replay with `agent-mesh-sim --seed {} --runs 1`", self.seed)
+    }
+}
+
+/// An intent the run created
+struct TrackedIntent {
+    address: Pubkey,
+    escrow: Pubkey,
+    parties: Parties,
+}
+
+pub struct Simulation {
+    seed: u64,
+    config: SimConfig,
+    rng: ChaCha8Rng,
+    mesh: MeshTest,
+    /// Config admin, fee treasury, arbiter and keeper
+    admin: Keypair,
+    mint: Pubkey,
+    /// Admin's token account: receives protocol fees and arbiter shares
+    treasury: Pubkey,
+    agents: Vec<TestAgent>,
+    intents: Vec<TrackedIntent>,
+    trace: Vec<StepRecord>,
+}
+
+impl Simulation {
+    /// Start a validator with a config that charges fees, holds releases and
+    /// times out accepted intents, and register the initial agents
+    pub async fn start(seed: u64, config: SimConfig) -> Result<Self> {
+        let mut mesh = MeshTest::start().await?;
+        let admin = mesh.context.payer.insecure_clone();
+        let mint = mesh.create_mint(6).await?;
+        let treasury = mesh.mint_to(&mint, &admin.pubkey(), 0).await?;
+
+        let mut setup: Vec<Instruction> = [
+            ProtocolParameter::FeeTreasury(admin.pubkey()),
+            ProtocolParameter::ProtocolFeeBps(PROTOCOL_FEE_BPS),
+            ProtocolParameter::CancellationWindow(TimeSpan::Seconds(CANCELLATION_WINDOW)),
+            ProtocolParameter::CancellationPenaltyBps(CANCELLATION_PENALTY_BPS),
+            ProtocolParameter::ChallengeWindow(TimeSpan::Seconds(CHALLENGE_WINDOW)),
+            ProtocolParameter::CompletionWindow(TimeSpan::Seconds(COMPLETION_WINDOW)),
+            ProtocolParameter::AddArbiter(admin.pubkey()),
+            ProtocolParameter::DisputeFeeBps(DISPUTE_FEE_BPS),
+        ]
+        .into_iter()
+        .map(|parameter| instructions::update_parameter(&admin.pubkey(), parameter))
+        .collect();
+        setup.push(instructions::fund_crank_treasury(&admin.pubkey(), CRANK_TREASURY_FUNDING));
+        mesh.process(&setup, &[]).await?;
+
+        let mut sim = Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            config,
+            mesh,
+            admin,
+            mint,
+            treasury,
+            agents: Vec::new(),
+            intents: Vec::new(),
+            trace: Vec::new(),
+        };
+        for _ in 0..sim.config.agents {
+            sim.register().await?;
+        }
+        Ok(sim)
+    }
+
+    /// Take the configured number of steps, checking the invariants after each
+    pub async fn run(mut self) -> Result<Report> {
+        for _ in 0..self.config.steps {
+            let action = Action::generate(&mut self.rng, self.agents.len(), &self.parties());
+            let before = self.snapshot().await?;
+            let accepted = self.execute(&action).await?;
+            let after = self.snapshot().await?;
+            let target = action.intent();
+            self.trace.push(StepRecord { action, accepted });
+
+            if let Err(violation) = invariant::check(&before, &after, target, &self.parties()) {
+                return Err(SimError::Violation(Box::new(Failure {
+                    seed: self.seed,
+                    trace: self.trace,
+                    violation,
+                })));
+            }
+        }
+
+        Ok(Report {
+            seed: self.seed,
+            steps: self.trace.len(),
+            accepted: self.trace.iter().filter(|record| record.accepted).count(),
+            agents: self.agents.len(),
+            intents: self.intents.len(),
+        })
+    }
+
+    fn parties(&self) -> Vec<Parties> {
+        self.intents.iter().map(|intent| intent.parties).collect()
+    }
+
+    /// Take one step; `false` if the program rejected it
+    async fn execute(&mut self, action: &Action) -> Result<bool> {
+        match *action {
+            Action::Register => {
+                self.register().await?;
+                Ok(true)
+            }
+            Action::Create {
+                from,
+                to,
+                tokens,
+                auto_release,
+            } => {
+                let options = if auto_release { IntentOption::AUTO_RELEASE } else { 0 };
+                let created = self
+                    .mesh
+                    .create_intent(&self.agents[from], &self.agents[to], &self.mint, tokens, options)
+                    .await;
+                let address = match created {
+                    Ok(address) => address,
+                    Err(err) => return rejected(err),
+                };
+                self.intents.push(TrackedIntent {
+                    address,
+                    escrow: pda::escrow(&address, &self.mint),
+                    parties: (from, to),
+                });
+                Ok(true)
+            }
+            Action::Accept { actor, intent } => {
+                let address = self.intents[intent].address;
+                match self.mesh.accept_intent(&self.agents[actor], address).await {
+                    Ok(()) => Ok(true),
+                    Err(err) => rejected(err),
+                }
+            }
+            Action::Complete { actor, intent } => {
+                let tracked = &self.intents[intent];
+                let agent = &self.agents[actor];
+                let ix = UpdateIntentStatusBuilder::new(
+                    &agent.owner(),
+                    tracked.address,
+                    agent.agent,
+                    IntentStatus::Completed as u8,
+                )
+                .result(
+                    commitment::commit(CommitmentScheme::Sha256, b"sim result"),
+                    "ipfs://sim-result".to_string(),
+                )
+                .release(tracked.escrow, agent.token_account)
+                .fee_token_account(self.treasury)
+                .instruction();
+                self.send(&[ix], actor).await
+            }
+            Action::Settle { actor, intent } => {
+                let tracked = &self.intents[intent];
+                let agent = &self.agents[actor];
+                let submit = instructions::submit_result(
+                    &agent.owner(),
+                    tracked.address,
+                    commitment::commit(CommitmentScheme::Sha256, b"sim result"),
+                    "ipfs://sim-result".to_string(),
+                );
+                let settle = SettleIntentBuilder::new(&agent.owner(), tracked.address)
+                    .release(tracked.escrow, agent.token_account)
+                    .fee_token_account(self.treasury)
+                    .instruction();
+                self.send(&[submit, settle], actor).await
+            }
+            Action::Cancel { actor, intent } => {
+                let tracked = &self.intents[intent];
+                let (requester, provider) = tracked.parties;
+                let provider = &self.agents[provider];
+                let agent = &self.agents[actor];
+                let ix = instructions::cancel_intent(
+                    &agent.owner(),
+                    agent.agent,
+                    tracked.address,
+                    Some(tracked.escrow),
+                    Some(self.agents[requester].token_account),
+                    Some((provider.agent, provider.token_account)),
+                    None,
+                );
+                self.send(&[ix], actor).await
+            }
+            Action::Expire { intent, crank } => {
+                let tracked = &self.intents[intent];
+                let (requester, provider) = tracked.parties;
+                let assignee = self.agents[provider].agent;
+                let refund_account = self.agents[requester].token_account;
+                let ix = if crank {
+                    instructions::process_expirations(
+                        &self.admin.pubkey(),
+                        &[Expiration {
+                            intent: tracked.address,
+                            assignee,
+                            escrow: tracked.escrow,
+                            refund_account,
+                        }],
+                    )
+                } else {
+                    instructions::flag_timeout(
+                        &self.admin.pubkey(),
+                        tracked.address,
+                        &assignee,
+                        tracked.escrow,
+                        refund_account,
+                        None,
+                        None,
+                        None,
+                    )
+                };
+                self.send_as_admin(&[ix]).await
+            }
+            Action::Dispute { actor, intent } => {
+                let tracked = &self.intents[intent];
+                let agent = &self.agents[actor];
+                let ix = instructions::open_dispute(
+                    &agent.owner(),
+                    agent.agent,
+                    tracked.address,
+                    tracked.escrow,
+                    agent.token_account,
+                    "ipfs://sim-dispute".to_string(),
+                );
+                self.send(&[ix], actor).await
+            }
+            Action::Resolve {
+                intent,
+                opener_prevails,
+            } => {
+                let (address, escrow, (requester, provider)) = {
+                    let tracked = &self.intents[intent];
+                    (tracked.address, tracked.escrow, tracked.parties)
+                };
+                let account = self.intent_account(&address).await?;
+                let dispute: Option<Dispute> = self.mesh.account(&pda::dispute(&address).0).await?;
+                let opener = dispute.map_or(self.agents[requester].owner(), |dispute| dispute.opener);
+                let ruling = if opener_prevails {
+                    DisputeRuling::Refund {
+                        refund_token_account: get_associated_token_address(&account.payer, &self.mint),
+                        booking: None,
+                    }
+                } else {
+                    DisputeRuling::Forfeit {
+                        arbiter_token_account: Some(self.treasury),
+                        counterparty_token_account: Some(account.release_account),
+                    }
+                };
+                let ix = instructions::resolve_dispute(
+                    &self.admin.pubkey(),
+                    address,
+                    &self.agents[provider].agent,
+                    opener,
+                    escrow,
+                    get_associated_token_address(&opener, &self.mint),
+                    ruling,
+                );
+                self.send_as_admin(&[ix]).await
+            }
+            Action::Finalize { intent } => {
+                let (address, escrow) = (self.intents[intent].address, self.intents[intent].escrow);
+                let account = self.intent_account(&address).await?;
+                let ix = instructions::finalize_ready_releases(
+                    &self.admin.pubkey(),
+                    Some(self.treasury),
+                    &[ReadyRelease {
+                        intent: address,
+                        escrow,
+                        release_account: account.release_account,
+                        delegate_release_account: None,
+                    }],
+                );
+                self.send_as_admin(&[ix]).await
+            }
+            Action::Warp { seconds } => {
+                self.mesh.warp_seconds(seconds).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Spawn an agent whose owner key comes from the run's RNG
+    async fn register(&mut self) -> Result<()> {
+        let owner = keypair_from_seed(&self.rng.gen::<[u8; 32]>()).expect("32-byte seed");
+        let agent = self
+            .mesh
+            .spawn_agent_with(owner, &self.mint, self.config.tokens_per_agent)
+            .await?;
+        self.agents.push(agent);
+        Ok(())
+    }
+
+    /// Send as agent `actor`'s owner
+    async fn send(&mut self, ixs: &[Instruction], actor: usize) -> Result<bool> {
+        let owner = self.agents[actor].owner.insecure_clone();
+        match self.mesh.process(ixs, &[&owner]).await {
+            Ok(()) => Ok(true),
+            Err(err) => rejected(err),
+        }
+    }
+
+    /// Send signed only by the admin, which pays every transaction
+    async fn send_as_admin(&mut self, ixs: &[Instruction]) -> Result<bool> {
+        match self.mesh.process(ixs, &[]).await {
+            Ok(()) => Ok(true),
+            Err(err) => rejected(err),
+        }
+    }
+
+    async fn intent_account(&mut self, address: &Pubkey) -> Result<AgentIntent> {
+        Ok(self.mesh.account(address).await?.expect("tracked intents are never closed"))
+    }
+
+    /// Observe everything the invariants look at
+    async fn snapshot(&mut self) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::default();
+
+        for tracked in &self.intents {
+            let intent: AgentIntent = self
+                .mesh
+                .account(&tracked.address)
+                .await?
+                .expect("tracked intents are never closed");
+            let escrow_balance = self.mesh.token_balance(&tracked.escrow).await?;
+            snapshot.intents.push(IntentView {
+                status: intent.status,
+                released: intent.released,
+                release_pending: intent.release_pending(),
+                payment_amount: intent.payment_amount,
+                escrow_balance,
+            });
+            snapshot.balances.insert(tracked.escrow, escrow_balance);
+        }
+
+        for agent in &self.agents {
+            let balance = self.mesh.token_balance(&agent.token_account).await?;
+            snapshot.balances.insert(agent.token_account, balance);
+            let stats: Option<AgentStats> = self.mesh.account(&pda::agent_stats(&agent.agent).0).await?;
+            snapshot
+                .stats
+                .push(stats.map_or((0, 0), |stats| (stats.completed, stats.failed)));
+        }
+        let treasury = self.mesh.token_balance(&self.treasury).await?;
+        snapshot.balances.insert(self.treasury, treasury);
+
+        let mint = self
+            .mesh
+            .context
+            .banks_client
+            .get_account(self.mint)
+            .await?
+            .expect("run mint exists");
+        snapshot.supply = spl_token::state::Mint::unpack(&mint.data)
+            .expect("run mint is an SPL mint")
+            .supply;
+
+        Ok(snapshot)
+    }
+}
+
+/// A transaction the program (or runtime) refused is an expected outcome
+/// of a step; anything else means the validator itself failed
+fn rejected(err: BanksClientError) -> Result<bool> {
+    match err {
+        BanksClientError::TransactionError(_) | BanksClientError::SimulationError { .. } => Ok(false),
+        err => Err(err.into()),
+    }
+}
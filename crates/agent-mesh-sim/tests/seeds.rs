@@ -0,0 +1,17 @@
+//! A fixed set of seeds must keep every invariant, so `cargo test` catches
+//! a regression the simulation would report.
+
+use agent_mesh_sim::SimConfig;
+
+/// Seeds run on every test pass; widen the range with the binary's `--runs`
+const SEEDS: std::ops::Range<u64> = 0..8;
+
+#[tokio::test]
+async fn fixed_seeds_keep_every_invariant() {
+    for seed in SEEDS {
+        match agent_mesh_sim::run(seed, SimConfig::default()).await {
+            Ok(report) => assert_eq!(report.steps, SimConfig::default().steps),
+            Err(err) => panic!("{err}"),
+        }
+    }
+}
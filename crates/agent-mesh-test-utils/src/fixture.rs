@@ -0,0 +1,410 @@
+//! A started test validator with helpers for the common setup steps.
+
+use agent_mesh::{instruction, CommitmentScheme, IndexRole, IntentStatus, Permission, ProtocolParameter};
+use agent_mesh_client::{commitment, instructions, pda, quote};
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::spl_token;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::account::AccountSharedData;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::{program_test, Result};
+
+/// Lamports given to wallets created by [`MeshTest::funded_keypair`]
+pub const DEFAULT_FUNDING: u64 = 10_000_000_000;
+
+/// Pricing of profiles created by [`MeshTest::spawn_agent`], in micro-units
+/// per 1K tokens
+pub const DEFAULT_PRICING: u64 = 1_000;
+
+/// An agent registered by [`MeshTest::spawn_agent`]: a funded owner wallet
+/// (also the agent wallet) with its own model profile and a token account
+/// holding the test mint
+pub struct TestAgent {
+    pub owner: Keypair,
+    pub agent: Pubkey,
+    pub model_profile: Pubkey,
+    /// Owner's associated token account for the mint it was spawned with;
+    /// funds its intents and receives its payouts
+    pub token_account: Pubkey,
+}
+
+impl TestAgent {
+    pub fn owner(&self) -> Pubkey {
+        self.owner.pubkey()
+    }
+}
+
+/// Test validator with the Agent Mesh program loaded and the protocol config
+/// initialized; the context payer is the config admin and attestor
+pub struct MeshTest {
+    pub context: ProgramTestContext,
+    nonce: u64,
+}
+
+impl MeshTest {
+    pub async fn start() -> Result<Self> {
+        Self::start_with(program_test()).await
+    }
+
+    /// Start a customized [`program_test`] and initialize the config
+    pub async fn start_with(test: solana_program_test::ProgramTest) -> Result<Self> {
+        let mut context = test.start_with_context().await;
+        // The native processor has no loader data; stand in for it so the
+        // payer is the upgrade authority `initialize_config` requires
+        let program_data = AccountSharedData::new_data(
+            Rent::default().minimum_balance(UpgradeableLoaderState::size_of_programdata_metadata()),
+            &UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(context.payer.pubkey()),
+            },
+            &bpf_loader_upgradeable::ID,
+        )
+        .expect("program data serializes");
+        context.set_account(&pda::program_data().0, &program_data);
+
+        let mut mesh = Self { context, nonce: 0 };
+        let admin = mesh.payer();
+        mesh.process(&[instructions::initialize_config(&admin, admin)], &[])
+            .await?;
+        Ok(mesh)
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    /// Send `instructions` paid by the context payer, with any extra signers
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.context.banks_client.process_transaction(tx).await
+    }
+
+    /// Fetch and decode an Anchor account; `None` if it doesn't exist or
+    /// isn't of type `T`
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> Result<Option<T>> {
+        let account = self.context.banks_client.get_account(*address).await?;
+        Ok(account.and_then(|account| T::try_deserialize(&mut account.data.as_slice()).ok()))
+    }
+
+    /// Move the clock forward by `seconds`
+    pub async fn warp_seconds(&mut self, seconds: i64) -> Result<()> {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await?;
+        clock.unix_timestamp = clock.unix_timestamp.saturating_add(seconds);
+        self.context.set_sysvar(&clock);
+        Ok(())
+    }
+
+    /// Set a protocol parameter as the config admin
+    pub async fn update_parameter(&mut self, parameter: ProtocolParameter) -> Result<()> {
+        let admin = self.payer();
+        self.process(&[instructions::update_parameter(&admin, parameter)], &[])
+            .await
+    }
+
+    /// Distinct nonce for bookings and profile ids created by the fixtures
+    pub fn next_nonce(&mut self) -> u64 {
+        self.nonce += 1;
+        self.nonce
+    }
+
+    // === Wallets and tokens ===
+
+    pub async fn funded_keypair(&mut self) -> Result<Keypair> {
+        let keypair = Keypair::new();
+        self.airdrop(&keypair.pubkey(), DEFAULT_FUNDING).await?;
+        Ok(keypair)
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) -> Result<()> {
+        let ix = system_instruction::transfer(&self.payer(), to, lamports);
+        self.process(&[ix], &[]).await
+    }
+
+    /// New SPL mint with the context payer as mint authority
+    pub async fn create_mint(&mut self, decimals: u8) -> Result<Pubkey> {
+        let mint = Keypair::new();
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await?;
+        let ixs = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &payer, None, decimals)
+                .expect("valid mint instruction"),
+        ];
+        self.process(&ixs, &[&mint]).await?;
+        Ok(mint.pubkey())
+    }
+
+    /// Create `owner`'s associated token account for a [`MeshTest::create_mint`]
+    /// mint if needed and mint `amount` into it; returns the token account
+    pub async fn mint_to(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Result<Pubkey> {
+        let payer = self.payer();
+        let token_account = get_associated_token_address(owner, mint);
+        let mut ixs = vec![create_associated_token_account_idempotent(
+            &payer,
+            owner,
+            mint,
+            &spl_token::ID,
+        )];
+        if amount > 0 {
+            ixs.push(
+                spl_token::instruction::mint_to(&spl_token::ID, mint, &token_account, &payer, &[], amount)
+                    .expect("valid mint_to instruction"),
+            );
+        }
+        self.process(&ixs, &[]).await?;
+        Ok(token_account)
+    }
+
+    /// Token balance, 0 if the account doesn't exist
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> Result<u64> {
+        let account = self.context.banks_client.get_account(*token_account).await?;
+        Ok(account
+            .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+            .map_or(0, |account| account.amount))
+    }
+
+    // === Agents ===
+
+    /// Create a model profile owned (and billed to) `owner`
+    pub async fn create_profile(&mut self, owner: &Keypair, label: &str, pricing: u64) -> Result<Pubkey> {
+        let mut profile_id = [0u8; 16];
+        profile_id[..8].copy_from_slice(&self.next_nonce().to_le_bytes());
+        let ix = instructions::create_model_profile(
+            &owner.pubkey(),
+            instruction::CreateModelProfile {
+                profile_id,
+                label: label.to_string(),
+                provider_uri: format!("https://{label}.test"),
+                pricing,
+                billing_wallet: owner.pubkey(),
+                max_tokens_per_day: u64::MAX,
+                max_requests_per_min: u64::MAX,
+            },
+        );
+        self.process(&[ix], &[owner]).await?;
+        Ok(pda::model_profile(&owner.pubkey(), &profile_id).0)
+    }
+
+    pub async fn register_agent(
+        &mut self,
+        owner: &Keypair,
+        model_profile: Pubkey,
+        permissions: u64,
+    ) -> Result<Pubkey> {
+        let ix = instructions::register_agent(
+            &owner.pubkey(),
+            owner.pubkey(),
+            model_profile,
+            "ipfs://test-agent".to_string(),
+            permissions,
+            None,
+            instructions::RegisterOptions::default(),
+        );
+        self.process(&[ix], &[owner]).await?;
+        Ok(pda::agent(&owner.pubkey()).0)
+    }
+
+    /// Funded owner, profile at [`DEFAULT_PRICING`], registered agent that
+    /// can create and accept intents, and `tokens` of `mint`
+    pub async fn spawn_agent(&mut self, mint: &Pubkey, tokens: u64) -> Result<TestAgent> {
+        self.spawn_agent_with(Keypair::new(), mint, tokens).await
+    }
+
+    /// [`MeshTest::spawn_agent`] with a caller-chosen owner, e.g. one derived
+    /// from a seed for reproducible runs; it is funded here
+    pub async fn spawn_agent_with(&mut self, owner: Keypair, mint: &Pubkey, tokens: u64) -> Result<TestAgent> {
+        self.airdrop(&owner.pubkey(), DEFAULT_FUNDING).await?;
+        let model_profile = self.create_profile(&owner, "test-model", DEFAULT_PRICING).await?;
+        let agent = self
+            .register_agent(
+                &owner,
+                model_profile,
+                Permission::CAN_CREATE_INTENT | Permission::CAN_ACCEPT_INTENT,
+            )
+            .await?;
+        let token_account = self.mint_to(mint, &owner.pubkey(), tokens).await?;
+        Ok(TestAgent {
+            owner,
+            agent,
+            model_profile,
+            token_account,
+        })
+    }
+
+    // === Intent lifecycle ===
+
+    /// Open an intent from `from` to `to` budgeting `tokens` at the provider's
+    /// pricing, funded from `from`'s token account; returns the intent PDA
+    pub async fn create_intent(
+        &mut self,
+        from: &TestAgent,
+        to: &TestAgent,
+        mint: &Pubkey,
+        tokens: u64,
+        options: u8,
+    ) -> Result<Pubkey> {
+        let profile: agent_mesh::ModelProfile = self
+            .account(&to.model_profile)
+            .await?
+            .expect("provider model profile exists");
+        let payment_amount = quote(profile.pricing, tokens).expect("quote fits in u64");
+        let nonce = self.intent_nonce(from.agent, to.agent).await?;
+        let (sent_page, received_page) = self.index_pages(from.agent, to.agent).await?;
+        let builder = instructions::CreateIntentBuilder::new(
+            &from.owner(),
+            from.agent,
+            to.agent,
+            *mint,
+            from.token_account,
+            nonce,
+            instruction::CreateIntent {
+                payload_uri: "ipfs://test-payload".to_string(),
+                payload: commitment::commit(CommitmentScheme::Sha256, b"test payload"),
+                payment_amount,
+                options,
+                tip_amount: 0,
+            },
+        )
+        .index_pages(sent_page, received_page);
+        let intent = builder.intent();
+        let create_escrow =
+            create_associated_token_account_idempotent(&self.payer(), &intent, mint, &spl_token::ID);
+        self.process(&[create_escrow, builder.instruction()], &[&from.owner])
+            .await?;
+        Ok(intent)
+    }
+
+    /// Nonce the next intent from `from_agent` to `to_agent` takes
+    pub async fn intent_nonce(&mut self, from_agent: Pubkey, to_agent: Pubkey) -> Result<u64> {
+        let counter: Option<agent_mesh::NonceCounter> =
+            self.account(&pda::nonce_counter(&from_agent, &to_agent).0).await?;
+        Ok(counter.map_or(0, |counter| counter.next))
+    }
+
+    /// Index pages the next intent between the agents is appended to
+    pub async fn index_pages(&mut self, from_agent: Pubkey, to_agent: Pubkey) -> Result<(u32, u32)> {
+        let sent: Option<agent_mesh::IntentIndex> =
+            self.account(&pda::intent_index(&from_agent, IndexRole::Sent).0).await?;
+        let received: Option<agent_mesh::IntentIndex> =
+            self.account(&pda::intent_index(&to_agent, IndexRole::Received).0).await?;
+        Ok((
+            sent.map_or(0, |index| index.next_page()),
+            received.map_or(0, |index| index.next_page()),
+        ))
+    }
+
+    /// Accept as the provider, taking any tip into its token account
+    pub async fn accept_intent(&mut self, to: &TestAgent, intent: Pubkey) -> Result<()> {
+        let account: agent_mesh::AgentIntent =
+            self.account(&intent).await?.expect("intent exists");
+        let tip = (account.tip_amount > 0)
+            .then(|| (pda::escrow(&intent, &account.payment_mint), to.token_account));
+        let accept = instructions::accept_intent(&to.owner(), intent, to.agent, tip);
+        self.process(&[accept], &[&to.owner]).await
+    }
+
+    /// Complete as the provider, releasing the escrow into its token account
+    /// (or scheduling the release for auto-release intents). Declared
+    /// royalties go to each recipient's associated token account and SLA
+    /// penalties to the payer's.
+    pub async fn complete_intent(&mut self, to: &TestAgent, intent: Pubkey) -> Result<()> {
+        let account: agent_mesh::AgentIntent =
+            self.account(&intent).await?.expect("intent exists");
+        let mut builder = instructions::UpdateIntentStatusBuilder::new(
+            &to.owner(),
+            intent,
+            to.agent,
+            IntentStatus::Completed as u8,
+        )
+        .result(
+            commitment::commit(CommitmentScheme::Sha256, b"test result"),
+            "ipfs://test-result".to_string(),
+        )
+        .release(pda::escrow(&intent, &account.payment_mint), to.token_account);
+        if account.sla_penalty_bps > 0 {
+            builder = builder.sla_refund(get_associated_token_address(&account.payer, &account.payment_mint));
+        }
+        if account.royalty_bps > 0 {
+            builder = builder.royalties(&self.royalty_recipients(intent, &account).await?);
+        }
+        self.process(&[builder.instruction()], &[&to.owner]).await
+    }
+
+    /// Complete an accepted intent through `submit_result` and the slim
+    /// `settle_intent`, releasing the escrow like [`Self::complete_intent`]
+    pub async fn settle_intent(&mut self, to: &TestAgent, intent: Pubkey) -> Result<()> {
+        let account: agent_mesh::AgentIntent =
+            self.account(&intent).await?.expect("intent exists");
+        let submit = instructions::submit_result(
+            &to.owner(),
+            intent,
+            commitment::commit(CommitmentScheme::Sha256, b"test result"),
+            "ipfs://test-result".to_string(),
+        );
+        let mut builder = instructions::SettleIntentBuilder::new(&to.owner(), intent)
+            .release(pda::escrow(&intent, &account.payment_mint), to.token_account);
+        if account.sla_penalty_bps > 0 {
+            builder = builder.sla_refund(get_associated_token_address(&account.payer, &account.payment_mint));
+        }
+        if account.royalty_bps > 0 {
+            builder = builder.royalties(&self.royalty_recipients(intent, &account).await?);
+        }
+        self.process(&[submit, builder.instruction()], &[&to.owner]).await
+    }
+
+    /// Associated token accounts of every declared royalty recipient
+    async fn royalty_recipients(
+        &mut self,
+        intent: Pubkey,
+        account: &agent_mesh::AgentIntent,
+    ) -> Result<Vec<Pubkey>> {
+        let schedule: agent_mesh::RoyaltySchedule = self
+            .account(&pda::royalties(&intent).0)
+            .await?
+            .expect("royalty declaration exists");
+        Ok(schedule
+            .splits
+            .iter()
+            .map(|split| get_associated_token_address(&split.recipient, &account.payment_mint))
+            .collect())
+    }
+
+    /// Create, accept and complete an intent; returns the intent PDA
+    pub async fn run_intent(
+        &mut self,
+        from: &TestAgent,
+        to: &TestAgent,
+        mint: &Pubkey,
+        tokens: u64,
+    ) -> Result<Pubkey> {
+        let intent = self.create_intent(from, to, mint, tokens, 0).await?;
+        self.accept_intent(to, intent).await?;
+        self.complete_intent(to, intent).await?;
+        Ok(intent)
+    }
+}
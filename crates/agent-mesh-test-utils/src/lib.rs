@@ -0,0 +1,61 @@
+//! `solana-program-test` fixtures for the Agent Mesh program.
+//!
+//! - [`program_test`]: a `ProgramTest` with the program loaded as a native
+//!   processor (no BPF build needed)
+//! - [`MeshTest`]: a started test validator with the protocol config
+//!   initialized, plus helpers to fund wallets, mint test tokens, register
+//!   agents ([`TestAgent`]) and drive intents through their lifecycle
+//! - [`MeshTest::warp_seconds`]: move the clock for challenge windows,
+//!   trials, bookings and other time-gated flows
+//! - [`program_error`]: the program's error code a transaction failed with
+//!
+//! Integrators typically start one [`MeshTest`] per test, call
+//! [`MeshTest::spawn_agent`] for each participant and then either
+//! [`MeshTest::run_intent`] or the individual lifecycle steps.
+
+#![allow(clippy::result_large_err)]
+
+pub mod fixture;
+
+pub use agent_mesh_client::{self, PROGRAM_ID};
+pub use fixture::{MeshTest, TestAgent};
+pub use solana_program_test::{self, BanksClientError, ProgramTest, ProgramTestContext};
+
+use solana_program_test::processor;
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+pub type Result<T> = std::result::Result<T, BanksClientError>;
+
+/// Custom error code a transaction failed with, to compare against
+/// `u32::from(agent_mesh::ErrorCode::…)`; `None` if it succeeded or failed
+/// for another reason
+pub fn program_error<T>(result: &Result<T>) -> Option<u32> {
+    match result.as_ref().err()?.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
+
+/// Anchor's entrypoint ties the account slice and account lifetimes together,
+/// which the native processor signature doesn't; the slice is leaked for the
+/// duration of the test instead.
+fn process_instruction<'a, 'b, 'c, 'd>(
+    program_id: &'a Pubkey,
+    accounts: &'b [AccountInfo<'c>],
+    data: &'d [u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    agent_mesh::entry(program_id, accounts, data)
+}
+
+/// `ProgramTest` with the Agent Mesh program registered; add further
+/// programs or accounts before starting it
+pub fn program_test() -> ProgramTest {
+    let mut test = ProgramTest::new("agent_mesh", PROGRAM_ID, processor!(process_instruction));
+    test.prefer_bpf(false);
+    test
+}
@@ -0,0 +1,717 @@
+//! Authorization and refund paths of the intent lifecycle, run against the
+//! program through the [`MeshTest`] fixtures.
+
+use agent_mesh::{
+    instruction, CommitmentScheme, Deadline, ErrorCode, IntentOption, IntentStatus,
+    ProtocolParameter,
+};
+use agent_mesh_test_utils::agent_mesh_client::instructions::DisputeRuling;
+use agent_mesh_test_utils::agent_mesh_client::{commitment, instructions, pda, quote};
+use agent_mesh_test_utils::fixture::DEFAULT_PRICING;
+use agent_mesh_test_utils::{program_error, MeshTest};
+use anchor_spl::token::spl_token;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+const TOKENS: u64 = 10_000;
+const BALANCE: u64 = 1_000_000;
+
+async fn intent_status(mesh: &mut MeshTest, intent: &Pubkey) -> u8 {
+    let account: agent_mesh::AgentIntent =
+        mesh.account(intent).await.unwrap().expect("intent exists");
+    account.status
+}
+
+#[tokio::test]
+async fn cancel_and_close_require_the_escrow() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let intent = mesh
+        .create_intent(&from, &to, &mint, TOKENS, 0)
+        .await
+        .unwrap();
+    let escrow = pda::escrow(&intent, &mint);
+    let payment = quote(DEFAULT_PRICING, TOKENS).unwrap();
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), payment);
+
+    // Without the escrow the cancellation would strand the payment
+    let cancel = instructions::cancel_intent(
+        &from.owner(),
+        from.agent,
+        intent,
+        None,
+        Some(from.token_account),
+        None,
+        None,
+    );
+    let result = mesh.process(&[cancel], &[&from.owner]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::EscrowAccountRequired))
+    );
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Pending as u8
+    );
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), payment);
+
+    let cancel = instructions::cancel_intent(
+        &from.owner(),
+        from.agent,
+        intent,
+        Some(escrow),
+        Some(from.token_account),
+        None,
+        None,
+    );
+    mesh.process(&[cancel], &[&from.owner]).await.unwrap();
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE
+    );
+
+    // Closing has to take the intent's own escrow with it
+    let account: agent_mesh::AgentIntent = mesh.account(&intent).await.unwrap().unwrap();
+    let close =
+        instructions::close_intent(intent, account.rent_payer, from.token_account, None, None);
+    let result = mesh.process(&[close], &[]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::InvalidEscrowAccount))
+    );
+
+    let close = instructions::close_intent(intent, account.rent_payer, escrow, None, None);
+    mesh.process(&[close], &[]).await.unwrap();
+    let banks = &mut mesh.context.banks_client;
+    assert!(banks.get_account(intent).await.unwrap().is_none());
+    assert!(banks.get_account(escrow).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn open_intent_rejects_a_payer_that_is_not_the_requester() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let stranger = mesh.funded_keypair().await.unwrap();
+    let stranger_tokens = mesh
+        .mint_to(&mint, &stranger.pubkey(), BALANCE)
+        .await
+        .unwrap();
+
+    let nonce = mesh.intent_nonce(from.agent, to.agent).await.unwrap();
+    let builder = instructions::CreateIntentBuilder::new(
+        &stranger.pubkey(),
+        from.agent,
+        to.agent,
+        mint,
+        stranger_tokens,
+        nonce,
+        instruction::CreateIntent {
+            payload_uri: "ipfs://test-payload".to_string(),
+            payload: commitment::commit(CommitmentScheme::Sha256, b"test payload"),
+            payment_amount: quote(DEFAULT_PRICING, TOKENS).unwrap(),
+            options: 0,
+            tip_amount: 0,
+        },
+    );
+    let intent = builder.intent();
+    let create_escrow =
+        create_associated_token_account_idempotent(&mesh.payer(), &intent, &mint, &spl_token::ID);
+    let result = mesh
+        .process(&[create_escrow, builder.instruction()], &[&stranger])
+        .await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+    assert_eq!(mesh.token_balance(&stranger_tokens).await.unwrap(), BALANCE);
+    assert_eq!(
+        mesh.intent_nonce(from.agent, to.agent).await.unwrap(),
+        nonce
+    );
+}
+
+#[tokio::test]
+async fn booking_rejects_a_payer_that_is_not_the_requester() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let stranger = mesh.funded_keypair().await.unwrap();
+    let stranger_tokens = mesh
+        .mint_to(&mint, &stranger.pubkey(), BALANCE)
+        .await
+        .unwrap();
+
+    let booking = pda::booking(&from.agent, &to.agent, 0).0;
+    let create_vault =
+        create_associated_token_account_idempotent(&mesh.payer(), &booking, &mint, &spl_token::ID);
+    mesh.process(&[create_vault], &[]).await.unwrap();
+    let deposit = quote(DEFAULT_PRICING, TOKENS).unwrap();
+    let create_booking = |payer: &Pubkey, payer_tokens: Pubkey| {
+        instructions::create_booking(
+            payer,
+            from.agent,
+            to.agent,
+            to.model_profile,
+            mint,
+            payer_tokens,
+            instruction::CreateBooking {
+                nonce: 0,
+                tokens_per_hour: TOKENS,
+                start: Deadline::UnixTimestamp(0),
+                end: Deadline::UnixTimestamp(3_600),
+                deposit,
+            },
+        )
+    };
+
+    // A booking funded by someone else would pass for the requester's own
+    let result = mesh
+        .process(
+            &[create_booking(&stranger.pubkey(), stranger_tokens)],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+    assert_eq!(mesh.token_balance(&stranger_tokens).await.unwrap(), BALANCE);
+
+    mesh.process(
+        &[create_booking(&from.owner(), from.token_account)],
+        &[&from.owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        mesh.token_balance(&pda::booking_vault(&booking, &mint))
+            .await
+            .unwrap(),
+        deposit
+    );
+}
+
+#[tokio::test]
+async fn redelegated_intent_settles_into_the_accounts_fixed_at_redelegation() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let provider = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let delegate = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let intent = mesh
+        .create_intent(
+            &from,
+            &provider,
+            &mint,
+            TOKENS,
+            IntentOption::ALLOW_REDELEGATION,
+        )
+        .await
+        .unwrap();
+    mesh.accept_intent(&provider, intent).await.unwrap();
+
+    let redelegate = instructions::redelegate_intent(
+        &provider.owner(),
+        provider.agent,
+        intent,
+        &delegate.owner(),
+        provider.token_account,
+        delegate.token_account,
+        None,
+        4_000,
+    );
+    mesh.process(&[redelegate], &[&provider.owner, &delegate.owner])
+        .await
+        .unwrap();
+
+    let escrow = pda::escrow(&intent, &mint);
+    let settle = |billing| {
+        let submit = instructions::submit_result(
+            &delegate.owner(),
+            intent,
+            commitment::commit(CommitmentScheme::Sha256, b"test result"),
+            "ipfs://test-result".to_string(),
+        );
+        let settle = instructions::SettleIntentBuilder::new(&delegate.owner(), intent)
+            .release(escrow, billing)
+            .delegate_billing(delegate.token_account)
+            .instruction();
+        [submit, settle]
+    };
+
+    // The delegate settles, but can't take the provider's share
+    let result = mesh
+        .process(&settle(delegate.token_account), &[&delegate.owner])
+        .await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Accepted as u8
+    );
+
+    mesh.process(&settle(provider.token_account), &[&delegate.owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Completed as u8
+    );
+    let payment = quote(DEFAULT_PRICING, TOKENS).unwrap();
+    let delegate_share = payment * 4_000 / 10_000;
+    assert_eq!(
+        mesh.token_balance(&delegate.token_account).await.unwrap(),
+        delegate_share
+    );
+    assert_eq!(
+        mesh.token_balance(&provider.token_account).await.unwrap(),
+        payment - delegate_share
+    );
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn late_cancellation_forfeits_the_penalty_to_the_provider() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    mesh.update_parameter(ProtocolParameter::CancellationPenaltyBps(2_500))
+        .await
+        .unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let intent = mesh
+        .create_intent(&from, &to, &mint, TOKENS, 0)
+        .await
+        .unwrap();
+    mesh.accept_intent(&to, intent).await.unwrap();
+    // The config's cancellation window is zero, so it closes right away
+    mesh.warp_seconds(1).await.unwrap();
+    let escrow = pda::escrow(&intent, &mint);
+
+    // Past the window only the requester may still back out
+    let cancel = instructions::cancel_intent(
+        &to.owner(),
+        to.agent,
+        intent,
+        Some(escrow),
+        Some(from.token_account),
+        None,
+        None,
+    );
+    let result = mesh.process(&[cancel], &[&to.owner]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::CancellationWindowClosed))
+    );
+
+    let cancel = instructions::cancel_intent(
+        &from.owner(),
+        from.agent,
+        intent,
+        Some(escrow),
+        Some(from.token_account),
+        Some((to.agent, to.token_account)),
+        None,
+    );
+    mesh.process(&[cancel], &[&from.owner]).await.unwrap();
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Cancelled as u8
+    );
+    let penalty = quote(DEFAULT_PRICING, TOKENS).unwrap() / 4;
+    assert_eq!(
+        mesh.token_balance(&to.token_account).await.unwrap(),
+        penalty
+    );
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE - penalty
+    );
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn timed_out_intent_slashes_the_bond_to_the_payer() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    for parameter in [
+        ProtocolParameter::AcceptanceBondBps(1_000),
+        ProtocolParameter::TimeoutTipBps(1_000),
+        ProtocolParameter::CompletionWindow(TimeSpan::Seconds(60)),
+    ] {
+        mesh.update_parameter(parameter).await.unwrap();
+    }
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let cranker = mesh.funded_keypair().await.unwrap();
+    let cranker_tokens = mesh.mint_to(&mint, &cranker.pubkey(), 0).await.unwrap();
+    let intent = mesh
+        .create_intent(&from, &to, &mint, TOKENS, 0)
+        .await
+        .unwrap();
+    let escrow = pda::escrow(&intent, &mint);
+
+    // Accepting takes the bond first
+    let accept = instructions::accept_intent(&to.owner(), intent, to.agent, None);
+    let result = mesh.process(&[accept], &[&to.owner]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::BondRequired))
+    );
+    let bond = instructions::post_bond(&to.owner(), intent, to.agent, mint, to.token_account);
+    mesh.process(&[bond], &[&to.owner]).await.unwrap();
+    mesh.accept_intent(&to, intent).await.unwrap();
+
+    let flag = || {
+        instructions::flag_timeout(
+            &cranker.pubkey(),
+            intent,
+            &to.agent,
+            escrow,
+            from.token_account,
+            Some(to.owner()),
+            Some(cranker_tokens),
+            None,
+        )
+    };
+    let result = mesh.process(&[flag()], &[&cranker]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::NotTimedOut))
+    );
+
+    mesh.warp_seconds(61).await.unwrap();
+    mesh.process(&[flag()], &[&cranker]).await.unwrap();
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Failed as u8
+    );
+    let bond = quote(DEFAULT_PRICING, TOKENS).unwrap() / 10;
+    let tip = bond / 10;
+    assert_eq!(mesh.token_balance(&cranker_tokens).await.unwrap(), tip);
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE + bond - tip
+    );
+    assert_eq!(
+        mesh.token_balance(&to.token_account).await.unwrap(),
+        BALANCE - bond
+    );
+    let banks = &mut mesh.context.banks_client;
+    assert!(banks
+        .get_account(pda::bond_vault(&intent).0)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn selecting_a_bid_refunds_the_difference_to_the_payer() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let high = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let low = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let stranger = mesh.funded_keypair().await.unwrap();
+    // Open intents are addressed to the requester's own agent
+    let intent = mesh
+        .create_intent(&from, &from, &mint, TOKENS, IntentOption::OPEN_BIDDING)
+        .await
+        .unwrap();
+    let escrow = pda::escrow(&intent, &mint);
+    let payment = quote(DEFAULT_PRICING, TOKENS).unwrap();
+
+    for (bidder, price) in [(&high, payment * 3 / 5), (&low, payment * 2 / 5)] {
+        let bid = instructions::place_bid(&bidder.owner(), intent, bidder.agent, price, 60);
+        mesh.process(&[bid], &[&bidder.owner]).await.unwrap();
+    }
+    let select = |bidder: &Pubkey| {
+        instructions::select_bid(
+            &stranger.pubkey(),
+            intent,
+            from.agent,
+            bidder,
+            escrow,
+            from.token_account,
+        )
+    };
+
+    // Anyone may award the lowest bid, but only once bidding has closed
+    let result = mesh.process(&[select(&low.agent)], &[&stranger]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+    mesh.warp_seconds(10 * 60 + 1).await.unwrap();
+    let result = mesh.process(&[select(&high.agent)], &[&stranger]).await;
+    assert_eq!(
+        program_error(&result),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+    mesh.process(&[select(&low.agent)], &[&stranger])
+        .await
+        .unwrap();
+
+    let price = payment * 2 / 5;
+    let account: agent_mesh::AgentIntent = mesh.account(&intent).await.unwrap().unwrap();
+    assert_eq!(account.winning_agent, low.agent);
+    assert_eq!(account.payment_amount, price);
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), price);
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE - price
+    );
+
+    // The winner takes it from there as the provider
+    mesh.accept_intent(&low, intent).await.unwrap();
+    mesh.complete_intent(&low, intent).await.unwrap();
+    assert_eq!(mesh.token_balance(&low.token_account).await.unwrap(), price);
+}
+
+#[tokio::test]
+async fn late_completion_refunds_the_sla_penalty() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let intent = mesh
+        .create_intent(&from, &to, &mint, TOKENS, 0)
+        .await
+        .unwrap();
+    // An hour to deliver, then 10% of the payment per hour late
+    let sla = instructions::set_sla(&from.owner(), intent, from.agent, 3_600, 1_000);
+    mesh.process(&[sla], &[&from.owner]).await.unwrap();
+    mesh.accept_intent(&to, intent).await.unwrap();
+
+    mesh.warp_seconds(3 * 3_600).await.unwrap();
+    mesh.complete_intent(&to, intent).await.unwrap();
+
+    let payment = quote(DEFAULT_PRICING, TOKENS).unwrap();
+    let penalty = payment / 5;
+    assert_eq!(
+        mesh.token_balance(&to.token_account).await.unwrap(),
+        payment - penalty
+    );
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE - payment + penalty
+    );
+}
+
+#[tokio::test]
+async fn dispute_fee_is_split_or_returned_with_the_ruling() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let arbiter = mesh.payer();
+    for parameter in [
+        ProtocolParameter::ChallengeWindow(TimeSpan::Seconds(3_600)),
+        ProtocolParameter::DisputeFeeBps(500),
+        ProtocolParameter::DisputeArbiterShareBps(4_000),
+        ProtocolParameter::AddArbiter(arbiter),
+    ] {
+        mesh.update_parameter(parameter).await.unwrap();
+    }
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let arbiter_tokens = mesh.mint_to(&mint, &arbiter, 0).await.unwrap();
+    let payment = quote(DEFAULT_PRICING, TOKENS).unwrap();
+    let fee = payment / 20;
+
+    let mut disputed = Vec::new();
+    for _ in 0..2 {
+        let intent = mesh
+            .create_intent(&from, &to, &mint, TOKENS, IntentOption::AUTO_RELEASE)
+            .await
+            .unwrap();
+        mesh.accept_intent(&to, intent).await.unwrap();
+        mesh.complete_intent(&to, intent).await.unwrap();
+        let escrow = pda::escrow(&intent, &mint);
+        let open = instructions::open_dispute(
+            &from.owner(),
+            from.agent,
+            intent,
+            escrow,
+            from.token_account,
+            "ipfs://test-dispute".to_string(),
+        );
+        mesh.process(&[open], &[&from.owner]).await.unwrap();
+        assert_eq!(mesh.token_balance(&escrow).await.unwrap(), payment + fee);
+        disputed.push((intent, escrow));
+    }
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE - 2 * (payment + fee)
+    );
+    let resolve = |(intent, escrow): (Pubkey, Pubkey), ruling| {
+        instructions::resolve_dispute(
+            &arbiter,
+            intent,
+            &to.agent,
+            from.owner(),
+            escrow,
+            from.token_account,
+            ruling,
+        )
+    };
+
+    // A failed challenge forfeits the fee to the arbiter and the provider,
+    // and the payment stays held for release
+    let (intent, escrow) = disputed[0];
+    let forfeit = DisputeRuling::Forfeit {
+        arbiter_token_account: Some(arbiter_tokens),
+        counterparty_token_account: Some(to.token_account),
+    };
+    mesh.process(&[resolve(disputed[0], forfeit)], &[])
+        .await
+        .unwrap();
+    let arbiter_share = fee * 2 / 5;
+    assert_eq!(
+        mesh.token_balance(&arbiter_tokens).await.unwrap(),
+        arbiter_share
+    );
+    assert_eq!(
+        mesh.token_balance(&to.token_account).await.unwrap(),
+        fee - arbiter_share
+    );
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), payment);
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Completed as u8
+    );
+
+    // A successful one returns the payment and the fee
+    let (intent, escrow) = disputed[1];
+    let refund = DisputeRuling::Refund {
+        refund_token_account: from.token_account,
+        booking: None,
+    };
+    mesh.process(&[resolve(disputed[1], refund)], &[])
+        .await
+        .unwrap();
+    assert_eq!(mesh.token_balance(&escrow).await.unwrap(), 0);
+    assert_eq!(
+        intent_status(&mut mesh, &intent).await,
+        IntentStatus::Failed as u8
+    );
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE - payment - fee
+    );
+}
+
+#[tokio::test]
+async fn crank_batches_skip_unready_intents_and_tip_per_processed_one() {
+    let mut mesh = MeshTest::start().await.unwrap();
+    let tip = 5_000;
+    for parameter in [
+        ProtocolParameter::CompletionWindow(TimeSpan::Seconds(60)),
+        ProtocolParameter::ChallengeWindow(TimeSpan::Seconds(60)),
+        ProtocolParameter::CrankTipLamports(tip),
+    ] {
+        mesh.update_parameter(parameter).await.unwrap();
+    }
+    let fund = instructions::fund_crank_treasury(&mesh.payer(), 1_000_000_000);
+    mesh.process(&[fund], &[]).await.unwrap();
+    let mint = mesh.create_mint(6).await.unwrap();
+    let from = mesh.spawn_agent(&mint, BALANCE).await.unwrap();
+    let to = mesh.spawn_agent(&mint, 0).await.unwrap();
+    let cranker = mesh.funded_keypair().await.unwrap();
+    let payment = quote(DEFAULT_PRICING, TOKENS).unwrap();
+
+    let mut stale = Vec::new();
+    for _ in 0..2 {
+        let intent = mesh
+            .create_intent(&from, &to, &mint, TOKENS, 0)
+            .await
+            .unwrap();
+        mesh.accept_intent(&to, intent).await.unwrap();
+        stale.push(intent);
+    }
+    let completed = mesh
+        .create_intent(&from, &to, &mint, TOKENS, IntentOption::AUTO_RELEASE)
+        .await
+        .unwrap();
+    mesh.accept_intent(&to, completed).await.unwrap();
+    mesh.complete_intent(&to, completed).await.unwrap();
+    mesh.warp_seconds(61).await.unwrap();
+
+    // Accepted and completed after the warp, so neither is due yet
+    let fresh = mesh
+        .create_intent(&from, &to, &mint, TOKENS, IntentOption::AUTO_RELEASE)
+        .await
+        .unwrap();
+    mesh.accept_intent(&to, fresh).await.unwrap();
+    let expirations: Vec<_> = stale
+        .iter()
+        .chain([&fresh])
+        .map(|&intent| instructions::Expiration {
+            intent,
+            assignee: to.agent,
+            escrow: pda::escrow(&intent, &mint),
+            refund_account: from.token_account,
+        })
+        .collect();
+    let before = mesh
+        .context
+        .banks_client
+        .get_balance(cranker.pubkey())
+        .await
+        .unwrap();
+    let crank = instructions::process_expirations(&cranker.pubkey(), &expirations);
+    mesh.process(&[crank], &[&cranker]).await.unwrap();
+    for intent in &stale {
+        assert_eq!(
+            intent_status(&mut mesh, intent).await,
+            IntentStatus::Failed as u8
+        );
+    }
+    assert_eq!(
+        intent_status(&mut mesh, &fresh).await,
+        IntentStatus::Accepted as u8
+    );
+    assert_eq!(
+        mesh.token_balance(&from.token_account).await.unwrap(),
+        BALANCE - 2 * payment
+    );
+    let banks = &mut mesh.context.banks_client;
+    assert_eq!(
+        banks.get_balance(cranker.pubkey()).await.unwrap(),
+        before + 2 * tip
+    );
+
+    mesh.complete_intent(&to, fresh).await.unwrap();
+    let releases: Vec<_> = [completed, fresh]
+        .into_iter()
+        .map(|intent| instructions::ReadyRelease {
+            intent,
+            escrow: pda::escrow(&intent, &mint),
+            release_account: to.token_account,
+            delegate_release_account: None,
+        })
+        .collect();
+    let crank = instructions::process_settlements(&cranker.pubkey(), None, &releases);
+    mesh.process(&[crank], &[&cranker]).await.unwrap();
+    assert_eq!(
+        mesh.token_balance(&to.token_account).await.unwrap(),
+        payment
+    );
+    assert_eq!(
+        mesh.token_balance(&pda::escrow(&fresh, &mint))
+            .await
+            .unwrap(),
+        payment
+    );
+    let banks = &mut mesh.context.banks_client;
+    assert_eq!(
+        banks.get_balance(cranker.pubkey()).await.unwrap(),
+        before + 3 * tip
+    );
+}
@@ -1,5 +1,14 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, ed25519_program, system_instruction, sysvar};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use spl_account_compression::{program::SplAccountCompression, Noop};
 
 declare_id!("AgentMesh111111111111111111111111111111111");
 
@@ -7,31 +16,81 @@ declare_id!("AgentMesh111111111111111111111111111111111");
 pub mod agent_mesh {
     use super::*;
 
-    /// Register a new agent identity on-chain
+    /// Register a new agent identity on-chain, or register again after
+    /// `close_agent` (see `RegisterOption` for updating an existing identity
+    /// and resetting reputation)
     pub fn register_agent(
         ctx: Context<RegisterAgent>,
         agent_wallet: Pubkey,
         model_profile: Pubkey,
         metadata_uri: String,
         permissions: u64,
+        options: u8,
     ) -> Result<()> {
+        ctx.accounts.config.require_uri_len(&metadata_uri)?;
+        require_keys_eq!(
+            ctx.accounts.model_profile.key(),
+            model_profile,
+            ErrorCode::InvalidModelProfile
+        );
+        check_profile_access(
+            &ctx.accounts.model_profile,
+            ctx.accounts.agent.key(),
+            ctx.accounts.owner.key(),
+            ctx.accounts.profile_authorization.as_ref(),
+        )?;
+
         let agent = &mut ctx.accounts.agent;
         let clock = Clock::get()?;
-
-        agent.owner_wallet = ctx.accounts.owner.key();
+        let existing = agent.owner_wallet != Pubkey::default();
+        if existing {
+            require!(
+                options & RegisterOption::UPDATE_EXISTING != 0,
+                ErrorCode::AgentAlreadyRegistered
+            );
+            // init_if_needed doesn't realloc; longer URIs go through update_agent
+            require!(
+                AgentIdentity::space(metadata_uri.len()) <= agent.to_account_info().data_len(),
+                ErrorCode::UriTooLong
+            );
+        } else {
+            agent.owner_wallet = ctx.accounts.owner.key();
+            agent.created_at = clock.unix_timestamp;
+            agent.bump = ctx.bumps.agent;
+            agent.version = AgentIdentity::VERSION;
+        }
         agent.agent_wallet = agent_wallet;
         agent.model_profile = model_profile;
         agent.metadata_uri = metadata_uri;
         agent.permissions = permissions;
-        agent.created_at = clock.unix_timestamp;
         agent.updated_at = clock.unix_timestamp;
-        agent.bump = ctx.bumps.agent;
+        if options & RegisterOption::RESET_REPUTATION != 0 {
+            agent.reputation_since = clock.unix_timestamp;
+            ctx.accounts.stats.reset();
+        }
+        ctx.accounts.stats.init(agent.key(), ctx.bumps.stats);
 
-        emit!(AgentRegistered {
-            agent: agent.key(),
-            owner: agent.owner_wallet,
-            agent_wallet: agent.agent_wallet,
-        });
+        if existing {
+            emit!(AgentUpdated {
+                agent: agent.key(),
+                updated_at: agent.updated_at,
+            });
+        } else {
+            emit!(AgentRegistered {
+                agent: agent.key(),
+                owner: agent.owner_wallet,
+                agent_wallet: agent.agent_wallet,
+            });
+        }
+
+        // The badge mint outlives close_agent, so an identity registered
+        // again keeps the badge minted the first time
+        if let Some(badge_mint) = &ctx.accounts.badge_mint {
+            if badge_mint.lamports() == 0 {
+                ctx.accounts.config.require_settlement_open()?;
+                mint_identity_badge(ctx.accounts, ctx.bumps.badge_mint)?;
+            }
+        }
 
         Ok(())
     }
@@ -44,6 +103,24 @@ pub mod agent_mesh {
         metadata_uri: Option<String>,
         permissions: Option<u64>,
     ) -> Result<()> {
+        if let Some(uri) = &metadata_uri {
+            ctx.accounts.config.require_uri_len(uri)?;
+        }
+        if let Some(profile) = model_profile {
+            let profile_account = ctx
+                .accounts
+                .model_profile
+                .as_ref()
+                .ok_or(ErrorCode::InvalidModelProfile)?;
+            require_keys_eq!(profile_account.key(), profile, ErrorCode::InvalidModelProfile);
+            check_profile_access(
+                profile_account,
+                ctx.accounts.agent.key(),
+                ctx.accounts.owner.key(),
+                ctx.accounts.profile_authorization.as_ref(),
+            )?;
+        }
+
         let agent = &mut ctx.accounts.agent;
         let clock = Clock::get()?;
 
@@ -70,6 +147,396 @@ pub mod agent_mesh {
         Ok(())
     }
 
+    /// Close the signer's agent and return its rent. Intents already open
+    /// with the agent keep their escrow; the payer can still cancel them.
+    pub fn close_agent(ctx: Context<CloseAgent>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let agent = ctx.accounts.agent.to_account_info();
+
+        emit!(AgentClosed {
+            agent: agent.key(),
+            owner: ctx.accounts.owner.key(),
+            lamports: agent.lamports(),
+        });
+        emit_rent_refund(&agent, ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Open the `AgentStats` of an agent registered before stats were kept
+    /// (permissionless). Its totals start from zero.
+    pub fn open_agent_stats(ctx: Context<OpenAgentStats>) -> Result<()> {
+        ctx.accounts.stats.init(ctx.accounts.agent.key(), ctx.bumps.stats);
+        Ok(())
+    }
+
+    /// Let another agent act for the signer's agent with a subset of its
+    /// permissions until `expires`. Granting again replaces the permissions
+    /// and expiry. With `CAN_CREATE_INTENT` the delegate can create intents
+    /// from the grantor funded from the grantor's tokens, up to whatever the
+    /// grantor has SPL-approved to the delegation PDA.
+    pub fn grant_delegation(ctx: Context<GrantDelegation>, permissions: u64, expires: Deadline) -> Result<()> {
+        let grantor = &ctx.accounts.agent;
+        let clock = Clock::get()?;
+
+        grantor.require_active()?;
+        require!(
+            permissions != 0 && permissions & !grantor.permissions == 0,
+            ErrorCode::InvalidDelegation
+        );
+        require!(
+            !expires.passed(&clock, &ctx.accounts.config),
+            ErrorCode::InvalidDelegation
+        );
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.grantor = grantor.key();
+        delegation.delegate = ctx.accounts.delegate.key();
+        delegation.permissions = permissions;
+        delegation.expires = expires;
+        delegation.granted_at = clock.unix_timestamp;
+        delegation.bump = ctx.bumps.delegation;
+
+        emit!(DelegationGranted {
+            grantor: delegation.grantor,
+            delegate: delegation.delegate,
+            permissions,
+            expires,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a delegation and return its rent. Intents the delegate already
+    /// created stay open.
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        let delegation = &ctx.accounts.delegation;
+
+        emit!(DelegationRevoked {
+            grantor: delegation.grantor,
+            delegate: delegation.delegate,
+        });
+        emit_rent_refund(&delegation.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Designate a guardian key that may `freeze_agent` alongside the owner
+    /// (`Pubkey::default()` removes it)
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        agent.guardian = guardian;
+        agent.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(GuardianSet {
+            agent: agent.key(),
+            guardian,
+        });
+
+        Ok(())
+    }
+
+    /// Freeze a compromised agent (owner or guardian). Until the owner
+    /// unfreezes it, the agent can't create, accept or work intents, and its
+    /// agent wallet can't move funds or vote. Every delegation granted so far
+    /// stops working for good.
+    pub fn freeze_agent(ctx: Context<FreezeAgent>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let now = Clock::get()?.unix_timestamp;
+        agent.frozen = true;
+        agent.delegations_revoked_at = now;
+        agent.updated_at = now;
+
+        emit!(AgentFrozen {
+            agent: agent.key(),
+            frozen_by: ctx.accounts.authority.key(),
+            frozen_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Lift a freeze (owner only). Revoked delegations stay revoked.
+    pub fn unfreeze_agent(ctx: Context<UnfreezeAgent>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        agent.frozen = false;
+        agent.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(AgentUnfrozen { agent: agent.key() });
+
+        Ok(())
+    }
+
+    /// Advertise the agent's current load (owner or agent wallet). While a
+    /// fresh signal reports no free slots, `create_intent` rejects new work
+    /// for the agent; off-chain matchers can rank providers by `queue_depth`.
+    pub fn set_capacity(ctx: Context<SetCapacity>, available_slots: u32, queue_depth: u32) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let clock = Clock::get()?;
+
+        agent.available_slots = available_slots;
+        agent.queue_depth = queue_depth;
+        agent.capacity_updated_at = clock.unix_timestamp;
+
+        emit!(CapacityUpdated {
+            agent: agent.key(),
+            available_slots,
+            queue_depth,
+            updated_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the smallest payment the agent accepts per intent (owner only),
+    /// so zero- or dust-payment intents can't flood its inbox. Trial intents
+    /// are exempt.
+    pub fn set_min_payment(ctx: Context<SetMinPayment>, min_payment: u64) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        agent.min_payment = min_payment;
+        agent.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(MinPaymentUpdated {
+            agent: agent.key(),
+            min_payment,
+        });
+
+        Ok(())
+    }
+
+    /// Rotate the agent's X25519 encryption key. The outgoing key stays valid
+    /// for `overlap` seconds so payloads already encrypted to it can still be
+    /// opened; its hash is kept in `key_history`. An `overlap` of 0 retires
+    /// the old key immediately (e.g. after a compromise).
+    pub fn rotate_encryption_key(
+        ctx: Context<RotateEncryptionKey>,
+        new_key: [u8; 32],
+        overlap: i64,
+    ) -> Result<()> {
+        require!(new_key != [0u8; 32], ErrorCode::InvalidEncryptionKey);
+        require!(
+            (0..=MAX_KEY_OVERLAP).contains(&overlap),
+            ErrorCode::InvalidParameter
+        );
+
+        let agent = &mut ctx.accounts.agent;
+        let now = Clock::get()?.unix_timestamp;
+        require!(new_key != agent.encryption_key, ErrorCode::InvalidEncryptionKey);
+        require!(
+            overlap == 0 || now >= agent.previous_key_expires_at,
+            ErrorCode::KeyRotationInProgress
+        );
+
+        if agent.encryption_key != [0u8; 32] {
+            let slot = agent.key_version as usize % KEY_HISTORY_LEN;
+            agent.key_history[slot] = keccak::hash(&agent.encryption_key).to_bytes();
+            agent.previous_encryption_key = agent.encryption_key;
+            agent.previous_key_expires_at = now.checked_add(overlap).ok_or(ErrorCode::MathOverflow)?;
+        }
+        agent.encryption_key = new_key;
+        agent.key_version = agent.key_version.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        agent.updated_at = now;
+
+        emit!(EncryptionKeyRotated {
+            agent: agent.key(),
+            key_version: agent.key_version,
+            key_hash: keccak::hash(&new_key).to_bytes(),
+            previous_key_expires_at: agent.previous_key_expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize the singleton protocol config (the program's upgrade
+    /// authority only, so nobody can take the protocol between deploy and init)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, attestor: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.admin = ctx.accounts.admin.key();
+        config.attestor = attestor;
+        config.mode = OperatingMode::Active as u8;
+        config.paused = false;
+        config.governance = Pubkey::default();
+        config.max_uri_len = DEFAULT_URI_LEN as u16;
+        config.intent_rate_window = DEFAULT_INTENT_RATE_WINDOW;
+        config.bidding_window = DEFAULT_BIDDING_WINDOW;
+        config.program_version = PROGRAM_VERSION;
+        config.bump = ctx.bumps.config;
+
+        emit!(ConfigInitialized {
+            config: config.key(),
+            admin: config.admin,
+            attestor: config.attestor,
+        });
+
+        Ok(())
+    }
+
+    /// Record the running program's version in the config after an upgrade
+    /// (admin only)
+    pub fn sync_program_version(ctx: Context<UpdateConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous = config.program_version;
+        config.program_version = PROGRAM_VERSION;
+
+        emit!(ProgramVersionSynced {
+            config: config.key(),
+            previous,
+            current: PROGRAM_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Return the deployed program's version (and the one recorded in the
+    /// config) as return data, for clients to check compatibility via simulation
+    pub fn get_version(ctx: Context<GetVersion>) -> Result<VersionInfo> {
+        Ok(VersionInfo {
+            program: PROGRAM_VERSION,
+            config: ctx.accounts.config.program_version,
+        })
+    }
+
+    /// Rotate the attestor authority (admin only)
+    pub fn set_attestor(ctx: Context<UpdateConfig>, attestor: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.attestor = attestor;
+
+        emit!(AttestorUpdated {
+            config: config.key(),
+            attestor,
+        });
+
+        Ok(())
+    }
+
+    /// Switch the protocol between active and draining (admin only)
+    pub fn set_operating_mode(ctx: Context<UpdateConfig>, mode: u8) -> Result<()> {
+        require!(OperatingMode::is_valid(mode), ErrorCode::InvalidOperatingMode);
+
+        let config = &mut ctx.accounts.config;
+        config.mode = mode;
+
+        emit!(OperatingModeChanged {
+            config: config.key(),
+            mode,
+        });
+
+        Ok(())
+    }
+
+    /// Emergency circuit breaker: halt or resume every fund-moving instruction (admin only)
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = paused;
+
+        emit!(PauseToggled {
+            config: config.key(),
+            paused,
+        });
+
+        Ok(())
+    }
+
+    /// Hand parameter control to an spl-governance governance account, which
+    /// signs when a passed proposal executes. Until then the admin holds it.
+    /// The account must be a live governance of `realm` under
+    /// `governance_program`, so a mistyped key can't take control.
+    pub fn set_governance(ctx: Context<SetGovernance>) -> Result<()> {
+        let governance_program = ctx.accounts.governance_program.key();
+        let governance_account = &ctx.accounts.governance;
+        let realm = &ctx.accounts.realm;
+        require!(
+            *governance_account.owner == governance_program && *realm.owner == governance_program,
+            ErrorCode::InvalidGovernanceAccounts
+        );
+        let governance_realm = governance_account
+            .try_borrow_data()?
+            .get(GOVERNANCE_REALM_OFFSET..GOVERNANCE_REALM_OFFSET + 32)
+            .and_then(|bytes| Pubkey::try_from(bytes).ok())
+            .ok_or(ErrorCode::InvalidGovernanceAccounts)?;
+        require_keys_eq!(governance_realm, realm.key(), ErrorCode::InvalidGovernanceAccounts);
+
+        let governance = governance_account.key();
+        let config = &mut ctx.accounts.config;
+        config.governance = governance;
+
+        emit!(GovernanceUpdated {
+            config: config.key(),
+            governance,
+        });
+
+        Ok(())
+    }
+
+    /// Update a single protocol parameter (governance, or admin before handover)
+    pub fn update_parameter(ctx: Context<GovernConfig>, parameter: ProtocolParameter) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.apply(&parameter)?;
+
+        emit!(ParameterUpdated {
+            config: config.key(),
+            parameter,
+        });
+
+        Ok(())
+    }
+
+    /// Mark an agent or paying program as fee-exempt (10_000 bps) or give it a
+    /// partial protocol fee discount at settlement (admin only)
+    pub fn add_fee_allowlist(
+        ctx: Context<AddFeeAllowlist>,
+        subject: Pubkey,
+        discount_bps: u16,
+    ) -> Result<()> {
+        require!(discount_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+
+        let entry = &mut ctx.accounts.fee_allowlist;
+        let clock = Clock::get()?;
+
+        entry.subject = subject;
+        entry.discount_bps = discount_bps;
+        entry.added_at = clock.unix_timestamp;
+        entry.bump = ctx.bumps.fee_allowlist;
+
+        emit!(FeeAllowlistUpdated {
+            subject,
+            discount_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a fee allowlist entry (admin only)
+    pub fn remove_fee_allowlist(ctx: Context<RemoveFeeAllowlist>) -> Result<()> {
+        emit!(FeeAllowlistUpdated {
+            subject: ctx.accounts.fee_allowlist.subject,
+            discount_bps: 0,
+        });
+        emit_rent_refund(&ctx.accounts.fee_allowlist.to_account_info(), ctx.accounts.admin.key());
+
+        Ok(())
+    }
+
+    /// Create the insurance pool and premium vault for a payment mint (admin only)
+    pub fn initialize_insurance_pool(ctx: Context<InitializeInsurancePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.insurance_pool;
+
+        pool.mint = ctx.accounts.mint.key();
+        pool.vault = ctx.accounts.vault.key();
+        pool.total_premiums = 0;
+        pool.bump = ctx.bumps.insurance_pool;
+
+        emit!(InsurancePoolInitialized {
+            pool: pool.key(),
+            mint: pool.mint,
+            vault: pool.vault,
+        });
+
+        Ok(())
+    }
+
     /// Create a new model profile for LLM configuration
     pub fn create_model_profile(
         ctx: Context<CreateModelProfile>,
@@ -86,6 +553,14 @@ pub mod agent_mesh {
 
         profile.owner_wallet = ctx.accounts.owner.key();
         profile.profile_id = profile_id;
+        profile.verified = false;
+        profile.is_public = false;
+        profile.attestation = Pubkey::default();
+        profile.attested_at = 0;
+        profile.latest_version = 0;
+        profile.snapshot_current = false;
+        profile.payout_mint = Pubkey::default();
+        profile.max_slippage_bps = 0;
         profile.label = label;
         profile.provider_uri = provider_uri;
         profile.pricing = pricing;
@@ -95,6 +570,7 @@ pub mod agent_mesh {
         profile.created_at = clock.unix_timestamp;
         profile.updated_at = clock.unix_timestamp;
         profile.bump = ctx.bumps.model_profile;
+        profile.version = ModelProfile::VERSION;
 
         emit!(ModelProfileCreated {
             profile: profile.key(),
@@ -105,9 +581,10 @@ pub mod agent_mesh {
         Ok(())
     }
 
-    /// Update a model profile
+    /// Update a model profile. The new contents are snapshotted as the next
+    /// ProfileVersion in the same instruction, so intents can pin them.
     pub fn update_model_profile(
-        ctx: Context<UpdateModelProfile>,
+        ctx: Context<UpdateProfileContents>,
         label: Option<String>,
         provider_uri: Option<String>,
         pricing: Option<u64>,
@@ -122,6 +599,10 @@ pub mod agent_mesh {
             profile.label = l;
         }
         if let Some(uri) = provider_uri {
+            // A new endpoint hasn't been attested; drop verification
+            if uri != profile.provider_uri {
+                profile.verified = false;
+            }
             profile.provider_uri = uri;
         }
         if let Some(p) = pricing {
@@ -144,364 +625,11696 @@ pub mod agent_mesh {
             updated_at: profile.updated_at,
         });
 
-        Ok(())
+        snapshot_contents(
+            profile,
+            &mut ctx.accounts.profile_version,
+            ctx.bumps.profile_version,
+            clock.unix_timestamp,
+        )
     }
 
-    /// Create an intent from one agent to another
-    pub fn create_intent(
-        ctx: Context<CreateIntent>,
-        nonce: u64,
-        payload_hash: [u8; 32],
-        payload_uri: String,
-        payment_amount: u64,
+    /// Append the profile's current contents as an immutable ProfileVersion
+    pub fn snapshot_profile(ctx: Context<SnapshotProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.model_profile;
+        let clock = Clock::get()?;
+
+        require!(!profile.snapshot_current, ErrorCode::SnapshotUpToDate);
+
+        snapshot_contents(
+            profile,
+            &mut ctx.accounts.profile_version,
+            ctx.bumps.profile_version,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Choose a payout mint other than the escrowed one, converted at release
+    /// through an allow-listed swap. `Pubkey::default()` pays out as escrowed.
+    pub fn set_payout_preference(
+        ctx: Context<UpdateModelProfile>,
+        payout_mint: Pubkey,
+        max_slippage_bps: u16,
     ) -> Result<()> {
-        let intent = &mut ctx.accounts.intent;
+        require!(max_slippage_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+
+        let profile = &mut ctx.accounts.model_profile;
         let clock = Clock::get()?;
 
-        // Verify from_agent has CAN_CREATE_INTENT permission
+        profile.payout_mint = payout_mint;
+        profile.max_slippage_bps = max_slippage_bps;
+        profile.updated_at = clock.unix_timestamp;
+
+        emit!(ModelProfileUpdated {
+            profile: profile.key(),
+            updated_at: profile.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Offer trials on a profile: a new consumer agent locks `deposit` of
+    /// `mint` to get `requests` free intents within `duration`.
+    /// `requests` of 0 turns trials off; running trials keep their terms.
+    pub fn configure_trial(
+        ctx: Context<UpdateModelProfile>,
+        requests: u16,
+        deposit: u64,
+        mint: Pubkey,
+        duration: TimeSpan,
+    ) -> Result<()> {
         require!(
-            ctx.accounts.from_agent.permissions & Permission::CAN_CREATE_INTENT != 0,
-            ErrorCode::InsufficientPermissions
+            requests == 0 || (deposit > 0 && duration.is_valid() && !duration.is_zero()),
+            ErrorCode::InvalidTrial
         );
 
-        intent.from_agent = ctx.accounts.from_agent.key();
-        intent.to_agent = ctx.accounts.to_agent.key();
-        intent.nonce = nonce;
-        intent.status = IntentStatus::Pending as u8;
-        intent.payload_hash = payload_hash;
-        intent.payload_uri = payload_uri;
-        intent.payment_amount = payment_amount;
-        intent.payment_mint = ctx.accounts.payment_mint.key();
-        intent.result_hash = [0u8; 32];
-        intent.result_uri = String::new();
-        intent.created_at = clock.unix_timestamp;
-        intent.updated_at = clock.unix_timestamp;
-        intent.bump = ctx.bumps.intent;
-
-        // Transfer payment to escrow if amount > 0
-        if payment_amount > 0 {
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.from_token_account.to_account_info(),
-                to: ctx.accounts.escrow_token_account.to_account_info(),
-                authority: ctx.accounts.payer.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-            token::transfer(cpi_ctx, payment_amount)?;
-        }
+        let profile = &mut ctx.accounts.model_profile;
+        let clock = Clock::get()?;
 
-        emit!(IntentCreated {
-            intent: intent.key(),
-            from_agent: intent.from_agent,
-            to_agent: intent.to_agent,
-            payment_amount,
+        profile.trial_requests = requests;
+        profile.trial_deposit = deposit;
+        profile.trial_mint = mint;
+        profile.trial_duration = duration;
+        profile.updated_at = clock.unix_timestamp;
+
+        emit!(ModelProfileUpdated {
+            profile: profile.key(),
+            updated_at: profile.updated_at,
         });
 
         Ok(())
     }
 
-    /// Update intent status (called by to_agent's owner)
-    pub fn update_intent_status(
-        ctx: Context<UpdateIntentStatus>,
-        new_status: u8,
-        result_hash: Option<[u8; 32]>,
-        result_uri: Option<String>,
-    ) -> Result<()> {
-        let intent = &mut ctx.accounts.intent;
-        let clock = Clock::get()?;
+    /// Lock the profile's trial deposit and open a trial for the consumer
+    /// agent. One trial per agent and profile: the trial account outlives
+    /// its refund so the same agent can't start over.
+    pub fn start_trial(ctx: Context<StartTrial>) -> Result<()> {
+        ctx.accounts.config.require_accepting_intents()?;
 
-        // Verify to_agent has CAN_ACCEPT_INTENT permission
+        let profile = &ctx.accounts.model_profile;
+        require!(profile.trial_requests > 0, ErrorCode::InvalidTrial);
         require!(
-            ctx.accounts.to_agent.permissions & Permission::CAN_ACCEPT_INTENT != 0,
+            ctx.accounts.agent.permissions & Permission::CAN_CREATE_INTENT != 0,
             ErrorCode::InsufficientPermissions
         );
+        ctx.accounts.agent.require_active()?;
 
-        intent.status = new_status;
-        if let Some(hash) = result_hash {
-            intent.result_hash = hash;
-        }
-        if let Some(uri) = result_uri {
-            intent.result_uri = uri;
-        }
-        intent.updated_at = clock.unix_timestamp;
+        let vault = &ctx.accounts.trial_vault;
+        require!(
+            vault.owner == ctx.accounts.trial.key() && vault.mint == profile.trial_mint,
+            ErrorCode::InvalidTrial
+        );
 
-        // Release escrow if completed and payment exists
-        if new_status == IntentStatus::Completed as u8 && intent.payment_amount > 0 {
-            let seeds = &[
-                b"intent",
-                intent.from_agent.as_ref(),
-                intent.to_agent.as_ref(),
-                &intent.nonce.to_le_bytes(),
-                &[intent.bump],
-            ];
-            let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.from_token_account.to_account_info(),
+            to: vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, profile.trial_deposit)?;
 
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.escrow_token_account.to_account_info(),
-                to: ctx.accounts.billing_token_account.to_account_info(),
-                authority: intent.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-            token::transfer(cpi_ctx, intent.payment_amount)?;
-        }
+        let trial = &mut ctx.accounts.trial;
+        let clock = Clock::get()?;
+        trial.model_profile = profile.key();
+        trial.agent = ctx.accounts.agent.key();
+        trial.owner = ctx.accounts.owner.key();
+        trial.vault = vault.key();
+        trial.deposit = profile.trial_deposit;
+        trial.requests_total = profile.trial_requests;
+        trial.requests_used = 0;
+        trial.started_at = clock.unix_timestamp;
+        trial.expires = profile.trial_duration.after(&clock);
+        trial.settled = false;
+        trial.bump = ctx.bumps.trial;
 
-        emit!(IntentStatusUpdated {
-            intent: intent.key(),
-            status: new_status,
+        emit!(TrialStarted {
+            trial: trial.key(),
+            model_profile: trial.model_profile,
+            agent: trial.agent,
+            deposit: trial.deposit,
+            requests: trial.requests_total,
+            expires: trial.expires,
         });
 
         Ok(())
     }
-}
 
-// === Permission Flags ===
-pub struct Permission;
-impl Permission {
-    pub const CAN_SWAP: u64 = 1 << 0;
-    pub const CAN_TRANSFER: u64 = 1 << 1;
+    /// Return the trial deposit to its owner once the trial period is over
+    pub fn refund_trial(ctx: Context<RefundTrial>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let clock = Clock::get()?;
+        let trial = &ctx.accounts.trial;
+        require!(!trial.settled, ErrorCode::InvalidTrial);
+        require!(
+            trial.expires.passed(&clock, &ctx.accounts.config),
+            ErrorCode::TrialStillActive
+        );
+
+        transfer_from_trial(
+            trial,
+            &ctx.accounts.trial_vault,
+            &ctx.accounts.refund_token_account,
+            &ctx.accounts.token_program,
+        )?;
+
+        let trial = &mut ctx.accounts.trial;
+        trial.settled = true;
+
+        emit!(TrialSettled {
+            trial: trial.key(),
+            requests_used: trial.requests_used,
+            refunded: trial.deposit,
+            forfeited: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Provider keeps an abusive consumer's deposit (profile owner only,
+    /// while the trial is running). Ends the trial.
+    pub fn forfeit_trial(ctx: Context<ForfeitTrial>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let clock = Clock::get()?;
+        let trial = &ctx.accounts.trial;
+        require!(
+            !trial.settled && !trial.expires.passed(&clock, &ctx.accounts.config),
+            ErrorCode::InvalidTrial
+        );
+
+        transfer_from_trial(
+            trial,
+            &ctx.accounts.trial_vault,
+            &ctx.accounts.billing_token_account,
+            &ctx.accounts.token_program,
+        )?;
+
+        let trial = &mut ctx.accounts.trial;
+        trial.settled = true;
+
+        emit!(TrialSettled {
+            trial: trial.key(),
+            requests_used: trial.requests_used,
+            refunded: 0,
+            forfeited: trial.deposit,
+        });
+
+        Ok(())
+    }
+
+    /// Open a payout vault for a profile and mint. Settlements paid into it
+    /// accumulate and are swept to the billing wallet every `sweep_interval`.
+    pub fn open_payout_vault(ctx: Context<OpenPayoutVault>, sweep_interval: i64) -> Result<()> {
+        require!(sweep_interval > 0, ErrorCode::InvalidPayoutVault);
+
+        let payout_vault = &mut ctx.accounts.payout_vault;
+        let clock = Clock::get()?;
+
+        payout_vault.model_profile = ctx.accounts.model_profile.key();
+        payout_vault.mint = ctx.accounts.mint.key();
+        payout_vault.vault = ctx.accounts.vault.key();
+        payout_vault.sweep_interval = sweep_interval;
+        payout_vault.last_sweep_at = clock.unix_timestamp;
+        payout_vault.accrued = 0;
+        payout_vault.bump = ctx.bumps.payout_vault;
+
+        emit!(PayoutVaultOpened {
+            payout_vault: payout_vault.key(),
+            model_profile: payout_vault.model_profile,
+            mint: payout_vault.mint,
+            sweep_interval,
+        });
+
+        Ok(())
+    }
+
+    /// Keeper sweep of a payout vault to the billing wallet, once per interval
+    pub fn sweep_payouts(ctx: Context<SweepPayouts>) -> Result<()> {
+        let clock = Clock::get()?;
+        let payout_vault = &ctx.accounts.payout_vault;
+
+        require!(
+            clock.unix_timestamp >= payout_vault.last_sweep_at.saturating_add(payout_vault.sweep_interval),
+            ErrorCode::SweepNotDue
+        );
+
+        sweep_payout_vault(ctx.accounts, clock.unix_timestamp)
+    }
+
+    /// Sweep a payout vault immediately (profile owner only)
+    pub fn sweep_now(ctx: Context<SweepPayouts>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.model_profile.owner_wallet,
+            ErrorCode::Unauthorized
+        );
+
+        let clock = Clock::get()?;
+        sweep_payout_vault(ctx.accounts, clock.unix_timestamp)
+    }
+
+    /// Open or close a model profile to agents of any owner
+    pub fn set_profile_public(ctx: Context<UpdateModelProfile>, is_public: bool) -> Result<()> {
+        let profile = &mut ctx.accounts.model_profile;
+        let clock = Clock::get()?;
+
+        profile.is_public = is_public;
+        profile.updated_at = clock.unix_timestamp;
+
+        emit!(ModelProfileUpdated {
+            profile: profile.key(),
+            updated_at: profile.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Authorize a specific agent to link a model profile it doesn't own
+    pub fn grant_profile_access(ctx: Context<GrantProfileAccess>) -> Result<()> {
+        let authorization = &mut ctx.accounts.profile_authorization;
+        let clock = Clock::get()?;
+
+        authorization.model_profile = ctx.accounts.model_profile.key();
+        authorization.agent = ctx.accounts.agent.key();
+        authorization.granted_at = clock.unix_timestamp;
+        authorization.bump = ctx.bumps.profile_authorization;
+
+        emit!(ProfileAccessGranted {
+            profile: authorization.model_profile,
+            agent: authorization.agent,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke an agent's profile authorization. Agents already linked keep
+    /// their link until they next change profiles.
+    pub fn revoke_profile_access(ctx: Context<RevokeProfileAccess>) -> Result<()> {
+        let authorization = &ctx.accounts.profile_authorization;
+
+        emit!(ProfileAccessRevoked {
+            profile: authorization.model_profile,
+            agent: authorization.agent,
+        });
+        emit_rent_refund(&authorization.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Mark a model profile as verified (called by the configured attestor)
+    pub fn attest_profile(ctx: Context<AttestProfile>, attestation: Pubkey) -> Result<()> {
+        let profile = &mut ctx.accounts.model_profile;
+        let clock = Clock::get()?;
+
+        profile.verified = true;
+        profile.attestation = attestation;
+        profile.attested_at = clock.unix_timestamp;
+
+        emit!(ProfileAttested {
+            profile: profile.key(),
+            attestor: ctx.accounts.attestor.key(),
+            attestation,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a model profile's verification (called by the configured attestor)
+    pub fn revoke_attestation(ctx: Context<AttestProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.model_profile;
+
+        profile.verified = false;
+
+        emit!(ProfileAttestationRevoked {
+            profile: profile.key(),
+            attestor: ctx.accounts.attestor.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Commit shared context (a conversation or workspace snapshot) once so any
+    /// number of intents can reference it by address. The hash is part of the
+    /// PDA seeds, so the address itself commits to the content.
+    pub fn create_context_ref(
+        ctx: Context<CreateContextRef>,
+        context_hash: [u8; 32],
+        context_uri: String,
+    ) -> Result<()> {
+        ctx.accounts.config.require_uri_len(&context_uri)?;
+
+        let context = &mut ctx.accounts.context;
+
+        context.agent = ctx.accounts.agent.key();
+        context.owner = ctx.accounts.owner.key();
+        context.context_hash = context_hash;
+        context.context_uri = context_uri;
+        context.created_at = Clock::get()?.unix_timestamp;
+        context.bump = ctx.bumps.context;
+
+        emit!(ContextRefCreated {
+            context: context.key(),
+            agent: context.agent,
+            context_hash,
+            context_uri: context.context_uri.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a context reference and reclaim its rent. Intents that referenced
+    /// it keep its address, which still commits to the hash.
+    pub fn close_context_ref(ctx: Context<CloseContextRef>) -> Result<()> {
+        emit!(ContextRefClosed {
+            context: ctx.accounts.context.key(),
+            agent: ctx.accounts.context.agent,
+        });
+        emit_rent_refund(&ctx.accounts.context.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Publish a reusable intent template under the owner's agent: the
+    /// payload schema intents stamped from it follow, plus default payment,
+    /// completion window and the permissions a provider must hold. Templates
+    /// are immutable; publish a new `template_id` to change one.
+    pub fn publish_template(
+        ctx: Context<PublishTemplate>,
+        template_id: [u8; 16],
+        schema_hash: [u8; 32],
+        default_payment: u64,
+        completion_window: TimeSpan,
+        required_permissions: u64,
+    ) -> Result<()> {
+        require!(completion_window.is_valid(), ErrorCode::InvalidParameter);
+
+        let template = &mut ctx.accounts.template;
+        template.agent = ctx.accounts.agent.key();
+        template.owner = ctx.accounts.owner.key();
+        template.template_id = template_id;
+        template.schema_hash = schema_hash;
+        template.default_payment = default_payment;
+        template.completion_window = completion_window;
+        template.required_permissions = required_permissions;
+        template.created_at = Clock::get()?.unix_timestamp;
+        template.bump = ctx.bumps.template;
+
+        emit!(TemplatePublished {
+            template: template.key(),
+            agent: template.agent,
+            template_id,
+            schema_hash,
+            default_payment,
+            completion_window,
+            required_permissions,
+        });
+
+        Ok(())
+    }
+
+    /// Close a template and reclaim its rent. Intents stamped from it keep
+    /// its address and the terms they copied.
+    pub fn close_template(ctx: Context<CloseTemplate>) -> Result<()> {
+        emit!(TemplateClosed {
+            template: ctx.accounts.template.key(),
+            agent: ctx.accounts.template.agent,
+        });
+        emit_rent_refund(&ctx.accounts.template.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Register payload schema `schema_id` under the owner's agent, with its
+    /// first version: the hash of the schema document (e.g. a JSON Schema),
+    /// its media type and where to fetch it. Ids are global, so agents built
+    /// on different frameworks can agree on one registry entry per payload
+    /// contract and have intents reference it.
+    pub fn register_schema(
+        ctx: Context<RegisterSchema>,
+        schema_id: [u8; 16],
+        schema_hash: [u8; 32],
+        media_type: String,
+        schema_uri: String,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.schema_registry;
+        registry.schema_id = schema_id;
+        registry.agent = ctx.accounts.agent.key();
+        registry.owner = ctx.accounts.owner.key();
+        registry.latest_version = 0;
+        registry.created_at = Clock::get()?.unix_timestamp;
+        registry.bump = ctx.bumps.schema_registry;
+
+        publish_schema(
+            &mut ctx.accounts.schema,
+            registry,
+            ctx.bumps.schema,
+            &ctx.accounts.config,
+            schema_hash,
+            media_type,
+            schema_uri,
+        )
+    }
+
+    /// Publish the next version of a registered schema (registry owner).
+    /// Versions are immutable; intents keep referencing the one they were
+    /// created with.
+    pub fn publish_schema_version(
+        ctx: Context<PublishSchemaVersion>,
+        schema_hash: [u8; 32],
+        media_type: String,
+        schema_uri: String,
+    ) -> Result<()> {
+        publish_schema(
+            &mut ctx.accounts.schema,
+            &mut ctx.accounts.schema_registry,
+            ctx.bumps.schema,
+            &ctx.accounts.config,
+            schema_hash,
+            media_type,
+            schema_uri,
+        )
+    }
+
+    /// Stop new intents from referencing a schema version (registry owner).
+    /// Existing intents keep it.
+    pub fn deprecate_schema(ctx: Context<DeprecateSchema>) -> Result<()> {
+        let schema = &mut ctx.accounts.schema;
+        schema.deprecated = true;
+
+        emit!(SchemaDeprecated {
+            schema: schema.key(),
+            schema_id: ctx.accounts.schema_registry.schema_id,
+            version: schema.version,
+        });
+
+        Ok(())
+    }
+
+    /// Open the message channel between two agents; either agent's owner may
+    /// open it and pays its rent
+    pub fn open_channel(ctx: Context<OpenChannel>) -> Result<()> {
+        let channel = &mut ctx.accounts.channel;
+        channel.agent_a = ctx.accounts.agent_a.key();
+        channel.agent_b = ctx.accounts.agent_b.key();
+        channel.rent_payer = ctx.accounts.owner.key();
+        channel.message_count = 0;
+        channel.last_message_hash = [0u8; 32];
+        channel.last_message_at = 0;
+        channel.opened_at = Clock::get()?.unix_timestamp;
+        channel.bump = ctx.bumps.channel;
+
+        emit!(ChannelOpened {
+            channel: channel.key(),
+            agent_a: channel.agent_a,
+            agent_b: channel.agent_b,
+        });
+
+        Ok(())
+    }
+
+    /// Post a message to a channel as one of its agents. Only the hash and
+    /// URI are committed, in an event carrying the message's sequence number;
+    /// nothing is escrowed.
+    pub fn post_message(ctx: Context<PostMessage>, message_hash: [u8; 32], message_uri: String) -> Result<()> {
+        ctx.accounts.config.require_uri_len(&message_uri)?;
+
+        let from_agent = ctx.accounts.from_agent.key();
+        let channel = &mut ctx.accounts.channel;
+        let to_agent = channel.peer(&from_agent).ok_or(ErrorCode::Unauthorized)?;
+        let sequence = channel.message_count;
+        channel.message_count = sequence.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        channel.last_message_hash = message_hash;
+        channel.last_message_at = Clock::get()?.unix_timestamp;
+
+        emit!(MessagePosted {
+            channel: channel.key(),
+            sequence,
+            from_agent,
+            to_agent,
+            message_hash,
+            message_uri,
+        });
+
+        Ok(())
+    }
+
+    /// Close a channel (either agent's owner) and refund its rent to whoever
+    /// opened it
+    pub fn close_channel(ctx: Context<CloseChannel>) -> Result<()> {
+        let channel = &ctx.accounts.channel;
+        require!(
+            channel.peer(&ctx.accounts.agent.key()).is_some(),
+            ErrorCode::Unauthorized
+        );
+
+        emit!(ChannelClosed {
+            channel: channel.key(),
+            agent_a: channel.agent_a,
+            agent_b: channel.agent_b,
+            message_count: channel.message_count,
+        });
+        emit_rent_refund(&channel.to_account_info(), channel.rent_payer);
+
+        Ok(())
+    }
+
+    /// Create an intent from one agent to another, at the pair's next nonce.
+    /// `tip_amount` is escrowed alongside the payment and paid to the provider
+    /// as soon as it accepts.
+    pub fn create_intent(
+        mut ctx: Context<CreateIntent>,
+        payload_uri: String,
+        payload: ContentCommitment,
+        payment_amount: u64,
+        options: u8,
+        tip_amount: u64,
+    ) -> Result<()> {
+        require!(options & IntentOption::PREPAID == 0, ErrorCode::InvalidParameter);
+        open_intent(&mut ctx, payload, payload_uri, payment_amount, options, tip_amount, IntentAuthority::Payer)
+    }
+
+    /// Create an intent stamped from the `template` account, like
+    /// `create_intent`. `payment_amount` defaults to the template's
+    /// `default_payment`; the intent records the template and takes its
+    /// completion window and required provider permissions.
+    pub fn create_intent_from_template(
+        mut ctx: Context<CreateIntent>,
+        payload_uri: String,
+        payload: ContentCommitment,
+        payment_amount: Option<u64>,
+        options: u8,
+        tip_amount: u64,
+    ) -> Result<()> {
+        require!(options & IntentOption::PREPAID == 0, ErrorCode::InvalidParameter);
+        let template = ctx.accounts.template.as_ref().ok_or(ErrorCode::InvalidTemplate)?;
+        let payment_amount = payment_amount.unwrap_or(template.default_payment);
+        open_intent(&mut ctx, payload, payload_uri, payment_amount, options, tip_amount, IntentAuthority::Payer)
+    }
+
+    /// Land an intent signed off-chain by the from-agent's `agent_wallet`. The
+    /// transaction must carry an ed25519 program instruction over the
+    /// `SignedIntent` message right before this one; the submitting `payer`
+    /// (a relayer) pays rent and funds the escrow from `from_token_account`.
+    /// The message signs the pair's next nonce, so it lands at most once.
+    pub fn submit_signed_intent(
+        mut ctx: Context<CreateIntent>,
+        payload_uri: String,
+        payload: ContentCommitment,
+        payment_amount: u64,
+        options: u8,
+        expires: Deadline,
+    ) -> Result<()> {
+        require!(
+            !expires.passed(&Clock::get()?, &ctx.accounts.config),
+            ErrorCode::SignedIntentExpired
+        );
+        let instructions = ctx
+            .accounts
+            .instructions
+            .as_ref()
+            .ok_or(ErrorCode::InvalidSignature)?;
+
+        let message = SignedIntent {
+            program_id: crate::ID,
+            from_agent: ctx.accounts.from_agent.key(),
+            to_agent: ctx.accounts.to_agent.key(),
+            nonce: ctx.accounts.nonce_counter.next,
+            payload,
+            payload_uri: payload_uri.clone(),
+            payment_amount,
+            payment_mint: ctx.accounts.payment_mint.key(),
+            options,
+            expires,
+        }
+        .to_message()?;
+        verify_ed25519_signature(instructions, &ctx.accounts.from_agent.agent_wallet, &message)?;
+
+        require!(options & IntentOption::PREPAID == 0, ErrorCode::InvalidParameter);
+        open_intent(&mut ctx, payload, payload_uri, payment_amount, options, 0, IntentAuthority::Verified)
+    }
+
+    /// Land an intent sent by a remote agent (see `register_remote_agent`)
+    /// as a `CreateIntent` bridge message, from its identity. Like
+    /// `submit_signed_intent`, the message names the pair's next nonce, so
+    /// it lands at most once, and the relaying `payer` pays rent and funds
+    /// the escrow from `from_token_account`, typically with tokens redeemed
+    /// from the token bridge in the same transaction. Refunds go back to
+    /// that account's owner.
+    pub fn submit_bridged_intent(mut ctx: Context<CreateIntent>, payload_uri: String) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let (Some(posted_vaa), Some(emitter), Some(remote)) =
+            (&accounts.posted_vaa, &accounts.foreign_emitter, &accounts.remote_agent)
+        else {
+            return err!(ErrorCode::InvalidBridgeMessage);
+        };
+        let message = read_bridge_message(posted_vaa, emitter)?;
+        message.require_sender(remote)?;
+        require_keys_eq!(
+            accounts.from_agent.owner_wallet,
+            remote.key(),
+            ErrorCode::InvalidBridgeMessage
+        );
+        let BridgeAction::CreateIntent {
+            to_agent,
+            nonce,
+            payload,
+            payload_uri: sent_uri,
+            payment_mint,
+            payment_amount,
+            options,
+        } = message.action
+        else {
+            return err!(ErrorCode::InvalidBridgeMessage);
+        };
+        require!(
+            to_agent == accounts.to_agent.key()
+                && nonce == accounts.nonce_counter.next
+                && sent_uri == payload_uri
+                && payment_mint == accounts.payment_mint.key(),
+            ErrorCode::InvalidBridgeMessage
+        );
+        require!(options & IntentOption::PREPAID == 0, ErrorCode::InvalidParameter);
+
+        open_intent(&mut ctx, payload, payload_uri, payment_amount, options, 0, IntentAuthority::Verified)?;
+
+        let intent = &ctx.accounts.intent;
+        emit!(BridgedIntentSubmitted {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            chain: message.chain,
+            sender: message.sender,
+            message_sequence: message.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a provider's x402 quote into escrow. The provider's HTTP service
+    /// answers an unpaid request with 402 and an `X402Quote` signed by its
+    /// agent wallet; the requester lands it here (right after an ed25519
+    /// program instruction over `X402Quote::to_message`) and retries the
+    /// request with the resulting intent as its payment proof. The intent is
+    /// marked `PREPAID` and settles on the usual escrow rails.
+    pub fn settle_x402_payment(
+        mut ctx: Context<CreateIntent>,
+        payload_uri: String,
+        resource_hash: [u8; 32],
+        amount: u64,
+        expires: Deadline,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        require!(
+            !expires.passed(&Clock::get()?, &accounts.config),
+            ErrorCode::X402QuoteExpired
+        );
+        let instructions = accounts
+            .instructions
+            .as_ref()
+            .ok_or(ErrorCode::InvalidSignature)?;
+
+        let message = X402Quote {
+            program_id: crate::ID,
+            from_agent: accounts.from_agent.key(),
+            to_agent: accounts.to_agent.key(),
+            nonce: accounts.nonce_counter.next,
+            resource_hash,
+            payment_mint: accounts.payment_mint.key(),
+            amount,
+            expires,
+        }
+        .to_message()?;
+        verify_ed25519_signature(instructions, &accounts.to_agent.agent_wallet, &message)?;
+
+        // The quote commits to the resource, not to content behind a URI
+        let resource = ContentCommitment {
+            scheme: CommitmentScheme::Sha256 as u8,
+            length: 0,
+            hash: resource_hash,
+        };
+        open_intent(
+            &mut ctx,
+            resource,
+            payload_uri,
+            amount,
+            IntentOption::PREPAID,
+            0,
+            IntentAuthority::Payer,
+        )?;
+
+        let intent = &ctx.accounts.intent;
+        emit!(X402PaymentSettled {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            from_agent: intent.from_agent,
+            to_agent: intent.to_agent,
+            resource_hash,
+            payment_mint: intent.payment_mint,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Bid on an open intent as a provider agent's owner: `price` out of the
+    /// escrowed budget, and `eta` seconds to complete once accepted
+    pub fn place_bid(ctx: Context<PlaceBid>, price: u64, eta: i64) -> Result<()> {
+        ctx.accounts.config.require_accepting_intents()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let agent = &ctx.accounts.agent;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Pending as u8 && intent.awaiting_bid(),
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            !intent.bid_deadline.passed(&clock, &ctx.accounts.config),
+            ErrorCode::BiddingClosed
+        );
+        require!(
+            agent.permissions & Permission::CAN_ACCEPT_INTENT != 0 && intent.permits(agent),
+            ErrorCode::InsufficientPermissions
+        );
+        agent.require_active()?;
+        require!(agent.has_capacity(clock.unix_timestamp), ErrorCode::ProviderAtCapacity);
+        require!(
+            agent.key() != intent.from_agent && price <= intent.payment_amount && eta >= 0,
+            ErrorCode::InvalidBid
+        );
+        require!(price >= agent.min_payment, ErrorCode::PaymentBelowMinimum);
+
+        let bid = &mut ctx.accounts.bid;
+        bid.intent = intent.key();
+        bid.agent = agent.key();
+        bid.owner = ctx.accounts.owner.key();
+        bid.price = price;
+        bid.eta = eta;
+        bid.placed_at = clock.unix_timestamp;
+        bid.bump = ctx.bumps.bid;
+
+        // Ties go to the earlier bid
+        if intent.best_bid == Pubkey::default() || price < intent.best_bid_price {
+            intent.best_bid = bid.key();
+            intent.best_bid_price = price;
+        }
+        intent.touch(clock.unix_timestamp);
+
+        emit!(BidPlaced {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            bid: bid.key(),
+            agent: agent.key(),
+            price,
+            eta,
+        });
+
+        Ok(())
+    }
+
+    /// Award an open intent to a bid: any bid, as the requester's owner, or
+    /// the lowest one, by anyone once bidding has closed. The payment drops
+    /// to the bid's price and the rest of the escrow goes back to the payer;
+    /// the winner then accepts as the intent's provider.
+    pub fn select_bid(ctx: Context<SelectBid>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let bid = &ctx.accounts.bid;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Pending as u8 && intent.awaiting_bid(),
+            ErrorCode::InvalidStatusTransition
+        );
+        let by_requester = ctx.accounts.authority.key() == ctx.accounts.from_agent.owner_wallet;
+        require!(
+            by_requester || (bid.key() == intent.best_bid && intent.bid_deadline.passed(&clock, config)),
+            ErrorCode::Unauthorized
+        );
+
+        let refund = intent.payment_amount - bid.price;
+        let coverage_bps = if intent.options & IntentOption::INSURED != 0 {
+            config.insured_coverage_bps
+        } else {
+            config.base_coverage_bps
+        };
+        intent.winning_agent = bid.agent;
+        intent.payment_amount = bid.price;
+        intent.reimbursement_cap = bps_of(bid.price, coverage_bps)?;
+        intent.touch(clock.unix_timestamp);
+
+        if refund > 0 {
+            let refund_account = &ctx.accounts.refund_token_account;
+            transfer_from_escrow(
+                intent,
+                &ctx.accounts.escrow_token_account,
+                refund_account,
+                &ctx.accounts.token_program,
+                refund,
+            )?;
+
+            emit!(EscrowRefunded {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                payer: intent.payer,
+                refund_account: refund_account.key(),
+                amount: refund,
+                refunded_at: clock.unix_timestamp,
+            });
+        }
+
+        emit!(BidSelected {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            bid: bid.key(),
+            agent: bid.agent,
+            price: bid.price,
+            eta: bid.eta,
+            selected_by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a bid and reclaim its rent, as its owner; the standing
+    /// lowest bid stays until the intent is awarded or finished
+    pub fn close_bid(ctx: Context<CloseBid>) -> Result<()> {
+        let intent = &ctx.accounts.intent;
+        let bid = &ctx.accounts.bid;
+        require!(
+            bid.key() != intent.best_bid
+                || !intent.awaiting_bid()
+                || IntentStatus::is_terminal(intent.status),
+            ErrorCode::InvalidStatusTransition
+        );
+
+        emit_rent_refund(&bid.to_account_info(), bid.owner);
+        emit!(BidClosed {
+            bid: bid.key(),
+            intent: intent.key(),
+            agent: bid.agent,
+        });
+
+        Ok(())
+    }
+
+    /// Post the acceptance bond (`acceptance_bond_bps` of the payment) as the
+    /// assignee's owner, ahead of accepting. It is returned through
+    /// `settle_bond` once the intent completes or is cancelled, and goes to
+    /// the requester if the intent fails.
+    pub fn post_bond(ctx: Context<PostBond>) -> Result<()> {
+        ctx.accounts.config.require_accepting_intents()?;
+
+        let intent = &mut ctx.accounts.intent;
+        require!(
+            intent.status == IntentStatus::Pending as u8 && intent.bond == 0 && !intent.awaiting_bid(),
+            ErrorCode::InvalidStatusTransition
+        );
+        let amount = bps_of(intent.payment_amount, ctx.accounts.config.acceptance_bond_bps)?;
+        require!(amount > 0, ErrorCode::InvalidParameter);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            to: ctx.accounts.bond_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        intent.bond = amount;
+        intent.bond_payer = ctx.accounts.owner.key();
+        intent.touch(Clock::get()?.unix_timestamp);
+
+        emit!(BondPosted {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            agent: ctx.accounts.to_agent.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Update intent status (called by to_agent's owner): Pending to
+    /// Accepted to Completed, or to Failed from either, which refunds the
+    /// escrow to the payer (and any booking-funded part to the booking).
+    /// Completing requires a result commitment in a known scheme.
+    /// When completing, `swap` routes the provider's payout through an
+    /// allow-listed swap program into the payout mint set on the intent with
+    /// `set_payout_swap`, or else the profile's preferred one.
+    pub fn update_intent_status<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateIntentStatus<'info>>,
+        new_status: u8,
+        result: Option<ContentCommitment>,
+        result_uri: Option<String>,
+        swap: Option<SwapRoute>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+        if let Some(uri) = &result_uri {
+            ctx.accounts.config.require_uri_len(uri)?;
+        }
+
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+
+        // Verify to_agent has CAN_ACCEPT_INTENT permission
+        require!(
+            ctx.accounts.to_agent.permissions & Permission::CAN_ACCEPT_INTENT != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        ctx.accounts.to_agent.require_active()?;
+        require!(!intent.awaiting_bid(), ErrorCode::BidNotSelected);
+
+        // Completing goes through acceptance, so its bond, deadline and
+        // capacity checks always apply
+        let previous_status = intent.status;
+        let accepting = previous_status == IntentStatus::Pending as u8 && new_status == IntentStatus::Accepted as u8;
+        let completing =
+            previous_status == IntentStatus::Accepted as u8 && new_status == IntentStatus::Completed as u8;
+        let failing = !IntentStatus::is_terminal(previous_status) && new_status == IntentStatus::Failed as u8;
+        require!(accepting || completing || failing, ErrorCode::InvalidStatusTransition);
+        if accepting {
+            accept_assignment(intent, &ctx.accounts.config, &clock, ctx.accounts.owner.key())?;
+        }
+        if new_status == IntentStatus::Completed as u8 {
+            // Team intents complete through settle_team_intent
+            require!(intent.team_size == 0, ErrorCode::TeamUnsupported);
+            intent.completed_at = clock.unix_timestamp;
+        }
+        intent.status = new_status;
+        if let Some(result) = result {
+            result.require_known_scheme()?;
+            intent.result_hash = result.hash;
+            intent.result_scheme = result.scheme;
+            intent.result_length = result.length;
+            // Attestations only come with submit_result
+            intent.result_signer = Pubkey::default();
+        }
+        if let Some(uri) = result_uri {
+            intent.result_uri = uri;
+        }
+        if new_status == IntentStatus::Completed as u8 {
+            intent.result_commitment().require_known_scheme()?;
+            intent.require_attested_result()?;
+        }
+        intent.touch(clock.unix_timestamp);
+
+        // The tip is the provider's as soon as it takes the job
+        if accepting {
+            intent.payout_group = ctx.accounts.to_agent.group;
+            pay_tip(
+                intent,
+                ctx.accounts.escrow_token_account.as_ref(),
+                ctx.accounts.billing_token_account.as_ref(),
+                &ctx.accounts.token_program,
+                ctx.accounts.to_agent.key(),
+            )?;
+        }
+
+        // Release escrow if completed and payment exists
+        if new_status == IntentStatus::Completed as u8 && intent.payment_amount > 0 {
+            let swap = swap
+                .map(|route| {
+                    payout_swap(
+                        intent,
+                        route,
+                        ctx.accounts.to_agent.model_profile,
+                        ctx.accounts.model_profile.as_ref(),
+                        ctx.accounts.swap_program.as_ref(),
+                    )
+                })
+                .transpose()?;
+            release_on_completion(
+                intent,
+                &ctx.accounts.config,
+                CompletionPayout {
+                    escrow: ctx
+                        .accounts
+                        .escrow_token_account
+                        .as_mut()
+                        .ok_or(ErrorCode::EscrowAccountRequired)?,
+                    billing: ctx
+                        .accounts
+                        .billing_token_account
+                        .as_mut()
+                        .ok_or(ErrorCode::EscrowAccountRequired)?,
+                    delegate_billing: ctx.accounts.delegate_billing_token_account.as_ref(),
+                    fee_account: ctx.accounts.fee_token_account.as_ref(),
+                    fee_allowlist: ctx.accounts.fee_allowlist.as_ref(),
+                    royalties: ctx.accounts.royalties.as_ref(),
+                    refund_account: ctx.accounts.refund_token_account.as_ref(),
+                    payout_vault: ctx.accounts.payout_vault.as_mut(),
+                    token_program: &ctx.accounts.token_program,
+                    remaining_accounts: ctx.remaining_accounts,
+                },
+                swap,
+                &clock,
+            )?;
+        }
+
+        // Giving up on the intent hands back everything still escrowed
+        if failing {
+            let escrow = ctx.accounts.escrow_token_account.as_ref();
+            require!(
+                escrow.is_some() || intent.payment_amount.saturating_add(intent.tip_amount) == 0,
+                ErrorCode::EscrowAccountRequired
+            );
+            if let Some(escrow) = escrow {
+                require!(
+                    escrow.owner == intent.key() && escrow.mint == intent.payment_mint,
+                    ErrorCode::InvalidEscrowAccount
+                );
+                let refund_account = ctx.accounts.refund_token_account.as_ref();
+                let refund = refund_escrow(
+                    intent,
+                    escrow,
+                    refund_account,
+                    booking_refund(ctx.accounts.booking.as_mut(), ctx.accounts.booking_vault.as_ref()),
+                    &ctx.accounts.token_program,
+                    escrow.amount,
+                )?;
+                if let Some(refund_account) = refund_account.filter(|_| refund > 0) {
+                    emit!(EscrowRefunded {
+                        intent: intent.key(),
+                        sequence: intent.sequence,
+                        payer: intent.payer,
+                        refund_account: refund_account.key(),
+                        amount: refund,
+                        refunded_at: clock.unix_timestamp,
+                    });
+                }
+            }
+        }
+
+        emit!(IntentStatusUpdated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            nonce: intent.nonce,
+            previous_status,
+            status: new_status,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            updated_at: intent.updated_at,
+        });
+        record_transition(intent, previous_status, ctx.accounts.owner.key());
+        ctx.accounts
+            .assignee_stats
+            .record_outcome(intent, previous_status, clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    /// Accept a pending intent (assignee's owner), taking any tip into
+    /// `billing_token_account`. The lightweight counterpart of
+    /// `update_intent_status` to Accepted; the signer is recorded as the
+    /// intent's `assignee_owner` for `submit_result` and `settle_intent`.
+    pub fn accept_intent(ctx: Context<AcceptIntent>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.to_agent.permissions & Permission::CAN_ACCEPT_INTENT != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        ctx.accounts.to_agent.require_active()?;
+        require!(
+            intent.status == IntentStatus::Pending as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(!intent.awaiting_bid(), ErrorCode::BidNotSelected);
+        if let Some(schema) = &ctx.accounts.schema {
+            require_keys_eq!(intent.schema, schema.key(), ErrorCode::InvalidSchema);
+        }
+
+        let previous_status = intent.status;
+        accept_assignment(intent, &ctx.accounts.config, &clock, ctx.accounts.owner.key())?;
+        intent.payout_group = ctx.accounts.to_agent.group;
+        intent.status = IntentStatus::Accepted as u8;
+        intent.touch(clock.unix_timestamp);
+        pay_tip(
+            intent,
+            ctx.accounts.escrow_token_account.as_ref(),
+            ctx.accounts.billing_token_account.as_ref(),
+            &ctx.accounts.token_program,
+            ctx.accounts.to_agent.key(),
+        )?;
+
+        emit!(IntentStatusUpdated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            nonce: intent.nonce,
+            previous_status,
+            status: intent.status,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            updated_at: intent.updated_at,
+        });
+        record_transition(intent, previous_status, ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Commit the result of an accepted intent ahead of `settle_intent`
+    /// (assignee's owner). May be called again to replace the result until
+    /// the intent settles. This is the only step that may grow the intent
+    /// account, so settlement itself never reallocates.
+    ///
+    /// Passing the instructions sysvar attests the result: the transaction
+    /// must then carry an ed25519 program instruction right before this one
+    /// over the `ResultAttestation` message, signed by the assignee's
+    /// `agent_wallet`, which is recorded as the intent's `result_signer`.
+    pub fn submit_result(ctx: Context<SubmitResult>, result: ContentCommitment, result_uri: String) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+        ctx.accounts.config.require_uri_len(&result_uri)?;
+        result.require_known_scheme()?;
+
+        let assignee = &ctx.accounts.assignee_agent;
+        assignee.require_active()?;
+
+        let intent = &mut ctx.accounts.intent;
+        require!(
+            intent.status == IntentStatus::Accepted as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+
+        intent.result_signer = match &ctx.accounts.instructions {
+            Some(instructions) => {
+                let message = ResultAttestation {
+                    program_id: crate::ID,
+                    intent: intent.key(),
+                    result_hash: result.hash,
+                }
+                .to_message()?;
+                verify_ed25519_signature(instructions, &assignee.agent_wallet, &message)?;
+                assignee.agent_wallet
+            }
+            None => Pubkey::default(),
+        };
+        intent.result_hash = result.hash;
+        intent.result_scheme = result.scheme;
+        intent.result_length = result.length;
+        intent.result_uri = result_uri;
+        intent.touch(Clock::get()?.unix_timestamp);
+
+        emit!(ResultSubmitted {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            result_signer: intent.result_signer,
+            submitted_at: intent.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Complete an accepted intent whose result was submitted (assignee's
+    /// owner), releasing the escrow like `update_intent_status` does. It
+    /// takes no strings and never reallocates, which keeps it cheap enough to
+    /// compose with other instructions.
+    pub fn settle_intent<'info>(ctx: Context<'_, '_, 'info, 'info, SettleIntent<'info>>) -> Result<()> {
+        settle_accepted_intent(ctx, None)
+    }
+
+    /// `settle_intent`, converting the provider's payout into the intent's
+    /// `payout_mint` (else its profile's) through `route` on the allow-listed
+    /// `swap_program`. The route's accounts follow any royalty recipients in
+    /// the remaining accounts; the swap must return at least the route's
+    /// quote less the matching `max_slippage_bps`.
+    pub fn settle_intent_with_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleIntent<'info>>,
+        route: SwapRoute,
+    ) -> Result<()> {
+        settle_accepted_intent(ctx, Some(route))
+    }
+
+    /// Have the provider's payout for an accepted intent swapped into
+    /// `payout_mint` at settlement, accepting up to `max_slippage_bps` below
+    /// the route's quote (assignee's owner). `Pubkey::default()` pays out in
+    /// the escrowed mint again. Not for auto-release or re-delegated intents,
+    /// whose payouts aren't swapped.
+    pub fn set_payout_swap(
+        ctx: Context<SetPayoutSwap>,
+        payout_mint: Pubkey,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        require!(max_slippage_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+
+        let intent = &mut ctx.accounts.intent;
+        require!(
+            intent.status == IntentStatus::Accepted as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            intent.options & IntentOption::AUTO_RELEASE == 0
+                && intent.delegate_agent == Pubkey::default()
+                && intent.team_size == 0
+                && payout_mint != intent.payment_mint,
+            ErrorCode::InvalidSwapRoute
+        );
+
+        intent.payout_mint = payout_mint;
+        intent.max_slippage_bps = if payout_mint == Pubkey::default() { 0 } else { max_slippage_bps };
+        intent.touch(Clock::get()?.unix_timestamp);
+
+        emit!(PayoutSwapSet {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            payout_mint: intent.payout_mint,
+            max_slippage_bps: intent.max_slippage_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Trust `address` as the agent-mesh contract emitting bridge messages
+    /// on Wormhole chain `chain`, replacing any emitter registered for that
+    /// chain (admin only)
+    pub fn register_foreign_emitter(
+        ctx: Context<RegisterForeignEmitter>,
+        chain: u16,
+        address: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            chain != WORMHOLE_CHAIN_SOLANA && address != [0u8; 32],
+            ErrorCode::InvalidParameter
+        );
+
+        let emitter = &mut ctx.accounts.foreign_emitter;
+        emitter.chain = chain;
+        emitter.address = address;
+        emitter.bump = ctx.bumps.foreign_emitter;
+
+        emit!(ForeignEmitterUpdated { chain, address });
+
+        Ok(())
+    }
+
+    /// Stop accepting bridge messages from a chain (admin only). Remote
+    /// agents registered from it keep their identities but can't act.
+    pub fn remove_foreign_emitter(ctx: Context<RemoveForeignEmitter>) -> Result<()> {
+        emit!(ForeignEmitterUpdated {
+            chain: ctx.accounts.foreign_emitter.chain,
+            address: [0u8; 32],
+        });
+        emit_rent_refund(&ctx.accounts.foreign_emitter.to_account_info(), ctx.accounts.admin.key());
+
+        Ok(())
+    }
+
+    /// Register, or update, the identity of an agent living on another
+    /// chain from its `RegisterAgent` bridge message (anyone may relay it).
+    /// The identity is owned by the agent's `RemoteAgent` PDA, so it takes
+    /// part in intents like any other agent but only acts through bridge
+    /// messages. `chain`, `sender` and `metadata_uri` must repeat the
+    /// message's; updates must be relayed in the order they were emitted.
+    pub fn register_remote_agent(
+        ctx: Context<RegisterRemoteAgent>,
+        chain: u16,
+        sender: [u8; 32],
+        metadata_uri: String,
+    ) -> Result<()> {
+        ctx.accounts.config.require_uri_len(&metadata_uri)?;
+        let message = read_bridge_message(&ctx.accounts.posted_vaa, &ctx.accounts.foreign_emitter)?;
+        require!(
+            message.chain == chain && message.sender == sender,
+            ErrorCode::InvalidBridgeMessage
+        );
+        let BridgeAction::RegisterAgent {
+            model_profile,
+            permissions,
+            metadata_uri: sent_uri,
+        } = message.action
+        else {
+            return err!(ErrorCode::InvalidBridgeMessage);
+        };
+        require!(sent_uri == metadata_uri, ErrorCode::InvalidBridgeMessage);
+        require_keys_eq!(
+            ctx.accounts.model_profile.key(),
+            model_profile,
+            ErrorCode::InvalidModelProfile
+        );
+
+        let remote = &mut ctx.accounts.remote_agent;
+        let agent = &mut ctx.accounts.agent;
+        check_profile_access(&ctx.accounts.model_profile, agent.key(), remote.key(), None)?;
+
+        let clock = Clock::get()?;
+        let existing = remote.agent != Pubkey::default();
+        if existing {
+            require!(
+                message.sequence > remote.last_sequence,
+                ErrorCode::BridgeMessageReplayed
+            );
+            // init_if_needed doesn't realloc, so the URI can't outgrow the account
+            require!(
+                AgentIdentity::space(metadata_uri.len()) <= agent.to_account_info().data_len(),
+                ErrorCode::UriTooLong
+            );
+        } else {
+            remote.agent = agent.key();
+            remote.chain = chain;
+            remote.address = sender;
+            remote.bump = ctx.bumps.remote_agent;
+
+            agent.owner_wallet = remote.key();
+            agent.created_at = clock.unix_timestamp;
+            agent.bump = ctx.bumps.agent;
+            agent.version = AgentIdentity::VERSION;
+        }
+        remote.last_sequence = message.sequence;
+        // Remote agents hold no Solana key to sign with
+        agent.agent_wallet = Pubkey::default();
+        agent.model_profile = model_profile;
+        agent.metadata_uri = metadata_uri;
+        agent.permissions = permissions;
+        agent.updated_at = clock.unix_timestamp;
+        ctx.accounts.stats.init(agent.key(), ctx.bumps.stats);
+
+        if existing {
+            emit!(AgentUpdated {
+                agent: agent.key(),
+                updated_at: agent.updated_at,
+            });
+        } else {
+            emit!(AgentRegistered {
+                agent: agent.key(),
+                owner: agent.owner_wallet,
+                agent_wallet: agent.agent_wallet,
+            });
+        }
+        emit!(RemoteAgentRegistered {
+            agent: agent.key(),
+            chain,
+            address: sender,
+            message_sequence: message.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Complete an intent assigned to a remote agent from its
+    /// `CompleteIntent` bridge message and release the escrow like
+    /// `settle_intent` (anyone may relay it). A still pending intent is
+    /// accepted first. The guardians' signatures over the message stand in
+    /// for the owner's, so the remote agent is recorded as the result's
+    /// signer and the provider's share can only go to the message's
+    /// `payout` account. Intents re-delegated to a remote agent can't
+    /// complete this way.
+    pub fn complete_bridged_intent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CompleteBridgedIntent<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+        let message = read_bridge_message(&ctx.accounts.posted_vaa, &ctx.accounts.foreign_emitter)?;
+        let remote = &ctx.accounts.remote_agent;
+        message.require_sender(remote)?;
+        let BridgeAction::CompleteIntent { intent: intent_key, result, payout } = message.action else {
+            return err!(ErrorCode::InvalidBridgeMessage);
+        };
+        result.require_known_scheme()?;
+        if let Some(billing) = &ctx.accounts.billing_token_account {
+            require_keys_eq!(billing.key(), payout, ErrorCode::InvalidBridgeMessage);
+        }
+
+        let assignee = &ctx.accounts.assignee_agent;
+        assignee.require_active()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+        require_keys_eq!(intent.key(), intent_key, ErrorCode::InvalidBridgeMessage);
+        require!(intent.team_size == 0, ErrorCode::TeamUnsupported);
+        require!(
+            intent.delegate_agent == Pubkey::default(),
+            ErrorCode::InvalidBridgeMessage
+        );
+
+        if intent.status == IntentStatus::Pending as u8 {
+            require!(
+                assignee.permissions & Permission::CAN_ACCEPT_INTENT != 0,
+                ErrorCode::InsufficientPermissions
+            );
+            require!(!intent.awaiting_bid(), ErrorCode::BidNotSelected);
+
+            accept_assignment(intent, &ctx.accounts.config, &clock, remote.key())?;
+            intent.payout_group = assignee.group;
+            intent.status = IntentStatus::Accepted as u8;
+            intent.touch(clock.unix_timestamp);
+            pay_tip(
+                intent,
+                ctx.accounts.escrow_token_account.as_ref(),
+                ctx.accounts.billing_token_account.as_ref(),
+                &ctx.accounts.token_program,
+                assignee.key(),
+            )?;
+
+            emit!(IntentStatusUpdated {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                nonce: intent.nonce,
+                previous_status: IntentStatus::Pending as u8,
+                status: intent.status,
+                result_hash: intent.result_hash,
+                result_uri: intent.result_uri.clone(),
+                updated_at: intent.updated_at,
+            });
+            record_transition(intent, IntentStatus::Pending as u8, remote.key());
+        }
+        require!(
+            intent.status == IntentStatus::Accepted as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require_keys_eq!(intent.assignee_owner, remote.key(), ErrorCode::Unauthorized);
+
+        intent.result_hash = result.hash;
+        intent.result_scheme = result.scheme;
+        intent.result_length = result.length;
+        intent.result_signer = remote.key();
+
+        let previous_status = intent.status;
+        intent.status = IntentStatus::Completed as u8;
+        intent.completed_at = clock.unix_timestamp;
+        intent.touch(clock.unix_timestamp);
+
+        if intent.payment_amount > 0 {
+            release_on_completion(
+                intent,
+                &ctx.accounts.config,
+                CompletionPayout {
+                    escrow: ctx
+                        .accounts
+                        .escrow_token_account
+                        .as_mut()
+                        .ok_or(ErrorCode::EscrowAccountRequired)?,
+                    billing: ctx
+                        .accounts
+                        .billing_token_account
+                        .as_mut()
+                        .ok_or(ErrorCode::EscrowAccountRequired)?,
+                    delegate_billing: None,
+                    fee_account: ctx.accounts.fee_token_account.as_ref(),
+                    fee_allowlist: ctx.accounts.fee_allowlist.as_ref(),
+                    royalties: ctx.accounts.royalties.as_ref(),
+                    refund_account: ctx.accounts.refund_token_account.as_ref(),
+                    payout_vault: ctx.accounts.payout_vault.as_mut(),
+                    token_program: &ctx.accounts.token_program,
+                    remaining_accounts: ctx.remaining_accounts,
+                },
+                None,
+                &clock,
+            )?;
+        }
+
+        emit!(IntentStatusUpdated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            nonce: intent.nonce,
+            previous_status,
+            status: intent.status,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            updated_at: intent.updated_at,
+        });
+        emit!(BridgedResultAttested {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            chain: message.chain,
+            sender: message.sender,
+            message_sequence: message.sequence,
+            result_hash: intent.result_hash,
+            payout,
+        });
+        record_transition(intent, previous_status, remote.key());
+        ctx.accounts
+            .assignee_stats
+            .record_outcome(intent, previous_status, clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    /// Reserve a provider's capacity for a time window at the provider's current
+    /// price, paying part of the total upfront into the booking vault
+    pub fn create_booking(
+        ctx: Context<CreateBooking>,
+        nonce: u64,
+        tokens_per_hour: u64,
+        start: Deadline,
+        end: Deadline,
+        deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.require_accepting_intents()?;
+
+        require!(
+            ctx.accounts.from_agent.permissions & Permission::CAN_CREATE_INTENT != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        let window = start.until(&end).ok_or(ErrorCode::InvalidBooking)?;
+        require!(tokens_per_hour > 0, ErrorCode::InvalidBooking);
+
+        let profile = &ctx.accounts.model_profile;
+        require!(profile.pricing > 0, ErrorCode::InvalidBooking);
+
+        let vault = &ctx.accounts.booking_vault;
+        let booking_key = ctx.accounts.booking.key();
+        require_keys_eq!(vault.owner, booking_key, ErrorCode::InvalidBooking);
+        require_keys_eq!(vault.mint, ctx.accounts.payment_mint.key(), ErrorCode::InvalidBooking);
+
+        // Capacity is reserved in whole hours
+        let tokens_reserved = tokens_per_hour
+            .checked_mul(window.hours())
+            .ok_or(ErrorCode::MathOverflow)?;
+        let total_cost = cost_for_tokens(tokens_reserved, profile.pricing)?;
+        require!(deposit <= total_cost, ErrorCode::InvalidBooking);
+
+        let booking = &mut ctx.accounts.booking;
+        booking.from_agent = ctx.accounts.from_agent.key();
+        booking.to_agent = ctx.accounts.to_agent.key();
+        booking.payer = ctx.accounts.payer.key();
+        booking.payment_mint = ctx.accounts.payment_mint.key();
+        booking.vault = vault.key();
+        booking.billing_wallet = profile.billing_wallet;
+        booking.nonce = nonce;
+        booking.locked_price = profile.pricing;
+        booking.tokens_per_hour = tokens_per_hour;
+        booking.tokens_reserved = tokens_reserved;
+        booking.tokens_used = 0;
+        booking.deposit_remaining = deposit;
+        booking.start = start;
+        booking.end = end;
+        booking.bump = ctx.bumps.booking;
+
+        if deposit > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.from_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token::transfer(cpi_ctx, deposit)?;
+        }
+
+        emit!(BookingCreated {
+            booking: booking.key(),
+            from_agent: booking.from_agent,
+            to_agent: booking.to_agent,
+            tokens_reserved,
+            locked_price: booking.locked_price,
+            deposit,
+            start,
+            end,
+        });
+
+        Ok(())
+    }
+
+    /// Close a booking after its window ends. Any unused deposit compensates
+    /// the provider for the capacity it held; rent returns to the payer.
+    pub fn close_booking(ctx: Context<CloseBooking>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let booking = &ctx.accounts.booking;
+        let clock = Clock::get()?;
+
+        require!(
+            booking.end.passed(&clock, &ctx.accounts.config),
+            ErrorCode::BookingStillActive
+        );
+
+        let remaining = booking.deposit_remaining;
+        if remaining > 0 {
+            let nonce = booking.nonce.to_le_bytes();
+            let seeds = &[
+                b"booking".as_ref(),
+                booking.from_agent.as_ref(),
+                booking.to_agent.as_ref(),
+                &nonce,
+                &[booking.bump],
+            ];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.booking_vault.to_account_info(),
+                to: ctx.accounts.billing_token_account.to_account_info(),
+                authority: booking.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, remaining)?;
+        }
+
+        emit!(BookingClosed {
+            booking: booking.key(),
+            tokens_used: booking.tokens_used,
+            forfeited_deposit: remaining,
+        });
+        emit_rent_refund(&booking.to_account_info(), booking.payer);
+
+        Ok(())
+    }
+
+    /// Set up a provider's receipt tree. The merkle tree account must already be
+    /// allocated and owned by the account compression program.
+    pub fn initialize_receipt_tree(
+        ctx: Context<InitializeReceiptTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let tree = &mut ctx.accounts.receipt_tree;
+
+        tree.agent = ctx.accounts.agent.key();
+        tree.merkle_tree = ctx.accounts.merkle_tree.key();
+        tree.num_receipts = 0;
+        tree.bump = ctx.bumps.receipt_tree;
+
+        let seeds = &[b"receipt_tree".as_ref(), tree.agent.as_ref(), &[tree.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = spl_account_compression::cpi::accounts::Initialize {
+            merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+            authority: tree.to_account_info(),
+            noop: ctx.accounts.noop_program.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.compression_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        spl_account_compression::cpi::init_empty_merkle_tree(cpi_ctx, max_depth, max_buffer_size)?;
+
+        emit!(ReceiptTreeInitialized {
+            receipt_tree: tree.key(),
+            agent: tree.agent,
+            merkle_tree: tree.merkle_tree,
+        });
+
+        Ok(())
+    }
+
+    /// Append a usage receipt for an intent to the provider's receipt tree.
+    /// Only the leaf hash is stored; the full receipt is in the emitted event.
+    pub fn submit_receipt(
+        ctx: Context<SubmitReceipt>,
+        tokens_in: u64,
+        tokens_out: u64,
+        latency_ms: u32,
+        cost: u64,
+    ) -> Result<()> {
+        let intent = &ctx.accounts.intent;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Accepted as u8
+                || intent.status == IntentStatus::Completed as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+
+        let receipt = UsageReceipt {
+            intent: intent.key(),
+            agent: ctx.accounts.agent.key(),
+            tokens_in,
+            tokens_out,
+            latency_ms,
+            cost,
+            submitted_at: clock.unix_timestamp,
+        };
+        let leaf = keccak::hashv(&[&receipt.try_to_vec()?]).to_bytes();
+
+        let tree = &mut ctx.accounts.receipt_tree;
+        let leaf_index = tree.num_receipts;
+
+        let seeds = &[b"receipt_tree".as_ref(), tree.agent.as_ref(), &[tree.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = spl_account_compression::cpi::accounts::Modify {
+            merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+            authority: tree.to_account_info(),
+            noop: ctx.accounts.noop_program.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.compression_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        spl_account_compression::cpi::append(cpi_ctx, leaf)?;
+
+        tree.num_receipts = leaf_index.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(ReceiptSubmitted {
+            merkle_tree: tree.merkle_tree,
+            leaf_index,
+            leaf,
+            receipt,
+        });
+
+        Ok(())
+    }
+
+    /// Back out of an intent and refund the escrow to the payer. Either party may
+    /// cancel while Pending, or within the config's cancellation window after
+    /// acceptance, without penalty. Once the window has closed the requester
+    /// may still cancel, forfeiting `cancellation_penalty_bps` of the escrow
+    /// to the provider for the capacity it reserved.
+    pub fn cancel_intent(ctx: Context<CancelIntent>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let config = &ctx.accounts.config;
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+
+        let penalty_bps = match intent.status {
+            s if s == IntentStatus::Pending as u8 => 0,
+            s if s == IntentStatus::Accepted as u8 => {
+                if !intent.cancel_deadline.passed(&clock, config) {
+                    0
+                } else if ctx.accounts.agent.key() == intent.from_agent && config.cancellation_penalty_bps > 0 {
+                    config.cancellation_penalty_bps
+                } else {
+                    return err!(ErrorCode::CancellationWindowClosed);
+                }
+            }
+            _ => return err!(ErrorCode::InvalidStatusTransition),
+        };
+
+        let previous_status = intent.status;
+        intent.status = IntentStatus::Cancelled as u8;
+        intent.touch(clock.unix_timestamp);
+
+        // A funded intent can't be cancelled around its escrow
+        let escrow = ctx.accounts.escrow_token_account.as_ref();
+        require!(
+            escrow.is_some() || intent.payment_amount.saturating_add(intent.tip_amount) == 0,
+            ErrorCode::EscrowAccountRequired
+        );
+        let escrowed = escrow.map_or(0, |e| e.amount);
+        let penalty = bps_of(escrowed, penalty_bps)?;
+        let refund = escrowed - penalty;
+        if penalty > 0 {
+            let escrow = ctx
+                .accounts
+                .escrow_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            let provider = ctx
+                .accounts
+                .to_agent
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            let penalty_account = ctx
+                .accounts
+                .penalty_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            require_keys_eq!(penalty_account.owner, provider.owner_wallet, ErrorCode::Unauthorized);
+            transfer_from_escrow(intent, escrow, penalty_account, &ctx.accounts.token_program, penalty)?;
+        }
+        // The booking-funded part goes back to the booking, the rest to the payer
+        let refund = if refund > 0 {
+            let escrow = ctx
+                .accounts
+                .escrow_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            let refund_account = ctx.accounts.refund_token_account.as_ref();
+            let paid = refund_escrow(
+                intent,
+                escrow,
+                refund_account,
+                booking_refund(ctx.accounts.booking.as_mut(), ctx.accounts.booking_vault.as_ref()),
+                &ctx.accounts.token_program,
+                refund,
+            )?;
+            if let Some(refund_account) = refund_account.filter(|_| paid > 0) {
+                emit!(EscrowRefunded {
+                    intent: intent.key(),
+                    sequence: intent.sequence,
+                    payer: intent.payer,
+                    refund_account: refund_account.key(),
+                    amount: paid,
+                    refunded_at: clock.unix_timestamp,
+                });
+            }
+            paid
+        } else {
+            0
+        };
+
+        emit!(IntentCancelled {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            cancelled_by: ctx.accounts.agent.key(),
+            previous_status,
+            refund,
+            penalty,
+            cancelled_at: clock.unix_timestamp,
+        });
+        emit!(IntentStatusUpdated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            nonce: intent.nonce,
+            previous_status,
+            status: intent.status,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            updated_at: intent.updated_at,
+        });
+        record_transition(intent, previous_status, ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Fail an accepted intent whose `complete_deadline` has passed
+    /// (permissionless). The escrow is refunded to the payer and the bond
+    /// slashed to it, less `timeout_tip_bps` for the caller, closing the bond
+    /// vault to its poster.
+    pub fn flag_timeout(ctx: Context<FlagTimeout>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+        require!(intent.timed_out(&clock, config), ErrorCode::NotTimedOut);
+
+        let previous_status = intent.status;
+        let refund_account = &ctx.accounts.refund_token_account;
+        let token_program = &ctx.accounts.token_program;
+        let refund = fail_timed_out_intent(
+            intent,
+            &ctx.accounts.escrow_token_account,
+            refund_account,
+            booking_refund(ctx.accounts.booking.as_mut(), ctx.accounts.booking_vault.as_ref()),
+            token_program,
+            clock.unix_timestamp,
+        )?;
+
+        let tip = bps_of(intent.bond, config.timeout_tip_bps)?;
+        let slashed = intent.bond - tip;
+        if intent.bond > 0 {
+            let vault = ctx
+                .accounts
+                .bond_vault
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            if tip > 0 {
+                let tip_account = ctx
+                    .accounts
+                    .cranker_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::EscrowAccountRequired)?;
+                transfer_from_escrow(intent, vault, tip_account, token_program, tip)?;
+            }
+            if slashed > 0 {
+                transfer_from_escrow(intent, vault, refund_account, token_program, slashed)?;
+            }
+            let bond_payer = ctx
+                .accounts
+                .bond_payer
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            emit_rent_refund(&vault.to_account_info(), intent.bond_payer);
+            close_escrow_account(intent, vault, bond_payer, token_program)?;
+            intent.bond = 0;
+        }
+
+        emit!(IntentTimedOut {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            flagged_by: ctx.accounts.cranker.key(),
+            refund,
+            bond_slashed: slashed,
+            tip,
+            timed_out_at: clock.unix_timestamp,
+        });
+        emit!(IntentStatusUpdated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            nonce: intent.nonce,
+            previous_status,
+            status: intent.status,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            updated_at: intent.updated_at,
+        });
+        record_transition(intent, previous_status, ctx.accounts.cranker.key());
+        ctx.accounts
+            .assignee_stats
+            .record_outcome(intent, previous_status, clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    /// Pay out a finished intent's acceptance bond (permissionless) and close
+    /// the bond vault to its poster: back to the poster once the intent was
+    /// completed and released, or cancelled; to the payer if it failed.
+    pub fn settle_bond(ctx: Context<SettleBond>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        require!(
+            IntentStatus::is_terminal(intent.status) && !intent.release_pending() && !intent.disputed,
+            ErrorCode::InvalidStatusTransition
+        );
+        let slashed = intent.status == IntentStatus::Failed as u8;
+        let recipient = &ctx.accounts.recipient_token_account;
+        let owner = if slashed { intent.payer } else { intent.bond_payer };
+        require_keys_eq!(recipient.owner, owner, ErrorCode::Unauthorized);
+
+        let vault = &ctx.accounts.bond_vault;
+        let amount = vault.amount;
+        if amount > 0 {
+            transfer_from_escrow(intent, vault, recipient, &ctx.accounts.token_program, amount)?;
+        }
+        emit_rent_refund(&vault.to_account_info(), intent.bond_payer);
+        close_escrow_account(intent, vault, &ctx.accounts.bond_payer, &ctx.accounts.token_program)?;
+
+        intent.bond = 0;
+        intent.touch(Clock::get()?.unix_timestamp);
+
+        emit!(BondSettled {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            recipient: recipient.key(),
+            amount,
+            slashed,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished intent and its escrow token account. Anyone may call;
+    /// all rent goes back to the intent's `rent_payer`.
+    pub fn close_intent(ctx: Context<CloseIntent>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let intent = &ctx.accounts.intent;
+        require!(
+            IntentStatus::is_terminal(intent.status) && !intent.release_pending(),
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(intent.bond == 0, ErrorCode::BondOutstanding);
+        let rent_payer = ctx.accounts.rent_payer.key();
+        let mut lamports = intent.to_account_info().lamports();
+
+        // The escrow goes with the intent, and only once it's been paid out
+        let escrow = &ctx.accounts.escrow_token_account;
+        require!(escrow.amount == 0, ErrorCode::EscrowNotEmpty);
+        let escrow_info = escrow.to_account_info();
+        lamports = lamports
+            .checked_add(escrow_info.lamports())
+            .ok_or(ErrorCode::MathOverflow)?;
+        emit_rent_refund(&escrow_info, rent_payer);
+        close_escrow_account(intent, escrow, &ctx.accounts.rent_payer, &ctx.accounts.token_program)?;
+
+        // The royalty declaration's rent goes back to the requester who paid it
+        if let Some(royalties) = &ctx.accounts.royalties {
+            let owner = ctx
+                .accounts
+                .royalty_owner
+                .as_ref()
+                .ok_or(ErrorCode::Unauthorized)?;
+            require_keys_eq!(owner.key(), royalties.owner, ErrorCode::Unauthorized);
+            emit_rent_refund(&royalties.to_account_info(), owner.key());
+            royalties.close(owner.to_account_info())?;
+        }
+
+        // As is the team declaration's
+        if let Some(team) = &ctx.accounts.team {
+            let owner = ctx
+                .accounts
+                .team_owner
+                .as_ref()
+                .ok_or(ErrorCode::Unauthorized)?;
+            require_keys_eq!(owner.key(), team.owner, ErrorCode::Unauthorized);
+            emit_rent_refund(&team.to_account_info(), owner.key());
+            team.close(owner.to_account_info())?;
+        }
+
+        emit_rent_refund(&intent.to_account_info(), rent_payer);
+        emit!(IntentClosed {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            rent_payer,
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Retry a failed intent as a new attempt (requester's owner, who pays
+    /// its rent), optionally with a different provider. Whatever the failed
+    /// intent still holds in escrow moves into the new attempt's escrow; the
+    /// rest of `payment_amount` (default: the failed intent's) plus
+    /// `tip_amount` is topped up from `from_token_account`, which must belong
+    /// to the intent's payer. The attempts are linked both ways, and the
+    /// failed one may then be closed.
+    pub fn retry_intent(
+        ctx: Context<RetryIntent>,
+        payment_amount: Option<u64>,
+        tip_amount: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_accepting_intents()?;
+
+        let previous = &mut ctx.accounts.previous_intent;
+        let clock = Clock::get()?;
+        require!(
+            previous.status == IntentStatus::Failed as u8
+                && !previous.disputed
+                && previous.retried_as == Pubkey::default(),
+            ErrorCode::InvalidStatusTransition
+        );
+
+        let from_agent = &ctx.accounts.from_agent;
+        let to_agent = &ctx.accounts.to_agent;
+        require!(
+            from_agent.permissions & Permission::CAN_CREATE_INTENT != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        from_agent.require_active()?;
+        require!(to_agent.key() != from_agent.key(), ErrorCode::InvalidParameter);
+        require!(to_agent.has_capacity(clock.unix_timestamp), ErrorCode::ProviderAtCapacity);
+        to_agent.require_active()?;
+        require!(previous.permits(to_agent), ErrorCode::InsufficientPermissions);
+
+        let payment_amount = payment_amount.unwrap_or(previous.payment_amount);
+        require!(payment_amount >= to_agent.min_payment, ErrorCode::PaymentBelowMinimum);
+        let funded = payment_amount
+            .checked_add(tip_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // Everything still escrowed is carried over, never refunded here
+        let carried = ctx.accounts.previous_escrow.amount;
+        require!(funded >= carried, ErrorCode::InvalidParameter);
+        let topped_up = funded - carried;
+
+        // The payload stays sealed only if the provider keeps its keys
+        let same_provider = to_agent.key() == previous.provider();
+        let (sealed_to, ephemeral_pubkey, encrypted_key, profile_version) = if same_provider {
+            (previous.sealed_to, previous.ephemeral_pubkey, previous.encrypted_key, previous.profile_version)
+        } else {
+            ([0u8; 32], [0u8; 32], [0u8; 32], 0)
+        };
+
+        // Retries count toward the requester's rate limit like new intents
+        ctx.accounts
+            .rate_limit
+            .record(from_agent.key(), ctx.bumps.rate_limit, &clock, config)?;
+
+        let counter = &mut ctx.accounts.nonce_counter;
+        if counter.from_agent == Pubkey::default() {
+            counter.from_agent = from_agent.key();
+            counter.to_agent = to_agent.key();
+            counter.bump = ctx.bumps.nonce_counter;
+        }
+        let nonce = counter.next;
+        counter.next = counter.next.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        let intent = &mut ctx.accounts.intent;
+        intent.set_inner(AgentIntent {
+            from_agent: from_agent.key(),
+            to_agent: to_agent.key(),
+            payer: previous.payer,
+            rent_payer: ctx.accounts.owner.key(),
+            nonce,
+            status: IntentStatus::Pending as u8,
+            payload_hash: previous.payload_hash,
+            payload_scheme: previous.payload_scheme,
+            payload_length: previous.payload_length,
+            payload_uri: previous.payload_uri.clone(),
+            sealed_to,
+            ephemeral_pubkey,
+            encrypted_key,
+            payment_amount,
+            payment_mint: previous.payment_mint,
+            tip_amount,
+            // Bidding is over and the failed attempt's cover isn't carried
+            options: previous.options
+                & !(IntentOption::INSURED | IntentOption::PREPAID | IntentOption::OPEN_BIDDING),
+            reimbursement_cap: bps_of(payment_amount, config.base_coverage_bps)?,
+            profile_version,
+            context: previous.context,
+            // Refunds of the carried booking credit still go to the booking
+            booking: previous.booking,
+            booking_credit: previous.booking_credit.min(carried),
+            created_at: clock.unix_timestamp,
+            updated_at: clock.unix_timestamp,
+            bump: ctx.bumps.intent,
+            version: AgentIntent::VERSION,
+            template: previous.template,
+            completion_window: previous.completion_window,
+            required_permissions: previous.required_permissions,
+            schema: previous.schema,
+            attempt: previous.attempt.checked_add(1).ok_or(ErrorCode::MathOverflow)?,
+            previous_attempt: previous.key(),
+            ..Default::default()
+        });
+
+        if carried > 0 {
+            transfer_from_escrow(
+                previous,
+                &ctx.accounts.previous_escrow,
+                &ctx.accounts.escrow_token_account,
+                &ctx.accounts.token_program,
+                carried,
+            )?;
+        }
+        if topped_up > 0 {
+            let from_token_account = ctx
+                .accounts
+                .from_token_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidParameter)?;
+            require_keys_eq!(from_token_account.owner, intent.payer, ErrorCode::Unauthorized);
+            fund_intent(
+                from_token_account,
+                ctx.accounts.escrow_token_account.to_account_info(),
+                &ctx.accounts.owner,
+                FundingAuthority::Payer,
+                &ctx.accounts.token_program,
+                topped_up,
+            )?;
+        }
+
+        previous.retried_as = intent.key();
+        previous.touch(clock.unix_timestamp);
+
+        emit!(IntentCreated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            from_agent: intent.from_agent,
+            to_agent: intent.to_agent,
+            payer: intent.payer,
+            nonce: intent.nonce,
+            payload_hash: intent.payload_hash,
+            payload_uri: intent.payload_uri.clone(),
+            payment_amount,
+            payment_mint: intent.payment_mint,
+            tip_amount,
+            options: intent.options,
+            context: intent.context,
+            template: intent.template,
+            schema: intent.schema,
+            created_at: intent.created_at,
+        });
+        emit!(IntentRetried {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            previous_intent: previous.key(),
+            previous_sequence: previous.sequence,
+            attempt: intent.attempt,
+            from_agent: intent.from_agent,
+            to_agent: intent.to_agent,
+            carried,
+            topped_up,
+            retried_at: clock.unix_timestamp,
+        });
+        record_transition(intent, IntentStatus::Pending as u8, ctx.accounts.owner.key());
+
+        let intent_key = intent.key();
+        append_to_index(
+            &mut ctx.accounts.sent_index,
+            &mut ctx.accounts.sent_index_page,
+            (ctx.bumps.sent_index, ctx.bumps.sent_index_page),
+            ctx.accounts.from_agent.key(),
+            IndexRole::Sent,
+            intent_key,
+        );
+        append_to_index(
+            &mut ctx.accounts.received_index,
+            &mut ctx.accounts.received_index_page,
+            (ctx.bumps.received_index, ctx.bumps.received_index_page),
+            ctx.accounts.to_agent.key(),
+            IndexRole::Received,
+            intent_key,
+        );
+
+        let from_stats = &mut ctx.accounts.from_stats;
+        from_stats.intents_sent = from_stats.intents_sent.saturating_add(1);
+        from_stats.updated_at = clock.unix_timestamp;
+        let to_stats = &mut ctx.accounts.to_stats;
+        to_stats.intents_received = to_stats.intents_received.saturating_add(1);
+        to_stats.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Keeper crank: pay out auto-release intents whose challenge window has
+    /// passed. Remaining accounts come in groups of four per intent:
+    /// `[intent, escrow, release_account, delegate_release_account]` (repeat
+    /// the release account when there is no delegate). Intents that aren't
+    /// ready yet are skipped so one stale entry doesn't fail the batch.
+    /// Releases into a payout vault here are not added to its `accrued` tally.
+    pub fn finalize_ready_releases<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeReadyReleases<'info>>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let released = release_ready_batch(
+            ctx.remaining_accounts,
+            config,
+            ctx.accounts.fee_token_account.as_ref(),
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(ReleasesFinalized {
+            keeper: ctx.accounts.keeper.key(),
+            released,
+            finalized_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Fund the crank treasury that tips `process_expirations` and
+    /// `process_settlements` callers (anyone may top it up)
+    pub fn fund_crank_treasury(ctx: Context<FundCrankTreasury>, lamports: u64) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+        require!(lamports > 0, ErrorCode::InvalidParameter);
+
+        let treasury = &mut ctx.accounts.crank_treasury;
+        treasury.bump = ctx.bumps.crank_treasury;
+        treasury.funded = treasury
+            .funded
+            .checked_add(lamports)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let funder = &ctx.accounts.funder;
+        invoke(
+            &system_instruction::transfer(funder.key, &treasury.key(), lamports),
+            &[
+                funder.to_account_info(),
+                treasury.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit!(CrankTreasuryFunded {
+            funder: ctx.accounts.funder.key(),
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Keeper crank: fail accepted intents past their completion deadline
+    /// and refund their escrow, like `flag_timeout`. Remaining accounts come
+    /// in groups of four per intent: `[intent, escrow, refund_account,
+    /// assignee_stats]`, the refund account owned by the intent's payer and
+    /// the stats being the assignee's `AgentStats`. Intents that haven't
+    /// timed out, and bonded ones (`flag_timeout` settles their bond), are
+    /// skipped. The caller earns `crank_tip_lamports` per intent from the
+    /// crank treasury.
+    pub fn process_expirations<'info>(ctx: Context<'_, '_, 'info, 'info, ProcessCrank<'info>>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let groups = ctx.remaining_accounts.chunks_exact(4);
+        require!(
+            groups.remainder().is_empty() && groups.len() <= MAX_BATCH_RELEASES,
+            ErrorCode::InvalidReleaseBatch
+        );
+
+        let clock = Clock::get()?;
+        let cranker = ctx.accounts.cranker.key();
+        let mut processed = 0u32;
+        for group in groups {
+            let mut intent = Account::<AgentIntent>::try_from(&group[0])?;
+            // Bonded and booking-funded intents need flag_timeout's accounts
+            if !intent.timed_out(&clock, config) || intent.bond > 0 || intent.booking_credit > 0 {
+                continue;
+            }
+
+            let escrow = Account::<TokenAccount>::try_from(&group[1])?;
+            let refund_account = Account::<TokenAccount>::try_from(&group[2])?;
+            require!(
+                escrow.owner == intent.key() && escrow.mint == intent.payment_mint,
+                ErrorCode::InvalidEscrowAccount
+            );
+            require_keys_eq!(refund_account.owner, intent.payer, ErrorCode::Unauthorized);
+            let mut stats = Account::<AgentStats>::try_from(&group[3])?;
+            require_keys_eq!(stats.agent, intent.assignee(), ErrorCode::InvalidAgentStats);
+
+            let previous_status = intent.status;
+            let refund = fail_timed_out_intent(
+                &mut intent,
+                &escrow,
+                &refund_account,
+                None,
+                &ctx.accounts.token_program,
+                clock.unix_timestamp,
+            )?;
+
+            emit!(IntentTimedOut {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                flagged_by: cranker,
+                refund,
+                bond_slashed: 0,
+                tip: 0,
+                timed_out_at: clock.unix_timestamp,
+            });
+            emit!(IntentStatusUpdated {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                nonce: intent.nonce,
+                previous_status,
+                status: intent.status,
+                result_hash: intent.result_hash,
+                result_uri: intent.result_uri.clone(),
+                updated_at: intent.updated_at,
+            });
+            record_transition(&mut intent, previous_status, cranker);
+            stats.record_outcome(&intent, previous_status, clock.unix_timestamp)?;
+            intent.exit(&crate::ID)?;
+            stats.exit(&crate::ID)?;
+            processed += 1;
+        }
+
+        pay_crank_tips(ctx.accounts, processed)
+    }
+
+    /// Keeper crank: `finalize_ready_releases` with a tip of
+    /// `crank_tip_lamports` per released intent from the crank treasury. The
+    /// fee account must be set for batches that owe a protocol fee.
+    pub fn process_settlements<'info>(ctx: Context<'_, '_, 'info, 'info, ProcessCrank<'info>>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let released = release_ready_batch(
+            ctx.remaining_accounts,
+            config,
+            ctx.accounts.fee_token_account.as_ref(),
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(ReleasesFinalized {
+            keeper: ctx.accounts.cranker.key(),
+            released,
+            finalized_at: now,
+        });
+
+        pay_crank_tips(ctx.accounts, released)
+    }
+
+    /// Challenge a completed auto-release intent before its escrow pays out
+    /// (requester only). The opener posts the config's dispute fee into the
+    /// escrow, and the release is held until an arbiter resolves it.
+    pub fn open_dispute(ctx: Context<OpenDispute>, reason_uri: String) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+        config.require_uri_len(&reason_uri)?;
+
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+        require!(
+            intent.release_pending() && !intent.disputed,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            !intent.release_deadline.passed(&clock, config),
+            ErrorCode::ChallengeWindowClosed
+        );
+
+        let fee = config.dispute_fee(intent.payment_amount)?;
+        if fee > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.opener_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.opener.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token::transfer(cpi_ctx, fee)?;
+        }
+
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.intent = intent.key();
+        dispute.opener = ctx.accounts.opener.key();
+        dispute.fee = fee;
+        dispute.reason_uri = reason_uri;
+        dispute.opened_at = clock.unix_timestamp;
+        dispute.bump = ctx.bumps.dispute;
+
+        intent.disputed = true;
+        intent.touch(clock.unix_timestamp);
+
+        emit!(DisputeOpened {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            dispute: dispute.key(),
+            opener: dispute.opener,
+            fee,
+            reason_uri: dispute.reason_uri.clone(),
+            opened_at: dispute.opened_at,
+        });
+
+        Ok(())
+    }
+
+    /// Settle an open dispute (arbiter only). If the opener prevails the
+    /// payment goes back to the payer, the dispute fee back to the opener and
+    /// the intent is marked failed. Otherwise the fee is split between the
+    /// arbiter (`dispute_arbiter_share_bps`) and the provider's release
+    /// account, and the intent is released as usual by the keeper crank.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, opener_prevails: bool) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let dispute = &ctx.accounts.dispute;
+        let escrow = &ctx.accounts.escrow_token_account;
+        let token_program = &ctx.accounts.token_program;
+        let clock = Clock::get()?;
+        require!(intent.disputed, ErrorCode::InvalidStatusTransition);
+
+        let previous_status = intent.status;
+        intent.disputed = false;
+        intent.touch(clock.unix_timestamp);
+
+        let (arbiter_share, counterparty_share) = if opener_prevails {
+            let refund_account = ctx.accounts.refund_token_account.as_ref();
+            let refund = refund_escrow(
+                intent,
+                escrow,
+                refund_account,
+                booking_refund(ctx.accounts.booking.as_mut(), ctx.accounts.booking_vault.as_ref()),
+                token_program,
+                intent.payment_amount,
+            )?;
+            if dispute.fee > 0 {
+                transfer_from_escrow(intent, escrow, &ctx.accounts.opener_token_account, token_program, dispute.fee)?;
+            }
+            intent.status = IntentStatus::Failed as u8;
+
+            if let Some(refund_account) = refund_account.filter(|_| refund > 0) {
+                emit!(EscrowRefunded {
+                    intent: intent.key(),
+                    sequence: intent.sequence,
+                    payer: intent.payer,
+                    refund_account: refund_account.key(),
+                    amount: refund,
+                    refunded_at: clock.unix_timestamp,
+                });
+            }
+            (0, 0)
+        } else {
+            let arbiter_share = bps_of(dispute.fee, config.dispute_arbiter_share_bps)?;
+            let counterparty_share = dispute.fee - arbiter_share;
+            if arbiter_share > 0 {
+                let arbiter_account = ctx
+                    .accounts
+                    .arbiter_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::EscrowAccountRequired)?;
+                require_keys_eq!(arbiter_account.owner, ctx.accounts.arbiter.key(), ErrorCode::Unauthorized);
+                transfer_from_escrow(intent, escrow, arbiter_account, token_program, arbiter_share)?;
+            }
+            if counterparty_share > 0 {
+                let counterparty_account = ctx
+                    .accounts
+                    .counterparty_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::EscrowAccountRequired)?;
+                require_keys_eq!(
+                    counterparty_account.key(),
+                    intent.release_account,
+                    ErrorCode::Unauthorized
+                );
+                transfer_from_escrow(intent, escrow, counterparty_account, token_program, counterparty_share)?;
+            }
+            (arbiter_share, counterparty_share)
+        };
+
+        emit!(DisputeResolved {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            dispute: dispute.key(),
+            arbiter: ctx.accounts.arbiter.key(),
+            opener_prevailed: opener_prevails,
+            fee: dispute.fee,
+            arbiter_share,
+            counterparty_share,
+            resolved_at: clock.unix_timestamp,
+        });
+        emit_rent_refund(&dispute.to_account_info(), dispute.opener);
+        if intent.status != previous_status {
+            emit!(IntentStatusUpdated {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                nonce: intent.nonce,
+                previous_status,
+                status: intent.status,
+                result_hash: intent.result_hash,
+                result_uri: intent.result_uri.clone(),
+                updated_at: intent.updated_at,
+            });
+            record_transition(intent, previous_status, ctx.accounts.arbiter.key());
+            ctx.accounts
+                .assignee_stats
+                .record_outcome(intent, previous_status, clock.unix_timestamp)?;
+        }
+
+        Ok(())
+    }
+
+    /// File a claim against the insurance pool for a failed or disputed
+    /// intent whose provider didn't make the requester whole (requester
+    /// only). `amount` is capped by the intent's `reimbursement_cap`; an
+    /// arbiter decides the payout.
+    pub fn claim_insurance(ctx: Context<ClaimInsurance>, amount: u64, reason_uri: String) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+        config.require_uri_len(&reason_uri)?;
+
+        let intent = &ctx.accounts.intent;
+        require!(intent.reimbursement_cap > 0, ErrorCode::NotInsured);
+        require!(
+            intent.status == IntentStatus::Failed as u8 || intent.disputed,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            amount > 0 && amount <= intent.reimbursement_cap,
+            ErrorCode::InvalidInsuranceClaim
+        );
+
+        let clock = Clock::get()?;
+        let claim = &mut ctx.accounts.insurance_claim;
+        claim.intent = intent.key();
+        claim.claimant = ctx.accounts.claimant.key();
+        claim.amount = amount;
+        claim.reason_uri = reason_uri;
+        claim.filed_at = clock.unix_timestamp;
+        claim.bump = ctx.bumps.insurance_claim;
+
+        emit!(InsuranceClaimFiled {
+            claim: claim.key(),
+            intent: claim.intent,
+            claimant: claim.claimant,
+            amount,
+            reason_uri: claim.reason_uri.clone(),
+            filed_at: claim.filed_at,
+        });
+
+        Ok(())
+    }
+
+    /// Settle an insurance claim (arbiter only), paying `payout` (0 rejects
+    /// it) from the pool's vault to the intent's payer. Either way the
+    /// intent's coverage is used up, so it can't be claimed against again.
+    pub fn resolve_insurance_claim(ctx: Context<ResolveInsuranceClaim>, payout: u64) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let claim = &ctx.accounts.insurance_claim;
+        let pool = &ctx.accounts.insurance_pool;
+        let vault = &ctx.accounts.insurance_vault;
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+        require!(payout <= claim.amount, ErrorCode::InvalidInsuranceClaim);
+        require!(payout <= vault.amount, ErrorCode::InsurancePoolDepleted);
+
+        if payout > 0 {
+            let payer_account = ctx
+                .accounts
+                .payer_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            require_keys_eq!(payer_account.owner, intent.payer, ErrorCode::Unauthorized);
+
+            let seeds = &[b"insurance_pool".as_ref(), pool.mint.as_ref(), &[pool.bump]];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: vault.to_account_info(),
+                to: payer_account.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, payout)?;
+        }
+
+        intent.reimbursement_cap = 0;
+        intent.touch(clock.unix_timestamp);
+
+        emit!(InsuranceClaimResolved {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            claim: claim.key(),
+            arbiter: ctx.accounts.arbiter.key(),
+            claimant: claim.claimant,
+            requested: claim.amount,
+            payout,
+            resolved_at: clock.unix_timestamp,
+        });
+        emit_rent_refund(&claim.to_account_info(), claim.claimant);
+
+        Ok(())
+    }
+
+    /// Declare the upstream royalty recipients (e.g. dataset owners) named in
+    /// the payload's schema. Only the requester can declare, once, while the
+    /// intent is still pending, so the provider sees the splits before
+    /// accepting. On completion each recipient receives its share of the
+    /// provider's payout from escrow.
+    pub fn declare_royalties(
+        ctx: Context<DeclareRoyalties>,
+        schema_hash: [u8; 32],
+        splits: Vec<RoyaltySplit>,
+    ) -> Result<()> {
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Pending as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            intent.options & IntentOption::AUTO_RELEASE == 0,
+            ErrorCode::RoyaltiesUnsupported
+        );
+        require!(intent.team_size == 0, ErrorCode::TeamUnsupported);
+        require!(
+            !splits.is_empty() && splits.len() <= MAX_ROYALTY_SPLITS,
+            ErrorCode::InvalidRoyaltySplits
+        );
+        let mut royalty_bps: u16 = 0;
+        for split in &splits {
+            require!(
+                split.bps > 0 && split.recipient != Pubkey::default(),
+                ErrorCode::InvalidRoyaltySplits
+            );
+            royalty_bps = royalty_bps
+                .checked_add(split.bps)
+                .filter(|total| *total <= BPS_DENOMINATOR)
+                .ok_or(ErrorCode::InvalidBasisPoints)?;
+        }
+
+        let royalties = &mut ctx.accounts.royalties;
+        royalties.intent = intent.key();
+        royalties.owner = ctx.accounts.owner.key();
+        royalties.schema_hash = schema_hash;
+        royalties.splits = splits;
+        royalties.bump = ctx.bumps.royalties;
+
+        intent.royalty_bps = royalty_bps;
+        intent.touch(clock.unix_timestamp);
+
+        emit!(RoyaltiesDeclared {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            schema_hash,
+            splits: royalties.splits.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Split a pending intent across a team of executing agents (requester
+    /// only). `members` must include the provider, who accepts for the team,
+    /// and their shares must add up to the whole payment; the intent settles
+    /// once `quorum` members have submitted results.
+    pub fn declare_team(ctx: Context<DeclareTeam>, members: Vec<TeamShare>, quorum: u8) -> Result<()> {
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Pending as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            intent.options & (IntentOption::AUTO_RELEASE | IntentOption::ATTESTED_RESULT) == 0
+                && intent.royalty_bps == 0
+                && intent.delegate_agent == Pubkey::default(),
+            ErrorCode::TeamUnsupported
+        );
+        require!(
+            !members.is_empty()
+                && members.len() <= MAX_TEAM_MEMBERS
+                && quorum > 0
+                && quorum as usize <= members.len()
+                && members.iter().any(|member| member.agent == intent.provider()),
+            ErrorCode::InvalidTeam
+        );
+        let mut total_bps: u16 = 0;
+        for (i, member) in members.iter().enumerate() {
+            require!(
+                member.share_bps > 0
+                    && member.agent != intent.from_agent
+                    && members[..i].iter().all(|other| other.agent != member.agent),
+                ErrorCode::InvalidTeam
+            );
+            total_bps = total_bps
+                .checked_add(member.share_bps)
+                .ok_or(ErrorCode::InvalidBasisPoints)?;
+        }
+        require!(total_bps == BPS_DENOMINATOR, ErrorCode::InvalidTeam);
+
+        let team = &mut ctx.accounts.team;
+        team.intent = intent.key();
+        team.owner = ctx.accounts.owner.key();
+        team.quorum = quorum;
+        team.completed = 0;
+        team.members = members
+            .iter()
+            .map(|member| TeamMember {
+                agent: member.agent,
+                share_bps: member.share_bps,
+                ..Default::default()
+            })
+            .collect();
+        team.bump = ctx.bumps.team;
+
+        intent.team_size = members.len() as u8;
+        intent.touch(clock.unix_timestamp);
+
+        emit!(TeamDeclared {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            members,
+            quorum,
+        });
+
+        Ok(())
+    }
+
+    /// Submit one team member's result on an accepted team intent, as that
+    /// member's owner; its share is paid into `payout_token_account` at
+    /// settlement
+    pub fn submit_team_result(
+        ctx: Context<SubmitTeamResult>,
+        result: ContentCommitment,
+        result_uri: String,
+    ) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+        ctx.accounts.config.require_uri_len(&result_uri)?;
+        result.require_known_scheme()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let agent = &ctx.accounts.agent;
+        let clock = Clock::get()?;
+
+        require!(
+            agent.permissions & Permission::CAN_ACCEPT_INTENT != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        agent.require_active()?;
+        require!(
+            intent.status == IntentStatus::Accepted as u8 && !intent.disputed,
+            ErrorCode::InvalidStatusTransition
+        );
+
+        let team = &mut ctx.accounts.team;
+        let member = team
+            .members
+            .iter_mut()
+            .find(|member| member.agent == agent.key())
+            .ok_or(ErrorCode::Unauthorized)?;
+        require!(member.completed_at == 0, ErrorCode::InvalidStatusTransition);
+        member.result = result;
+        member.payout = ctx.accounts.payout_token_account.key();
+        member.completed_at = clock.unix_timestamp;
+        team.completed += 1;
+
+        intent.touch(clock.unix_timestamp);
+
+        emit!(TeamResultSubmitted {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            agent: agent.key(),
+            result,
+            result_uri,
+            completed: team.completed,
+            quorum: team.quorum,
+        });
+
+        Ok(())
+    }
+
+    /// Complete a team intent once its quorum has submitted (permissionless).
+    /// The protocol fee comes off the top and each submitting member is paid
+    /// its share of the rest; shares of members that didn't submit go back
+    /// to the payer. Remaining accounts are the submitting members' payout
+    /// token accounts, in team order.
+    pub fn settle_team_intent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleTeamIntent<'info>>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let team = &ctx.accounts.team;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Accepted as u8 && !intent.disputed,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(team.completed >= team.quorum, ErrorCode::TeamQuorumNotReached);
+
+        let previous_status = intent.status;
+        intent.status = IntentStatus::Completed as u8;
+        intent.completed_at = clock.unix_timestamp;
+        intent.released = true;
+        intent.touch(clock.unix_timestamp);
+
+        let escrow = &ctx.accounts.escrow_token_account;
+        let token_program = &ctx.accounts.token_program;
+        let penalty = sla_penalty(intent, clock.unix_timestamp)?;
+        if penalty > 0 {
+            let refund_account = ctx
+                .accounts
+                .refund_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            require_keys_eq!(refund_account.owner, intent.payer, ErrorCode::Unauthorized);
+            transfer_from_escrow(intent, escrow, refund_account, token_program, penalty)?;
+            intent.payment_amount -= penalty;
+
+            emit!(SlaPenaltyApplied {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                late_by: clock.unix_timestamp - intent.accepted_at - intent.sla_target,
+                penalty,
+                refund_account: refund_account.key(),
+            });
+        }
+        let fee = bps_of(intent.payment_amount, config.protocol_fee_bps)?;
+        if fee > 0 {
+            let fee_account = ctx
+                .accounts
+                .fee_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            require_keys_eq!(fee_account.owner, config.fee_treasury, ErrorCode::Unauthorized);
+            transfer_from_escrow(intent, escrow, fee_account, token_program, fee)?;
+        }
+
+        let distributable = intent.payment_amount - fee;
+        let submitted = team.members.iter().filter(|member| member.completed_at != 0);
+        require!(
+            ctx.remaining_accounts.len() == team.completed as usize,
+            ErrorCode::InvalidTeam
+        );
+        let mut paid = 0;
+        for (member, info) in submitted.zip(ctx.remaining_accounts) {
+            let payout = Account::<TokenAccount>::try_from(info)?;
+            require_keys_eq!(payout.key(), member.payout, ErrorCode::InvalidTeam);
+            let amount = bps_of(distributable, member.share_bps)?;
+            if amount > 0 {
+                transfer_from_escrow(intent, escrow, &payout, token_program, amount)?;
+                paid += amount;
+            }
+        }
+
+        let refund = distributable - paid;
+        if refund > 0 {
+            let refund_account = ctx
+                .accounts
+                .refund_token_account
+                .as_ref()
+                .ok_or(ErrorCode::EscrowAccountRequired)?;
+            require_keys_eq!(refund_account.owner, intent.payer, ErrorCode::Unauthorized);
+            transfer_from_escrow(intent, escrow, refund_account, token_program, refund)?;
+
+            emit!(EscrowRefunded {
+                intent: intent.key(),
+                sequence: intent.sequence,
+                payer: intent.payer,
+                refund_account: refund_account.key(),
+                amount: refund,
+                refunded_at: clock.unix_timestamp,
+            });
+        }
+
+        emit!(TeamSettled {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            members_paid: team.completed,
+            paid,
+            fee,
+            refund,
+            settled_at: clock.unix_timestamp,
+        });
+        emit!(IntentStatusUpdated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            nonce: intent.nonce,
+            previous_status,
+            status: intent.status,
+            result_hash: intent.result_hash,
+            result_uri: intent.result_uri.clone(),
+            updated_at: intent.updated_at,
+        });
+        record_transition(intent, previous_status, Pubkey::default());
+        ctx.accounts
+            .assignee_stats
+            .record_outcome(intent, previous_status, clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    /// Set a pending intent's SLA (requester only): completing more than
+    /// `target` seconds after acceptance forfeits `penalty_bps` of the payment
+    /// per hour late back to the payer. `(0, 0)` clears it.
+    pub fn set_sla(ctx: Context<SetSla>, target: i64, penalty_bps: u16) -> Result<()> {
+        let intent = &mut ctx.accounts.intent;
+        require!(
+            intent.status == IntentStatus::Pending as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            target >= 0 && (target > 0 || penalty_bps == 0),
+            ErrorCode::InvalidParameter
+        );
+        require!(penalty_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+
+        intent.sla_target = target;
+        intent.sla_penalty_bps = penalty_bps;
+        intent.touch(Clock::get()?.unix_timestamp);
+
+        emit!(SlaSet {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            target,
+            penalty_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Record that the intent's payload is encrypted to the provider (requester
+    /// only, while pending). `sealed_to` must be a key the provider currently
+    /// accepts; the provider recovers the payload's content key from
+    /// `ephemeral_pubkey` and `encrypted_key` (see the client SDK's `seal`
+    /// module for the convention).
+    pub fn seal_payload(
+        ctx: Context<SealPayload>,
+        sealed_to: [u8; 32],
+        ephemeral_pubkey: [u8; 32],
+        encrypted_key: [u8; 32],
+    ) -> Result<()> {
+        let intent = &mut ctx.accounts.intent;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.status == IntentStatus::Pending as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            ctx.accounts.to_agent.accepts_encryption_key(&sealed_to, clock.unix_timestamp),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(ephemeral_pubkey != [0u8; 32], ErrorCode::InvalidEncryptionKey);
+
+        intent.sealed_to = sealed_to;
+        intent.ephemeral_pubkey = ephemeral_pubkey;
+        intent.encrypted_key = encrypted_key;
+        intent.touch(clock.unix_timestamp);
+
+        emit!(PayloadSealed {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            sealed_to,
+            ephemeral_pubkey,
+            encrypted_key,
+        });
+
+        Ok(())
+    }
+
+    /// Hand an accepted intent to another agent, keeping the escrow in place.
+    /// Only allowed if the payer opted in with `IntentOption::ALLOW_REDELEGATION`
+    /// and the delegate's owner co-signs. Both parties' payout accounts are
+    /// fixed here, so whoever settles can't redirect either share.
+    pub fn redelegate_intent(ctx: Context<RedelegateIntent>, delegate_share_bps: u16) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let intent = &mut ctx.accounts.intent;
+        let delegate = &ctx.accounts.delegate_agent;
+        let clock = Clock::get()?;
+
+        require!(
+            intent.options & IntentOption::ALLOW_REDELEGATION != 0,
+            ErrorCode::RedelegationNotAllowed
+        );
+        require!(
+            intent.status == IntentStatus::Accepted as u8,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            intent.delegate_agent == Pubkey::default(),
+            ErrorCode::AlreadyRedelegated
+        );
+        require!(intent.team_size == 0, ErrorCode::TeamUnsupported);
+        require!(
+            delegate.key() != intent.provider() && delegate.key() != intent.from_agent,
+            ErrorCode::InvalidDelegate
+        );
+        require!(
+            delegate.permissions & Permission::CAN_ACCEPT_INTENT != 0 && intent.permits(delegate),
+            ErrorCode::InsufficientPermissions
+        );
+        delegate.require_active()?;
+        require!(delegate_share_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+
+        // The provider's share goes to its own wallet, its profile's billing
+        // wallet or its payout group
+        let billing = &ctx.accounts.billing_token_account;
+        let billing_wallet = ctx
+            .accounts
+            .model_profile
+            .as_ref()
+            .map(|profile| profile.billing_wallet);
+        require!(
+            intent.pays_into(billing)
+                && (intent.payout_group != Pubkey::default()
+                    || billing.owner == ctx.accounts.to_agent.owner_wallet
+                    || Some(billing.owner) == billing_wallet),
+            ErrorCode::Unauthorized
+        );
+
+        intent.delegate_agent = delegate.key();
+        intent.delegate_share_bps = delegate_share_bps;
+        intent.assignee_owner = delegate.owner_wallet;
+        intent.release_account = billing.key();
+        intent.delegate_release_account = ctx.accounts.delegate_billing_token_account.key();
+        // Split payouts aren't swapped
+        intent.payout_mint = Pubkey::default();
+        intent.max_slippage_bps = 0;
+        intent.touch(clock.unix_timestamp);
+
+        emit!(IntentRedelegated {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            to_agent: intent.provider(),
+            delegate_agent: intent.delegate_agent,
+            delegate_share_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Let the agent spend up to `daily_cap` of `mint` per day from its
+    /// owner's token accounts (owner only)
+    pub fn open_spend_limit(ctx: Context<OpenSpendLimit>, daily_cap: u64) -> Result<()> {
+        let spend_limit = &mut ctx.accounts.spend_limit;
+        let clock = Clock::get()?;
+
+        spend_limit.agent = ctx.accounts.agent.key();
+        spend_limit.mint = ctx.accounts.mint.key();
+        spend_limit.daily_cap = daily_cap;
+        spend_limit.spent_today = 0;
+        spend_limit.day_started_at = clock.unix_timestamp;
+        spend_limit.bump = ctx.bumps.spend_limit;
+
+        emit!(SpendLimitUpdated {
+            agent: spend_limit.agent,
+            mint: spend_limit.mint,
+            daily_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Change the agent's daily cap for a mint; takes effect immediately
+    /// against what was already spent today (owner only)
+    pub fn set_spend_limit(ctx: Context<UpdateSpendLimit>, daily_cap: u64) -> Result<()> {
+        let spend_limit = &mut ctx.accounts.spend_limit;
+        spend_limit.daily_cap = daily_cap;
+
+        emit!(SpendLimitUpdated {
+            agent: spend_limit.agent,
+            mint: spend_limit.mint,
+            daily_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Restrict `execute_transfer` for this mint to token accounts owned by
+    /// `destinations`; an empty list allows any destination (owner only)
+    pub fn set_transfer_destinations(
+        ctx: Context<UpdateSpendLimit>,
+        destinations: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            destinations.len() <= MAX_TRANSFER_DESTINATIONS,
+            ErrorCode::InvalidParameter
+        );
+
+        let spend_limit: &mut SpendLimit = &mut ctx.accounts.spend_limit;
+        spend_limit.destinations = [Pubkey::default(); MAX_TRANSFER_DESTINATIONS];
+        spend_limit.destination_count = 0;
+        for destination in &destinations {
+            set_insert(
+                &mut spend_limit.destinations,
+                &mut spend_limit.destination_count,
+                *destination,
+            )?;
+        }
+
+        emit!(TransferDestinationsUpdated {
+            agent: spend_limit.agent,
+            mint: spend_limit.mint,
+            destinations,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke the agent's spending authority for a mint (owner only)
+    pub fn close_spend_limit(ctx: Context<CloseSpendLimit>) -> Result<()> {
+        emit!(SpendLimitUpdated {
+            agent: ctx.accounts.spend_limit.agent,
+            mint: ctx.accounts.spend_limit.mint,
+            daily_cap: 0,
+        });
+        emit_rent_refund(&ctx.accounts.spend_limit.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Set aside `deposit` of `mint` for the agent's intents. `create_intent`
+    /// draws payments from the budget vault, a token account owned by the
+    /// budget PDA, so the agent wallet can commit them without the owner
+    /// co-signing; at most `daily_cap` per day and `total_cap` over the
+    /// budget's life (0 = no cap) (owner only)
+    pub fn open_budget(ctx: Context<OpenBudget>, daily_cap: u64, total_cap: u64, deposit: u64) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let vault = &ctx.accounts.budget_vault;
+        require_keys_eq!(vault.owner, ctx.accounts.budget.key(), ErrorCode::InvalidBudget);
+        require_keys_eq!(vault.mint, ctx.accounts.mint.key(), ErrorCode::PaymentMintMismatch);
+
+        let budget = &mut ctx.accounts.budget;
+        let clock = Clock::get()?;
+
+        budget.agent = ctx.accounts.agent.key();
+        budget.mint = ctx.accounts.mint.key();
+        budget.vault = vault.key();
+        budget.daily_cap = daily_cap;
+        budget.total_cap = total_cap;
+        budget.spent_today = 0;
+        budget.day_started_at = clock.unix_timestamp;
+        budget.total_spent = 0;
+        budget.bump = ctx.bumps.budget;
+
+        if deposit > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.owner_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), deposit)?;
+        }
+
+        emit!(BudgetUpdated {
+            agent: budget.agent,
+            mint: budget.mint,
+            daily_cap,
+            total_cap,
+            balance: vault.amount.checked_add(deposit).ok_or(ErrorCode::MathOverflow)?,
+        });
+
+        Ok(())
+    }
+
+    /// Top up the agent's budget vault from the owner's token account
+    /// (owner only)
+    pub fn fund_budget(ctx: Context<FundBudget>, amount: u64) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            to: ctx.accounts.budget_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let budget = &ctx.accounts.budget;
+        emit!(BudgetUpdated {
+            agent: budget.agent,
+            mint: budget.mint,
+            daily_cap: budget.daily_cap,
+            total_cap: budget.total_cap,
+            balance: ctx
+                .accounts
+                .budget_vault
+                .amount
+                .checked_add(amount)
+                .ok_or(ErrorCode::MathOverflow)?,
+        });
+
+        Ok(())
+    }
+
+    /// Change the budget's caps; they take effect immediately against what
+    /// was already spent (owner only)
+    pub fn set_budget_caps(ctx: Context<UpdateBudget>, daily_cap: u64, total_cap: u64) -> Result<()> {
+        let budget = &mut ctx.accounts.budget;
+        budget.daily_cap = daily_cap;
+        budget.total_cap = total_cap;
+
+        emit!(BudgetUpdated {
+            agent: budget.agent,
+            mint: budget.mint,
+            daily_cap,
+            total_cap,
+            balance: ctx.accounts.budget_vault.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return what's left in the budget vault to the owner and close the
+    /// budget and its vault (owner only). Intents it already funded keep
+    /// their escrow; their refunds go to the budget PDA's token accounts, so
+    /// close only once they have settled.
+    pub fn close_budget(ctx: Context<CloseBudget>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let budget = &ctx.accounts.budget;
+        let vault = &ctx.accounts.budget_vault;
+        let seeds = &[
+            b"budget".as_ref(),
+            budget.agent.as_ref(),
+            budget.mint.as_ref(),
+            &[budget.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        let refunded = vault.amount;
+        if refunded > 0 {
+            let cpi_accounts = Transfer {
+                from: vault.to_account_info(),
+                to: ctx.accounts.owner_token_account.to_account_info(),
+                authority: budget.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer);
+            token::transfer(cpi_ctx, refunded)?;
+        }
+
+        emit_rent_refund(&vault.to_account_info(), ctx.accounts.owner.key());
+        let cpi_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: budget.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer))?;
+
+        emit!(BudgetClosed {
+            agent: budget.agent,
+            mint: budget.mint,
+            total_spent: budget.total_spent,
+            refunded,
+        });
+        emit_rent_refund(&budget.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Move `amount` out of one of the owner's token accounts, signed by the
+    /// agent wallet. The owner must have approved the agent PDA as delegate
+    /// on `source_token_account`; the transfer counts against the agent's
+    /// daily spend limit for the mint and must go to an allowed destination.
+    pub fn execute_transfer(ctx: Context<ExecuteTransfer>, amount: u64) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let agent = &ctx.accounts.agent;
+        require!(
+            agent.permissions & Permission::CAN_TRANSFER != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        agent.require_active()?;
+        let destination = &ctx.accounts.destination_token_account;
+        let spend_limit = &mut ctx.accounts.spend_limit;
+        require!(
+            spend_limit.allows_destination(&destination.owner),
+            ErrorCode::DestinationNotAllowed
+        );
+
+        let clock = Clock::get()?;
+        spend_limit.record(amount, clock.unix_timestamp)?;
+
+        let seeds = &[b"agent".as_ref(), agent.owner_wallet.as_ref(), &[agent.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source_token_account.to_account_info(),
+            to: destination.to_account_info(),
+            authority: agent.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(TransferExecuted {
+            agent: agent.key(),
+            mint: spend_limit.mint,
+            destination: destination.key(),
+            amount,
+            executed_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Swap out of one of the owner's token accounts through an allow-listed
+    /// swap program (the Jupiter router), signed by the agent wallet. The
+    /// owner must have approved the agent PDA as delegate on
+    /// `source_token_account`; the route's accounts are passed as remaining
+    /// accounts and the agent PDA signs as the transfer authority. The input
+    /// spent counts against the agent's daily spend limit for that mint.
+    pub fn execute_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteSwap<'info>>,
+        amount_in: u64,
+        min_out: u64,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_settlement_open()?;
+
+        let agent = &ctx.accounts.agent;
+        require!(
+            agent.permissions & Permission::CAN_SWAP != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        agent.require_active()?;
+        let swap_program = &ctx.accounts.swap_program;
+        require!(
+            config.is_swap_program(swap_program.key),
+            ErrorCode::SwapProgramNotAllowed
+        );
+        require!(amount_in > 0, ErrorCode::InvalidSwapRoute);
+
+        let source = &mut ctx.accounts.source_token_account;
+        let destination = &mut ctx.accounts.destination_token_account;
+        require!(source.mint != destination.mint, ErrorCode::InvalidSwapRoute);
+        let source_before = source.amount;
+        let destination_before = destination.amount;
+
+        let agent_key = agent.key();
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|a| AccountMeta {
+                pubkey: *a.key,
+                is_signer: a.is_signer || *a.key == agent_key,
+                is_writable: a.is_writable,
+            })
+            .collect();
+        let ix = Instruction {
+            program_id: *swap_program.key,
+            accounts,
+            data,
+        };
+        let mut infos = ctx.remaining_accounts.to_vec();
+        infos.push(agent.to_account_info());
+        infos.push(swap_program.to_account_info());
+
+        let seeds = &[b"agent".as_ref(), agent.owner_wallet.as_ref(), &[agent.bump]];
+        invoke_signed(&ix, &infos, &[&seeds[..]])?;
+
+        source.reload()?;
+        destination.reload()?;
+        let amount_spent = source_before.saturating_sub(source.amount);
+        let amount_out = destination.amount.saturating_sub(destination_before);
+        require!(amount_spent <= amount_in, ErrorCode::InvalidSwapRoute);
+        require!(amount_out >= min_out, ErrorCode::SlippageExceeded);
+
+        let clock = Clock::get()?;
+        ctx.accounts.spend_limit.record(amount_spent, clock.unix_timestamp)?;
+
+        emit!(SwapExecuted {
+            agent: agent_key,
+            swap_program: *swap_program.key,
+            input_mint: source.mint,
+            output_mint: destination.mint,
+            amount_in: amount_spent,
+            amount_out,
+            executed_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Found an agent group under the signer's agent, which joins as its
+    /// first member with every role. The group PDA owns the group's
+    /// treasury: its associated token accounts.
+    pub fn create_group(ctx: Context<CreateGroup>, group_id: [u8; 16]) -> Result<()> {
+        let group = &mut ctx.accounts.group;
+        group.founder = ctx.accounts.agent.key();
+        group.group_id = group_id;
+        group.members = vec![GroupMember {
+            agent: group.founder,
+            roles: GroupRole::ADMIN | GroupRole::TREASURER,
+        }];
+        group.created_at = Clock::get()?.unix_timestamp;
+        group.bump = ctx.bumps.group;
+
+        emit!(GroupCreated {
+            group: group.key(),
+            founder: group.founder,
+            group_id,
+        });
+        emit!(GroupMemberUpdated {
+            group: group.key(),
+            agent: group.founder,
+            roles: GroupRole::ADMIN | GroupRole::TREASURER,
+        });
+
+        Ok(())
+    }
+
+    /// Add an agent to a group (an admin's owner, with the new member's
+    /// owner co-signing), or change an existing member's `GroupRole`s (an
+    /// admin's owner). The group must keep at least one admin.
+    pub fn set_group_member(ctx: Context<SetGroupMember>, roles: u8) -> Result<()> {
+        let group = &mut ctx.accounts.group;
+        require!(
+            group.roles_of(&ctx.accounts.admin_agent.key()) & GroupRole::ADMIN != 0,
+            ErrorCode::Unauthorized
+        );
+        require!(roles & !GroupRole::ALL == 0, ErrorCode::InvalidParameter);
+
+        let agent = ctx.accounts.member_agent.key();
+        match group.members.iter_mut().find(|member| member.agent == agent) {
+            Some(member) => member.roles = roles,
+            None => {
+                require!(ctx.accounts.member_owner.is_some(), ErrorCode::Unauthorized);
+                require!(group.members.len() < MAX_GROUP_MEMBERS, ErrorCode::GroupFull);
+                group.members.push(GroupMember { agent, roles });
+            }
+        }
+        require!(group.has_admin(), ErrorCode::GroupNeedsAdmin);
+
+        emit!(GroupMemberUpdated {
+            group: group.key(),
+            agent,
+            roles,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a member from a group, as an admin's owner or the member's own
+    /// owner leaving. Its earnings stop flowing to the group treasury.
+    pub fn remove_group_member(ctx: Context<RemoveGroupMember>) -> Result<()> {
+        let group = &mut ctx.accounts.group;
+        let agent = ctx.accounts.member_agent.key();
+        let signer_agent = ctx.accounts.signer_agent.key();
+        require!(
+            signer_agent == agent || group.roles_of(&signer_agent) & GroupRole::ADMIN != 0,
+            ErrorCode::Unauthorized
+        );
+        let index = group
+            .members
+            .iter()
+            .position(|member| member.agent == agent)
+            .ok_or(ErrorCode::NotGroupMember)?;
+        group.members.remove(index);
+        require!(group.has_admin(), ErrorCode::GroupNeedsAdmin);
+
+        let member_agent = &mut ctx.accounts.member_agent;
+        if member_agent.group == group.key() {
+            member_agent.group = Pubkey::default();
+            emit!(EarningsRoutingSet {
+                agent,
+                group: Pubkey::default(),
+            });
+        }
+
+        emit!(GroupMemberUpdated {
+            group: group.key(),
+            agent,
+            roles: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Route the signer's agent's earnings into the group treasury, or stop
+    /// (`enabled = false`). Intents it takes from then on must pay the
+    /// provider's share and tip into a token account the group owns.
+    pub fn set_earnings_routing(ctx: Context<SetEarningsRouting>, enabled: bool) -> Result<()> {
+        let group = &ctx.accounts.group;
+        let agent = &mut ctx.accounts.agent;
+        require!(
+            group.members.iter().any(|member| member.agent == agent.key()),
+            ErrorCode::NotGroupMember
+        );
+        agent.group = if enabled { group.key() } else { Pubkey::default() };
+        agent.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(EarningsRoutingSet {
+            agent: agent.key(),
+            group: agent.group,
+        });
+
+        Ok(())
+    }
+
+    /// Spend `amount` from the group treasury as a `TREASURER` member's owner
+    pub fn spend_from_group(ctx: Context<SpendFromGroup>, amount: u64) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let group = &ctx.accounts.group;
+        let agent = ctx.accounts.agent.key();
+        require!(
+            group.roles_of(&agent) & GroupRole::TREASURER != 0,
+            ErrorCode::Unauthorized
+        );
+
+        let seeds = &[
+            b"group".as_ref(),
+            group.founder.as_ref(),
+            &group.group_id,
+            &[group.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            to: ctx.accounts.destination_token_account.to_account_info(),
+            authority: group.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(GroupTreasurySpent {
+            group: group.key(),
+            agent,
+            mint: ctx.accounts.treasury_token_account.mint,
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Escrow `amount` of a reward against a spec. The spec hash is part of
+    /// the PDA seeds, so the address commits to the work being paid for.
+    /// `reviewer` decides who earns it; the sponsor can only withdraw once
+    /// `expires` has passed.
+    pub fn post_bounty(
+        ctx: Context<PostBounty>,
+        spec_hash: [u8; 32],
+        spec_uri: String,
+        amount: u64,
+        reviewer: Pubkey,
+        expires: Deadline,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_accepting_intents()?;
+        config.require_uri_len(&spec_uri)?;
+        require!(amount > 0, ErrorCode::InvalidBounty);
+
+        let clock = Clock::get()?;
+        require!(!expires.passed(&clock, config), ErrorCode::InvalidBounty);
+
+        let vault = &ctx.accounts.bounty_vault;
+        let bounty_key = ctx.accounts.bounty.key();
+        require_keys_eq!(vault.owner, bounty_key, ErrorCode::InvalidBounty);
+        require_keys_eq!(vault.mint, ctx.accounts.mint.key(), ErrorCode::InvalidBounty);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.sponsor_token_account.to_account_info(),
+            to: vault.to_account_info(),
+            authority: ctx.accounts.sponsor.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let bounty = &mut ctx.accounts.bounty;
+        bounty.sponsor = ctx.accounts.sponsor.key();
+        bounty.reviewer = reviewer;
+        bounty.mint = vault.mint;
+        bounty.vault = vault.key();
+        bounty.spec_hash = spec_hash;
+        bounty.spec_uri = spec_uri;
+        bounty.amount = amount;
+        bounty.expires = expires;
+        bounty.created_at = clock.unix_timestamp;
+        bounty.bump = ctx.bumps.bounty;
+
+        emit!(BountyPosted {
+            bounty: bounty_key,
+            sponsor: bounty.sponsor,
+            reviewer,
+            mint: bounty.mint,
+            spec_hash,
+            spec_uri: bounty.spec_uri.clone(),
+            amount,
+            expires,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the whole vault to the owner of the agent that did the work
+    /// (reviewer only). The vault and bounty rent return to the sponsor.
+    pub fn award_bounty(ctx: Context<AwardBounty>) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let accounts = &ctx.accounts;
+        let amount = pay_out_bounty(
+            &accounts.bounty,
+            &accounts.bounty_vault,
+            accounts.claimant_token_account.to_account_info(),
+            accounts.sponsor.to_account_info(),
+            accounts.token_program.to_account_info(),
+        )?;
+
+        emit!(BountyAwarded {
+            bounty: accounts.bounty.key(),
+            claimant: accounts.claimant.key(),
+            amount,
+        });
+        emit_rent_refund(&accounts.bounty.to_account_info(), accounts.sponsor.key());
+
+        Ok(())
+    }
+
+    /// Return the reward to the sponsor. The reviewer may withdraw a bounty
+    /// at any time; the sponsor only after it expires unawarded.
+    pub fn cancel_bounty(ctx: Context<CancelBounty>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let config = &accounts.config;
+        config.require_settlement_open()?;
+
+        let bounty = &accounts.bounty;
+        let authority = accounts.authority.key();
+        if authority != bounty.reviewer {
+            require_keys_eq!(authority, bounty.sponsor, ErrorCode::Unauthorized);
+            require!(
+                bounty.expires.passed(&Clock::get()?, config),
+                ErrorCode::BountyStillOpen
+            );
+        }
+
+        let refunded = pay_out_bounty(
+            bounty,
+            &accounts.bounty_vault,
+            accounts.sponsor_token_account.to_account_info(),
+            accounts.sponsor.to_account_info(),
+            accounts.token_program.to_account_info(),
+        )?;
+
+        emit!(BountyCancelled {
+            bounty: bounty.key(),
+            authority,
+            refunded,
+        });
+        emit_rent_refund(&bounty.to_account_info(), accounts.sponsor.key());
+
+        Ok(())
+    }
+
+    /// Let the agent relay the owner's vote on one spl-governance proposal
+    /// (owner only). The owner must also set the agent PDA as governance
+    /// delegate on their token owner record in the realm.
+    pub fn approve_vote(
+        ctx: Context<ApproveVote>,
+        governance_program: Pubkey,
+        realm: Pubkey,
+        proposal: Pubkey,
+    ) -> Result<()> {
+        let approval = &mut ctx.accounts.vote_approval;
+        approval.agent = ctx.accounts.agent.key();
+        approval.governance_program = governance_program;
+        approval.realm = realm;
+        approval.proposal = proposal;
+        approval.approved_at = Clock::get()?.unix_timestamp;
+        approval.bump = ctx.bumps.vote_approval;
+
+        emit!(VoteApproved {
+            agent: approval.agent,
+            governance_program,
+            realm,
+            proposal,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a vote approval (owner only). Votes already cast stand.
+    pub fn revoke_vote_approval(ctx: Context<RevokeVoteApproval>) -> Result<()> {
+        let approval = &ctx.accounts.vote_approval;
+        emit!(VoteApprovalRevoked {
+            agent: approval.agent,
+            realm: approval.realm,
+            proposal: approval.proposal,
+        });
+        emit_rent_refund(&approval.to_account_info(), ctx.accounts.owner.key());
+
+        Ok(())
+    }
+
+    /// Cast the owner's vote on an approved proposal through spl-governance,
+    /// signed by the agent wallet. The agent PDA signs the CPI as the
+    /// governance delegate of the owner's token owner record; the agent
+    /// wallet pays for the vote record.
+    pub fn cast_vote(ctx: Context<CastVote>, vote: GovernanceVote) -> Result<()> {
+        ctx.accounts.config.require_settlement_open()?;
+
+        let agent = &ctx.accounts.agent;
+        require!(
+            agent.permissions & Permission::CAN_VOTE != 0,
+            ErrorCode::InsufficientPermissions
+        );
+        agent.require_active()?;
+
+        // Only the owner's own voting power may be used
+        let governance_program = &ctx.accounts.governance_program;
+        let record = &ctx.accounts.voter_token_owner_record;
+        require_keys_eq!(*record.owner, governance_program.key(), ErrorCode::InvalidGovernanceAccounts);
+        let record_owner = record
+            .try_borrow_data()?
+            .get(TOKEN_OWNER_RECORD_OWNER_OFFSET..TOKEN_OWNER_RECORD_OWNER_OFFSET + 32)
+            .and_then(|bytes| Pubkey::try_from(bytes).ok())
+            .ok_or(ErrorCode::InvalidGovernanceAccounts)?;
+        require_keys_eq!(record_owner, agent.owner_wallet, ErrorCode::InvalidGovernanceAccounts);
+
+        let accounts = &ctx.accounts;
+        let mut metas = vec![
+            AccountMeta::new_readonly(accounts.realm.key(), false),
+            AccountMeta::new(accounts.governance.key(), false),
+            AccountMeta::new(accounts.proposal.key(), false),
+            AccountMeta::new(accounts.proposal_owner_record.key(), false),
+            AccountMeta::new(record.key(), false),
+            AccountMeta::new_readonly(agent.key(), true),
+            AccountMeta::new(accounts.vote_record.key(), false),
+            AccountMeta::new_readonly(accounts.governing_token_mint.key(), false),
+            AccountMeta::new(accounts.agent_wallet.key(), true),
+            AccountMeta::new_readonly(accounts.system_program.key(), false),
+            AccountMeta::new_readonly(accounts.realm_config.key(), false),
+        ];
+        let mut infos = vec![
+            accounts.realm.to_account_info(),
+            accounts.governance.to_account_info(),
+            accounts.proposal.to_account_info(),
+            accounts.proposal_owner_record.to_account_info(),
+            record.to_account_info(),
+            agent.to_account_info(),
+            accounts.vote_record.to_account_info(),
+            accounts.governing_token_mint.to_account_info(),
+            accounts.agent_wallet.to_account_info(),
+            accounts.system_program.to_account_info(),
+            accounts.realm_config.to_account_info(),
+        ];
+        for weight_record in [&accounts.voter_weight_record, &accounts.max_voter_weight_record]
+            .into_iter()
+            .flatten()
+        {
+            metas.push(AccountMeta::new_readonly(weight_record.key(), false));
+            infos.push(weight_record.to_account_info());
+        }
+        infos.push(governance_program.to_account_info());
+
+        let mut data = vec![SPL_GOVERNANCE_CAST_VOTE];
+        vote.serialize(&mut data)?;
+        let ix = Instruction {
+            program_id: governance_program.key(),
+            accounts: metas,
+            data,
+        };
+        let seeds = &[b"agent".as_ref(), agent.owner_wallet.as_ref(), &[agent.bump]];
+        invoke_signed(&ix, &infos, &[&seeds[..]])?;
+
+        emit!(VoteCast {
+            agent: agent.key(),
+            realm: accounts.realm.key(),
+            proposal: accounts.proposal.key(),
+            vote_record: accounts.vote_record.key(),
+            vote,
+            cast_at: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Bind a `.sol` domain the owner holds to their agent, so the agent can
+    /// be addressed as `name.sol`. `name` is the label without the `.sol`
+    /// suffix; the name record must be the one Name Service derives for it.
+    pub fn bind_name(ctx: Context<BindName>, name: String) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= MAX_SOL_NAME_LEN && !name.contains('.'),
+            ErrorCode::InvalidName
+        );
+        let name_record = &ctx.accounts.name_record;
+        require_keys_eq!(name_record.key(), sol_name_record(&name), ErrorCode::InvalidName);
+        require_keys_eq!(
+            name_record_owner(name_record)?,
+            ctx.accounts.owner.key(),
+            ErrorCode::NameNotOwned
+        );
+
+        let binding = &mut ctx.accounts.agent_name;
+        binding.agent = ctx.accounts.agent.key();
+        binding.owner = ctx.accounts.owner.key();
+        binding.name_record = name_record.key();
+        binding.name = name;
+        binding.bound_at = Clock::get()?.unix_timestamp;
+        binding.bump = ctx.bumps.agent_name;
+
+        emit!(NameBound {
+            agent: binding.agent,
+            name_record: binding.name_record,
+            name: binding.name.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Remove an agent's name binding. The owner may unbind at any time;
+    /// anyone may clear a binding whose domain has since changed hands.
+    pub fn unbind_name(ctx: Context<UnbindName>) -> Result<()> {
+        let binding = &ctx.accounts.agent_name;
+        if ctx.accounts.authority.key() != binding.owner {
+            let stale = ctx.accounts.name_record.data_is_empty()
+                || name_record_owner(&ctx.accounts.name_record)? != binding.owner;
+            require!(stale, ErrorCode::Unauthorized);
+        }
+
+        emit!(NameUnbound {
+            agent: binding.agent,
+            name_record: binding.name_record,
+            name: binding.name.clone(),
+        });
+        emit_rent_refund(&binding.to_account_info(), binding.owner);
+
+        Ok(())
+    }
+
+    /// Upgrade an agent identity written under an older account layout to
+    /// `AgentIdentity::VERSION`. Anyone may call it; the payer covers any
+    /// extra rent.
+    pub fn migrate_agent(ctx: Context<MigrateAccount>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let mut agent: AgentIdentity = load_for_migration(
+            &accounts.account,
+            AgentIdentity::LAYOUT_GROWTH,
+            &accounts.payer,
+            &accounts.system_program,
+        )?;
+        let from_version = migrated_from(agent.version, AgentIdentity::VERSION)?;
+        if from_version < 2 {
+            agent.reputation_since = 0;
+        }
+        if from_version < 3 {
+            agent.group = Pubkey::default();
+        }
+        if from_version < 4 {
+            agent.guardian = Pubkey::default();
+            agent.frozen = false;
+            agent.delegations_revoked_at = 0;
+        }
+        agent.version = AgentIdentity::VERSION;
+        store_migrated(&accounts.account, &agent)?;
+
+        emit!(AccountMigrated {
+            account: accounts.account.key(),
+            from_version,
+            to_version: agent.version,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a model profile to `ModelProfile::VERSION`, like `migrate_agent`
+    pub fn migrate_model_profile(ctx: Context<MigrateAccount>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let mut profile: ModelProfile = load_for_migration(
+            &accounts.account,
+            ModelProfile::LAYOUT_GROWTH,
+            &accounts.payer,
+            &accounts.system_program,
+        )?;
+        let from_version = migrated_from(profile.version, ModelProfile::VERSION)?;
+        profile.version = ModelProfile::VERSION;
+        store_migrated(&accounts.account, &profile)?;
+
+        emit!(AccountMigrated {
+            account: accounts.account.key(),
+            from_version,
+            to_version: profile.version,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade an intent to `AgentIntent::VERSION`, like `migrate_agent`
+    pub fn migrate_intent(ctx: Context<MigrateAccount>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let mut intent: AgentIntent = load_for_migration(
+            &accounts.account,
+            AgentIntent::LAYOUT_GROWTH,
+            &accounts.payer,
+            &accounts.system_program,
+        )?;
+        let from_version = migrated_from(intent.version, AgentIntent::VERSION)?;
+        if from_version < 2 {
+            intent.assignee_owner = Pubkey::default();
+        }
+        if from_version < 3 {
+            intent.template = Pubkey::default();
+            intent.completion_window = TimeSpan::default();
+            intent.required_permissions = 0;
+        }
+        if from_version < 4 {
+            intent.payout_group = Pubkey::default();
+        }
+        if from_version < 5 {
+            intent.result_signer = Pubkey::default();
+        }
+        if from_version < 6 {
+            intent.schema = Pubkey::default();
+        }
+        if from_version < 7 {
+            intent.attempt = 0;
+            intent.previous_attempt = Pubkey::default();
+            intent.retried_as = Pubkey::default();
+        }
+        if from_version < 8 {
+            intent.payout_mint = Pubkey::default();
+            intent.max_slippage_bps = 0;
+        }
+        if from_version < 9 {
+            intent.booking_credit = 0;
+        }
+        intent.version = AgentIntent::VERSION;
+        store_migrated(&accounts.account, &intent)?;
+
+        emit!(AccountMigrated {
+            account: accounts.account.key(),
+            from_version,
+            to_version: intent.version,
+        });
+
+        Ok(())
+    }
+}
+
+// === Helpers ===
+
+/// Read a program account of type `T` for a `migrate_*` instruction.
+/// Layouts only change by appending fields after `version`, so an account
+/// too short to hold the current one is first grown by `growth` bytes, the
+/// most any older layout lacks (rent topped up by `payer`).
+fn load_for_migration<'info, T: AccountDeserialize>(
+    account: &AccountInfo<'info>,
+    growth: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<T> {
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::Unauthorized);
+    if let Ok(loaded) = T::try_deserialize(&mut &account.try_borrow_data()?[..]) {
+        return Ok(loaded);
+    }
+
+    let space = account.data_len() + growth;
+    let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.to_account_info(), account.clone(), system_program.to_account_info()],
+        )?;
+    }
+    account.realloc(space, false)?;
+    T::try_deserialize(&mut &account.try_borrow_data()?[..])
+}
+
+/// Write a migrated account back in its current layout
+fn store_migrated<T: AccountSerialize>(account: &AccountInfo, migrated: &T) -> Result<()> {
+    let mut data = account.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    migrated.try_serialize(&mut writer)
+}
+
+/// The version an account is being migrated from. Accounts written before
+/// versioning may hold leftover string bytes where `version` now sits, so
+/// anything newer than `current` is version 0.
+fn migrated_from(version: u8, current: u8) -> Result<u8> {
+    require!(version != current, ErrorCode::AlreadyMigrated);
+    Ok(if version > current { 0 } else { version })
+}
+
+/// Move tokens out of an intent's funding account, signed by the payer or,
+/// when a delegate creates the intent, by the delegation PDA
+fn fund_intent<'info>(
+    from: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    payer: &Signer<'info>,
+    authority: FundingAuthority<'_, 'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_program = token_program.to_account_info();
+    let (seeds, authority) = match authority {
+        FundingAuthority::Payer => {
+            let cpi_accounts = Transfer {
+                from: from.to_account_info(),
+                to,
+                authority: payer.to_account_info(),
+            };
+            return token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount);
+        }
+        FundingAuthority::Delegation(delegation) => (
+            [
+                b"delegation".as_ref(),
+                delegation.grantor.as_ref(),
+                delegation.delegate.as_ref(),
+                std::slice::from_ref(&delegation.bump),
+            ],
+            delegation.to_account_info(),
+        ),
+        FundingAuthority::Budget(budget) => (
+            [
+                b"budget".as_ref(),
+                budget.agent.as_ref(),
+                budget.mint.as_ref(),
+                std::slice::from_ref(&budget.bump),
+            ],
+            budget.to_account_info(),
+        ),
+    };
+
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: from.to_account_info(),
+        to,
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)
+}
+
+/// How `open_intent` knows the from-agent stands behind an intent
+#[derive(PartialEq)]
+enum IntentAuthority {
+    /// The payer signs as the from-agent's owner or agent wallet, or as a
+    /// delegate's owner under the passed delegation
+    Payer,
+    /// Checked by the caller: an ed25519 signature by the agent wallet, or a
+    /// bridge message from the from-agent's remote owner
+    Verified,
+}
+
+/// Who signs for the tokens funding an intent
+enum FundingAuthority<'a, 'info> {
+    /// The transaction's payer, owner or delegate of the funding account
+    Payer,
+    /// The delegation PDA, approved on the grantor's funding account
+    Delegation(&'a Account<'info, Delegation>),
+    /// The budget PDA, owner of its vault
+    Budget(&'a Account<'info, AgentBudget>),
+}
+
+fn funding_authority<'a, 'info>(
+    delegation: Option<&'a Account<'info, Delegation>>,
+    budget: Option<&'a Account<'info, AgentBudget>>,
+) -> FundingAuthority<'a, 'info> {
+    match (budget, delegation) {
+        (Some(budget), _) => FundingAuthority::Budget(budget),
+        (None, Some(delegation)) => FundingAuthority::Delegation(delegation),
+        (None, None) => FundingAuthority::Payer,
+    }
+}
+
+/// Start the clock on an intent being accepted by `owner`: check the
+/// acceptance bond and set the cancellation and completion deadlines. A
+/// template's completion window takes precedence over the config's.
+fn accept_assignment(intent: &mut AgentIntent, config: &ProtocolConfig, clock: &Clock, owner: Pubkey) -> Result<()> {
+    require!(
+        intent.bond >= bps_of(intent.payment_amount, config.acceptance_bond_bps)?,
+        ErrorCode::BondRequired
+    );
+    intent.accepted_at = clock.unix_timestamp;
+    intent.cancel_deadline = config.cancellation_window.after(clock);
+    let completion_window = if intent.completion_window.is_zero() {
+        config.completion_window
+    } else {
+        intent.completion_window
+    };
+    if !completion_window.is_zero() {
+        intent.complete_deadline = completion_window.after(clock);
+    }
+    intent.assignee_owner = owner;
+    Ok(())
+}
+
+/// Pay the intent's tip, if any, to the provider taking the job
+fn pay_tip<'info>(
+    intent: &Account<'info, AgentIntent>,
+    escrow: Option<&Account<'info, TokenAccount>>,
+    billing: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+    agent: Pubkey,
+) -> Result<()> {
+    if intent.tip_amount == 0 {
+        return Ok(());
+    }
+    let escrow = escrow.ok_or(ErrorCode::EscrowAccountRequired)?;
+    let billing = billing.ok_or(ErrorCode::EscrowAccountRequired)?;
+    require!(intent.pays_into(billing), ErrorCode::InvalidGroupPayout);
+    transfer_from_escrow(intent, escrow, billing, token_program, intent.tip_amount)?;
+
+    emit!(TipPaid {
+        intent: intent.key(),
+        sequence: intent.sequence,
+        agent,
+        billing_account: billing.key(),
+        amount: intent.tip_amount,
+    });
+    Ok(())
+}
+
+/// Accounts a completed intent's payout draws on
+struct CompletionPayout<'a, 'info> {
+    escrow: &'a mut Account<'info, TokenAccount>,
+    billing: &'a mut Account<'info, TokenAccount>,
+    delegate_billing: Option<&'a Account<'info, TokenAccount>>,
+    fee_account: Option<&'a Account<'info, TokenAccount>>,
+    fee_allowlist: Option<&'a Account<'info, FeeAllowlist>>,
+    royalties: Option<&'a Account<'info, RoyaltySchedule>>,
+    /// Payer's account for the SLA penalty of a late completion
+    refund_account: Option<&'a Account<'info, TokenAccount>>,
+    payout_vault: Option<&'a mut Account<'info, PayoutVault>>,
+    token_program: &'a Program<'info, Token>,
+    /// Royalty recipients in declaration order, then any swap route accounts
+    remaining_accounts: &'info [AccountInfo<'info>],
+}
+
+/// Route the provider's payout through an allow-listed swap program into
+/// `payout_mint`, from the intent or the provider's profile
+struct PayoutSwap<'a, 'info> {
+    route: SwapRoute,
+    payout_mint: Pubkey,
+    max_slippage_bps: u16,
+    swap_program: &'a AccountInfo<'info>,
+}
+
+/// Resolve the swap for a settlement given a `route`: into the payout mint
+/// and bound set on the intent with `set_payout_swap`, else those of the
+/// provider's profile (`set_payout_preference`)
+fn payout_swap<'a, 'info>(
+    intent: &AgentIntent,
+    route: SwapRoute,
+    provider_profile: Pubkey,
+    model_profile: Option<&'a Account<'info, ModelProfile>>,
+    swap_program: Option<&'a AccountInfo<'info>>,
+) -> Result<PayoutSwap<'a, 'info>> {
+    let (payout_mint, max_slippage_bps) = if intent.payout_mint != Pubkey::default() {
+        (intent.payout_mint, intent.max_slippage_bps)
+    } else {
+        let profile = model_profile.ok_or(ErrorCode::InvalidSwapRoute)?;
+        require_keys_eq!(profile.key(), provider_profile, ErrorCode::InvalidModelProfile);
+        (profile.payout_mint, profile.max_slippage_bps)
+    };
+
+    Ok(PayoutSwap {
+        route,
+        payout_mint,
+        max_slippage_bps,
+        swap_program: swap_program.ok_or(ErrorCode::InvalidSwapRoute)?,
+    })
+}
+
+/// Pay out a just-completed intent's escrow: the SLA penalty back to the
+/// payer, then the protocol fee, royalties, the provider's payout (swapped
+/// if asked) and the delegate's share. Auto-release intents only lock in
+/// the destinations and fee here and pay out after the challenge window.
+fn release_on_completion<'info>(
+    intent: &mut Account<'info, AgentIntent>,
+    config: &ProtocolConfig,
+    payout: CompletionPayout<'_, 'info>,
+    swap: Option<PayoutSwap<'_, 'info>>,
+    clock: &Clock,
+) -> Result<()> {
+    let CompletionPayout {
+        escrow,
+        billing,
+        delegate_billing,
+        fee_account,
+        fee_allowlist,
+        royalties: royalty_schedule,
+        refund_account,
+        payout_vault,
+        token_program,
+        remaining_accounts,
+    } = payout;
+    require!(intent.pays_into(billing), ErrorCode::InvalidGroupPayout);
+    // Re-delegated intents pay the accounts fixed at redelegation
+    if intent.delegate_agent != Pubkey::default() {
+        require_keys_eq!(billing.key(), intent.release_account, ErrorCode::Unauthorized);
+        let delegate_billing = delegate_billing.ok_or(ErrorCode::EscrowAccountRequired)?;
+        require_keys_eq!(
+            delegate_billing.key(),
+            intent.delegate_release_account,
+            ErrorCode::Unauthorized
+        );
+    }
+
+    // A late completion forfeits the accrued SLA penalty to the payer
+    let penalty = sla_penalty(intent, clock.unix_timestamp)?;
+    if penalty > 0 {
+        let refund_account = refund_account.ok_or(ErrorCode::EscrowAccountRequired)?;
+        require_keys_eq!(refund_account.owner, intent.payer, ErrorCode::Unauthorized);
+        transfer_from_escrow(intent, escrow, refund_account, token_program, penalty)?;
+        intent.payment_amount -= penalty;
+
+        emit!(SlaPenaltyApplied {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            late_by: clock.unix_timestamp - intent.accepted_at - intent.sla_target,
+            penalty,
+            refund_account: refund_account.key(),
+        });
+    }
+
+    // Protocol fee comes off the top, reduced for allow-listed partners
+    let discount_bps = match fee_allowlist {
+        Some(entry) if entry.applies_to(intent) => entry.discount_bps,
+        Some(_) => return err!(ErrorCode::InvalidFeeAllowlist),
+        None => 0,
+    };
+    let fee_bps = bps_of(
+        config.protocol_fee_bps as u64,
+        BPS_DENOMINATOR - discount_bps,
+    )? as u16;
+
+    // Auto-release intents lock in the destinations and fee now and
+    // pay out once the challenge window has passed
+    if intent.options & IntentOption::AUTO_RELEASE != 0 {
+        require!(swap.is_none(), ErrorCode::InvalidSwapRoute);
+        intent.release_account = billing.key();
+        intent.delegate_release_account = if intent.delegate_agent != Pubkey::default() {
+            delegate_billing.ok_or(ErrorCode::EscrowAccountRequired)?.key()
+        } else {
+            Pubkey::default()
+        };
+        intent.release_fee_bps = fee_bps;
+        intent.release_deadline = config.challenge_window.after(clock);
+
+        emit!(ReleaseScheduled {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            release_account: intent.release_account,
+            release_deadline: intent.release_deadline,
+        });
+        return Ok(());
+    }
+
+    let (fee, delegate_amount, mut provider_amount) = split_payment(intent, fee_bps)?;
+    if fee > 0 {
+        let fee_account = fee_account.ok_or(ErrorCode::EscrowAccountRequired)?;
+        require_keys_eq!(fee_account.owner, config.fee_treasury, ErrorCode::Unauthorized);
+        transfer_from_escrow(intent, escrow, fee_account, token_program, fee)?;
+    }
+
+    // Declared royalties come out of the provider's share. Recipient
+    // token accounts lead the remaining accounts in declaration order;
+    // any swap route accounts follow them.
+    let mut route_accounts = remaining_accounts;
+    let mut royalties = 0;
+    if intent.royalty_bps > 0 {
+        let schedule = royalty_schedule.ok_or(ErrorCode::InvalidRoyaltySplits)?;
+        require!(
+            remaining_accounts.len() >= schedule.splits.len(),
+            ErrorCode::InvalidRoyaltySplits
+        );
+        let (royalty_accounts, rest) = remaining_accounts.split_at(schedule.splits.len());
+        route_accounts = rest;
+
+        for (split, info) in schedule.splits.iter().zip(royalty_accounts) {
+            let recipient = Account::<TokenAccount>::try_from(info)?;
+            require!(
+                recipient.owner == split.recipient && recipient.mint == intent.payment_mint,
+                ErrorCode::InvalidRoyaltySplits
+            );
+            let amount = bps_of(provider_amount, split.bps)?;
+            if amount > 0 {
+                transfer_from_escrow(intent, escrow, &recipient, token_program, amount)?;
+                royalties += amount;
+            }
+        }
+        provider_amount -= royalties;
+    }
+
+    if provider_amount > 0 {
+        match swap {
+            Some(PayoutSwap {
+                route,
+                payout_mint,
+                max_slippage_bps,
+                swap_program,
+            }) => {
+                require!(
+                    intent.delegate_agent == Pubkey::default(),
+                    ErrorCode::InvalidSwapRoute
+                );
+                require!(
+                    payout_mint != Pubkey::default() && billing.mint == payout_mint,
+                    ErrorCode::InvalidSwapRoute
+                );
+                require!(
+                    config.is_swap_program(swap_program.key),
+                    ErrorCode::SwapProgramNotAllowed
+                );
+                // The settler is the assignee's owner, the only party a poor
+                // quote shortchanges
+                let min_out = bps_of(route.quoted_out, BPS_DENOMINATOR - max_slippage_bps)?;
+
+                let escrow_before = escrow.amount;
+                let billing_before = billing.amount;
+
+                // The intent PDA signs for the escrow it owns
+                let intent_key = intent.key();
+                let accounts = route_accounts
+                    .iter()
+                    .map(|a| AccountMeta {
+                        pubkey: *a.key,
+                        is_signer: a.is_signer || *a.key == intent_key,
+                        is_writable: a.is_writable,
+                    })
+                    .collect();
+                let ix = Instruction {
+                    program_id: *swap_program.key,
+                    accounts,
+                    data: route.data,
+                };
+                let mut infos = route_accounts.to_vec();
+                infos.push(intent.to_account_info());
+                infos.push(swap_program.to_account_info());
+
+                let nonce = intent.nonce.to_le_bytes();
+                let seeds = &[
+                    b"intent".as_ref(),
+                    intent.from_agent.as_ref(),
+                    intent.to_agent.as_ref(),
+                    &nonce,
+                    &[intent.bump],
+                ];
+                invoke_signed(&ix, &infos, &[&seeds[..]])?;
+
+                escrow.reload()?;
+                billing.reload()?;
+                require!(
+                    escrow_before.checked_sub(escrow.amount) == Some(provider_amount),
+                    ErrorCode::InvalidSwapRoute
+                );
+                require!(
+                    billing.amount.saturating_sub(billing_before) >= min_out,
+                    ErrorCode::SlippageExceeded
+                );
+            }
+            None => {
+                // A payout mint set on the intent is only reached through a route
+                require!(intent.payout_mint == Pubkey::default(), ErrorCode::InvalidSwapRoute);
+                transfer_from_escrow(intent, escrow, billing, token_program, provider_amount)?
+            }
+        }
+
+        // Payouts into a payout vault accrue until the next sweep
+        if let Some(payout_vault) = payout_vault {
+            require_keys_eq!(billing.key(), payout_vault.vault, ErrorCode::InvalidPayoutVault);
+            payout_vault.accrued = payout_vault
+                .accrued
+                .checked_add(provider_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+    }
+    if delegate_amount > 0 {
+        let delegate_billing = delegate_billing.ok_or(ErrorCode::EscrowAccountRequired)?;
+        transfer_from_escrow(intent, escrow, delegate_billing, token_program, delegate_amount)?;
+    }
+    intent.released = true;
+
+    emit!(EscrowReleased {
+        intent: intent.key(),
+        sequence: intent.sequence,
+        provider_account: billing.key(),
+        payout_mint: billing.mint,
+        provider_amount,
+        delegate_amount,
+        protocol_fee: fee,
+        royalties,
+        released_at: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Complete and pay out an accepted intent for `settle_intent`, through a
+/// swap into the payout mint when `route` is given
+fn settle_accepted_intent<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SettleIntent<'info>>,
+    route: Option<SwapRoute>,
+) -> Result<()> {
+    ctx.accounts.config.require_settlement_open()?;
+    ctx.accounts.assignee_agent.require_active()?;
+
+    let intent = &mut ctx.accounts.intent;
+    let clock = Clock::get()?;
+    require!(
+        intent.status == IntentStatus::Accepted as u8,
+        ErrorCode::InvalidStatusTransition
+    );
+    require!(intent.team_size == 0, ErrorCode::TeamUnsupported);
+    intent.result_commitment().require_known_scheme()?;
+    intent.require_attested_result()?;
+
+    let previous_status = intent.status;
+    intent.status = IntentStatus::Completed as u8;
+    intent.completed_at = clock.unix_timestamp;
+    intent.touch(clock.unix_timestamp);
+
+    let swap = route
+        .map(|route| {
+            payout_swap(
+                intent,
+                route,
+                ctx.accounts.assignee_agent.model_profile,
+                ctx.accounts.model_profile.as_ref(),
+                ctx.accounts.swap_program.as_ref(),
+            )
+        })
+        .transpose()?;
+    if intent.payment_amount > 0 {
+        release_on_completion(
+            intent,
+            &ctx.accounts.config,
+            CompletionPayout {
+                escrow: ctx
+                    .accounts
+                    .escrow_token_account
+                    .as_mut()
+                    .ok_or(ErrorCode::EscrowAccountRequired)?,
+                billing: ctx
+                    .accounts
+                    .billing_token_account
+                    .as_mut()
+                    .ok_or(ErrorCode::EscrowAccountRequired)?,
+                delegate_billing: ctx.accounts.delegate_billing_token_account.as_ref(),
+                fee_account: ctx.accounts.fee_token_account.as_ref(),
+                fee_allowlist: ctx.accounts.fee_allowlist.as_ref(),
+                royalties: ctx.accounts.royalties.as_ref(),
+                refund_account: ctx.accounts.refund_token_account.as_ref(),
+                payout_vault: ctx.accounts.payout_vault.as_mut(),
+                token_program: &ctx.accounts.token_program,
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            swap,
+            &clock,
+        )?;
+    }
+
+    emit!(IntentStatusUpdated {
+        intent: intent.key(),
+        sequence: intent.sequence,
+        nonce: intent.nonce,
+        previous_status,
+        status: intent.status,
+        result_hash: intent.result_hash,
+        result_uri: intent.result_uri.clone(),
+        updated_at: intent.updated_at,
+    });
+    record_transition(intent, previous_status, ctx.accounts.owner.key());
+    ctx.accounts
+        .assignee_stats
+        .record_outcome(intent, previous_status, clock.unix_timestamp)?;
+
+    Ok(())
+}
+
+/// Write a profile's current contents to `snapshot` as its next version
+fn snapshot_contents<'info>(
+    profile: &mut Account<'info, ModelProfile>,
+    snapshot: &mut Account<'info, ProfileVersion>,
+    bump: u8,
+    now: i64,
+) -> Result<()> {
+    let version = profile
+        .latest_version
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    snapshot.model_profile = profile.key();
+    snapshot.version = version;
+    snapshot.label = profile.label.clone();
+    snapshot.provider_uri = profile.provider_uri.clone();
+    snapshot.pricing = profile.pricing;
+    snapshot.billing_wallet = profile.billing_wallet;
+    snapshot.max_tokens_per_day = profile.max_tokens_per_day;
+    snapshot.max_requests_per_min = profile.max_requests_per_min;
+    snapshot.created_at = now;
+    snapshot.bump = bump;
+
+    profile.latest_version = version;
+    profile.snapshot_current = true;
+
+    emit!(ProfileSnapshotted {
+        profile: profile.key(),
+        version,
+        pricing: snapshot.pricing,
+    });
+
+    Ok(())
+}
+
+/// The booking an intent drew its deposit from, to take back a refund
+struct BookingRefund<'a, 'info> {
+    booking: &'a mut Account<'info, CapacityBooking>,
+    vault: &'a Account<'info, TokenAccount>,
+}
+
+/// Pair a context's optional booking accounts, if both were passed
+fn booking_refund<'a, 'info>(
+    booking: Option<&'a mut Account<'info, CapacityBooking>>,
+    vault: Option<&'a Account<'info, TokenAccount>>,
+) -> Option<BookingRefund<'a, 'info>> {
+    Some(BookingRefund {
+        booking: booking?,
+        vault: vault?,
+    })
+}
+
+/// Refund `amount` out of an intent's escrow. The part the booking deposit
+/// funded goes back to the booking's vault, restoring its deposit; the rest
+/// goes to the payer's `refund_account`. Returns what the payer got.
+fn refund_escrow<'info>(
+    intent: &mut Account<'info, AgentIntent>,
+    escrow: &Account<'info, TokenAccount>,
+    refund_account: Option<&Account<'info, TokenAccount>>,
+    booking: Option<BookingRefund<'_, 'info>>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<u64> {
+    let credit = amount.min(intent.booking_credit);
+    if credit > 0 {
+        let BookingRefund { booking, vault } = booking.ok_or(ErrorCode::InvalidBooking)?;
+        require_keys_eq!(booking.key(), intent.booking, ErrorCode::InvalidBooking);
+        require_keys_eq!(vault.key(), booking.vault, ErrorCode::InvalidBooking);
+        transfer_from_escrow(intent, escrow, vault, token_program, credit)?;
+        booking.deposit_remaining = booking
+            .deposit_remaining
+            .checked_add(credit)
+            .ok_or(ErrorCode::MathOverflow)?;
+        intent.booking_credit -= credit;
+    }
+
+    let refund = amount - credit;
+    if refund > 0 {
+        let refund_account = refund_account.ok_or(ErrorCode::EscrowAccountRequired)?;
+        require_keys_eq!(refund_account.owner, intent.payer, ErrorCode::Unauthorized);
+        transfer_from_escrow(intent, escrow, refund_account, token_program, refund)?;
+    }
+    Ok(refund)
+}
+
+/// Fail a timed-out intent and refund its escrow to the payer's
+/// `refund_account` (and any booking-funded part to the booking); returns
+/// the payer's refund
+fn fail_timed_out_intent<'info>(
+    intent: &mut Account<'info, AgentIntent>,
+    escrow: &Account<'info, TokenAccount>,
+    refund_account: &Account<'info, TokenAccount>,
+    booking: Option<BookingRefund<'_, 'info>>,
+    token_program: &Program<'info, Token>,
+    now: i64,
+) -> Result<u64> {
+    intent.status = IntentStatus::Failed as u8;
+    intent.touch(now);
+
+    let refund = refund_escrow(intent, escrow, Some(refund_account), booking, token_program, escrow.amount)?;
+    if refund > 0 {
+        emit!(EscrowRefunded {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            payer: intent.payer,
+            refund_account: refund_account.key(),
+            amount: refund,
+            refunded_at: now,
+        });
+    }
+
+    Ok(refund)
+}
+
+/// Pay out the auto-release intents in a batch of
+/// `[intent, escrow, release_account, delegate_release_account]` groups
+/// whose challenge window has passed; returns how many were released
+fn release_ready_batch<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    config: &ProtocolConfig,
+    fee_account: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+) -> Result<u32> {
+    let groups = remaining_accounts.chunks_exact(4);
+    require!(
+        groups.remainder().is_empty() && groups.len() <= MAX_BATCH_RELEASES,
+        ErrorCode::InvalidReleaseBatch
+    );
+    if let Some(fee_account) = fee_account {
+        require_keys_eq!(fee_account.owner, config.fee_treasury, ErrorCode::Unauthorized);
+    }
+
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let mut released = 0u32;
+    for group in groups {
+        let mut intent = Account::<AgentIntent>::try_from(&group[0])?;
+        if !intent.release_pending()
+            || intent.disputed
+            || !intent.release_deadline.passed(&clock, config)
+        {
+            continue;
+        }
+
+        let escrow = Account::<TokenAccount>::try_from(&group[1])?;
+        let billing = Account::<TokenAccount>::try_from(&group[2])?;
+        require!(
+            escrow.owner == intent.key() && escrow.mint == intent.payment_mint,
+            ErrorCode::InvalidEscrowAccount
+        );
+        require_keys_eq!(billing.key(), intent.release_account, ErrorCode::InvalidReleaseBatch);
+
+        let (fee, delegate_amount, provider_amount) =
+            split_payment(&intent, intent.release_fee_bps)?;
+        if fee > 0 {
+            let fee_account = fee_account.ok_or(ErrorCode::EscrowAccountRequired)?;
+            transfer_from_escrow(&intent, &escrow, fee_account, token_program, fee)?;
+        }
+        if provider_amount > 0 {
+            transfer_from_escrow(&intent, &escrow, &billing, token_program, provider_amount)?;
+        }
+        if delegate_amount > 0 {
+            let delegate_billing = Account::<TokenAccount>::try_from(&group[3])?;
+            require_keys_eq!(
+                delegate_billing.key(),
+                intent.delegate_release_account,
+                ErrorCode::InvalidReleaseBatch
+            );
+            transfer_from_escrow(&intent, &escrow, &delegate_billing, token_program, delegate_amount)?;
+        }
+
+        intent.released = true;
+        intent.touch(now);
+        intent.exit(&crate::ID)?;
+        released += 1;
+
+        emit!(EscrowReleased {
+            intent: intent.key(),
+            sequence: intent.sequence,
+            provider_account: billing.key(),
+            payout_mint: billing.mint,
+            provider_amount,
+            delegate_amount,
+            protocol_fee: fee,
+            royalties: 0,
+            released_at: now,
+        });
+    }
+
+    Ok(released)
+}
+
+/// Tip the cranker `crank_tip_lamports` per processed item, as far as the
+/// treasury's balance above its rent reserve goes
+fn pay_crank_tips(accounts: &mut ProcessCrank, processed: u32) -> Result<()> {
+    let treasury = accounts.crank_treasury.to_account_info();
+    let reserve = Rent::get()?.minimum_balance(treasury.data_len());
+    let lamports = accounts
+        .config
+        .crank_tip_lamports
+        .saturating_mul(processed as u64)
+        .min(treasury.lamports().saturating_sub(reserve));
+    if lamports > 0 {
+        **treasury.try_borrow_mut_lamports()? -= lamports;
+        **accounts.cranker.to_account_info().try_borrow_mut_lamports()? += lamports;
+        accounts.crank_treasury.paid = accounts.crank_treasury.paid.saturating_add(lamports);
+    }
+
+    emit!(CrankTipsPaid {
+        cranker: accounts.cranker.key(),
+        processed,
+        lamports,
+    });
+
+    Ok(())
+}
+
+/// Log the rent an account hands back when it closes to `recipient`
+fn emit_rent_refund(account: &AccountInfo, recipient: Pubkey) {
+    emit!(RentRefunded {
+        account: account.key(),
+        recipient,
+        lamports: account.lamports(),
+    });
+}
+
+/// Write the registry's next schema version into `schema`
+fn publish_schema<'info>(
+    schema: &mut Account<'info, PayloadSchema>,
+    registry: &mut Account<'info, SchemaRegistry>,
+    bump: u8,
+    config: &ProtocolConfig,
+    schema_hash: [u8; 32],
+    media_type: String,
+    schema_uri: String,
+) -> Result<()> {
+    config.require_uri_len(&schema_uri)?;
+    require!(
+        !media_type.is_empty() && media_type.len() <= MAX_MEDIA_TYPE_LEN,
+        ErrorCode::InvalidSchema
+    );
+
+    let version = registry.next_version();
+    registry.latest_version = version;
+
+    schema.registry = registry.key();
+    schema.version = version;
+    schema.schema_hash = schema_hash;
+    schema.media_type = media_type;
+    schema.schema_uri = schema_uri;
+    schema.deprecated = false;
+    schema.published_at = Clock::get()?.unix_timestamp;
+    schema.bump = bump;
+
+    emit!(SchemaPublished {
+        schema: schema.key(),
+        schema_id: registry.schema_id,
+        version,
+        agent: registry.agent,
+        schema_hash,
+        media_type: schema.media_type.clone(),
+        schema_uri: schema.schema_uri.clone(),
+    });
+
+    Ok(())
+}
+
+/// Mint the agent's identity badge to its agent wallet: a one-of-one Token
+/// Metadata NFT whose verified creator and update authority is the agent PDA,
+/// so wallets can display it and anyone can check it against the registry.
+/// The holding account is frozen before the master edition takes over the
+/// freeze authority, which nobody can thaw, so the badge is soulbound.
+fn mint_identity_badge(accounts: &RegisterAgent, badge_bump: u8) -> Result<()> {
+    let (
+        Some(agent_wallet),
+        Some(mint),
+        Some(token_account),
+        Some(metadata),
+        Some(edition),
+        Some(token_program),
+        Some(associated_token_program),
+        Some(metadata_program),
+    ) = (
+        &accounts.agent_wallet,
+        &accounts.badge_mint,
+        &accounts.badge_token_account,
+        &accounts.badge_metadata,
+        &accounts.badge_edition,
+        &accounts.token_program,
+        &accounts.associated_token_program,
+        &accounts.token_metadata_program,
+    )
+    else {
+        return err!(ErrorCode::InvalidBadgeAccounts);
+    };
+    let agent = &accounts.agent;
+    let owner = &accounts.owner;
+    let system_program = &accounts.system_program;
+    let agent_key = agent.key();
+    let agent_seeds = &[b"agent".as_ref(), owner.key.as_ref(), &[agent.bump]];
+    let mint_seeds = &[b"badge".as_ref(), agent_key.as_ref(), &[badge_bump]];
+
+    invoke_signed(
+        &system_instruction::create_account(
+            owner.key,
+            mint.key,
+            Rent::get()?.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &token::ID,
+        ),
+        &[owner.to_account_info(), mint.to_account_info()],
+        &[&mint_seeds[..]],
+    )?;
+    token::initialize_mint2(
+        CpiContext::new(
+            token_program.to_account_info(),
+            token::InitializeMint2 { mint: mint.to_account_info() },
+        ),
+        0,
+        &agent_key,
+        Some(&agent_key),
+    )?;
+    associated_token::create(CpiContext::new(
+        associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: owner.to_account_info(),
+            associated_token: token_account.to_account_info(),
+            authority: agent_wallet.to_account_info(),
+            mint: mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        },
+    ))?;
+    let signer = &[&agent_seeds[..]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::MintTo {
+                mint: mint.to_account_info(),
+                to: token_account.to_account_info(),
+                authority: agent.to_account_info(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    token::freeze_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::FreezeAccount {
+            account: token_account.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: agent.to_account_info(),
+        },
+        signer,
+    ))?;
+
+    let mut data = vec![TOKEN_METADATA_CREATE_METADATA_V3];
+    BadgeMetadataArgs {
+        name: format!("Mesh Agent {}", &agent_key.to_string()[..8]),
+        symbol: BADGE_SYMBOL.to_string(),
+        uri: agent.metadata_uri.clone(),
+        seller_fee_basis_points: 0,
+        creators: Some(vec![BadgeCreator {
+            address: agent_key,
+            verified: true,
+            share: 100,
+        }]),
+        collection: None,
+        uses: None,
+        is_mutable: true,
+        collection_details: None,
+    }
+    .serialize(&mut data)?;
+    invoke_signed(
+        &Instruction {
+            program_id: metadata_program.key(),
+            accounts: vec![
+                AccountMeta::new(metadata.key(), false),
+                AccountMeta::new_readonly(mint.key(), false),
+                AccountMeta::new_readonly(agent_key, true),
+                AccountMeta::new(owner.key(), true),
+                AccountMeta::new_readonly(agent_key, true),
+                AccountMeta::new_readonly(system_program.key(), false),
+            ],
+            data,
+        },
+        &[
+            metadata.to_account_info(),
+            mint.to_account_info(),
+            agent.to_account_info(),
+            owner.to_account_info(),
+            system_program.to_account_info(),
+            metadata_program.to_account_info(),
+        ],
+        signer,
+    )?;
+
+    // A max supply of zero: no prints, so the badge stays one-of-one
+    let mut data = vec![TOKEN_METADATA_CREATE_MASTER_EDITION_V3];
+    Some(0u64).serialize(&mut data)?;
+    invoke_signed(
+        &Instruction {
+            program_id: metadata_program.key(),
+            accounts: vec![
+                AccountMeta::new(edition.key(), false),
+                AccountMeta::new(mint.key(), false),
+                AccountMeta::new_readonly(agent_key, true),
+                AccountMeta::new_readonly(agent_key, true),
+                AccountMeta::new(owner.key(), true),
+                AccountMeta::new(metadata.key(), false),
+                AccountMeta::new_readonly(token_program.key(), false),
+                AccountMeta::new_readonly(system_program.key(), false),
+            ],
+            data,
+        },
+        &[
+            edition.to_account_info(),
+            mint.to_account_info(),
+            agent.to_account_info(),
+            owner.to_account_info(),
+            metadata.to_account_info(),
+            token_program.to_account_info(),
+            system_program.to_account_info(),
+            metadata_program.to_account_info(),
+        ],
+        signer,
+    )?;
+
+    emit!(IdentityBadgeMinted {
+        agent: agent_key,
+        agent_wallet: agent_wallet.key(),
+        mint: mint.key(),
+        metadata: metadata.key(),
+    });
+
+    Ok(())
+}
+
+/// Name Service record of `name.sol`: hashed as Name Service hashes names,
+/// with no class and the `.sol` TLD as parent
+fn sol_name_record(name: &str) -> Pubkey {
+    let hashed = hashv(&[NAME_SERVICE_HASH_PREFIX.as_bytes(), name.as_bytes()]);
+    Pubkey::find_program_address(
+        &[hashed.as_ref(), Pubkey::default().as_ref(), SOL_TLD_AUTHORITY.as_ref()],
+        &NAME_SERVICE_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Current holder of a Name Service record
+fn name_record_owner(record: &AccountInfo) -> Result<Pubkey> {
+    require_keys_eq!(*record.owner, NAME_SERVICE_PROGRAM_ID, ErrorCode::InvalidName);
+    record
+        .try_borrow_data()?
+        .get(NAME_RECORD_OWNER_OFFSET..NAME_RECORD_OWNER_OFFSET + 32)
+        .and_then(|bytes| Pubkey::try_from(bytes).ok())
+        .ok_or_else(|| error!(ErrorCode::InvalidName))
+}
+
+/// Initialize and fund an intent; shared by `create_intent`,
+/// `submit_signed_intent` and `settle_x402_payment`
+fn open_intent(
+    ctx: &mut Context<CreateIntent>,
+    payload: ContentCommitment,
+    payload_uri: String,
+    payment_amount: u64,
+    options: u8,
+    tip_amount: u64,
+    authority: IntentAuthority,
+) -> Result<()> {
+    ctx.accounts.config.require_accepting_intents()?;
+    ctx.accounts.config.require_uri_len(&payload_uri)?;
+    payload.require_known_scheme()?;
+
+    let config = &ctx.accounts.config;
+    let insured = options & IntentOption::INSURED != 0;
+    let insurance_premium = if insured {
+        bps_of(payment_amount, config.insurance_premium_bps)?
+    } else {
+        0
+    };
+    let coverage_bps = if insured {
+        config.insured_coverage_bps
+    } else {
+        config.base_coverage_bps
+    };
+    let reimbursement_cap = bps_of(payment_amount, coverage_bps)?;
+
+    let intent = &mut ctx.accounts.intent;
+    let clock = Clock::get()?;
+
+    // Verify from_agent has CAN_CREATE_INTENT permission
+    require!(
+        ctx.accounts.from_agent.permissions & Permission::CAN_CREATE_INTENT != 0,
+        ErrorCode::InsufficientPermissions
+    );
+    ctx.accounts.from_agent.require_active()?;
+    // The payer speaks for the from-agent as its owner or agent wallet, or
+    // as a delegate's owner under a delegation that allows it
+    let payer = ctx.accounts.payer.key();
+    match &ctx.accounts.delegation {
+        Some(delegation) => {
+            let delegate_agent = ctx
+                .accounts
+                .delegate_agent
+                .as_ref()
+                .ok_or(ErrorCode::InvalidDelegation)?;
+            require_keys_eq!(delegation.delegate, delegate_agent.key(), ErrorCode::InvalidDelegation);
+            require_keys_eq!(payer, delegate_agent.owner_wallet, ErrorCode::Unauthorized);
+            delegate_agent.require_active()?;
+            require!(
+                delegation.allows(&ctx.accounts.from_agent, Permission::CAN_CREATE_INTENT, &clock, config),
+                ErrorCode::InvalidDelegation
+            );
+        }
+        None => require!(
+            authority == IntentAuthority::Verified
+                || payer == ctx.accounts.from_agent.owner_wallet
+                || payer == ctx.accounts.from_agent.agent_wallet,
+            ErrorCode::Unauthorized
+        ),
+    }
+    // Only now is the from-agent's bucket ours to draw on
+    ctx.accounts
+        .rate_limit
+        .record(ctx.accounts.from_agent.key(), ctx.bumps.rate_limit, &clock, config)?;
+    // Open intents are addressed to the requester until a bid wins, so the
+    // provider checks happen in place_bid instead
+    let open = options & IntentOption::OPEN_BIDDING != 0;
+    if open {
+        require!(
+            ctx.accounts.to_agent.key() == ctx.accounts.from_agent.key()
+                && ctx.accounts.trial.is_none()
+                && ctx.accounts.booking.is_none()
+                && ctx.accounts.profile_version.is_none(),
+            ErrorCode::InvalidBid
+        );
+        // The requester's stats already come in as from_stats
+        require!(ctx.accounts.to_stats.is_none(), ErrorCode::InvalidAgentStats);
+    } else {
+        require!(ctx.accounts.to_stats.is_some(), ErrorCode::InvalidAgentStats);
+        require!(
+            ctx.accounts.to_agent.has_capacity(clock.unix_timestamp),
+            ErrorCode::ProviderAtCapacity
+        );
+        ctx.accounts.to_agent.require_active()?;
+        if let Some(template) = &ctx.accounts.template {
+            require!(
+                template.permits(&ctx.accounts.to_agent),
+                ErrorCode::InsufficientPermissions
+            );
+        }
+        require!(
+            payment_amount >= ctx.accounts.to_agent.min_payment || ctx.accounts.trial.is_some(),
+            ErrorCode::PaymentBelowMinimum
+        );
+    }
+
+    intent.from_agent = ctx.accounts.from_agent.key();
+    intent.to_agent = ctx.accounts.to_agent.key();
+    // Refunds go to whoever owns the funding account: the payer itself, or
+    // an agent that delegated the tokens to a relayer
+    intent.payer = ctx.accounts.from_token_account.owner;
+    intent.rent_payer = ctx.accounts.payer.key();
+    let counter = &mut ctx.accounts.nonce_counter;
+    if counter.from_agent == Pubkey::default() {
+        counter.from_agent = intent.from_agent;
+        counter.to_agent = intent.to_agent;
+        counter.bump = ctx.bumps.nonce_counter;
+    }
+    intent.nonce = counter.next;
+    counter.next = counter.next.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    intent.status = IntentStatus::Pending as u8;
+    intent.payload_hash = payload.hash;
+    intent.payload_scheme = payload.scheme;
+    intent.payload_length = payload.length;
+    intent.payload_uri = payload_uri;
+    intent.sealed_to = [0u8; 32];
+    intent.ephemeral_pubkey = [0u8; 32];
+    intent.encrypted_key = [0u8; 32];
+    intent.payment_amount = payment_amount;
+    intent.payment_mint = ctx.accounts.payment_mint.key();
+    intent.tip_amount = tip_amount;
+    intent.options = options;
+    intent.delegate_agent = Pubkey::default();
+    intent.delegate_share_bps = 0;
+    intent.insurance_premium = insurance_premium;
+    intent.reimbursement_cap = reimbursement_cap;
+    intent.profile_version = match &ctx.accounts.profile_version {
+        Some(version) => {
+            require_keys_eq!(
+                version.model_profile,
+                ctx.accounts.to_agent.model_profile,
+                ErrorCode::InvalidModelProfile
+            );
+            // An older version may be pinned only if it's no cheaper than
+            // the profile's current price
+            let profile = ctx
+                .accounts
+                .model_profile
+                .as_ref()
+                .ok_or(ErrorCode::InvalidModelProfile)?;
+            require_keys_eq!(profile.key(), version.model_profile, ErrorCode::InvalidModelProfile);
+            require!(version.pricing >= profile.pricing, ErrorCode::StaleProfileVersion);
+            version.version
+        }
+        None => 0,
+    };
+    intent.booking = Pubkey::default();
+    intent.booking_credit = 0;
+    intent.trial = Pubkey::default();
+    intent.context = ctx
+        .accounts
+        .context
+        .as_ref()
+        .map_or(Pubkey::default(), |context| context.key());
+    intent.schema = match &ctx.accounts.schema {
+        Some(schema) => {
+            require!(!schema.deprecated, ErrorCode::DeprecatedSchema);
+            // A template already fixes the payload schema by hash
+            if let Some(template) = &ctx.accounts.template {
+                require!(template.schema_hash == schema.schema_hash, ErrorCode::InvalidSchema);
+            }
+            schema.key()
+        }
+        None => Pubkey::default(),
+    };
+    intent.royalty_bps = 0;
+    intent.team_size = 0;
+    intent.result_hash = [0u8; 32];
+    intent.result_scheme = 0;
+    intent.result_length = 0;
+    intent.result_uri = String::new();
+    intent.created_at = clock.unix_timestamp;
+    intent.updated_at = clock.unix_timestamp;
+    intent.accepted_at = 0;
+    intent.completed_at = 0;
+    intent.cancel_deadline = Deadline::default();
+    intent.complete_deadline = Deadline::default();
+    intent.bid_deadline = if open {
+        config.bidding_window.after(&clock)
+    } else {
+        Deadline::default()
+    };
+    intent.release_deadline = Deadline::default();
+    intent.released = false;
+    intent.disputed = false;
+    intent.release_account = Pubkey::default();
+    intent.delegate_release_account = Pubkey::default();
+    intent.release_fee_bps = 0;
+    intent.sla_target = 0;
+    intent.sla_penalty_bps = 0;
+    intent.bond = 0;
+    intent.bond_payer = Pubkey::default();
+    intent.winning_agent = Pubkey::default();
+    intent.best_bid = Pubkey::default();
+    intent.best_bid_price = 0;
+    intent.transitions = 0;
+    intent.sequence = 0;
+    intent.bump = ctx.bumps.intent;
+    intent.version = AgentIntent::VERSION;
+    intent.assignee_owner = Pubkey::default();
+    intent.payout_group = Pubkey::default();
+    intent.result_signer = Pubkey::default();
+    intent.attempt = 0;
+    intent.previous_attempt = Pubkey::default();
+    intent.retried_as = Pubkey::default();
+    intent.payout_mint = Pubkey::default();
+    intent.max_slippage_bps = 0;
+    match &ctx.accounts.template {
+        Some(template) => {
+            intent.template = template.key();
+            intent.completion_window = template.completion_window;
+            intent.required_permissions = template.required_permissions;
+        }
+        None => {
+            intent.template = Pubkey::default();
+            intent.completion_window = TimeSpan::default();
+            intent.required_permissions = 0;
+        }
+    }
+
+    // Trial intents are free and count against the trial's allowance. The
+    // payer was checked against the from-agent above; delegates don't get to
+    // spend the grantor's free requests.
+    if let Some(trial) = ctx.accounts.trial.as_mut() {
+        require!(ctx.accounts.delegation.is_none(), ErrorCode::InvalidTrial);
+        require_keys_eq!(trial.agent, intent.from_agent, ErrorCode::InvalidTrial);
+        require_keys_eq!(trial.model_profile, ctx.accounts.to_agent.model_profile, ErrorCode::InvalidTrial);
+        require!(
+            !trial.settled
+                && trial.requests_used < trial.requests_total
+                && !trial.expires.passed(&clock, &ctx.accounts.config),
+            ErrorCode::TrialExhausted
+        );
+        require!(
+            payment_amount == 0 && ctx.accounts.booking.is_none(),
+            ErrorCode::InvalidTrial
+        );
+        trial.requests_used += 1;
+        intent.trial = trial.key();
+    }
+
+    // Intents inside a booking window draw tokens at the locked price and
+    // are funded from the booking deposit first
+    let mut payer_amount = payment_amount;
+    if let Some(booking) = ctx.accounts.booking.as_mut() {
+        require_keys_eq!(booking.from_agent, intent.from_agent, ErrorCode::InvalidBooking);
+        require_keys_eq!(booking.to_agent, intent.to_agent, ErrorCode::InvalidBooking);
+        require_keys_eq!(booking.payment_mint, intent.payment_mint, ErrorCode::InvalidBooking);
+        require!(
+            booking.start.reached(&clock, &ctx.accounts.config)
+                && !booking.end.passed(&clock, &ctx.accounts.config),
+            ErrorCode::OutsideBookingWindow
+        );
+
+        let tokens = tokens_for_cost(payment_amount, booking.locked_price)?;
+        let tokens_used = booking
+            .tokens_used
+            .checked_add(tokens)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(tokens_used <= booking.tokens_reserved, ErrorCode::BookingExhausted);
+        booking.tokens_used = tokens_used;
+
+        let credit = payment_amount.min(booking.deposit_remaining);
+        if credit > 0 {
+            let vault = ctx
+                .accounts
+                .booking_vault
+                .as_ref()
+                .ok_or(ErrorCode::InvalidBooking)?;
+            require_keys_eq!(vault.key(), booking.vault, ErrorCode::InvalidBooking);
+
+            let nonce = booking.nonce.to_le_bytes();
+            let seeds = &[
+                b"booking".as_ref(),
+                booking.from_agent.as_ref(),
+                booking.to_agent.as_ref(),
+                &nonce,
+                &[booking.bump],
+            ];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: vault.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: booking.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, credit)?;
+
+            booking.deposit_remaining -= credit;
+            payer_amount -= credit;
+            intent.booking_credit = credit;
+        }
+
+        intent.booking = booking.key();
+    }
+
+    // Transfer payment and tip to escrow if amount > 0
+    let payer_amount = payer_amount
+        .checked_add(tip_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // A budget pays from its vault, which the agent wallet may spend from
+    // within the caps; the vault's owner (the budget PDA) becomes the payer,
+    // so refunds flow back into the budget
+    if let Some(budget) = ctx.accounts.budget.as_mut() {
+        let from_agent = &ctx.accounts.from_agent;
+        let signer = ctx.accounts.payer.key();
+        require!(
+            signer == from_agent.agent_wallet || signer == from_agent.owner_wallet,
+            ErrorCode::Unauthorized
+        );
+        require_keys_eq!(ctx.accounts.from_token_account.key(), budget.vault, ErrorCode::InvalidBudget);
+        require!(ctx.accounts.delegation.is_none(), ErrorCode::InvalidBudget);
+
+        let spent = payer_amount
+            .checked_add(insurance_premium)
+            .ok_or(ErrorCode::MathOverflow)?;
+        budget.record(spent, clock.unix_timestamp)?;
+        emit!(BudgetSpent {
+            agent: budget.agent,
+            mint: budget.mint,
+            intent: intent.key(),
+            amount: spent,
+            spent_today: budget.spent_today,
+            total_spent: budget.total_spent,
+        });
+    }
+
+    if payer_amount > 0 {
+        fund_intent(
+            &ctx.accounts.from_token_account,
+            ctx.accounts.escrow_token_account.to_account_info(),
+            &ctx.accounts.payer,
+            funding_authority(ctx.accounts.delegation.as_ref(), ctx.accounts.budget.as_ref()),
+            &ctx.accounts.token_program,
+            payer_amount,
+        )?;
+    }
+
+    // Pay the insurance premium straight into the pool
+    if insurance_premium > 0 {
+        let pool = ctx
+            .accounts
+            .insurance_pool
+            .as_mut()
+            .ok_or(ErrorCode::InsurancePoolRequired)?;
+        let vault = ctx
+            .accounts
+            .insurance_vault
+            .as_ref()
+            .ok_or(ErrorCode::InsurancePoolRequired)?;
+        require_keys_eq!(pool.vault, vault.key(), ErrorCode::InsurancePoolRequired);
+        require_keys_eq!(pool.mint, intent.payment_mint, ErrorCode::InsurancePoolRequired);
+
+        fund_intent(
+            &ctx.accounts.from_token_account,
+            vault.to_account_info(),
+            &ctx.accounts.payer,
+            funding_authority(ctx.accounts.delegation.as_ref(), ctx.accounts.budget.as_ref()),
+            &ctx.accounts.token_program,
+            insurance_premium,
+        )?;
+
+        pool.total_premiums = pool
+            .total_premiums
+            .checked_add(insurance_premium)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    emit!(IntentCreated {
+        intent: intent.key(),
+        sequence: intent.sequence,
+        from_agent: intent.from_agent,
+        to_agent: intent.to_agent,
+        payer: intent.payer,
+        nonce: intent.nonce,
+        payload_hash: intent.payload_hash,
+        payload_uri: intent.payload_uri.clone(),
+        payment_amount,
+        payment_mint: intent.payment_mint,
+        tip_amount,
+        options: intent.options,
+        context: intent.context,
+        template: intent.template,
+        schema: intent.schema,
+        created_at: intent.created_at,
+    });
+    record_transition(intent, IntentStatus::Pending as u8, ctx.accounts.from_agent.owner_wallet);
+
+    // List the intent under both agents so clients can page through them
+    let intent_key = intent.key();
+    append_to_index(
+        &mut ctx.accounts.sent_index,
+        &mut ctx.accounts.sent_index_page,
+        (ctx.bumps.sent_index, ctx.bumps.sent_index_page),
+        ctx.accounts.from_agent.key(),
+        IndexRole::Sent,
+        intent_key,
+    );
+    append_to_index(
+        &mut ctx.accounts.received_index,
+        &mut ctx.accounts.received_index_page,
+        (ctx.bumps.received_index, ctx.bumps.received_index_page),
+        ctx.accounts.to_agent.key(),
+        IndexRole::Received,
+        intent_key,
+    );
+
+    let from_stats = &mut ctx.accounts.from_stats;
+    from_stats.intents_sent = from_stats.intents_sent.saturating_add(1);
+    from_stats.updated_at = clock.unix_timestamp;
+    if let Some(to_stats) = &mut ctx.accounts.to_stats {
+        to_stats.intents_received = to_stats.intents_received.saturating_add(1);
+        to_stats.updated_at = clock.unix_timestamp;
+    }
+
+    Ok(())
+}
+
+/// Empty a bounty's vault into `to` and close it, returning the vault rent
+/// to the sponsor. Returns the amount paid out.
+fn pay_out_bounty<'info>(
+    bounty: &Account<'info, Bounty>,
+    vault: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    sponsor: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<u64> {
+    let amount = vault.amount;
+    let seeds = &[
+        b"bounty".as_ref(),
+        bounty.sponsor.as_ref(),
+        bounty.spec_hash.as_ref(),
+        &[bounty.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if amount > 0 {
+        let cpi_accounts = Transfer {
+            from: vault.to_account_info(),
+            to,
+            authority: bounty.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+    }
+
+    emit_rent_refund(&vault.to_account_info(), sponsor.key());
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: sponsor,
+        authority: bounty.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer);
+    token::close_account(cpi_ctx)?;
+
+    Ok(amount)
+}
+
+/// An agent may link a profile its owner controls, a public profile, or one
+/// it holds a `ProfileAuthorization` for.
+fn check_profile_access(
+    profile: &Account<ModelProfile>,
+    agent: Pubkey,
+    owner: Pubkey,
+    authorization: Option<&Account<ProfileAuthorization>>,
+) -> Result<()> {
+    if profile.owner_wallet == owner || profile.is_public {
+        return Ok(());
+    }
+    match authorization {
+        Some(auth) if auth.model_profile == profile.key() && auth.agent == agent => Ok(()),
+        _ => err!(ErrorCode::ProfileNotAuthorized),
+    }
+}
+
+fn sweep_payout_vault(accounts: &mut SweepPayouts, now: i64) -> Result<()> {
+    accounts.config.require_settlement_open()?;
+
+    let amount = accounts.vault.amount;
+    let payout_vault = &mut accounts.payout_vault;
+
+    if amount > 0 {
+        let seeds = &[
+            b"payout_vault".as_ref(),
+            payout_vault.model_profile.as_ref(),
+            payout_vault.mint.as_ref(),
+            &[payout_vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: accounts.vault.to_account_info(),
+            to: accounts.billing_token_account.to_account_info(),
+            authority: payout_vault.to_account_info(),
+        };
+        let cpi_program = accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+    }
+
+    payout_vault.accrued = 0;
+    payout_vault.last_sweep_at = now;
+
+    emit!(PayoutsSwept {
+        payout_vault: payout_vault.key(),
+        amount,
+        swept_at: now,
+    });
+
+    Ok(())
+}
+
+/// `amount * bps / 10_000`, rounded down
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let scaled = (amount as u128) * (bps as u128) / (BPS_DENOMINATOR as u128);
+    u64::try_from(scaled).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Account layouts reserve this many bytes for each URI field; longer URIs
+/// realloc the account at the writer's expense
+pub const DEFAULT_URI_LEN: usize = 200;
+
+/// Hard ceiling for the config's `max_uri_len`
+pub const URI_LEN_LIMIT: usize = 1024;
+
+pub const MAX_ARBITERS: usize = 5;
+
+pub const MAX_SWAP_PROGRAMS: usize = 4;
+
+pub const MAX_ACCEPTED_MINTS: usize = 8;
+
+/// Most intents a single `finalize_ready_releases` call will process
+pub const MAX_BATCH_RELEASES: usize = 8;
+
+/// Most royalty recipients a single intent can declare
+pub const MAX_ROYALTY_SPLITS: usize = 8;
+
+/// Most executing agents a team intent can declare
+pub const MAX_TEAM_MEMBERS: usize = 8;
+
+/// Most member agents an `AgentGroup` can hold
+pub const MAX_GROUP_MEMBERS: usize = 16;
+
+/// Intents listed per `IntentIndexPage`
+pub const INTENT_INDEX_PAGE_LEN: usize = 32;
+
+/// Hashes of this many retired encryption keys are kept per agent
+pub const KEY_HISTORY_LEN: usize = 4;
+
+/// Longest an outgoing encryption key may stay valid after rotation (30 days)
+pub const MAX_KEY_OVERLAP: i64 = 30 * 24 * 60 * 60;
+
+/// Capacity signals older than this are ignored (10 minutes), so a provider
+/// that stops reporting doesn't stay closed
+pub const CAPACITY_SIGNAL_TTL: i64 = 10 * 60;
+
+/// Default time open intents take bids for (10 minutes)
+pub const DEFAULT_BIDDING_WINDOW: TimeSpan = TimeSpan::Seconds(10 * 60);
+
+/// Default length of the intent rate limit's slot buckets (about a minute)
+pub const DEFAULT_INTENT_RATE_WINDOW: u64 = 150;
+
+/// SLA penalty rates accrue per hour late
+pub const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+/// Slot-based bookings reserve capacity at the nominal 400ms slot time
+pub const SLOTS_PER_HOUR: u64 = 9_000;
+
+/// Spend limits reset this long after the first spend of a day (24 hours)
+pub const SPEND_LIMIT_WINDOW: i64 = 24 * 60 * 60;
+
+/// Most destination owners a spend limit can restrict transfers to
+pub const MAX_TRANSFER_DESTINATIONS: usize = 8;
+
+/// spl-governance `GovernanceInstruction::CastVote` index
+pub const SPL_GOVERNANCE_CAST_VOTE: u8 = 13;
+
+/// Offset of `realm` in an spl-governance governance account (after the
+/// account type)
+pub const GOVERNANCE_REALM_OFFSET: usize = 1;
+
+/// Offset of `governing_token_owner` in an spl-governance token owner record
+/// (after the account type, realm and governing token mint)
+pub const TOKEN_OWNER_RECORD_OWNER_OFFSET: usize = 1 + 32 + 32;
+
+/// Metaplex Token Metadata, which hosts agent identity badges
+/// (`metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s`)
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    11, 112, 101, 177, 227, 209, 124, 69, 56, 157, 82, 127, 107, 4, 195, 205,
+    88, 184, 108, 115, 26, 160, 253, 181, 73, 182, 209, 188, 3, 248, 41, 70,
+]);
+
+/// Token Metadata `CreateMetadataAccountV3` instruction index
+pub const TOKEN_METADATA_CREATE_METADATA_V3: u8 = 33;
+
+/// Token Metadata `CreateMasterEditionV3` instruction index
+pub const TOKEN_METADATA_CREATE_MASTER_EDITION_V3: u8 = 17;
+
+/// Symbol shown on agent identity badges
+pub const BADGE_SYMBOL: &str = "MESHID";
+
+/// Solana Name Service program (`namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX`)
+pub const NAME_SERVICE_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    11, 173, 81, 244, 19, 193, 243, 169, 148, 96, 217, 0, 216, 191, 46, 214,
+    146, 126, 202, 52, 215, 183, 132, 43, 248, 16, 169, 115, 8, 45, 30, 220,
+]);
+
+/// Name record of the `.sol` TLD, parent of every `.sol` domain
+/// (`58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx`)
+pub const SOL_TLD_AUTHORITY: Pubkey = Pubkey::new_from_array([
+    61, 83, 194, 75, 56, 54, 14, 211, 129, 58, 35, 223, 178, 223, 216, 32,
+    171, 88, 33, 203, 121, 41, 163, 141, 46, 170, 178, 82, 232, 56, 37, 149,
+]);
+
+/// Prefix Name Service hashes domain labels with
+pub const NAME_SERVICE_HASH_PREFIX: &str = "SPL Name Service";
+
+/// Offset of `owner` in a Name Service record header (after the parent name)
+pub const NAME_RECORD_OWNER_OFFSET: usize = 32;
+
+/// Longest `.sol` label an agent can bind
+pub const MAX_SOL_NAME_LEN: usize = 64;
+
+/// Longest media type a `PayloadSchema` records ("application/schema+json")
+pub const MAX_MEDIA_TYPE_LEN: usize = 64;
+
+/// Wormhole core bridge (`worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth`),
+/// owner of the posted VAAs bridge messages arrive in
+pub const WORMHOLE_CORE_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    14, 10, 88, 154, 65, 165, 95, 189, 102, 197, 42, 71, 95, 45, 146, 166,
+    211, 220, 155, 71, 71, 17, 76, 185, 175, 130, 90, 152, 181, 69, 211, 206,
+]);
+
+/// Tag the core bridge writes ahead of a verified `PostedVAA` account
+pub const POSTED_VAA_MAGIC: &[u8] = b"vaa";
+
+/// Wormhole chain id of Solana itself
+pub const WORMHOLE_CHAIN_SOLANA: u16 = 1;
+
+/// Model profile pricing is quoted per this many tokens
+pub const PRICING_UNIT_TOKENS: u64 = 1_000;
+
+fn cost_for_tokens(tokens: u64, price: u64) -> Result<u64> {
+    let cost = (tokens as u128) * (price as u128) / (PRICING_UNIT_TOKENS as u128);
+    u64::try_from(cost).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+fn tokens_for_cost(cost: u64, price: u64) -> Result<u64> {
+    require!(price > 0, ErrorCode::InvalidBooking);
+    let tokens = (cost as u128) * (PRICING_UNIT_TOKENS as u128) / (price as u128);
+    u64::try_from(tokens).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Check that the instruction preceding the current one is an ed25519 program
+/// instruction verifying a single signature by `signer` over `message`, with
+/// all data inline in that instruction.
+fn verify_ed25519_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = ix_sysvar::load_current_index_checked(instructions)?;
+    require!(current > 0, ErrorCode::InvalidSignature);
+    let ix = ix_sysvar::load_instruction_at_checked(current as usize - 1, instructions)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidSignature);
+
+    // Header: count (1), padding (1), then seven u16 offsets per signature
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidSignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let (signature_ix, pubkey_offset, pubkey_ix) = (read_u16(4), read_u16(6) as usize, read_u16(8));
+    let (message_offset, message_len, message_ix) =
+        (read_u16(10) as usize, read_u16(12) as usize, read_u16(14));
+    require!(
+        signature_ix == u16::MAX && pubkey_ix == u16::MAX && message_ix == u16::MAX,
+        ErrorCode::InvalidSignature
+    );
+
+    let pubkey = data
+        .get(pubkey_offset..pubkey_offset + 32)
+        .ok_or(ErrorCode::InvalidSignature)?;
+    let signed = data
+        .get(message_offset..message_offset + message_len)
+        .ok_or(ErrorCode::InvalidSignature)?;
+    require!(
+        pubkey == signer.as_ref() && signed == message,
+        ErrorCode::InvalidSignature
+    );
+    Ok(())
+}
+
+/// A bridge message read and checked by `read_bridge_message`
+struct BridgedMessage {
+    /// Wormhole chain the message was emitted on
+    chain: u16,
+    /// Emitter sequence of the VAA
+    sequence: u64,
+    sender: [u8; 32],
+    action: BridgeAction,
+}
+
+impl BridgedMessage {
+    /// Check that the message was sent by `remote`'s agent
+    fn require_sender(&self, remote: &RemoteAgent) -> Result<()> {
+        require!(
+            remote.chain == self.chain && remote.address == self.sender,
+            ErrorCode::InvalidBridgeMessage
+        );
+        Ok(())
+    }
+}
+
+/// Read the bridge message in a VAA the Wormhole core bridge has verified
+/// and posted, checking it was emitted by the chain's registered `emitter`
+/// for this program. Only the core bridge writes accounts it owns, and it
+/// only tags them `vaa` once the guardian signatures check out.
+fn read_bridge_message(posted_vaa: &AccountInfo, emitter: &ForeignEmitter) -> Result<BridgedMessage> {
+    require_keys_eq!(*posted_vaa.owner, WORMHOLE_CORE_PROGRAM_ID, ErrorCode::InvalidBridgeMessage);
+    let data = posted_vaa.try_borrow_data()?;
+    require!(data.starts_with(POSTED_VAA_MAGIC), ErrorCode::InvalidBridgeMessage);
+    let vaa = PostedVaa::deserialize(&mut &data[POSTED_VAA_MAGIC.len()..])
+        .map_err(|_| error!(ErrorCode::InvalidBridgeMessage))?;
+    require!(
+        vaa.emitter_chain == emitter.chain && vaa.emitter_address == emitter.address,
+        ErrorCode::InvalidBridgeMessage
+    );
+
+    let message = vaa
+        .payload
+        .strip_prefix(BridgeMessage::DOMAIN)
+        .and_then(|body| BridgeMessage::try_from_slice(body).ok())
+        .ok_or(ErrorCode::InvalidBridgeMessage)?;
+    require_keys_eq!(message.program_id, crate::ID, ErrorCode::InvalidBridgeMessage);
+    Ok(BridgedMessage {
+        chain: vaa.emitter_chain,
+        sequence: vaa.sequence,
+        sender: message.sender,
+        action: message.action,
+    })
+}
+
+/// Split an intent's payment into `(protocol fee, delegate share, provider share)`
+fn split_payment(intent: &AgentIntent, fee_bps: u16) -> Result<(u64, u64, u64)> {
+    let fee = bps_of(intent.payment_amount, fee_bps)?;
+    let distributable = intent.payment_amount - fee;
+
+    // A re-delegated intent pays the delegate its agreed share
+    let delegate_amount = if intent.delegate_agent != Pubkey::default() {
+        bps_of(distributable, intent.delegate_share_bps)?
+    } else {
+        0
+    };
+    Ok((fee, delegate_amount, distributable - delegate_amount))
+}
+
+/// SLA penalty for completing at `now`: `sla_penalty_bps` of the payment
+/// per hour (pro rata) past the target, capped at the whole payment
+fn sla_penalty(intent: &AgentIntent, now: i64) -> Result<u64> {
+    if intent.sla_penalty_bps == 0 || intent.accepted_at == 0 {
+        return Ok(0);
+    }
+    let late_by = now.saturating_sub(intent.accepted_at.saturating_add(intent.sla_target));
+    if late_by <= 0 {
+        return Ok(0);
+    }
+    let penalty = (intent.payment_amount as u128)
+        .checked_mul(intent.sla_penalty_bps as u128 * late_by as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / (BPS_DENOMINATOR as u128 * SECONDS_PER_HOUR as u128);
+    Ok(penalty.min(intent.payment_amount as u128) as u64)
+}
+
+/// Log the intent's move from `previous_status` to its current status as the
+/// next entry in its transition history, attributed to `actor` (the default
+/// key for permissionless settlements). Creation is entry 0, from and to
+/// Pending. Call after `touch` so the entry carries the mutation's sequence.
+fn record_transition(intent: &mut Account<AgentIntent>, previous_status: u8, actor: Pubkey) {
+    emit!(IntentTransition {
+        intent: intent.key(),
+        sequence: intent.sequence,
+        index: intent.transitions,
+        previous_status,
+        status: intent.status,
+        actor,
+        at: intent.updated_at,
+    });
+    intent.transitions = intent.transitions.saturating_add(1);
+}
+
+/// Append `intent` to `agent`'s index for `role`, filling in the index and
+/// its current page (with their `bumps`) the first time they're used
+fn append_to_index(
+    index: &mut IntentIndex,
+    page: &mut IntentIndexPage,
+    bumps: (u8, u8),
+    agent: Pubkey,
+    role: IndexRole,
+    intent: Pubkey,
+) {
+    if index.agent == Pubkey::default() {
+        index.agent = agent;
+        index.role = role as u8;
+        index.bump = bumps.0;
+    }
+    if page.agent == Pubkey::default() {
+        page.agent = agent;
+        page.role = role as u8;
+        page.page = index.next_page();
+        page.bump = bumps.1;
+    }
+    page.intents.push(intent);
+    index.count = index.count.saturating_add(1);
+}
+
+/// Move `amount` out of an intent's escrow, signed by the intent PDA
+fn transfer_from_escrow<'info>(
+    intent: &Account<'info, AgentIntent>,
+    escrow: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let nonce = intent.nonce.to_le_bytes();
+    let seeds = &[
+        b"intent".as_ref(),
+        intent.from_agent.as_ref(),
+        intent.to_agent.as_ref(),
+        &nonce,
+        &[intent.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: escrow.to_account_info(),
+        to: to.to_account_info(),
+        authority: intent.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Close an empty token account owned by the intent PDA
+fn close_escrow_account<'info>(
+    intent: &Account<'info, AgentIntent>,
+    account: &Account<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let nonce = intent.nonce.to_le_bytes();
+    let seeds = &[
+        b"intent".as_ref(),
+        intent.from_agent.as_ref(),
+        intent.to_agent.as_ref(),
+        &nonce,
+        &[intent.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = CloseAccount {
+        account: account.to_account_info(),
+        destination: destination.clone(),
+        authority: intent.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::close_account(cpi_ctx)
+}
+
+/// Move a trial's whole deposit out of its vault, signed by the trial PDA
+fn transfer_from_trial<'info>(
+    trial: &Account<'info, TrialAccount>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let seeds = &[
+        b"trial".as_ref(),
+        trial.model_profile.as_ref(),
+        trial.agent.as_ref(),
+        &[trial.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: vault.to_account_info(),
+        to: to.to_account_info(),
+        authority: trial.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, trial.deposit)
+}
+
+// === Permission Flags ===
+pub struct Permission;
+impl Permission {
+    pub const CAN_SWAP: u64 = 1 << 0;
+    pub const CAN_TRANSFER: u64 = 1 << 1;
     pub const CAN_VOTE: u64 = 1 << 2;
     pub const CAN_CREATE_INTENT: u64 = 1 << 3;
     pub const CAN_ACCEPT_INTENT: u64 = 1 << 4;
 }
 
-// === Intent Status ===
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub enum IntentStatus {
-    Pending = 0,
-    Accepted = 1,
-    Completed = 2,
-    Failed = 3,
+// === Intent Options ===
+pub struct IntentOption;
+impl IntentOption {
+    pub const INSURED: u8 = 1 << 0;
+    pub const ALLOW_REDELEGATION: u8 = 1 << 1;
+    /// Hold the escrow for the config's challenge window after completion,
+    /// then release through `finalize_ready_releases`
+    pub const AUTO_RELEASE: u8 = 1 << 2;
+    /// Funded against the provider's signed x402 quote; set only by
+    /// `settle_x402_payment`
+    pub const PREPAID: u8 = 1 << 3;
+    /// Open to bids from any provider for the config's bidding window; the
+    /// requester addresses the intent to its own agent until `select_bid`
+    pub const OPEN_BIDDING: u8 = 1 << 4;
+    /// Only complete with a result attested by the assignee's agent wallet
+    /// (see `submit_result`); single-provider intents only
+    pub const ATTESTED_RESULT: u8 = 1 << 5;
+}
+
+// === Register Options ===
+pub struct RegisterOption;
+impl RegisterOption {
+    /// Update the owner's identity in place if it already exists instead of
+    /// failing (its keys, capacity signal and creation time are kept)
+    pub const UPDATE_EXISTING: u8 = 1 << 0;
+    /// Only intents created from now on count toward the agent's reputation.
+    /// Otherwise a re-registered agent inherits the record of its PDA.
+    pub const RESET_REPUTATION: u8 = 1 << 1;
+}
+
+// === Group Roles ===
+pub struct GroupRole;
+impl GroupRole {
+    /// Add and remove members and change their roles
+    pub const ADMIN: u8 = 1 << 0;
+    /// Spend from the group treasury
+    pub const TREASURER: u8 = 1 << 1;
+    pub const ALL: u8 = Self::ADMIN | Self::TREASURER;
+}
+
+// === Intent Status ===
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum IntentStatus {
+    Pending = 0,
+    Accepted = 1,
+    Completed = 2,
+    Failed = 3,
+    Cancelled = 4,
+}
+
+impl IntentStatus {
+    pub fn is_terminal(status: u8) -> bool {
+        status == IntentStatus::Completed as u8
+            || status == IntentStatus::Failed as u8
+            || status == IntentStatus::Cancelled as u8
+    }
+}
+
+// === Intent Index ===
+/// Which of an agent's intents an `IntentIndex` lists
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum IndexRole {
+    Sent = 0,
+    Received = 1,
+}
+
+// === Content Commitments ===
+/// How a commitment's hash was computed over the bytes behind a URI
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CommitmentScheme {
+    Sha256 = 1,
+    Blake3 = 2,
+    /// Digest of an `ipfs://` CIDv1 with the `raw` codec and a sha2-256
+    /// multihash, i.e. sha256 of the content
+    IpfsCid = 3,
+}
+
+impl CommitmentScheme {
+    pub fn from_u8(scheme: u8) -> Option<Self> {
+        match scheme {
+            1 => Some(Self::Sha256),
+            2 => Some(Self::Blake3),
+            3 => Some(Self::IpfsCid),
+            _ => None,
+        }
+    }
+}
+
+/// What an intent's payload or result URI must resolve to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContentCommitment {
+    pub scheme: u8,                // CommitmentScheme
+    pub length: u64,               // content length in bytes
+    pub hash: [u8; 32],
+}
+
+impl ContentCommitment {
+    pub fn require_known_scheme(&self) -> Result<()> {
+        require!(
+            CommitmentScheme::from_u8(self.scheme).is_some(),
+            ErrorCode::UnknownCommitmentScheme
+        );
+        Ok(())
+    }
+}
+
+// === Program Version ===
+/// Semantic version of the on-chain program; keep in sync with Cargo.toml
+pub const PROGRAM_VERSION: ProgramVersion = ProgramVersion {
+    major: 0,
+    minor: 2,
+    patch: 0,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProgramVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ProgramVersion {
+    pub const SIZE: usize = 2 + 2 + 2;
+}
+
+/// Return data of `get_version`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct VersionInfo {
+    pub program: ProgramVersion,
+    pub config: ProgramVersion,
+}
+
+// === Operating Mode ===
+/// Active: normal operation. Draining: no new intents, but in-flight escrows
+/// can still settle. The emergency `paused` flag on the config overrides both.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OperatingMode {
+    Active = 0,
+    Draining = 1,
+}
+
+impl OperatingMode {
+    pub fn is_valid(mode: u8) -> bool {
+        mode <= OperatingMode::Draining as u8
+    }
+}
+
+// === Deadlines ===
+/// A point in time on one of the cluster's two clocks. `unix_timestamp` is a
+/// stake-weighted estimate that can drift from wall time, so windows for fast
+/// tasks can be set in slots instead. Both are compared with the config's
+/// tolerance for that clock. Capacity signal TTLs, key rotation overlaps and
+/// payout sweep cadences stay on unix time.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deadline {
+    UnixTimestamp(i64),
+    Slot(u64),
+}
+
+impl Default for Deadline {
+    fn default() -> Self {
+        Deadline::UnixTimestamp(0)
+    }
+}
+
+impl Deadline {
+    pub const SIZE: usize = 1 + 8;
+
+    /// Start of a window: true from `tolerance` before the deadline on
+    pub fn reached(&self, clock: &Clock, config: &ProtocolConfig) -> bool {
+        match *self {
+            Deadline::UnixTimestamp(at) => {
+                clock.unix_timestamp >= at.saturating_sub(config.timestamp_tolerance)
+            }
+            Deadline::Slot(at) => clock.slot >= at.saturating_sub(config.slot_tolerance),
+        }
+    }
+
+    /// End of a window: true once the deadline is more than `tolerance`
+    /// behind, so "still open" (`!passed`) and "over" never overlap
+    pub fn passed(&self, clock: &Clock, config: &ProtocolConfig) -> bool {
+        match *self {
+            Deadline::UnixTimestamp(at) => {
+                clock.unix_timestamp > at.saturating_add(config.timestamp_tolerance)
+            }
+            Deadline::Slot(at) => clock.slot > at.saturating_add(config.slot_tolerance),
+        }
+    }
+
+    /// Length of a window from `self` to a later `end` on the same clock
+    pub fn until(&self, end: &Deadline) -> Option<TimeSpan> {
+        match (*self, *end) {
+            (Deadline::UnixTimestamp(start), Deadline::UnixTimestamp(end)) if end > start => {
+                Some(TimeSpan::Seconds(end - start))
+            }
+            (Deadline::Slot(start), Deadline::Slot(end)) if end > start => {
+                Some(TimeSpan::Slots(end - start))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A window length in seconds or slots; turned into a `Deadline` on the same
+/// clock when the window opens
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeSpan {
+    Seconds(i64),
+    Slots(u64),
+}
+
+impl Default for TimeSpan {
+    fn default() -> Self {
+        TimeSpan::Seconds(0)
+    }
+}
+
+impl TimeSpan {
+    pub const SIZE: usize = 1 + 8;
+
+    pub fn is_valid(&self) -> bool {
+        !matches!(*self, TimeSpan::Seconds(secs) if secs < 0)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(*self, TimeSpan::Seconds(0) | TimeSpan::Slots(0))
+    }
+
+    pub fn after(&self, clock: &Clock) -> Deadline {
+        match *self {
+            TimeSpan::Seconds(secs) => Deadline::UnixTimestamp(clock.unix_timestamp.saturating_add(secs)),
+            TimeSpan::Slots(slots) => Deadline::Slot(clock.slot.saturating_add(slots)),
+        }
+    }
+
+    /// Whole hours covered, rounding up; slots count at the nominal slot time
+    pub fn hours(&self) -> u64 {
+        match *self {
+            TimeSpan::Seconds(secs) => (secs.max(0) as u64).div_ceil(3600),
+            TimeSpan::Slots(slots) => slots.div_ceil(SLOTS_PER_HOUR),
+        }
+    }
+}
+
+// === Account Structures ===
+
+#[account]
+#[derive(Default)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,             // 32
+    pub attestor: Pubkey,          // 32
+    pub mode: u8,                  // 1 (OperatingMode)
+    pub paused: bool,              // 1 (emergency stop for all fund movement)
+    pub insurance_premium_bps: u16, // 2 (charged on payment_amount when insured)
+    pub base_coverage_bps: u16,    // 2 (reimbursement cap without insurance)
+    pub insured_coverage_bps: u16, // 2 (reimbursement cap with insurance)
+    pub governance: Pubkey,        // 32 (spl-governance authority, default until handover)
+    pub protocol_fee_bps: u16,     // 2
+    pub fee_treasury: Pubkey,      // 32 (owner of fee token accounts)
+    pub challenge_window: TimeSpan, // 9
+    pub cancellation_window: TimeSpan, // 9 (after acceptance)
+    pub cancellation_penalty_bps: u16, // 2 (of the escrow, to the provider when the requester cancels late)
+    pub completion_window: TimeSpan, // 9 (after acceptance, zero = no deadline)
+    pub acceptance_bond_bps: u16,  // 2 (of payment_amount, posted by the provider to accept)
+    pub timeout_tip_bps: u16,      // 2 (of a slashed bond, to whoever flags the timeout)
+    pub bidding_window: TimeSpan,  // 9 (open intents take bids this long after creation)
+    pub timestamp_tolerance: i64,  // 8 (seconds of unix clock drift allowed at deadlines)
+    pub slot_tolerance: u64,       // 8 (slots allowed past slot deadlines)
+    pub intent_rate_window: u64,   // 8 (slots per rate limit bucket)
+    pub max_intents_per_window: u16, // 2 (per requesting agent, 0 = unlimited)
+    pub max_uri_len: u16,          // 2
+    pub arbiter_count: u8,         // 1
+    pub arbiters: [Pubkey; MAX_ARBITERS], // 32 * MAX_ARBITERS
+    pub dispute_fee_bps: u16,      // 2 (of payment_amount, posted to open a dispute)
+    pub min_dispute_fee: u64,      // 8 (floor for the dispute fee)
+    pub dispute_arbiter_share_bps: u16, // 2 (arbiter's cut of a forfeited fee)
+    pub swap_program_count: u8,    // 1
+    pub swap_programs: [Pubkey; MAX_SWAP_PROGRAMS], // 32 * MAX_SWAP_PROGRAMS
+    pub program_version: ProgramVersion, // 6 (last synced PROGRAM_VERSION)
+    pub crank_tip_lamports: u64,   // 8 (per intent processed by a crank instruction)
+    pub accepted_mint_count: u8,   // 1 (0 = any mint)
+    pub accepted_mints: [Pubkey; MAX_ACCEPTED_MINTS], // 32 * MAX_ACCEPTED_MINTS
+    pub bump: u8,                  // 1
+}
+
+impl ProtocolConfig {
+    pub const MAX_SIZE: usize = 32 + 32 + 1 + 1 + 2 + 2 + 2 + 32 + 2 + 32 + TimeSpan::SIZE
+        + TimeSpan::SIZE + 2 + TimeSpan::SIZE + 2 + 2 + TimeSpan::SIZE + 8 + 8 + 8 + 2 + 2 + 1 + 32 * MAX_ARBITERS + 2 + 8 + 2 + 1 + 32 * MAX_SWAP_PROGRAMS + ProgramVersion::SIZE + 8 + 1 + 32 * MAX_ACCEPTED_MINTS + 1;
+
+    /// New intents are only accepted while active and not paused
+    pub fn require_accepting_intents(&self) -> Result<()> {
+        self.require_settlement_open()?;
+        require!(
+            self.mode == OperatingMode::Active as u8,
+            ErrorCode::ProtocolDraining
+        );
+        Ok(())
+    }
+
+    /// In-flight intents may settle, refund or close unless paused
+    pub fn require_settlement_open(&self) -> Result<()> {
+        require!(!self.paused, ErrorCode::ProtocolPaused);
+        Ok(())
+    }
+
+    /// Who may change protocol parameters: governance once set, else the admin
+    pub fn parameter_authority(&self) -> Pubkey {
+        if self.governance != Pubkey::default() {
+            self.governance
+        } else {
+            self.admin
+        }
+    }
+
+    pub fn require_uri_len(&self, uri: &str) -> Result<()> {
+        require!(uri.len() <= self.max_uri_len as usize, ErrorCode::UriTooLong);
+        Ok(())
+    }
+
+    pub fn is_arbiter(&self, key: &Pubkey) -> bool {
+        self.arbiters[..self.arbiter_count as usize].contains(key)
+    }
+
+    /// Fee to open a dispute over an intent paying `payment_amount`
+    pub fn dispute_fee(&self, payment_amount: u64) -> Result<u64> {
+        Ok(bps_of(payment_amount, self.dispute_fee_bps)?.max(self.min_dispute_fee))
+    }
+
+    pub fn is_swap_program(&self, key: &Pubkey) -> bool {
+        self.swap_programs[..self.swap_program_count as usize].contains(key)
+    }
+
+    /// Intents and bookings may be paid in `mint`: any mint until the
+    /// registry has entries, then only those
+    pub fn accepts_mint(&self, mint: &Pubkey) -> bool {
+        self.accepted_mint_count == 0 || self.accepted_mints[..self.accepted_mint_count as usize].contains(mint)
+    }
+
+    pub fn apply(&mut self, parameter: &ProtocolParameter) -> Result<()> {
+        match *parameter {
+            ProtocolParameter::ProtocolFeeBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.protocol_fee_bps = bps;
+            }
+            ProtocolParameter::FeeTreasury(treasury) => {
+                self.fee_treasury = treasury;
+            }
+            ProtocolParameter::ChallengeWindow(span) => {
+                require!(span.is_valid(), ErrorCode::InvalidParameter);
+                self.challenge_window = span;
+            }
+            ProtocolParameter::CancellationWindow(span) => {
+                require!(span.is_valid(), ErrorCode::InvalidParameter);
+                self.cancellation_window = span;
+            }
+            ProtocolParameter::CancellationPenaltyBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.cancellation_penalty_bps = bps;
+            }
+            ProtocolParameter::CompletionWindow(span) => {
+                require!(span.is_valid(), ErrorCode::InvalidParameter);
+                self.completion_window = span;
+            }
+            ProtocolParameter::AcceptanceBondBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.acceptance_bond_bps = bps;
+            }
+            ProtocolParameter::TimeoutTipBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.timeout_tip_bps = bps;
+            }
+            ProtocolParameter::BiddingWindow(span) => {
+                require!(span.is_valid() && !span.is_zero(), ErrorCode::InvalidParameter);
+                self.bidding_window = span;
+            }
+            ProtocolParameter::TimestampTolerance(secs) => {
+                require!(secs >= 0, ErrorCode::InvalidParameter);
+                self.timestamp_tolerance = secs;
+            }
+            ProtocolParameter::SlotTolerance(slots) => {
+                self.slot_tolerance = slots;
+            }
+            ProtocolParameter::IntentRateWindow(slots) => {
+                require!(slots > 0, ErrorCode::InvalidParameter);
+                self.intent_rate_window = slots;
+            }
+            ProtocolParameter::MaxIntentsPerWindow(max) => {
+                self.max_intents_per_window = max;
+            }
+            ProtocolParameter::CrankTipLamports(lamports) => {
+                self.crank_tip_lamports = lamports;
+            }
+            ProtocolParameter::MaxUriLength(len) => {
+                require!(len as usize <= URI_LEN_LIMIT, ErrorCode::InvalidParameter);
+                self.max_uri_len = len;
+            }
+            ProtocolParameter::AddArbiter(arbiter) => {
+                set_insert(&mut self.arbiters, &mut self.arbiter_count, arbiter)?;
+            }
+            ProtocolParameter::RemoveArbiter(arbiter) => {
+                set_remove(&mut self.arbiters, &mut self.arbiter_count, arbiter)?;
+            }
+            ProtocolParameter::DisputeFeeBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.dispute_fee_bps = bps;
+            }
+            ProtocolParameter::MinDisputeFee(amount) => {
+                self.min_dispute_fee = amount;
+            }
+            ProtocolParameter::DisputeArbiterShareBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.dispute_arbiter_share_bps = bps;
+            }
+            ProtocolParameter::AddSwapProgram(program) => {
+                set_insert(&mut self.swap_programs, &mut self.swap_program_count, program)?;
+            }
+            ProtocolParameter::RemoveSwapProgram(program) => {
+                set_remove(&mut self.swap_programs, &mut self.swap_program_count, program)?;
+            }
+            ProtocolParameter::AddAcceptedMint(mint) => {
+                set_insert(&mut self.accepted_mints, &mut self.accepted_mint_count, mint)?;
+            }
+            ProtocolParameter::RemoveAcceptedMint(mint) => {
+                set_remove(&mut self.accepted_mints, &mut self.accepted_mint_count, mint)?;
+            }
+            ProtocolParameter::InsurancePremiumBps(bps) => {
+                require!(bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.insurance_premium_bps = bps;
+            }
+            ProtocolParameter::BaseCoverageBps(bps) => {
+                require!(bps <= self.insured_coverage_bps, ErrorCode::InvalidBasisPoints);
+                self.base_coverage_bps = bps;
+            }
+            ProtocolParameter::InsuredCoverageBps(bps) => {
+                require!(
+                    bps >= self.base_coverage_bps && bps <= BPS_DENOMINATOR,
+                    ErrorCode::InvalidBasisPoints
+                );
+                self.insured_coverage_bps = bps;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Insert into a fixed-capacity key set stored as `items[..count]`
+fn set_insert<const N: usize>(items: &mut [Pubkey; N], count: &mut u8, key: Pubkey) -> Result<()> {
+    let len = *count as usize;
+    require!(
+        len < N && !items[..len].contains(&key),
+        ErrorCode::InvalidParameter
+    );
+    items[len] = key;
+    *count += 1;
+    Ok(())
+}
+
+/// Swap-remove from a fixed-capacity key set stored as `items[..count]`
+fn set_remove<const N: usize>(items: &mut [Pubkey; N], count: &mut u8, key: Pubkey) -> Result<()> {
+    let len = *count as usize;
+    let index = items[..len]
+        .iter()
+        .position(|k| *k == key)
+        .ok_or(ErrorCode::InvalidParameter)?;
+    items[index] = items[len - 1];
+    items[len - 1] = Pubkey::default();
+    *count -= 1;
+    Ok(())
+}
+
+/// A governable protocol parameter and its new value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolParameter {
+    ProtocolFeeBps(u16),
+    FeeTreasury(Pubkey),
+    ChallengeWindow(TimeSpan),
+    CancellationWindow(TimeSpan),
+    CancellationPenaltyBps(u16),
+    CompletionWindow(TimeSpan),
+    AcceptanceBondBps(u16),
+    TimeoutTipBps(u16),
+    BiddingWindow(TimeSpan),
+    MaxUriLength(u16),
+    AddArbiter(Pubkey),
+    RemoveArbiter(Pubkey),
+    DisputeFeeBps(u16),
+    MinDisputeFee(u64),
+    DisputeArbiterShareBps(u16),
+    AddSwapProgram(Pubkey),
+    RemoveSwapProgram(Pubkey),
+    InsurancePremiumBps(u16),
+    BaseCoverageBps(u16),
+    InsuredCoverageBps(u16),
+    TimestampTolerance(i64),
+    SlotTolerance(u64),
+    IntentRateWindow(u64),
+    MaxIntentsPerWindow(u16),
+    CrankTipLamports(u64),
+    AddAcceptedMint(Pubkey),
+    RemoveAcceptedMint(Pubkey),
+}
+
+#[account]
+#[derive(Default)]
+pub struct AgentIdentity {
+    pub owner_wallet: Pubkey,      // 32
+    pub agent_wallet: Pubkey,      // 32
+    pub model_profile: Pubkey,     // 32
+    pub encryption_key: [u8; 32],  // 32 (X25519 public key, zero = unset)
+    pub previous_encryption_key: [u8; 32], // 32 (valid until previous_key_expires_at)
+    pub previous_key_expires_at: i64, // 8
+    pub key_version: u32,          // 4 (number of rotations)
+    pub key_history: [[u8; 32]; KEY_HISTORY_LEN], // 32 * 4 (keccak of retired keys)
+    pub available_slots: u32,      // 4 (advertised free capacity)
+    pub queue_depth: u32,          // 4 (advertised backlog)
+    pub capacity_updated_at: i64,  // 8 (0 = never signalled)
+    pub min_payment: u64,          // 8 (smallest payment_amount accepted, 0 = any)
+    pub metadata_uri: String,      // 4 + 200
+    pub permissions: u64,          // 8
+    pub created_at: i64,           // 8
+    pub updated_at: i64,           // 8
+    pub bump: u8,                  // 1
+    pub version: u8,               // 1 (account layout; fields added later go after it)
+    pub reputation_since: i64,     // 8 (v2: intents created earlier don't count toward reputation)
+    pub group: Pubkey,             // 32 (v3: AgentGroup its earnings flow to, default if none)
+    pub guardian: Pubkey,          // 32 (v4: may freeze the agent besides the owner, default if none)
+    pub frozen: bool,              // 1 (v4)
+    pub delegations_revoked_at: i64, // 8 (v4: delegations granted up to this time are void)
+}
+
+impl AgentIdentity {
+    pub const MAX_SIZE: usize =
+        32 + 32 + 32 + 32 + 32 + 8 + 4 + (32 * KEY_HISTORY_LEN) + 4 + 4 + 8 + 8 + (4 + 200) + 8 + 8 + 8 + 1 + 1 + 8 + 32
+            + 32 + 1 + 8;
+
+    /// Layout version written by this program (see `migrate_agent`)
+    pub const VERSION: u8 = 4;
+    /// Bytes appended since the unversioned layout
+    pub const LAYOUT_GROWTH: usize = 1 + 8 + 32 + 32 + 1 + 8;
+
+    // Byte offsets (including the discriminator) of fixed-position fields,
+    // for getProgramAccounts memcmp filters
+    pub const OWNER_WALLET_OFFSET: usize = 8;
+    pub const AGENT_WALLET_OFFSET: usize = Self::OWNER_WALLET_OFFSET + 32;
+    pub const MODEL_PROFILE_OFFSET: usize = Self::AGENT_WALLET_OFFSET + 32;
+
+    /// Account space (with discriminator) for a metadata URI of `metadata_len` bytes
+    pub fn space(metadata_len: usize) -> usize {
+        8 + Self::MAX_SIZE - DEFAULT_URI_LEN + metadata_len.max(DEFAULT_URI_LEN)
+    }
+
+    /// Space `register_agent` asks for: an identity that already exists
+    /// keeps its size, since `init_if_needed` only accepts an exact match
+    pub fn registration_space(account: &AccountInfo, metadata_len: usize) -> usize {
+        if account.owner == &crate::ID {
+            account.data_len()
+        } else {
+            Self::space(metadata_len)
+        }
+    }
+
+    /// False only while a capacity signal younger than `CAPACITY_SIGNAL_TTL`
+    /// reports no free slots; agents that never signal are always open
+    pub fn has_capacity(&self, now: i64) -> bool {
+        self.capacity_updated_at == 0
+            || self.available_slots > 0
+            || now > self.capacity_updated_at.saturating_add(CAPACITY_SIGNAL_TTL)
+    }
+
+    pub fn require_active(&self) -> Result<()> {
+        require!(!self.frozen, ErrorCode::AgentFrozen);
+        Ok(())
+    }
+
+    /// Whether payloads encrypted to `key` can still be opened at `now`
+    pub fn accepts_encryption_key(&self, key: &[u8; 32], now: i64) -> bool {
+        if *key == [0u8; 32] {
+            return false;
+        }
+        *key == self.encryption_key
+            || (*key == self.previous_encryption_key && now < self.previous_key_expires_at)
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct ModelProfile {
+    pub owner_wallet: Pubkey,         // 32
+    pub profile_id: [u8; 16],         // 16
+    pub verified: bool,               // 1 (fixed offset, filterable via memcmp)
+    pub is_public: bool,              // 1 (fixed offset, filterable via memcmp)
+    pub attestation: Pubkey,          // 32 (e.g. SAS attestation account)
+    pub attested_at: i64,             // 8
+    pub latest_version: u32,          // 4 (last ProfileVersion written)
+    pub snapshot_current: bool,       // 1 (latest ProfileVersion matches contents)
+    pub label: String,                // 4 + 64
+    pub provider_uri: String,         // 4 + 200
+    pub pricing: u64,                 // 8 (micro-units per 1K tokens)
+    pub billing_wallet: Pubkey,       // 32
+    pub max_tokens_per_day: u64,      // 8
+    pub max_requests_per_min: u64,    // 8
+    pub payout_mint: Pubkey,          // 32 (default = paid in the escrowed mint)
+    pub max_slippage_bps: u16,        // 2 (tolerance on payout swaps)
+    pub trial_requests: u16,          // 2 (free requests per trial, 0 = no trials)
+    pub trial_deposit: u64,           // 8 (refundable deposit to start a trial)
+    pub trial_mint: Pubkey,           // 32
+    pub trial_duration: TimeSpan,     // 9
+    pub created_at: i64,              // 8
+    pub updated_at: i64,              // 8
+    pub bump: u8,                     // 1
+    pub version: u8,                  // 1 (account layout; fields added later go after it)
+}
+
+impl ModelProfile {
+    pub const MAX_SIZE: usize = 32 + 16 + 1 + 1 + 32 + 8 + 4 + 1 + (4 + 64) + (4 + 200) + 8 + 32 + 8 + 8 + 32 + 2 + 2 + 8 + 32 + TimeSpan::SIZE + 8 + 8 + 1 + 1;
+
+    /// Layout version written by this program (see `migrate_model_profile`)
+    pub const VERSION: u8 = 1;
+    /// Bytes appended since the unversioned layout
+    pub const LAYOUT_GROWTH: usize = 1;
+
+    // Byte offsets (including the discriminator) of fixed-position fields,
+    // for getProgramAccounts memcmp filters
+    pub const OWNER_WALLET_OFFSET: usize = 8;
+    pub const VERIFIED_OFFSET: usize = Self::OWNER_WALLET_OFFSET + 32 + 16;
+    pub const IS_PUBLIC_OFFSET: usize = Self::VERIFIED_OFFSET + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct ProfileVersion {
+    pub model_profile: Pubkey,        // 32
+    pub version: u32,                 // 4
+    pub label: String,                // 4 + 64
+    pub provider_uri: String,         // 4 + 200
+    pub pricing: u64,                 // 8
+    pub billing_wallet: Pubkey,       // 32
+    pub max_tokens_per_day: u64,      // 8
+    pub max_requests_per_min: u64,    // 8
+    pub created_at: i64,              // 8
+    pub bump: u8,                     // 1
+}
+
+impl ProfileVersion {
+    pub const MAX_SIZE: usize = 32 + 4 + (4 + 64) + (4 + 200) + 8 + 32 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct ProfileAuthorization {
+    pub model_profile: Pubkey,     // 32
+    pub agent: Pubkey,             // 32
+    pub granted_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl ProfileAuthorization {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct CrankTreasury {
+    pub funded: u64,               // 8 (lamports deposited over its lifetime)
+    pub paid: u64,                 // 8 (lamports tipped to crankers)
+    pub bump: u8,                  // 1
+}
+
+impl CrankTreasury {
+    pub const MAX_SIZE: usize = 8 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct Delegation {
+    pub grantor: Pubkey,           // 32 (agent whose permissions are lent)
+    pub delegate: Pubkey,          // 32 (agent acting for it)
+    pub permissions: u64,          // 8 (subset of the grantor's)
+    pub expires: Deadline,         // 9
+    pub granted_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl Delegation {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + Deadline::SIZE + 8 + 1;
+
+    /// Whether the delegation grants `permission` right now. A freeze of
+    /// the grantor revokes every delegation granted up to it.
+    pub fn allows(&self, grantor: &AgentIdentity, permission: u64, clock: &Clock, config: &ProtocolConfig) -> bool {
+        self.permissions & permission != 0
+            && !self.expires.passed(clock, config)
+            && self.granted_at > grantor.delegations_revoked_at
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct ForeignEmitter {
+    pub chain: u16,                // 2 (Wormhole chain id)
+    pub address: [u8; 32],         // 32 (agent-mesh contract on that chain)
+    pub bump: u8,                  // 1
+}
+
+impl ForeignEmitter {
+    pub const MAX_SIZE: usize = 2 + 32 + 1;
+}
+
+/// Owner of a remote agent's `AgentIdentity`, at
+/// `["remote_agent", chain, address]`
+#[account]
+#[derive(Default)]
+pub struct RemoteAgent {
+    pub agent: Pubkey,             // 32 (AgentIdentity this PDA owns)
+    pub chain: u16,                // 2 (Wormhole chain id)
+    pub address: [u8; 32],         // 32 (agent's address on that chain)
+    pub last_sequence: u64,        // 8 (of the last RegisterAgent message applied)
+    pub bump: u8,                  // 1
+}
+
+impl RemoteAgent {
+    pub const MAX_SIZE: usize = 32 + 2 + 32 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct PayoutVault {
+    pub model_profile: Pubkey,     // 32
+    pub mint: Pubkey,              // 32
+    pub vault: Pubkey,             // 32 (token account owned by this PDA)
+    pub sweep_interval: i64,       // 8 (seconds)
+    pub last_sweep_at: i64,        // 8
+    pub accrued: u64,              // 8 (settled since last sweep)
+    pub bump: u8,                  // 1
+}
+
+impl PayoutVault {
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct FeeAllowlist {
+    pub subject: Pubkey,           // 32 (agent PDA or paying program/wallet)
+    pub discount_bps: u16,         // 2 (10_000 = fee-exempt)
+    pub added_at: i64,             // 8
+    pub bump: u8,                  // 1
+}
+
+impl FeeAllowlist {
+    pub const MAX_SIZE: usize = 32 + 2 + 8 + 1;
+
+    /// Entries match either agent on the intent, or whoever funded it
+    pub fn applies_to(&self, intent: &AgentIntent) -> bool {
+        self.subject == intent.from_agent
+            || self.subject == intent.provider()
+            || self.subject == intent.payer
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct InsurancePool {
+    pub mint: Pubkey,              // 32
+    pub vault: Pubkey,             // 32
+    pub total_premiums: u64,       // 8
+    pub bump: u8,                  // 1
+}
+
+impl InsurancePool {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct CapacityBooking {
+    pub from_agent: Pubkey,        // 32
+    pub to_agent: Pubkey,          // 32
+    pub payer: Pubkey,             // 32
+    pub payment_mint: Pubkey,      // 32
+    pub vault: Pubkey,             // 32 (token account owned by this PDA)
+    pub billing_wallet: Pubkey,    // 32 (receives forfeited deposit)
+    pub nonce: u64,                // 8
+    pub locked_price: u64,         // 8 (profile pricing at booking time)
+    pub tokens_per_hour: u64,      // 8
+    pub tokens_reserved: u64,      // 8
+    pub tokens_used: u64,          // 8
+    pub deposit_remaining: u64,    // 8
+    pub start: Deadline,           // 9
+    pub end: Deadline,             // 9
+    pub bump: u8,                  // 1
+}
+
+impl CapacityBooking {
+    pub const MAX_SIZE: usize = 32 * 6 + 8 * 6 + Deadline::SIZE * 2 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct TrialAccount {
+    pub model_profile: Pubkey,     // 32
+    pub agent: Pubkey,             // 32 (consumer agent)
+    pub owner: Pubkey,             // 32 (consumer's owner wallet, receives the refund)
+    pub vault: Pubkey,             // 32 (token account owned by this PDA)
+    pub deposit: u64,              // 8
+    pub requests_total: u16,       // 2
+    pub requests_used: u16,        // 2
+    pub started_at: i64,           // 8
+    pub expires: Deadline,         // 9
+    pub settled: bool,             // 1 (deposit refunded or forfeited)
+    pub bump: u8,                  // 1
+}
+
+impl TrialAccount {
+    pub const MAX_SIZE: usize = 32 * 4 + 8 + 2 + 2 + 8 + Deadline::SIZE + 1 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct ReceiptTree {
+    pub agent: Pubkey,             // 32
+    pub merkle_tree: Pubkey,       // 32
+    pub num_receipts: u64,         // 8 (next leaf index)
+    pub bump: u8,                  // 1
+}
+
+impl ReceiptTree {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + 1;
+}
+
+/// Swap instruction for converting a payout at release. The route's accounts
+/// are passed as remaining accounts; the intent PDA signs as the escrow owner.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapRoute {
+    /// Output amount the route was quoted at, before the slippage bound;
+    /// set by the settling assignee owner, who bears a low quote
+    pub quoted_out: u64,
+    pub data: Vec<u8>,
+}
+
+/// Off-chain intent authorization signed by the from-agent's `agent_wallet`.
+/// `nonce` is the pair's `NonceCounter::next`; landing the intent bumps it,
+/// so the signature can't be replayed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SignedIntent {
+    pub program_id: Pubkey,
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    pub nonce: u64,
+    pub payload: ContentCommitment,
+    pub payload_uri: String,
+    pub payment_amount: u64,
+    pub payment_mint: Pubkey,
+    pub options: u8,
+    pub expires: Deadline,
+}
+
+impl SignedIntent {
+    pub const DOMAIN: &'static [u8] = b"agent-mesh:signed-intent:v3";
+
+    /// Bytes the agent wallet signs: the domain tag followed by the borsh encoding
+    pub fn to_message(&self) -> Result<Vec<u8>> {
+        let mut message = Self::DOMAIN.to_vec();
+        message.extend(self.try_to_vec()?);
+        Ok(message)
+    }
+}
+
+/// x402 payment requirements for one request, signed by the provider's
+/// `agent_wallet`. Binding the quote to the requester and a nonce makes the
+/// intent PDA its single-use receipt.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct X402Quote {
+    pub program_id: Pubkey,
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    /// The pair's `NonceCounter::next` when quoted; the quote goes stale if
+    /// another intent between the agents lands first
+    pub nonce: u64,
+    /// sha256 of the requested resource; becomes the intent's payload hash
+    pub resource_hash: [u8; 32],
+    pub payment_mint: Pubkey,
+    pub amount: u64,
+    pub expires: Deadline,
+}
+
+impl X402Quote {
+    pub const DOMAIN: &'static [u8] = b"agent-mesh:x402-quote:v1";
+
+    /// Bytes the provider's agent wallet signs: the domain tag followed by the borsh encoding
+    pub fn to_message(&self) -> Result<Vec<u8>> {
+        let mut message = Self::DOMAIN.to_vec();
+        message.extend(self.try_to_vec()?);
+        Ok(message)
+    }
+}
+
+/// Executing agent's claim over a result, signed by the assignee's
+/// `agent_wallet` and checked by `submit_result`. It proves which machine
+/// key produced the result, not just which owner settled the intent.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ResultAttestation {
+    pub program_id: Pubkey,
+    pub intent: Pubkey,
+    pub result_hash: [u8; 32],
+}
+
+impl ResultAttestation {
+    pub const DOMAIN: &'static [u8] = b"agent-mesh:result-attestation:v1";
+
+    /// Bytes the agent wallet signs: the domain tag followed by the borsh encoding
+    pub fn to_message(&self) -> Result<Vec<u8>> {
+        let mut message = Self::DOMAIN.to_vec();
+        message.extend(self.try_to_vec()?);
+        Ok(message)
+    }
+}
+
+/// Body of a Wormhole `PostedVAA` account, after `POSTED_VAA_MAGIC`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PostedVaa {
+    pub vaa_version: u8,
+    pub consistency_level: u8,
+    pub vaa_time: u32,
+    pub vaa_signature_account: Pubkey,
+    pub submission_time: u32,
+    pub nonce: u32,
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+/// Message the agent-mesh contract on another chain publishes through
+/// Wormhole on behalf of one of its agents. The VAA payload is `DOMAIN`
+/// followed by the borsh encoding.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BridgeMessage {
+    pub program_id: Pubkey,
+    /// The acting agent's address on the emitting chain, left-padded to 32 bytes
+    pub sender: [u8; 32],
+    pub action: BridgeAction,
+}
+
+impl BridgeMessage {
+    pub const DOMAIN: &'static [u8] = b"agent-mesh:bridge:v1";
+
+    /// Bytes the remote contract publishes: the domain tag followed by the borsh encoding
+    pub fn to_payload(&self) -> Result<Vec<u8>> {
+        let mut payload = Self::DOMAIN.to_vec();
+        payload.extend(self.try_to_vec()?);
+        Ok(payload)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum BridgeAction {
+    /// Register or update the sender's identity (`register_remote_agent`)
+    RegisterAgent {
+        model_profile: Pubkey,
+        permissions: u64,
+        metadata_uri: String,
+    },
+    /// Open an intent from the sender's identity at the pair's next nonce
+    /// (`submit_bridged_intent`)
+    CreateIntent {
+        to_agent: Pubkey,
+        nonce: u64,
+        payload: ContentCommitment,
+        payload_uri: String,
+        payment_mint: Pubkey,
+        payment_amount: u64,
+        options: u8,
+    },
+    /// Complete an intent assigned to the sender's identity, paying its
+    /// share into the `payout` token account (`complete_bridged_intent`)
+    CompleteIntent {
+        intent: Pubkey,
+        result: ContentCommitment,
+        payout: Pubkey,
+    },
+}
+
+/// Leaf contents of a receipt tree; only the keccak hash is stored on-chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UsageReceipt {
+    pub intent: Pubkey,
+    pub agent: Pubkey,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub latency_ms: u32,
+    pub cost: u64,
+    pub submitted_at: i64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Dispute {
+    pub intent: Pubkey,            // 32
+    pub opener: Pubkey,            // 32 (requester's owner wallet, posted the fee)
+    pub fee: u64,                  // 8 (held in the intent escrow until resolved)
+    pub reason_uri: String,        // 4 + 200
+    pub opened_at: i64,            // 8
+    pub bump: u8,                  // 1
+}
+
+impl Dispute {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + (4 + 200) + 8 + 1;
+
+    /// Account space (with discriminator) for the given reason URI length
+    pub fn space(reason_len: usize) -> usize {
+        8 + Self::MAX_SIZE - DEFAULT_URI_LEN + reason_len.max(DEFAULT_URI_LEN)
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct InsuranceClaim {
+    pub intent: Pubkey,            // 32
+    pub claimant: Pubkey,          // 32 (requester's owner wallet)
+    pub amount: u64,               // 8 (requested, at most the intent's reimbursement_cap)
+    pub reason_uri: String,        // 4 + 200
+    pub filed_at: i64,             // 8
+    pub bump: u8,                  // 1
+}
+
+impl InsuranceClaim {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + (4 + 200) + 8 + 1;
+
+    /// Account space (with discriminator) for the given reason URI length
+    pub fn space(reason_len: usize) -> usize {
+        8 + Self::MAX_SIZE - DEFAULT_URI_LEN + reason_len.max(DEFAULT_URI_LEN)
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct ContextRef {
+    pub agent: Pubkey,             // 32 (agent that committed the context)
+    pub owner: Pubkey,             // 32 (agent's owner wallet, may close it)
+    pub context_hash: [u8; 32],    // 32
+    pub context_uri: String,       // 4 + 200
+    pub created_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl ContextRef {
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + (4 + 200) + 8 + 1;
+
+    /// Account space (with discriminator) for the given context URI length
+    pub fn space(uri_len: usize) -> usize {
+        8 + Self::MAX_SIZE - DEFAULT_URI_LEN + uri_len.max(DEFAULT_URI_LEN)
+    }
+}
+
+/// Reusable intent terms an agent publishes; `create_intent_from_template`
+/// stamps intents out of it
+#[account]
+#[derive(Default)]
+pub struct IntentTemplate {
+    pub agent: Pubkey,             // 32 (agent that published it)
+    pub owner: Pubkey,             // 32 (agent's owner wallet, may close it)
+    pub template_id: [u8; 16],     // 16
+    pub schema_hash: [u8; 32],     // 32 (hash of the payload schema stamped intents follow)
+    pub default_payment: u64,      // 8
+    pub completion_window: TimeSpan, // 9 (after acceptance, zero = config's)
+    pub required_permissions: u64, // 8 (Permission bits the provider must hold)
+    pub created_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl IntentTemplate {
+    pub const MAX_SIZE: usize = 32 + 32 + 16 + 32 + 8 + TimeSpan::SIZE + 8 + 8 + 1;
+
+    /// Whether `agent` holds every permission the template requires
+    pub fn permits(&self, agent: &AgentIdentity) -> bool {
+        agent.permissions & self.required_permissions == self.required_permissions
+    }
+}
+
+/// Registry entry for one payload contract: whoever registered `schema_id`
+/// publishes its versions as `PayloadSchema` accounts
+#[account]
+#[derive(Default)]
+pub struct SchemaRegistry {
+    pub schema_id: [u8; 16],       // 16
+    pub agent: Pubkey,             // 32 (agent that registered it)
+    pub owner: Pubkey,             // 32 (agent's owner wallet, publishes versions)
+    pub latest_version: u32,       // 4 (versions start at 1)
+    pub created_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl SchemaRegistry {
+    pub const MAX_SIZE: usize = 16 + 32 + 32 + 4 + 8 + 1;
+
+    /// Version the next `publish_schema_version` creates
+    pub fn next_version(&self) -> u32 {
+        self.latest_version.saturating_add(1)
+    }
+}
+
+/// One immutable version of a registered schema
+#[account]
+#[derive(Default)]
+pub struct PayloadSchema {
+    pub registry: Pubkey,          // 32
+    pub version: u32,              // 4
+    pub schema_hash: [u8; 32],     // 32 (hash of the schema document)
+    pub media_type: String,        // 4 + MAX_MEDIA_TYPE_LEN (of payloads following it)
+    pub schema_uri: String,        // 4 + 200
+    pub deprecated: bool,          // 1 (new intents may no longer reference it)
+    pub published_at: i64,         // 8
+    pub bump: u8,                  // 1
+}
+
+impl PayloadSchema {
+    pub const MAX_SIZE: usize = 32 + 4 + 32 + (4 + MAX_MEDIA_TYPE_LEN) + (4 + 200) + 1 + 8 + 1;
+
+    /// Account space (with discriminator) for the given schema URI length
+    pub fn space(uri_len: usize) -> usize {
+        8 + Self::MAX_SIZE - DEFAULT_URI_LEN + uri_len.max(DEFAULT_URI_LEN)
+    }
+}
+
+/// Off-intent messaging between two agents. `agent_a` sorts before
+/// `agent_b`, so each pair has a single channel.
+#[account]
+#[derive(Default)]
+pub struct MessageChannel {
+    pub agent_a: Pubkey,           // 32
+    pub agent_b: Pubkey,           // 32
+    pub rent_payer: Pubkey,        // 32 (opened the channel, refunded on close)
+    pub message_count: u64,        // 8 (also the next message's sequence)
+    pub last_message_hash: [u8; 32], // 32
+    pub last_message_at: i64,      // 8
+    pub opened_at: i64,            // 8
+    pub bump: u8,                  // 1
+}
+
+impl MessageChannel {
+    pub const MAX_SIZE: usize = 32 * 3 + 8 + 32 + 8 + 8 + 1;
+
+    /// The other agent, if `agent` is on this channel
+    pub fn peer(&self, agent: &Pubkey) -> Option<Pubkey> {
+        if *agent == self.agent_a {
+            Some(self.agent_b)
+        } else if *agent == self.agent_b {
+            Some(self.agent_a)
+        } else {
+            None
+        }
+    }
+}
+
+/// Upstream recipient of part of the provider's payout
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct RoyaltySplit {
+    /// Owner of the token account the royalty is paid into
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+
+#[account]
+#[derive(Default)]
+pub struct RoyaltySchedule {
+    pub intent: Pubkey,            // 32
+    pub owner: Pubkey,             // 32 (requester's owner wallet, paid the rent)
+    pub schema_hash: [u8; 32],     // 32 (payload schema declaring the splits)
+    pub splits: Vec<RoyaltySplit>, // 4 + MAX_ROYALTY_SPLITS * 34
+    pub bump: u8,                  // 1
+}
+
+impl RoyaltySchedule {
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + (4 + MAX_ROYALTY_SPLITS * (32 + 2)) + 1;
+}
+
+/// Executing agent of a team intent and its share of the payment
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TeamShare {
+    pub agent: Pubkey,
+    pub share_bps: u16,
+}
+
+/// One member's slot in an [`IntentTeam`]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TeamMember {
+    pub agent: Pubkey,             // 32
+    pub share_bps: u16,            // 2 (of the payment after the protocol fee)
+    pub result: ContentCommitment, // 41
+    pub payout: Pubkey,            // 32 (token account the share is paid into)
+    pub completed_at: i64,         // 8 (0 until the member submits its result)
+}
+
+impl TeamMember {
+    pub const SIZE: usize = 32 + 2 + (1 + 8 + 32) + 32 + 8;
+}
+
+/// Executing agents of an intent, each with a share of the escrow and its
+/// own result slot; settled once `quorum` of them have submitted
+#[account]
+#[derive(Default)]
+pub struct IntentTeam {
+    pub intent: Pubkey,            // 32
+    pub owner: Pubkey,             // 32 (requester's owner wallet, paid the rent)
+    pub quorum: u8,                // 1
+    pub completed: u8,             // 1 (members that have submitted)
+    pub members: Vec<TeamMember>,  // 4 + MAX_TEAM_MEMBERS * TeamMember::SIZE
+    pub bump: u8,                  // 1
+}
+
+impl IntentTeam {
+    pub const MAX_SIZE: usize = 32 + 32 + 1 + 1 + (4 + MAX_TEAM_MEMBERS * TeamMember::SIZE) + 1;
+}
+
+/// A provider's offer on an open intent
+#[account]
+#[derive(Default)]
+pub struct Bid {
+    pub intent: Pubkey,            // 32
+    pub agent: Pubkey,             // 32 (bidding provider)
+    pub owner: Pubkey,             // 32 (provider's owner wallet, paid the rent)
+    pub price: u64,                // 8
+    pub eta: i64,                  // 8 (seconds to complete once accepted)
+    pub placed_at: i64,            // 8
+    pub bump: u8,                  // 1
+}
+
+impl Bid {
+    pub const MAX_SIZE: usize = 32 * 3 + 8 + 8 + 8 + 1;
+}
+
+/// How much of one mint an agent may move out of its owner's token accounts
+/// per day, on top of the delegated allowance the token accounts grant
+#[account]
+#[derive(Default)]
+pub struct SpendLimit {
+    pub agent: Pubkey,             // 32
+    pub mint: Pubkey,              // 32
+    pub daily_cap: u64,            // 8
+    pub spent_today: u64,          // 8
+    pub day_started_at: i64,       // 8 (start of the current SPEND_LIMIT_WINDOW)
+    pub destination_count: u8,     // 1 (0 = transfers may go anywhere)
+    pub destinations: [Pubkey; MAX_TRANSFER_DESTINATIONS], // 32 * MAX_TRANSFER_DESTINATIONS (token account owners)
+    pub bump: u8,                  // 1
+}
+
+impl SpendLimit {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + 8 + 8 + 1 + 32 * MAX_TRANSFER_DESTINATIONS + 1;
+
+    pub fn allows_destination(&self, owner: &Pubkey) -> bool {
+        self.destination_count == 0
+            || self.destinations[..self.destination_count as usize].contains(owner)
+    }
+
+    /// Count `amount` against today's cap, starting a new day once the
+    /// current one has run its full window
+    pub fn record(&mut self, amount: u64, now: i64) -> Result<()> {
+        if now >= self.day_started_at.saturating_add(SPEND_LIMIT_WINDOW) {
+            self.day_started_at = now;
+            self.spent_today = 0;
+        }
+        let spent = self
+            .spent_today
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(spent <= self.daily_cap, ErrorCode::SpendLimitExceeded);
+        self.spent_today = spent;
+        Ok(())
+    }
+}
+
+/// Tokens an owner set aside for its agent's intents. The budget PDA owns
+/// `vault`; `create_intent` pays from it, signed by the agent wallet, within
+/// the caps
+#[account]
+#[derive(Default)]
+pub struct AgentBudget {
+    pub agent: Pubkey,             // 32
+    pub mint: Pubkey,              // 32
+    pub vault: Pubkey,             // 32 (token account owned by the budget PDA)
+    pub daily_cap: u64,            // 8 (0 = no daily cap)
+    pub total_cap: u64,            // 8 (0 = no lifetime cap)
+    pub spent_today: u64,          // 8
+    pub day_started_at: i64,       // 8 (start of the current SPEND_LIMIT_WINDOW)
+    pub total_spent: u64,          // 8 (refunds don't count back)
+    pub bump: u8,                  // 1
+}
+
+impl AgentBudget {
+    pub const MAX_SIZE: usize = 32 * 3 + 8 * 5 + 1;
+
+    /// Count `amount` against both caps, starting a new day once the
+    /// current one has run its full window
+    pub fn record(&mut self, amount: u64, now: i64) -> Result<()> {
+        if now >= self.day_started_at.saturating_add(SPEND_LIMIT_WINDOW) {
+            self.day_started_at = now;
+            self.spent_today = 0;
+        }
+        let spent_today = self
+            .spent_today
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let total_spent = self
+            .total_spent
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            (self.daily_cap == 0 || spent_today <= self.daily_cap)
+                && (self.total_cap == 0 || total_spent <= self.total_cap),
+            ErrorCode::BudgetExceeded
+        );
+        self.spent_today = spent_today;
+        self.total_spent = total_spent;
+        Ok(())
+    }
+}
+
+/// One agent's seat in an [`AgentGroup`]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct GroupMember {
+    pub agent: Pubkey,             // 32
+    pub roles: u8,                 // 1 (GroupRole flags, 0 = plain member)
+}
+
+impl GroupMember {
+    pub const SIZE: usize = 32 + 1;
+}
+
+/// A multi-agent organization. The group PDA owns the treasury (its token
+/// accounts); members can route their earnings into it, and `TREASURER`
+/// members spend from it.
+#[account]
+#[derive(Default)]
+pub struct AgentGroup {
+    pub founder: Pubkey,           // 32 (agent that created the group)
+    pub group_id: [u8; 16],        // 16
+    pub members: Vec<GroupMember>, // 4 + MAX_GROUP_MEMBERS * GroupMember::SIZE
+    pub created_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl AgentGroup {
+    pub const MAX_SIZE: usize = 32 + 16 + (4 + MAX_GROUP_MEMBERS * GroupMember::SIZE) + 8 + 1;
+
+    /// `GroupRole` flags of `agent`, 0 if it isn't a member
+    pub fn roles_of(&self, agent: &Pubkey) -> u8 {
+        self.members
+            .iter()
+            .find(|member| member.agent == *agent)
+            .map_or(0, |member| member.roles)
+    }
+
+    pub fn has_admin(&self) -> bool {
+        self.members.iter().any(|member| member.roles & GroupRole::ADMIN != 0)
+    }
+}
+
+/// Owner's approval for the agent to vote on one spl-governance proposal
+#[account]
+#[derive(Default)]
+pub struct VoteApproval {
+    pub agent: Pubkey,              // 32
+    pub governance_program: Pubkey, // 32 (spl-governance instance hosting the realm)
+    pub realm: Pubkey,              // 32
+    pub proposal: Pubkey,           // 32
+    pub approved_at: i64,           // 8
+    pub bump: u8,                   // 1
+}
+
+impl VoteApproval {
+    pub const MAX_SIZE: usize = 32 * 4 + 8 + 1;
+}
+
+/// spl-governance `VoteChoice`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GovernanceVoteChoice {
+    pub rank: u8,
+    pub weight_percentage: u8,
+}
+
+/// spl-governance `Vote`, serialized as-is into the CPI
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum GovernanceVote {
+    /// One choice per proposal option
+    Approve(Vec<GovernanceVoteChoice>),
+    Deny,
+    Abstain,
+    Veto,
+}
+
+/// Token Metadata `CreateMetadataAccountArgsV3` with its `DataV2` inlined,
+/// serialized as-is into the badge CPI
+#[derive(AnchorSerialize)]
+struct BadgeMetadataArgs {
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<BadgeCreator>>,
+    // Unused; always `None`, which serializes the same whatever the type
+    collection: Option<u8>,
+    uses: Option<u8>,
+    is_mutable: bool,
+    collection_details: Option<u8>,
+}
+
+/// Token Metadata `Creator`
+#[derive(AnchorSerialize)]
+struct BadgeCreator {
+    address: Pubkey,
+    verified: bool,
+    share: u8,
+}
+
+/// Reward escrowed against a spec, awarded to an agent by the reviewer
+#[account]
+#[derive(Default)]
+pub struct Bounty {
+    pub sponsor: Pubkey,           // 32 (funded the reward, receives refunds and rent)
+    pub reviewer: Pubkey,          // 32 (awards or withdraws the bounty)
+    pub mint: Pubkey,              // 32
+    pub vault: Pubkey,             // 32 (token account owned by this PDA)
+    pub spec_hash: [u8; 32],       // 32
+    pub spec_uri: String,          // 4 + 200
+    pub amount: u64,               // 8 (reward as posted)
+    pub expires: Deadline,         // 9 (sponsor may withdraw once passed)
+    pub created_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl Bounty {
+    pub const MAX_SIZE: usize = 32 * 4 + 32 + (4 + 200) + 8 + Deadline::SIZE + 8 + 1;
+
+    /// Account space (with discriminator) for the given spec URI length
+    pub fn space(uri_len: usize) -> usize {
+        8 + Self::MAX_SIZE - DEFAULT_URI_LEN + uri_len.max(DEFAULT_URI_LEN)
+    }
+}
+
+/// Reverse lookup from an agent to the `.sol` domain bound to it. Forward
+/// lookup needs no account: the domain's owner derives the agent PDA.
+#[account]
+#[derive(Default)]
+pub struct AgentName {
+    pub agent: Pubkey,             // 32
+    pub owner: Pubkey,             // 32 (agent's owner wallet, held the domain when bound)
+    pub name_record: Pubkey,       // 32 (Name Service record of the domain)
+    pub name: String,              // 4 + 64 (label without `.sol`)
+    pub bound_at: i64,             // 8
+    pub bump: u8,                  // 1
+}
+
+impl AgentName {
+    pub const MAX_SIZE: usize = 32 * 3 + (4 + MAX_SOL_NAME_LEN) + 8 + 1;
+}
+
+/// Intents an agent opened in the current bucket of `intent_rate_window`
+/// slots, checked against the config's `max_intents_per_window`
+#[account]
+#[derive(Default)]
+pub struct IntentRateLimit {
+    pub agent: Pubkey,             // 32
+    pub bucket: u64,               // 8 (slot / intent_rate_window)
+    pub count: u16,                // 2
+    pub bump: u8,                  // 1
+}
+
+impl IntentRateLimit {
+    pub const MAX_SIZE: usize = 32 + 8 + 2 + 1;
+
+    /// Count an intent opened by `agent`, which the caller has authenticated,
+    /// failing once the current bucket is full
+    pub fn record(&mut self, agent: Pubkey, bump: u8, clock: &Clock, config: &ProtocolConfig) -> Result<()> {
+        if self.agent == Pubkey::default() {
+            self.agent = agent;
+            self.bump = bump;
+        }
+        let bucket = clock.slot / config.intent_rate_window.max(1);
+        if self.bucket != bucket {
+            self.bucket = bucket;
+            self.count = 0;
+        }
+        require!(
+            config.max_intents_per_window == 0 || self.count < config.max_intents_per_window,
+            ErrorCode::IntentRateLimited
+        );
+        // Unlimited agents can open more intents per bucket than a u16 holds
+        self.count = self.count.saturating_add(1);
+        Ok(())
+    }
+}
+
+/// Next intent nonce between two agents, taken and bumped by every intent
+/// the from-agent opens toward the to-agent
+#[account]
+#[derive(Default)]
+pub struct NonceCounter {
+    pub from_agent: Pubkey,        // 32
+    pub to_agent: Pubkey,          // 32
+    pub next: u64,                 // 8
+    pub bump: u8,                  // 1
+}
+
+impl NonceCounter {
+    pub const MAX_SIZE: usize = 32 * 2 + 8 + 1;
+}
+
+/// How many intents an agent has sent or received, so clients can find its
+/// `IntentIndexPage`s without a program-wide scan
+#[account]
+#[derive(Default)]
+pub struct IntentIndex {
+    pub agent: Pubkey,             // 32
+    pub role: u8,                  // 1 (IndexRole)
+    pub count: u64,                // 8
+    pub bump: u8,                  // 1
+}
+
+impl IntentIndex {
+    pub const MAX_SIZE: usize = 32 + 1 + 8 + 1;
+
+    /// Page the next intent is appended to
+    pub fn next_page(&self) -> u32 {
+        (self.count / INTENT_INDEX_PAGE_LEN as u64) as u32
+    }
+}
+
+/// Up to `INTENT_INDEX_PAGE_LEN` of an agent's intents in creation order.
+/// Entries are never removed, so closed intents leave dangling addresses.
+#[account]
+#[derive(Default)]
+pub struct IntentIndexPage {
+    pub agent: Pubkey,             // 32
+    pub role: u8,                  // 1
+    pub page: u32,                 // 4
+    pub intents: Vec<Pubkey>,      // 4 + 32 * INTENT_INDEX_PAGE_LEN
+    pub bump: u8,                  // 1
+}
+
+impl IntentIndexPage {
+    pub const MAX_SIZE: usize = 32 + 1 + 4 + (4 + 32 * INTENT_INDEX_PAGE_LEN) + 1;
+}
+
+/// Running totals of an agent's intents, kept by the lifecycle instructions
+/// so dashboards and routers read them in one fetch instead of replaying
+/// events. Outcomes count toward the intent's assignee.
+#[account]
+#[derive(Default)]
+pub struct AgentStats {
+    pub agent: Pubkey,             // 32
+    pub intents_sent: u64,         // 8
+    pub intents_received: u64,     // 8 (addressed to it at creation; open intents don't count)
+    pub completed: u64,            // 8
+    pub failed: u64,               // 8
+    pub volume: u64,               // 8 (payments of completed intents, in base units of their mints)
+    pub total_completion_time: i64, // 8 (seconds from acceptance to completion, summed over completed)
+    pub updated_at: i64,           // 8
+    pub bump: u8,                  // 1
+}
+
+impl AgentStats {
+    pub const MAX_SIZE: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Fill in a freshly created account
+    pub fn init(&mut self, agent: Pubkey, bump: u8) {
+        if self.agent == Pubkey::default() {
+            self.agent = agent;
+            self.bump = bump;
+        }
+    }
+
+    /// Zero the totals, keeping the account's identity
+    pub fn reset(&mut self) {
+        *self = AgentStats {
+            agent: self.agent,
+            bump: self.bump,
+            ..Default::default()
+        };
+    }
+
+    /// Mean seconds from acceptance to completion, 0 before any completion
+    pub fn average_completion_time(&self) -> i64 {
+        if self.completed == 0 {
+            0
+        } else {
+            self.total_completion_time / self.completed as i64
+        }
+    }
+
+    /// Fold the move of an intent assigned to the agent from
+    /// `previous_status` to its current status into the totals
+    pub fn record_outcome(&mut self, intent: &AgentIntent, previous_status: u8, now: i64) -> Result<()> {
+        let completed = IntentStatus::Completed as u8;
+        if intent.status == previous_status {
+            return Ok(());
+        }
+        if previous_status == completed {
+            // A dispute overturned the completion
+            self.completed = self.completed.saturating_sub(1);
+            self.volume = self.volume.saturating_sub(intent.payment_amount);
+            self.total_completion_time = self
+                .total_completion_time
+                .saturating_sub(intent.completion_time());
+        }
+        if intent.status == completed {
+            self.completed = self.completed.saturating_add(1);
+            self.volume = self
+                .volume
+                .checked_add(intent.payment_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.total_completion_time = self
+                .total_completion_time
+                .saturating_add(intent.completion_time());
+        } else if intent.status == IntentStatus::Failed as u8 {
+            self.failed = self.failed.saturating_add(1);
+        }
+        self.updated_at = now;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct AgentIntent {
+    pub from_agent: Pubkey,        // 32
+    pub to_agent: Pubkey,          // 32
+    pub payer: Pubkey,             // 32 (funded the escrow, receives refunds)
+    pub rent_payer: Pubkey,        // 32 (funded intent/escrow rent, refunded on close)
+    pub nonce: u64,                // 8
+    pub status: u8,                // 1
+    pub payload_hash: [u8; 32],    // 32
+    pub payload_scheme: u8,        // 1 (CommitmentScheme of payload_hash)
+    pub payload_length: u64,       // 8 (bytes behind payload_uri)
+    pub payload_uri: String,       // 4 + 200
+    pub sealed_to: [u8; 32],       // 32 (provider X25519 key the payload is sealed to, zero = plaintext)
+    pub ephemeral_pubkey: [u8; 32], // 32 (requester's one-time X25519 key)
+    pub encrypted_key: [u8; 32],   // 32 (payload content key, masked with the shared secret)
+    pub payment_amount: u64,       // 8
+    pub payment_mint: Pubkey,      // 32
+    pub tip_amount: u64,           // 8 (escrowed until acceptance, then paid to the provider)
+    pub options: u8,               // 1 (IntentOption flags)
+    pub delegate_agent: Pubkey,    // 32 (default if not re-delegated)
+    pub delegate_share_bps: u16,   // 2
+    pub insurance_premium: u64,    // 8 (0 if not insured)
+    pub reimbursement_cap: u64,    // 8 (max payout if the provider defaults)
+    pub profile_version: u32,      // 4 (ProfileVersion priced against, 0 if none)
+    pub booking: Pubkey,           // 32 (CapacityBooking drawn from, default if none)
+    pub trial: Pubkey,             // 32 (TrialAccount drawn from, default if none)
+    pub context: Pubkey,           // 32 (shared ContextRef, default if none)
+    pub royalty_bps: u16,          // 2 (total declared royalties, 0 if none)
+    pub team_size: u8,             // 1 (members of the declared IntentTeam, 0 if single provider)
+    pub result_hash: [u8; 32],     // 32
+    pub result_scheme: u8,         // 1 (CommitmentScheme of result_hash, 0 until set)
+    pub result_length: u64,        // 8 (bytes behind result_uri)
+    pub result_uri: String,        // 4 + 200
+    pub created_at: i64,           // 8
+    pub updated_at: i64,           // 8
+    pub accepted_at: i64,          // 8 (0 until accepted)
+    pub completed_at: i64,         // 8 (0 until completed)
+    pub cancel_deadline: Deadline, // 9 (end of the cancellation window, set on acceptance)
+    pub complete_deadline: Deadline, // 9 (flag_timeout may fail the intent once passed; default if none)
+    pub bid_deadline: Deadline,    // 9 (open intents: end of bidding, default otherwise)
+    pub release_deadline: Deadline, // 9 (end of the challenge window, set on auto-release completion)
+    pub released: bool,            // 1 (escrow paid out to the provider)
+    pub disputed: bool,            // 1 (open dispute holds the release)
+    pub release_account: Pubkey,   // 32 (deferred payout destination)
+    pub delegate_release_account: Pubkey, // 32 (deferred delegate destination)
+    pub release_fee_bps: u16,      // 2 (protocol fee locked in at completion)
+    pub sla_target: i64,           // 8 (seconds from acceptance to completion, 0 = no SLA)
+    pub sla_penalty_bps: u16,      // 2 (of the payment per hour late)
+    pub bond: u64,                 // 8 (acceptance bond held in the bond vault)
+    pub bond_payer: Pubkey,        // 32 (posted the bond, gets it back unless the intent fails)
+    pub winning_agent: Pubkey,     // 32 (open intents: provider picked by select_bid)
+    pub best_bid: Pubkey,          // 32 (open intents: lowest Bid so far)
+    pub best_bid_price: u64,       // 8
+    pub transitions: u32,          // 4 (IntentTransition events logged so far)
+    pub sequence: u64,             // 8 (bumped on every mutation after creation)
+    pub bump: u8,                  // 1
+    pub version: u8,               // 1 (account layout; fields added later go after it)
+    pub assignee_owner: Pubkey,    // 32 (v2: assignee's owner wallet once accepted; signs submit_result and settle_intent)
+    pub template: Pubkey,          // 32 (v3: IntentTemplate stamped from, default if none)
+    pub completion_window: TimeSpan, // 9 (v3: template's window after acceptance, zero = config's)
+    pub required_permissions: u64, // 8 (v3: Permission bits the assignee must hold)
+    pub payout_group: Pubkey,      // 32 (v4: AgentGroup owning the provider's payout account, default if none)
+    pub result_signer: Pubkey,     // 32 (v5: agent wallet that attested result_hash, default if unattested)
+    pub schema: Pubkey,            // 32 (v6: PayloadSchema the payload follows, default if none)
+    pub attempt: u16,              // 2 (v7: retries before this attempt, 0 for the original)
+    pub previous_attempt: Pubkey,  // 32 (v7: failed intent this one retries, default if none)
+    pub retried_as: Pubkey,        // 32 (v7: attempt that retried this one, default if not retried)
+    pub payout_mint: Pubkey,       // 32 (v8: provider paid in this mint via a swap at settlement, default = escrowed)
+    pub max_slippage_bps: u16,     // 2 (v8: tolerance on that swap below the route's quote)
+    pub booking_credit: u64,       // 8 (v9: part of the escrow drawn from the booking deposit, refunded to its vault)
+}
+
+impl AgentIntent {
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + 32 + 8 + 1 + 32 + 1 + 8 + (4 + 200) + 32 + 32 + 32 + 8 + 32 + 8 + 1 + 32 + 2 + 8 + 8 + 4 + 32 + 32 + 32 + 32 + 2 + 1 + 32 + 1 + 8 + (4 + 200) + 8 + 8 + 8 + 8 + Deadline::SIZE + Deadline::SIZE + Deadline::SIZE + Deadline::SIZE + 1 + 1 + 32 + 32 + 2 + 8 + 2 + 8 + 32 + 32 + 32 + 8 + 4 + 8 + 1 + 1 + 32 + 32 + TimeSpan::SIZE + 8 + 32 + 32 + 32 + 2 + 32 + 32 + 32 + 2 + 8;
+
+    /// Layout version written by this program (see `migrate_intent`)
+    pub const VERSION: u8 = 9;
+    /// Bytes appended since the unversioned layout
+    pub const LAYOUT_GROWTH: usize = 1 + 32 + 32 + TimeSpan::SIZE + 8 + 32 + 32 + 32 + 2 + 32 + 32 + 32 + 2 + 8;
+
+    // Byte offsets (including the discriminator) of fixed-position fields,
+    // for getProgramAccounts memcmp filters
+    pub const FROM_AGENT_OFFSET: usize = 8;
+    pub const TO_AGENT_OFFSET: usize = Self::FROM_AGENT_OFFSET + 32;
+    pub const PAYER_OFFSET: usize = Self::TO_AGENT_OFFSET + 32;
+    pub const NONCE_OFFSET: usize = Self::PAYER_OFFSET + 32 + 32;
+    pub const STATUS_OFFSET: usize = Self::NONCE_OFFSET + 8;
+
+    /// Account space (with discriminator) for the given payload/result URI lengths
+    pub fn space(payload_len: usize, result_len: usize) -> usize {
+        8 + Self::MAX_SIZE - 2 * DEFAULT_URI_LEN
+            + payload_len.max(DEFAULT_URI_LEN)
+            + result_len.max(DEFAULT_URI_LEN)
+    }
+
+    pub fn payload_commitment(&self) -> ContentCommitment {
+        ContentCommitment {
+            scheme: self.payload_scheme,
+            length: self.payload_length,
+            hash: self.payload_hash,
+        }
+    }
+
+    pub fn result_commitment(&self) -> ContentCommitment {
+        ContentCommitment {
+            scheme: self.result_scheme,
+            length: self.result_length,
+            hash: self.result_hash,
+        }
+    }
+
+    /// Seconds from acceptance (or creation, if completed straight from
+    /// pending) to completion
+    pub fn completion_time(&self) -> i64 {
+        let started = if self.accepted_at > 0 { self.accepted_at } else { self.created_at };
+        self.completed_at.saturating_sub(started)
+    }
+
+    /// Enforce `IntentOption::ATTESTED_RESULT` before completion
+    pub fn require_attested_result(&self) -> Result<()> {
+        require!(
+            self.options & IntentOption::ATTESTED_RESULT == 0 || self.result_signer != Pubkey::default(),
+            ErrorCode::ResultNotAttested
+        );
+        Ok(())
+    }
+
+    /// Accepted and past its completion deadline, undisputed
+    pub fn timed_out(&self, clock: &Clock, config: &ProtocolConfig) -> bool {
+        self.status == IntentStatus::Accepted as u8
+            && !self.disputed
+            && self.complete_deadline != Deadline::default()
+            && self.complete_deadline.passed(clock, config)
+    }
+
+    /// Completed with the escrow still held for the challenge window
+    pub fn release_pending(&self) -> bool {
+        self.status == IntentStatus::Completed as u8 && !self.released && self.payment_amount > 0
+    }
+
+    /// Record a mutation at `now`. Events carry the resulting `sequence` so
+    /// consumers can order updates to an intent without relying on timestamps.
+    pub fn touch(&mut self, now: i64) {
+        self.sequence = self.sequence.saturating_add(1);
+        self.updated_at = now;
+    }
+
+    /// The agent currently responsible for executing the intent
+    pub fn assignee(&self) -> Pubkey {
+        if self.delegate_agent != Pubkey::default() {
+            self.delegate_agent
+        } else {
+            self.provider()
+        }
+    }
+
+    /// The provider the intent was placed with: `to_agent`, or the winning
+    /// bidder of an open intent
+    pub fn provider(&self) -> Pubkey {
+        if self.winning_agent != Pubkey::default() {
+            self.winning_agent
+        } else {
+            self.to_agent
+        }
+    }
+
+    /// Open intent still waiting for a bid to be selected
+    pub fn awaiting_bid(&self) -> bool {
+        self.options & IntentOption::OPEN_BIDDING != 0 && self.winning_agent == Pubkey::default()
+    }
+
+    /// Whether `agent` holds the permissions the intent's template requires
+    pub fn permits(&self, agent: &AgentIdentity) -> bool {
+        agent.permissions & self.required_permissions == self.required_permissions
+    }
+
+    /// Whether the provider may be paid into `account`: anywhere, unless it
+    /// routes its earnings to a group treasury
+    pub fn pays_into(&self, account: &TokenAccount) -> bool {
+        self.payout_group == Pubkey::default() || account.owner == self.payout_group
+    }
+}
+
+// === Contexts ===
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ProtocolConfig::MAX_SIZE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetVersion<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(_agent_wallet: Pubkey, _model_profile: Pubkey, metadata_uri: String)]
+pub struct RegisterAgent<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = AgentIdentity::registration_space(agent, metadata_uri.len()),
+        seeds = [b"agent", owner.key().as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AgentStats::MAX_SIZE,
+        seeds = [b"agent_stats", agent.key().as_ref()],
+        bump
+    )]
+    pub stats: Box<Account<'info, AgentStats>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub model_profile: Account<'info, ModelProfile>,
+
+    pub profile_authorization: Option<Account<'info, ProfileAuthorization>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    // Identity badge: pass all of these to mint one to the agent wallet
+
+    /// CHECK: receives the badge; must be the registered agent wallet
+    #[account(address = _agent_wallet @ ErrorCode::InvalidBadgeAccounts)]
+    pub agent_wallet: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: created here as the badge's mint
+    #[account(mut, seeds = [b"badge", agent.key().as_ref()], bump)]
+    pub badge_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: the agent wallet's associated token account, created here
+    #[account(mut)]
+    pub badge_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Token Metadata metadata PDA, derived and checked by that program
+    #[account(mut)]
+    pub badge_metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Token Metadata master edition PDA, derived and checked by that program
+    #[account(mut)]
+    pub badge_edition: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// CHECK: pinned to the Token Metadata program
+    #[account(address = TOKEN_METADATA_PROGRAM_ID)]
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct OpenAgentStats<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentStats::MAX_SIZE,
+        seeds = [b"agent_stats", agent.key().as_ref()],
+        bump
+    )]
+    pub stats: Account<'info, AgentStats>,
+
+    #[account(seeds = [b"agent", agent.owner_wallet.as_ref()], bump = agent.bump)]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(_agent_wallet: Option<Pubkey>, _model_profile: Option<Pubkey>, metadata_uri: Option<String>)]
+pub struct UpdateAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump,
+        realloc = AgentIdentity::space(
+            metadata_uri.as_ref().map_or(agent.metadata_uri.len(), |uri| uri.len())
+        ).max(agent.to_account_info().data_len()),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    pub model_profile: Option<Account<'info, ModelProfile>>,
+
+    pub profile_authorization: Option<Account<'info, ProfileAuthorization>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAgent<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    /// The owner or the agent's guardian
+    #[account(
+        constraint = authority.key() == agent.owner_wallet
+            || (agent.guardian != Pubkey::default() && authority.key() == agent.guardian) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnfreezeAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RotateEncryptionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump,
+        constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCapacity<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    /// The owner or the agent's operating wallet
+    #[account(
+        constraint = authority.key() == agent.owner_wallet
+            || authority.key() == agent.agent_wallet @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinPayment<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump,
+        constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(profile_id: [u8; 16])]
+pub struct CreateModelProfile<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ModelProfile::MAX_SIZE,
+        seeds = [b"model_profile", owner.key().as_ref(), &profile_id],
+        bump
+    )]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateModelProfile<'info> {
+    #[account(mut)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProfileContents<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ProfileVersion::MAX_SIZE,
+        seeds = [
+            b"profile_version",
+            model_profile.key().as_ref(),
+            &(model_profile.latest_version + 1).to_le_bytes()
+        ],
+        bump
+    )]
+    pub profile_version: Account<'info, ProfileVersion>,
+
+    #[account(mut)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(mut, constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotProfile<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProfileVersion::MAX_SIZE,
+        seeds = [
+            b"profile_version",
+            model_profile.key().as_ref(),
+            &(model_profile.latest_version + 1).to_le_bytes()
+        ],
+        bump
+    )]
+    pub profile_version: Account<'info, ProfileVersion>,
+
+    #[account(mut)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPayoutVault<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PayoutVault::MAX_SIZE,
+        seeds = [b"payout_vault", model_profile.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub payout_vault: Account<'info, PayoutVault>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"payout_vault_tokens", payout_vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = payout_vault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepPayouts<'info> {
+    #[account(
+        mut,
+        seeds = [b"payout_vault", model_profile.key().as_ref(), payout_vault.mint.as_ref()],
+        bump = payout_vault.bump,
+        has_one = model_profile,
+        has_one = vault
+    )]
+    pub payout_vault: Account<'info, PayoutVault>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        constraint = billing_token_account.owner == model_profile.billing_wallet @ ErrorCode::InvalidPayoutVault
+    )]
+    pub billing_token_account: Account<'info, TokenAccount>,
+
+    /// Any keeper for scheduled sweeps; the profile owner for `sweep_now`
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GrantProfileAccess<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ProfileAuthorization::MAX_SIZE,
+        seeds = [b"profile_auth", model_profile.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub profile_authorization: Account<'info, ProfileAuthorization>,
+
+    #[account(constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    /// CHECK: Agent PDA being authorized; may not be registered yet
+    pub agent: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeProfileAccess<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"profile_auth", model_profile.key().as_ref(), profile_authorization.agent.as_ref()],
+        bump = profile_authorization.bump
+    )]
+    pub profile_authorization: Account<'info, ProfileAuthorization>,
+
+    #[account(constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrantDelegation<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + Delegation::MAX_SIZE,
+        seeds = [b"delegation", agent.key().as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump,
+        constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        seeds = [b"agent", delegate.owner_wallet.as_ref()],
+        bump = delegate.bump,
+        constraint = delegate.key() != agent.key() @ ErrorCode::InvalidDelegation
+    )]
+    pub delegate: Account<'info, AgentIdentity>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"delegation", agent.key().as_ref(), delegation.delegate.as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump,
+        constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GovernConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = authority.key() == config.parameter_authority() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGovernance<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = authority.key() == config.parameter_authority() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: governance account taking over, owned by `governance_program`
+    pub governance: UncheckedAccount<'info>,
+
+    /// CHECK: realm the governance belongs to, owned by `governance_program`
+    pub realm: UncheckedAccount<'info>,
+
+    /// CHECK: spl-governance instance hosting the realm
+    #[account(executable)]
+    pub governance_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct AddFeeAllowlist<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + FeeAllowlist::MAX_SIZE,
+        seeds = [b"fee_allowlist", subject.as_ref()],
+        bump
+    )]
+    pub fee_allowlist: Account<'info, FeeAllowlist>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeAllowlist<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"fee_allowlist", fee_allowlist.subject.as_ref()],
+        bump = fee_allowlist.bump
+    )]
+    pub fee_allowlist: Account<'info, FeeAllowlist>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsurancePool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + InsurancePool::MAX_SIZE,
+        seeds = [b"insurance_pool", mint.key().as_ref()],
+        bump
+    )]
+    pub insurance_pool: Account<'info, InsurancePool>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"insurance_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = insurance_pool
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AttestProfile<'info> {
+    #[account(mut)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = attestor @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub attestor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(context_hash: [u8; 32], context_uri: String)]
+pub struct CreateContextRef<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = ContextRef::space(context_uri.len()),
+        seeds = [b"context", agent.key().as_ref(), context_hash.as_ref()],
+        bump
+    )]
+    pub context: Account<'info, ContextRef>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseContextRef<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub context: Account<'info, ContextRef>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_id: [u8; 16])]
+pub struct PublishTemplate<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + IntentTemplate::MAX_SIZE,
+        seeds = [b"template", agent.key().as_ref(), &template_id],
+        bump
+    )]
+    pub template: Account<'info, IntentTemplate>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTemplate<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub template: Account<'info, IntentTemplate>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema_id: [u8; 16], _schema_hash: [u8; 32], _media_type: String, schema_uri: String)]
+pub struct RegisterSchema<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SchemaRegistry::MAX_SIZE,
+        seeds = [b"schema_registry", schema_id.as_ref()],
+        bump
+    )]
+    pub schema_registry: Account<'info, SchemaRegistry>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PayloadSchema::space(schema_uri.len()),
+        seeds = [b"schema", schema_registry.key().as_ref(), &1u32.to_le_bytes()],
+        bump
+    )]
+    pub schema: Account<'info, PayloadSchema>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(_schema_hash: [u8; 32], _media_type: String, schema_uri: String)]
+pub struct PublishSchemaVersion<'info> {
+    #[account(
+        mut,
+        seeds = [b"schema_registry", schema_registry.schema_id.as_ref()],
+        bump = schema_registry.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub schema_registry: Account<'info, SchemaRegistry>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PayloadSchema::space(schema_uri.len()),
+        seeds = [
+            b"schema",
+            schema_registry.key().as_ref(),
+            &schema_registry.next_version().to_le_bytes()
+        ],
+        bump
+    )]
+    pub schema: Account<'info, PayloadSchema>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeprecateSchema<'info> {
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub schema_registry: Account<'info, SchemaRegistry>,
+
+    #[account(
+        mut,
+        constraint = schema.registry == schema_registry.key() @ ErrorCode::InvalidSchema
+    )]
+    pub schema: Account<'info, PayloadSchema>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenChannel<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MessageChannel::MAX_SIZE,
+        seeds = [b"channel", agent_a.key().as_ref(), agent_b.key().as_ref()],
+        bump
+    )]
+    pub channel: Account<'info, MessageChannel>,
+
+    #[account(
+        seeds = [b"agent", agent_a.owner_wallet.as_ref()],
+        bump = agent_a.bump,
+        constraint = agent_a.key() < agent_b.key() @ ErrorCode::InvalidChannel
+    )]
+    pub agent_a: Account<'info, AgentIdentity>,
+
+    #[account(seeds = [b"agent", agent_b.owner_wallet.as_ref()], bump = agent_b.bump)]
+    pub agent_b: Account<'info, AgentIdentity>,
+
+    #[account(
+        mut,
+        constraint = owner.key() == agent_a.owner_wallet
+            || owner.key() == agent_b.owner_wallet @ ErrorCode::Unauthorized
+    )]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostMessage<'info> {
+    #[account(mut, seeds = [b"channel", channel.agent_a.as_ref(), channel.agent_b.as_ref()], bump = channel.bump)]
+    pub channel: Account<'info, MessageChannel>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = from_agent.bump
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseChannel<'info> {
+    #[account(mut, close = rent_payer)]
+    pub channel: Account<'info, MessageChannel>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: Must be whoever opened the channel
+    #[account(mut, constraint = rent_payer.key() == channel.rent_payer @ ErrorCode::Unauthorized)]
+    pub rent_payer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(payload_uri: String)]
+pub struct CreateIntent<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + NonceCounter::MAX_SIZE,
+        seeds = [b"nonce_counter", from_agent.key().as_ref(), to_agent.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + IntentRateLimit::MAX_SIZE,
+        seeds = [b"intent_rate", from_agent.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, IntentRateLimit>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + IntentIndex::MAX_SIZE,
+        seeds = [b"intent_index", from_agent.key().as_ref(), &[IndexRole::Sent as u8]],
+        bump
+    )]
+    pub sent_index: Box<Account<'info, IntentIndex>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + IntentIndexPage::MAX_SIZE,
+        seeds = [
+            b"intent_index_page",
+            from_agent.key().as_ref(),
+            &[IndexRole::Sent as u8],
+            &sent_index.next_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub sent_index_page: Box<Account<'info, IntentIndexPage>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + IntentIndex::MAX_SIZE,
+        seeds = [b"intent_index", to_agent.key().as_ref(), &[IndexRole::Received as u8]],
+        bump
+    )]
+    pub received_index: Box<Account<'info, IntentIndex>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + IntentIndexPage::MAX_SIZE,
+        seeds = [
+            b"intent_index_page",
+            to_agent.key().as_ref(),
+            &[IndexRole::Received as u8],
+            &received_index.next_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub received_index_page: Box<Account<'info, IntentIndexPage>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AgentIntent::space(payload_uri.len(), 0),
+        seeds = [
+            b"intent",
+            from_agent.key().as_ref(),
+            to_agent.key().as_ref(),
+            &nonce_counter.next.to_le_bytes()
+        ],
+        bump
+    )]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, seeds = [b"agent_stats", from_agent.key().as_ref()], bump = from_stats.bump)]
+    pub from_stats: Box<Account<'info, AgentStats>>,
+
+    /// Required unless the intent is open to bids
+    #[account(mut, seeds = [b"agent_stats", to_agent.key().as_ref()], bump = to_stats.bump)]
+    pub to_stats: Option<Account<'info, AgentStats>>,
+
+    /// CHECK: Payment mint for the intent, checked against the config's
+    /// registry and the token accounts
+    #[account(constraint = config.accepts_mint(payment_mint.key) @ ErrorCode::MintNotAccepted)]
+    pub payment_mint: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = from_token_account.mint == payment_mint.key() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub from_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key() @ ErrorCode::InvalidEscrowAccount,
+        constraint = escrow_token_account.mint == payment_mint.key() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub profile_version: Option<Account<'info, ProfileVersion>>,
+
+    /// The provider's profile; required with `profile_version`
+    pub model_profile: Option<Box<Account<'info, ModelProfile>>>,
+
+    #[account(mut)]
+    pub insurance_pool: Option<Account<'info, InsurancePool>>,
+
+    #[account(mut)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub booking: Option<Account<'info, CapacityBooking>>,
+
+    #[account(mut)]
+    pub booking_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub trial: Option<Account<'info, TrialAccount>>,
+
+    pub context: Option<Account<'info, ContextRef>>,
+
+    /// Required by create_intent_from_template
+    pub template: Option<Account<'info, IntentTemplate>>,
+
+    /// Registered schema version the payload follows
+    pub schema: Option<Account<'info, PayloadSchema>>,
+
+    /// Lets `delegate_agent`'s owner create the intent from `from_agent`,
+    /// paying with tokens the grantor approved to this PDA
+    #[account(
+        seeds = [b"delegation", from_agent.key().as_ref(), delegation.delegate.as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
+
+    /// The delegation's delegate, whose owner must be the payer
+    #[account(
+        seeds = [b"agent", delegate_agent.owner_wallet.as_ref()],
+        bump = delegate_agent.bump
+    )]
+    pub delegate_agent: Option<Account<'info, AgentIdentity>>,
+
+    /// CHECK: Instructions sysvar, required by submit_signed_intent
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+
+    /// Required by submit_bridged_intent: the from-agent's remote owner,
+    /// the registered emitter of its chain and the posted VAA
+    #[account(
+        seeds = [b"remote_agent", &remote_agent.chain.to_le_bytes(), remote_agent.address.as_ref()],
+        bump = remote_agent.bump
+    )]
+    pub remote_agent: Option<Account<'info, RemoteAgent>>,
+
+    #[account(
+        seeds = [b"foreign_emitter", &foreign_emitter.chain.to_le_bytes()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Option<Account<'info, ForeignEmitter>>,
+
+    /// CHECK: Wormhole posted VAA, checked by read_bridge_message
+    pub posted_vaa: Option<AccountInfo<'info>>,
+
+    /// Pays from this budget's vault, passed as `from_token_account`; the
+    /// payer must be the from-agent's agent or owner wallet
+    #[account(
+        mut,
+        seeds = [b"budget", from_agent.key().as_ref(), payment_mint.key().as_ref()],
+        bump = budget.bump
+    )]
+    pub budget: Option<Account<'info, AgentBudget>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(_new_status: u8, _result: Option<ContentCommitment>, result_uri: Option<String>)]
+pub struct UpdateIntentStatus<'info> {
+    #[account(
+        mut,
+        realloc = AgentIntent::space(
+            intent.payload_uri.len(),
+            result_uri.as_ref().map_or(intent.result_uri.len(), |uri| uri.len())
+        ).max(intent.to_account_info().data_len()),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump,
+        constraint = intent.assignee() == to_agent.key() @ ErrorCode::Unauthorized
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, seeds = [b"agent_stats", intent.assignee().as_ref()], bump = assignee_stats.bump)]
+    pub assignee_stats: Box<Account<'info, AgentStats>>,
+
+    #[account(mut, constraint = owner.key() == to_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub billing_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub delegate_billing_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub model_profile: Option<Account<'info, ModelProfile>>,
+
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payout_vault: Option<Account<'info, PayoutVault>>,
+
+    #[account(
+        seeds = [b"fee_allowlist", fee_allowlist.subject.as_ref()],
+        bump = fee_allowlist.bump
+    )]
+    pub fee_allowlist: Option<Account<'info, FeeAllowlist>>,
+
+    /// CHECK: Must be on the config's swap program allowlist
+    pub swap_program: Option<AccountInfo<'info>>,
+
+    #[account(seeds = [b"royalties", intent.key().as_ref()], bump = royalties.bump)]
+    pub royalties: Option<Account<'info, RoyaltySchedule>>,
+
+    /// Payer's account for the SLA penalty of a late completion, or the
+    /// refund when failing the intent
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Required to fail an intent drawn from a booking deposit
+    #[account(mut)]
+    pub booking: Option<Account<'info, CapacityBooking>>,
+
+    #[account(mut)]
+    pub booking_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptIntent<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump,
+        constraint = intent.assignee() == to_agent.key() @ ErrorCode::Unauthorized
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = owner.key() == to_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    /// Required when the intent carries a tip
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub billing_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Schema the provider expects; acceptance fails unless the intent
+    /// references this version
+    pub schema: Option<Account<'info, PayloadSchema>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(_result: ContentCommitment, result_uri: String)]
+pub struct SubmitResult<'info> {
+    #[account(
+        mut,
+        realloc = AgentIntent::space(intent.payload_uri.len(), result_uri.len())
+            .max(intent.to_account_info().data_len()),
+        realloc::payer = owner,
+        realloc::zero = false,
+        constraint = owner.key() == intent.assignee_owner @ ErrorCode::Unauthorized
+    )]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(constraint = assignee_agent.key() == intent.assignee() @ ErrorCode::Unauthorized)]
+    pub assignee_agent: Box<Account<'info, AgentIdentity>>,
+
+    /// CHECK: Instructions sysvar, passed to attest the result
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleIntent<'info> {
+    #[account(mut, constraint = owner.key() == intent.assignee_owner @ ErrorCode::Unauthorized)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// Checked for a freeze
+    #[account(constraint = assignee_agent.key() == intent.assignee() @ ErrorCode::Unauthorized)]
+    pub assignee_agent: Box<Account<'info, AgentIdentity>>,
+
+    #[account(mut, seeds = [b"agent_stats", intent.assignee().as_ref()], bump = assignee_stats.bump)]
+    pub assignee_stats: Box<Account<'info, AgentStats>>,
+
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub billing_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub delegate_billing_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payout_vault: Option<Account<'info, PayoutVault>>,
+
+    #[account(
+        seeds = [b"fee_allowlist", fee_allowlist.subject.as_ref()],
+        bump = fee_allowlist.bump
+    )]
+    pub fee_allowlist: Option<Account<'info, FeeAllowlist>>,
+
+    #[account(seeds = [b"royalties", intent.key().as_ref()], bump = royalties.bump)]
+    pub royalties: Option<Account<'info, RoyaltySchedule>>,
+
+    /// Payer's account for the SLA penalty of a late completion
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Required by settle_intent_with_swap; must be on the config's
+    /// swap program allowlist
+    pub swap_program: Option<AccountInfo<'info>>,
+
+    /// The assignee's profile, for its payout preference when the intent
+    /// has no payout mint of its own
+    pub model_profile: Option<Account<'info, ModelProfile>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutSwap<'info> {
+    #[account(mut, constraint = owner.key() == intent.assignee_owner @ ErrorCode::Unauthorized)]
+    pub intent: Account<'info, AgentIntent>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct RegisterForeignEmitter<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + ForeignEmitter::MAX_SIZE,
+        seeds = [b"foreign_emitter", &chain.to_le_bytes()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveForeignEmitter<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"foreign_emitter", &foreign_emitter.chain.to_le_bytes()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16, sender: [u8; 32], metadata_uri: String)]
+pub struct RegisterRemoteAgent<'info> {
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + RemoteAgent::MAX_SIZE,
+        seeds = [b"remote_agent", &chain.to_le_bytes(), sender.as_ref()],
+        bump
+    )]
+    pub remote_agent: Account<'info, RemoteAgent>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = AgentIdentity::registration_space(agent, metadata_uri.len()),
+        seeds = [b"agent", remote_agent.key().as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + AgentStats::MAX_SIZE,
+        seeds = [b"agent_stats", agent.key().as_ref()],
+        bump
+    )]
+    pub stats: Box<Account<'info, AgentStats>>,
+
+    #[account(seeds = [b"foreign_emitter", &chain.to_le_bytes()], bump = foreign_emitter.bump)]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    /// CHECK: Wormhole posted VAA, checked by read_bridge_message
+    pub posted_vaa: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteBridgedIntent<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        constraint = assignee_agent.key() == intent.assignee() @ ErrorCode::Unauthorized,
+        constraint = assignee_agent.owner_wallet == remote_agent.key() @ ErrorCode::InvalidBridgeMessage
+    )]
+    pub assignee_agent: Box<Account<'info, AgentIdentity>>,
+
+    #[account(mut, seeds = [b"agent_stats", intent.assignee().as_ref()], bump = assignee_stats.bump)]
+    pub assignee_stats: Box<Account<'info, AgentStats>>,
+
+    #[account(
+        seeds = [b"remote_agent", &remote_agent.chain.to_le_bytes(), remote_agent.address.as_ref()],
+        bump = remote_agent.bump
+    )]
+    pub remote_agent: Account<'info, RemoteAgent>,
+
+    #[account(
+        seeds = [b"foreign_emitter", &remote_agent.chain.to_le_bytes()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    /// CHECK: Wormhole posted VAA, checked by read_bridge_message
+    pub posted_vaa: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Must be the message's `payout` account
+    #[account(mut)]
+    pub billing_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payout_vault: Option<Account<'info, PayoutVault>>,
+
+    #[account(
+        seeds = [b"fee_allowlist", fee_allowlist.subject.as_ref()],
+        bump = fee_allowlist.bump
+    )]
+    pub fee_allowlist: Option<Account<'info, FeeAllowlist>>,
+
+    #[account(seeds = [b"royalties", intent.key().as_ref()], bump = royalties.bump)]
+    pub royalties: Option<Account<'info, RoyaltySchedule>>,
+
+    /// Payer's account for the SLA penalty of a late completion
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CreateBooking<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CapacityBooking::MAX_SIZE,
+        seeds = [b"booking", from_agent.key().as_ref(), to_agent.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub booking: Account<'info, CapacityBooking>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = model_profile.key() == to_agent.model_profile @ ErrorCode::InvalidModelProfile)]
+    pub model_profile: Account<'info, ModelProfile>,
+
+    /// CHECK: Payment mint for the booking, checked against the config's
+    /// registry and the token accounts
+    #[account(constraint = config.accepts_mint(payment_mint.key) @ ErrorCode::MintNotAccepted)]
+    pub payment_mint: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = from_token_account.mint == payment_mint.key() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub from_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub booking_vault: Account<'info, TokenAccount>,
+
+    /// `open_intent` trusts the booking's from-agent, so only that agent's
+    /// wallets may book for it
+    #[account(
+        mut,
+        constraint = payer.key() == from_agent.owner_wallet
+            || payer.key() == from_agent.agent_wallet @ ErrorCode::Unauthorized
+    )]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBooking<'info> {
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer @ ErrorCode::Unauthorized
+    )]
+    pub booking: Account<'info, CapacityBooking>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = booking_vault.key() == booking.vault @ ErrorCode::InvalidBooking)]
+    pub booking_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = billing_token_account.owner == booking.billing_wallet @ ErrorCode::InvalidBooking)]
+    pub billing_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Original payer, receives the booking rent
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StartTrial<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + TrialAccount::MAX_SIZE,
+        seeds = [b"trial", model_profile.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub trial: Account<'info, TrialAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub from_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub trial_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundTrial<'info> {
+    #[account(mut)]
+    pub trial: Account<'info, TrialAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = trial_vault.key() == trial.vault @ ErrorCode::InvalidTrial)]
+    pub trial_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = refund_token_account.owner == trial.owner @ ErrorCode::Unauthorized)]
+    pub refund_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ForfeitTrial<'info> {
+    #[account(mut, has_one = model_profile @ ErrorCode::InvalidTrial)]
+    pub trial: Account<'info, TrialAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub model_profile: Account<'info, ModelProfile>,
+
+    #[account(constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, constraint = trial_vault.key() == trial.vault @ ErrorCode::InvalidTrial)]
+    pub trial_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = billing_token_account.owner == model_profile.billing_wallet @ ErrorCode::Unauthorized
+    )]
+    pub billing_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReceiptTree<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ReceiptTree::MAX_SIZE,
+        seeds = [b"receipt_tree", agent.key().as_ref()],
+        bump
+    )]
+    pub receipt_tree: Account<'info, ReceiptTree>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    /// CHECK: Zeroed tree account, validated by the account compression program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitReceipt<'info> {
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump,
+        constraint = intent.assignee() == agent.key() @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    /// Either the agent's owner or its runtime wallet
+    #[account(
+        constraint = authority.key() == agent.owner_wallet
+            || authority.key() == agent.agent_wallet @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"receipt_tree", agent.key().as_ref()],
+        bump = receipt_tree.bump,
+        has_one = merkle_tree
+    )]
+    pub receipt_tree: Account<'info, ReceiptTree>,
+
+    /// CHECK: Validated against receipt_tree and by the account compression program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Bid::MAX_SIZE,
+        seeds = [b"bid", intent.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(seeds = [b"agent", agent.owner_wallet.as_ref()], bump = agent.bump)]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SelectBid<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"bid", intent.key().as_ref(), bid.agent.as_ref()],
+        bump = bid.bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    /// The requester's owner, or anyone once bidding has closed
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key() @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = refund_token_account.owner == intent.payer @ ErrorCode::Unauthorized)]
+    pub refund_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBid<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"bid", intent.key().as_ref(), bid.agent.as_ref()],
+        bump = bid.bump,
+        has_one = owner
+    )]
+    pub bid: Account<'info, Bid>,
+
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PostBond<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump,
+        constraint = intent.assignee() == to_agent.key() @ ErrorCode::Unauthorized
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"bond", intent.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = intent
+    )]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    #[account(constraint = payment_mint.key() == intent.payment_mint @ ErrorCode::InvalidEscrowAccount)]
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = owner.key() == to_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelIntent<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// The requesting or executing agent
+    #[account(
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump,
+        constraint = agent.key() == intent.from_agent
+            || agent.key() == intent.assignee() @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    /// Required unless the intent carries no payment or tip
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key()
+            && escrow_token_account.mint == intent.payment_mint @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Provider agent, paid the penalty for a late cancellation
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump,
+        constraint = to_agent.key() == intent.provider() @ ErrorCode::Unauthorized
+    )]
+    pub to_agent: Option<Account<'info, AgentIdentity>>,
+
+    /// Provider owner's account for the penalty
+    #[account(mut)]
+    pub penalty_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Required to refund an intent drawn from a booking deposit
+    #[account(mut)]
+    pub booking: Option<Account<'info, CapacityBooking>>,
+
+    #[account(mut)]
+    pub booking_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FlagTimeout<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"agent_stats", intent.assignee().as_ref()], bump = assignee_stats.bump)]
+    pub assignee_stats: Box<Account<'info, AgentStats>>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key() @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Payer's account for the refund and the slashed bond
+    #[account(mut, constraint = refund_token_account.owner == intent.payer @ ErrorCode::Unauthorized)]
+    pub refund_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"bond", intent.key().as_ref()], bump)]
+    pub bond_vault: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Must be the bond's poster, receives the vault's rent
+    #[account(mut, constraint = bond_payer.key() == intent.bond_payer @ ErrorCode::Unauthorized)]
+    pub bond_payer: Option<AccountInfo<'info>>,
+
+    /// Caller's account for the tip
+    #[account(mut)]
+    pub cranker_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Required to refund an intent drawn from a booking deposit
+    #[account(mut)]
+    pub booking: Option<Account<'info, CapacityBooking>>,
+
+    #[account(mut)]
+    pub booking_vault: Option<Account<'info, TokenAccount>>,
+
+    pub cranker: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBond<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"bond", intent.key().as_ref()], bump)]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    /// Bond poster's account, or the payer's if the intent failed
+    #[account(mut)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Must be the bond's poster, receives the vault's rent
+    #[account(mut, constraint = bond_payer.key() == intent.bond_payer @ ErrorCode::Unauthorized)]
+    pub bond_payer: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseIntent<'info> {
+    #[account(mut, close = rent_payer)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key()
+            && escrow_token_account.mint == intent.payment_mint @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Must be the party recorded as having funded the rent
+    #[account(mut, constraint = rent_payer.key() == intent.rent_payer @ ErrorCode::Unauthorized)]
+    pub rent_payer: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"royalties", intent.key().as_ref()], bump = royalties.bump)]
+    pub royalties: Option<Account<'info, RoyaltySchedule>>,
+
+    /// CHECK: Must be the royalty declaration's owner, receives its rent
+    #[account(mut)]
+    pub royalty_owner: Option<AccountInfo<'info>>,
+
+    #[account(mut, seeds = [b"team", intent.key().as_ref()], bump = team.bump)]
+    pub team: Option<Account<'info, IntentTeam>>,
+
+    /// CHECK: Must be the team declaration's owner, receives its rent
+    #[account(mut)]
+    pub team_owner: Option<AccountInfo<'info>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RetryIntent<'info> {
+    #[account(
+        mut,
+        constraint = previous_intent.from_agent == from_agent.key() @ ErrorCode::Unauthorized
+    )]
+    pub previous_intent: Box<Account<'info, AgentIntent>>,
+
+    #[account(
+        mut,
+        constraint = previous_escrow.owner == previous_intent.key() @ ErrorCode::InvalidEscrowAccount,
+        constraint = previous_escrow.mint == previous_intent.payment_mint @ ErrorCode::PaymentMintMismatch
+    )]
+    pub previous_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + NonceCounter::MAX_SIZE,
+        seeds = [b"nonce_counter", from_agent.key().as_ref(), to_agent.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + IntentRateLimit::MAX_SIZE,
+        seeds = [b"intent_rate", from_agent.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, IntentRateLimit>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + IntentIndex::MAX_SIZE,
+        seeds = [b"intent_index", from_agent.key().as_ref(), &[IndexRole::Sent as u8]],
+        bump
+    )]
+    pub sent_index: Box<Account<'info, IntentIndex>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + IntentIndexPage::MAX_SIZE,
+        seeds = [
+            b"intent_index_page",
+            from_agent.key().as_ref(),
+            &[IndexRole::Sent as u8],
+            &sent_index.next_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub sent_index_page: Box<Account<'info, IntentIndexPage>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + IntentIndex::MAX_SIZE,
+        seeds = [b"intent_index", to_agent.key().as_ref(), &[IndexRole::Received as u8]],
+        bump
+    )]
+    pub received_index: Box<Account<'info, IntentIndex>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + IntentIndexPage::MAX_SIZE,
+        seeds = [
+            b"intent_index_page",
+            to_agent.key().as_ref(),
+            &[IndexRole::Received as u8],
+            &received_index.next_page().to_le_bytes()
+        ],
+        bump
+    )]
+    pub received_index_page: Box<Account<'info, IntentIndexPage>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = AgentIntent::space(previous_intent.payload_uri.len(), 0),
+        seeds = [
+            b"intent",
+            from_agent.key().as_ref(),
+            to_agent.key().as_ref(),
+            &nonce_counter.next.to_le_bytes()
+        ],
+        bump
+    )]
+    pub intent: Box<Account<'info, AgentIntent>>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key() @ ErrorCode::InvalidEscrowAccount,
+        constraint = escrow_token_account.mint == previous_intent.payment_mint @ ErrorCode::PaymentMintMismatch
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = from_agent.bump
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    /// The failed intent's provider, or another agent to retry with
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, seeds = [b"agent_stats", from_agent.key().as_ref()], bump = from_stats.bump)]
+    pub from_stats: Box<Account<'info, AgentStats>>,
+
+    #[account(mut, seeds = [b"agent_stats", to_agent.key().as_ref()], bump = to_stats.bump)]
+    pub to_stats: Box<Account<'info, AgentStats>>,
+
+    /// Required when the carried escrow falls short of the new payment and tip
+    #[account(
+        mut,
+        constraint = from_token_account.mint == previous_intent.payment_mint @ ErrorCode::PaymentMintMismatch
+    )]
+    pub from_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeReadyReleases<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub keeper: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FundCrankTreasury<'info> {
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = 8 + CrankTreasury::MAX_SIZE,
+        seeds = [b"crank_treasury"],
+        bump
+    )]
+    pub crank_treasury: Account<'info, CrankTreasury>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessCrank<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"crank_treasury"], bump = crank_treasury.bump)]
+    pub crank_treasury: Account<'info, CrankTreasury>,
+
+    /// Protocol fee account, for `process_settlements`
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(reason_uri: String)]
+pub struct OpenDispute<'info> {
+    #[account(
+        init,
+        payer = opener,
+        space = Dispute::space(reason_uri.len()),
+        seeds = [b"dispute", intent.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, constraint = opener.key() == from_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub opener: Signer<'info>,
+
+    #[account(mut)]
+    pub opener_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key()
+            && escrow_token_account.mint == intent.payment_mint @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        mut,
+        close = opener,
+        seeds = [b"dispute", intent.key().as_ref()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"agent_stats", intent.assignee().as_ref()], bump = assignee_stats.bump)]
+    pub assignee_stats: Box<Account<'info, AgentStats>>,
+
+    #[account(constraint = config.is_arbiter(&arbiter.key()) @ ErrorCode::Unauthorized)]
+    pub arbiter: Signer<'info>,
+
+    /// CHECK: Receives the dispute account's rent
+    #[account(mut, constraint = opener.key() == dispute.opener @ ErrorCode::Unauthorized)]
+    pub opener: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key() @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Returns the dispute fee when the opener prevails
+    #[account(mut, constraint = opener_token_account.owner == dispute.opener @ ErrorCode::Unauthorized)]
+    pub opener_token_account: Account<'info, TokenAccount>,
+
+    /// Payer's account for the payment refund when the opener prevails
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Arbiter's cut of a forfeited fee
+    #[account(mut)]
+    pub arbiter_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// The intent's release account, credited with the rest of a forfeited fee
+    #[account(mut)]
+    pub counterparty_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Required to refund an intent drawn from a booking deposit
+    #[account(mut)]
+    pub booking: Option<Account<'info, CapacityBooking>>,
+
+    #[account(mut)]
+    pub booking_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, reason_uri: String)]
+pub struct ClaimInsurance<'info> {
+    #[account(
+        init,
+        payer = claimant,
+        space = InsuranceClaim::space(reason_uri.len()),
+        seeds = [b"insurance_claim", intent.key().as_ref()],
+        bump
+    )]
+    pub insurance_claim: Account<'info, InsuranceClaim>,
+
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, constraint = claimant.key() == from_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub claimant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveInsuranceClaim<'info> {
+    #[account(
+        mut,
+        close = claimant,
+        seeds = [b"insurance_claim", intent.key().as_ref()],
+        bump = insurance_claim.bump
+    )]
+    pub insurance_claim: Account<'info, InsuranceClaim>,
+
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(constraint = config.is_arbiter(&arbiter.key()) @ ErrorCode::Unauthorized)]
+    pub arbiter: Signer<'info>,
+
+    /// CHECK: Receives the claim account's rent
+    #[account(mut, constraint = claimant.key() == insurance_claim.claimant @ ErrorCode::Unauthorized)]
+    pub claimant: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"insurance_pool", intent.payment_mint.as_ref()],
+        bump = insurance_pool.bump
+    )]
+    pub insurance_pool: Account<'info, InsurancePool>,
+
+    #[account(mut, address = insurance_pool.vault @ ErrorCode::InsurancePoolRequired)]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    /// Payer's account for the payout; required unless the claim is rejected
+    #[account(mut)]
+    pub payer_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DeclareRoyalties<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + RoyaltySchedule::MAX_SIZE,
+        seeds = [b"royalties", intent.key().as_ref()],
+        bump
+    )]
+    pub royalties: Account<'info, RoyaltySchedule>,
+
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, constraint = owner.key() == from_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeclareTeam<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + IntentTeam::MAX_SIZE,
+        seeds = [b"team", intent.key().as_ref()],
+        bump
+    )]
+    pub team: Account<'info, IntentTeam>,
+
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(mut, constraint = owner.key() == from_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitTeamResult<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"team", intent.key().as_ref()], bump = team.bump)]
+    pub team: Account<'info, IntentTeam>,
+
+    #[account(seeds = [b"agent", agent.owner_wallet.as_ref()], bump = agent.bump)]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    #[account(constraint = payout_token_account.mint == intent.payment_mint @ ErrorCode::InvalidEscrowAccount)]
+    pub payout_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SettleTeamIntent<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"team", intent.key().as_ref()], bump = team.bump)]
+    pub team: Account<'info, IntentTeam>,
+
+    #[account(mut, seeds = [b"agent_stats", intent.assignee().as_ref()], bump = assignee_stats.bump)]
+    pub assignee_stats: Box<Account<'info, AgentStats>>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == intent.key() @ ErrorCode::InvalidEscrowAccount
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Payer's account for the shares of members that didn't submit and any
+    /// SLA penalty
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetSla<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = owner.key() == from_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SealPayload<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(
+        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
+        bump = from_agent.bump,
+        constraint = from_agent.key() == intent.from_agent @ ErrorCode::Unauthorized
+    )]
+    pub from_agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump,
+        constraint = to_agent.key() == intent.provider() @ ErrorCode::Unauthorized
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = owner.key() == from_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedelegateIntent<'info> {
+    #[account(mut)]
+    pub intent: Account<'info, AgentIntent>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
+        bump = to_agent.bump,
+        constraint = intent.provider() == to_agent.key() @ ErrorCode::Unauthorized
+    )]
+    pub to_agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = owner.key() == to_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"agent", delegate_agent.owner_wallet.as_ref()],
+        bump = delegate_agent.bump
+    )]
+    pub delegate_agent: Account<'info, AgentIdentity>,
+
+    /// The delegate's owner, consenting to take the intent
+    #[account(constraint = delegate_owner.key() == delegate_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub delegate_owner: Signer<'info>,
+
+    /// Where the provider's share is paid at settlement
+    #[account(constraint = billing_token_account.mint == intent.payment_mint @ ErrorCode::PaymentMintMismatch)]
+    pub billing_token_account: Account<'info, TokenAccount>,
+
+    /// Where the delegate's share is paid at settlement
+    #[account(
+        constraint = delegate_billing_token_account.owner == delegate_agent.owner_wallet @ ErrorCode::Unauthorized,
+        constraint = delegate_billing_token_account.mint == intent.payment_mint @ ErrorCode::PaymentMintMismatch
+    )]
+    pub delegate_billing_token_account: Account<'info, TokenAccount>,
+
+    /// The provider's profile, when paying into its billing wallet
+    #[account(constraint = model_profile.key() == to_agent.model_profile @ ErrorCode::InvalidModelProfile)]
+    pub model_profile: Option<Account<'info, ModelProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct OpenSpendLimit<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SpendLimit::MAX_SIZE,
+        seeds = [b"spend_limit", agent.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub spend_limit: Account<'info, SpendLimit>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSpendLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"spend_limit", agent.key().as_ref(), spend_limit.mint.as_ref()],
+        bump = spend_limit.bump,
+        has_one = agent
+    )]
+    pub spend_limit: Account<'info, SpendLimit>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSpendLimit<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"spend_limit", agent.key().as_ref(), spend_limit.mint.as_ref()],
+        bump = spend_limit.bump,
+        has_one = agent
+    )]
+    pub spend_limit: Account<'info, SpendLimit>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBudget<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AgentBudget::MAX_SIZE,
+        seeds = [b"budget", agent.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub budget: Account<'info, AgentBudget>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.accepts_mint(&mint.key()) @ ErrorCode::MintNotAccepted
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub budget_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == mint.key() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundBudget<'info> {
+    #[account(
+        seeds = [b"budget", agent.key().as_ref(), budget.mint.as_ref()],
+        bump = budget.bump,
+        has_one = agent
+    )]
+    pub budget: Account<'info, AgentBudget>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = budget_vault.key() == budget.vault @ ErrorCode::InvalidBudget)]
+    pub budget_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == budget.mint @ ErrorCode::PaymentMintMismatch
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBudget<'info> {
+    #[account(
+        mut,
+        seeds = [b"budget", agent.key().as_ref(), budget.mint.as_ref()],
+        bump = budget.bump,
+        has_one = agent
+    )]
+    pub budget: Account<'info, AgentBudget>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(constraint = budget_vault.key() == budget.vault @ ErrorCode::InvalidBudget)]
+    pub budget_vault: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBudget<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"budget", agent.key().as_ref(), budget.mint.as_ref()],
+        bump = budget.bump,
+        has_one = agent
+    )]
+    pub budget: Account<'info, AgentBudget>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = budget_vault.key() == budget.vault @ ErrorCode::InvalidBudget)]
+    pub budget_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == budget.mint @ ErrorCode::PaymentMintMismatch
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTransfer<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump,
+        has_one = agent_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub agent_wallet: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"spend_limit", agent.key().as_ref(), source_token_account.mint.as_ref()],
+        bump = spend_limit.bump
+    )]
+    pub spend_limit: Account<'info, SpendLimit>,
+
+    /// Owner's token account with the agent PDA approved as delegate
+    #[account(
+        mut,
+        constraint = source_token_account.owner == agent.owner_wallet @ ErrorCode::Unauthorized,
+        constraint = source_token_account.delegate == COption::Some(agent.key()) @ ErrorCode::Unauthorized
+    )]
+    pub source_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = destination_token_account.mint == source_token_account.mint @ ErrorCode::InvalidParameter)]
+    pub destination_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSwap<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump,
+        has_one = agent_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub agent_wallet: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"spend_limit", agent.key().as_ref(), source_token_account.mint.as_ref()],
+        bump = spend_limit.bump
+    )]
+    pub spend_limit: Account<'info, SpendLimit>,
+
+    /// Owner's token account with the agent PDA approved as delegate
+    #[account(
+        mut,
+        constraint = source_token_account.owner == agent.owner_wallet @ ErrorCode::Unauthorized,
+        constraint = source_token_account.delegate == COption::Some(agent.key()) @ ErrorCode::Unauthorized
+    )]
+    pub source_token_account: Account<'info, TokenAccount>,
+
+    /// Receives the output; must belong to the owner too
+    #[account(
+        mut,
+        constraint = destination_token_account.owner == agent.owner_wallet @ ErrorCode::Unauthorized
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: must be on the config's swap program allowlist
+    pub swap_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(group_id: [u8; 16])]
+pub struct CreateGroup<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AgentGroup::MAX_SIZE,
+        seeds = [b"group", agent.key().as_ref(), &group_id],
+        bump
+    )]
+    pub group: Account<'info, AgentGroup>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetGroupMember<'info> {
+    #[account(
+        mut,
+        seeds = [b"group", group.founder.as_ref(), &group.group_id],
+        bump = group.bump
+    )]
+    pub group: Account<'info, AgentGroup>,
+
+    #[account(
+        seeds = [b"agent", admin.key().as_ref()],
+        bump = admin_agent.bump
+    )]
+    pub admin_agent: Account<'info, AgentIdentity>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"agent", member_agent.owner_wallet.as_ref()],
+        bump = member_agent.bump
+    )]
+    pub member_agent: Account<'info, AgentIdentity>,
+
+    /// Required to add a new member, as its consent
+    #[account(constraint = member_owner.key() == member_agent.owner_wallet @ ErrorCode::Unauthorized)]
+    pub member_owner: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveGroupMember<'info> {
+    #[account(
+        mut,
+        seeds = [b"group", group.founder.as_ref(), &group.group_id],
+        bump = group.bump
+    )]
+    pub group: Account<'info, AgentGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", member_agent.owner_wallet.as_ref()],
+        bump = member_agent.bump
+    )]
+    pub member_agent: Account<'info, AgentIdentity>,
+
+    /// The signer's agent: an admin, or the member itself
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = signer_agent.bump
+    )]
+    pub signer_agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetEarningsRouting<'info> {
+    #[account(
+        seeds = [b"group", group.founder.as_ref(), &group.group_id],
+        bump = group.bump
+    )]
+    pub group: Account<'info, AgentGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SpendFromGroup<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"group", group.founder.as_ref(), &group.group_id],
+        bump = group.bump
+    )]
+    pub group: Account<'info, AgentGroup>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    pub owner: Signer<'info>,
+
+    #[account(mut, constraint = treasury_token_account.owner == group.key() @ ErrorCode::Unauthorized)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == treasury_token_account.mint @ ErrorCode::InvalidParameter
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(spec_hash: [u8; 32], spec_uri: String)]
+pub struct PostBounty<'info> {
+    #[account(
+        init,
+        payer = sponsor,
+        space = Bounty::space(spec_uri.len()),
+        seeds = [b"bounty", sponsor.key().as_ref(), spec_hash.as_ref()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bounty_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AwardBounty<'info> {
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = reviewer @ ErrorCode::Unauthorized,
+        has_one = sponsor @ ErrorCode::Unauthorized
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = bounty_vault.key() == bounty.vault @ ErrorCode::InvalidBounty)]
+    pub bounty_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"agent", claimant.owner_wallet.as_ref()],
+        bump = claimant.bump
+    )]
+    pub claimant: Account<'info, AgentIdentity>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claimant.owner_wallet @ ErrorCode::InvalidBounty,
+        constraint = claimant_token_account.mint == bounty.mint @ ErrorCode::InvalidBounty
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    pub reviewer: Signer<'info>,
+
+    /// CHECK: Bounty sponsor, receives the bounty and vault rent
+    #[account(mut)]
+    pub sponsor: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBounty<'info> {
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = sponsor @ ErrorCode::Unauthorized
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, constraint = bounty_vault.key() == bounty.vault @ ErrorCode::InvalidBounty)]
+    pub bounty_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sponsor_token_account.owner == bounty.sponsor @ ErrorCode::InvalidBounty,
+        constraint = sponsor_token_account.mint == bounty.mint @ ErrorCode::InvalidBounty
+    )]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
+
+    /// Reviewer, or the sponsor once the bounty has expired
+    pub authority: Signer<'info>,
+
+    /// CHECK: Bounty sponsor, receives the bounty and vault rent
+    #[account(mut)]
+    pub sponsor: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(_governance_program: Pubkey, _realm: Pubkey, proposal: Pubkey)]
+pub struct ApproveVote<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VoteApproval::MAX_SIZE,
+        seeds = [b"vote_approval", agent.key().as_ref(), proposal.as_ref()],
+        bump
+    )]
+    pub vote_approval: Account<'info, VoteApproval>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVoteApproval<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"vote_approval", agent.key().as_ref(), vote_approval.proposal.as_ref()],
+        bump = vote_approval.bump,
+        has_one = agent
+    )]
+    pub vote_approval: Account<'info, VoteApproval>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// spl-governance accounts are validated by spl-governance itself; this
+/// program only pins the program, realm and proposal to the owner's approval
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"agent", agent.owner_wallet.as_ref()],
+        bump = agent.bump,
+        has_one = agent_wallet @ ErrorCode::Unauthorized
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    /// Pays for the vote record
+    #[account(mut)]
+    pub agent_wallet: Signer<'info>,
+
+    #[account(
+        seeds = [b"vote_approval", agent.key().as_ref(), proposal.key().as_ref()],
+        bump = vote_approval.bump,
+        has_one = governance_program @ ErrorCode::InvalidGovernanceAccounts,
+        has_one = realm @ ErrorCode::InvalidGovernanceAccounts
+    )]
+    pub vote_approval: Account<'info, VoteApproval>,
+
+    /// CHECK: spl-governance instance named in the approval
+    #[account(executable)]
+    pub governance_program: UncheckedAccount<'info>,
+
+    /// CHECK: realm named in the approval
+    pub realm: UncheckedAccount<'info>,
+
+    /// CHECK: proposal's governance
+    #[account(mut)]
+    pub governance: UncheckedAccount<'info>,
+
+    /// CHECK: proposal named in the approval (part of its seeds)
+    #[account(mut)]
+    pub proposal: UncheckedAccount<'info>,
+
+    /// CHECK: proposal owner's token owner record
+    #[account(mut)]
+    pub proposal_owner_record: UncheckedAccount<'info>,
+
+    /// CHECK: agent owner's token owner record; its owner is checked in the handler
+    #[account(mut)]
+    pub voter_token_owner_record: UncheckedAccount<'info>,
+
+    /// CHECK: vote record PDA, created by spl-governance
+    #[account(mut)]
+    pub vote_record: UncheckedAccount<'info>,
+
+    /// CHECK: governing token mint the owner votes with
+    pub governing_token_mint: UncheckedAccount<'info>,
+
+    /// CHECK: realm config PDA
+    pub realm_config: UncheckedAccount<'info>,
+
+    /// CHECK: voter weight record, for realms with a voter weight plugin
+    pub voter_weight_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: max voter weight record, for realms with a max voter weight plugin
+    pub max_voter_weight_record: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BindName<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AgentName::MAX_SIZE,
+        seeds = [b"agent_name", agent.key().as_ref()],
+        bump
+    )]
+    pub agent_name: Account<'info, AgentName>,
+
+    #[account(
+        seeds = [b"agent", owner.key().as_ref()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, AgentIdentity>,
+
+    /// CHECK: derived from the name and read as a Name Service record in the handler
+    pub name_record: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnbindName<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"agent_name", agent_name.agent.as_ref()],
+        bump = agent_name.bump,
+        has_one = owner,
+        has_one = name_record
+    )]
+    pub agent_name: Account<'info, AgentName>,
+
+    /// CHECK: read in the handler to tell whether the domain changed hands
+    pub name_record: UncheckedAccount<'info>,
+
+    /// CHECK: receives the binding's rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: owner and discriminator checked by load_for_migration
+    #[account(mut)]
+    pub account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// === Events ===
+
+#[event]
+pub struct ConfigInitialized {
+    pub config: Pubkey,
+    pub admin: Pubkey,
+    pub attestor: Pubkey,
+}
+
+#[event]
+pub struct ProgramVersionSynced {
+    pub config: Pubkey,
+    pub previous: ProgramVersion,
+    pub current: ProgramVersion,
+}
+
+#[event]
+pub struct AttestorUpdated {
+    pub config: Pubkey,
+    pub attestor: Pubkey,
+}
+
+#[event]
+pub struct OperatingModeChanged {
+    pub config: Pubkey,
+    pub mode: u8,
+}
+
+#[event]
+pub struct PauseToggled {
+    pub config: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct GovernanceUpdated {
+    pub config: Pubkey,
+    pub governance: Pubkey,
+}
+
+#[event]
+pub struct ParameterUpdated {
+    pub config: Pubkey,
+    pub parameter: ProtocolParameter,
+}
+
+#[event]
+pub struct FeeAllowlistUpdated {
+    pub subject: Pubkey,
+    pub discount_bps: u16,
+}
+
+#[event]
+pub struct InsurancePoolInitialized {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct AgentRegistered {
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    pub agent_wallet: Pubkey,
+}
+
+#[event]
+pub struct IdentityBadgeMinted {
+    pub agent: Pubkey,
+    pub agent_wallet: Pubkey,
+    pub mint: Pubkey,
+    pub metadata: Pubkey,
+}
+
+#[event]
+pub struct GuardianSet {
+    pub agent: Pubkey,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct AgentFrozen {
+    pub agent: Pubkey,
+    pub frozen_by: Pubkey,
+    pub frozen_at: i64,
+}
+
+#[event]
+pub struct AgentUnfrozen {
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct ForeignEmitterUpdated {
+    pub chain: u16,
+    /// Zero once the chain's emitter is removed
+    pub address: [u8; 32],
 }
 
-// === Account Structures ===
+#[event]
+pub struct RemoteAgentRegistered {
+    pub agent: Pubkey,
+    pub chain: u16,
+    pub address: [u8; 32],
+    pub message_sequence: u64,
+}
 
-#[account]
-#[derive(Default)]
-pub struct AgentIdentity {
-    pub owner_wallet: Pubkey,      // 32
-    pub agent_wallet: Pubkey,      // 32
-    pub model_profile: Pubkey,     // 32
-    pub metadata_uri: String,      // 4 + 200
-    pub permissions: u64,          // 8
-    pub created_at: i64,           // 8
-    pub updated_at: i64,           // 8
-    pub bump: u8,                  // 1
+#[event]
+pub struct BridgedIntentSubmitted {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub chain: u16,
+    pub sender: [u8; 32],
+    pub message_sequence: u64,
 }
 
-impl AgentIdentity {
-    pub const MAX_SIZE: usize = 32 + 32 + 32 + (4 + 200) + 8 + 8 + 8 + 1;
+#[event]
+pub struct BridgedResultAttested {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub chain: u16,
+    pub sender: [u8; 32],
+    pub message_sequence: u64,
+    pub result_hash: [u8; 32],
+    pub payout: Pubkey,
 }
 
-#[account]
-#[derive(Default)]
-pub struct ModelProfile {
-    pub owner_wallet: Pubkey,         // 32
-    pub profile_id: [u8; 16],         // 16
-    pub label: String,                // 4 + 64
-    pub provider_uri: String,         // 4 + 200
-    pub pricing: u64,                 // 8 (micro-units per 1K tokens)
-    pub billing_wallet: Pubkey,       // 32
-    pub max_tokens_per_day: u64,      // 8
-    pub max_requests_per_min: u64,    // 8
-    pub created_at: i64,              // 8
-    pub updated_at: i64,              // 8
-    pub bump: u8,                     // 1
+#[event]
+pub struct AgentClosed {
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct AgentUpdated {
+    pub agent: Pubkey,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct EncryptionKeyRotated {
+    pub agent: Pubkey,
+    pub key_version: u32,
+    pub key_hash: [u8; 32],
+    pub previous_key_expires_at: i64,
+}
+
+#[event]
+pub struct CapacityUpdated {
+    pub agent: Pubkey,
+    pub available_slots: u32,
+    pub queue_depth: u32,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct MinPaymentUpdated {
+    pub agent: Pubkey,
+    pub min_payment: u64,
+}
+
+#[event]
+pub struct ModelProfileCreated {
+    pub profile: Pubkey,
+    pub owner: Pubkey,
+    pub label: String,
+}
+
+#[event]
+pub struct ModelProfileUpdated {
+    pub profile: Pubkey,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct ProfileSnapshotted {
+    pub profile: Pubkey,
+    pub version: u32,
+    pub pricing: u64,
+}
+
+#[event]
+pub struct PayoutVaultOpened {
+    pub payout_vault: Pubkey,
+    pub model_profile: Pubkey,
+    pub mint: Pubkey,
+    pub sweep_interval: i64,
+}
+
+#[event]
+pub struct PayoutsSwept {
+    pub payout_vault: Pubkey,
+    pub amount: u64,
+    pub swept_at: i64,
+}
+
+#[event]
+pub struct ProfileAccessGranted {
+    pub profile: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct ProfileAccessRevoked {
+    pub profile: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct DelegationGranted {
+    pub grantor: Pubkey,
+    pub delegate: Pubkey,
+    pub permissions: u64,
+    pub expires: Deadline,
+}
+
+#[event]
+pub struct DelegationRevoked {
+    pub grantor: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct ProfileAttested {
+    pub profile: Pubkey,
+    pub attestor: Pubkey,
+    pub attestation: Pubkey,
+}
+
+#[event]
+pub struct ProfileAttestationRevoked {
+    pub profile: Pubkey,
+    pub attestor: Pubkey,
+}
+
+#[event]
+pub struct ContextRefCreated {
+    pub context: Pubkey,
+    pub agent: Pubkey,
+    pub context_hash: [u8; 32],
+    pub context_uri: String,
+}
+
+#[event]
+pub struct ContextRefClosed {
+    pub context: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct TemplatePublished {
+    pub template: Pubkey,
+    pub agent: Pubkey,
+    pub template_id: [u8; 16],
+    pub schema_hash: [u8; 32],
+    pub default_payment: u64,
+    pub completion_window: TimeSpan,
+    pub required_permissions: u64,
+}
+
+#[event]
+pub struct TemplateClosed {
+    pub template: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct SchemaPublished {
+    pub schema: Pubkey,
+    pub schema_id: [u8; 16],
+    pub version: u32,
+    pub agent: Pubkey,
+    pub schema_hash: [u8; 32],
+    pub media_type: String,
+    pub schema_uri: String,
+}
+
+#[event]
+pub struct SchemaDeprecated {
+    pub schema: Pubkey,
+    pub schema_id: [u8; 16],
+    pub version: u32,
+}
+
+#[event]
+pub struct ChannelOpened {
+    pub channel: Pubkey,
+    pub agent_a: Pubkey,
+    pub agent_b: Pubkey,
+}
+
+#[event]
+pub struct MessagePosted {
+    pub channel: Pubkey,
+    pub sequence: u64,
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    pub message_hash: [u8; 32],
+    pub message_uri: String,
+}
+
+#[event]
+pub struct ChannelClosed {
+    pub channel: Pubkey,
+    pub agent_a: Pubkey,
+    pub agent_b: Pubkey,
+    pub message_count: u64,
+}
+
+#[event]
+pub struct IntentCreated {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    pub payer: Pubkey,
+    pub nonce: u64,
+    pub payload_hash: [u8; 32],
+    pub payload_uri: String,
+    pub payment_amount: u64,
+    pub payment_mint: Pubkey,
+    pub tip_amount: u64,
+    pub options: u8,
+    pub context: Pubkey,
+    pub template: Pubkey,
+    pub schema: Pubkey,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct IntentStatusUpdated {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub nonce: u64,
+    pub previous_status: u8,
+    pub status: u8,
+    pub result_hash: [u8; 32],
+    pub result_uri: String,
+    pub updated_at: i64,
+}
+
+/// One entry of an intent's status history. `index` counts up from 0 with
+/// no gaps, so a consumer missing an entry knows it has to backfill.
+#[event]
+pub struct IntentTransition {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub index: u32,
+    pub previous_status: u8,
+    pub status: u8,
+    pub actor: Pubkey,
+    pub at: i64,
+}
+
+#[event]
+pub struct EscrowReleased {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub provider_account: Pubkey,
+    pub payout_mint: Pubkey,
+    pub provider_amount: u64,
+    pub delegate_amount: u64,
+    pub protocol_fee: u64,
+    /// Paid to declared royalty recipients out of the provider's share
+    pub royalties: u64,
+    pub released_at: i64,
+}
+
+#[event]
+pub struct ReleaseScheduled {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub release_account: Pubkey,
+    pub release_deadline: Deadline,
+}
+
+#[event]
+pub struct ReleasesFinalized {
+    pub keeper: Pubkey,
+    pub released: u32,
+    pub finalized_at: i64,
+}
+
+#[event]
+pub struct CrankTreasuryFunded {
+    pub funder: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct CrankTipsPaid {
+    pub cranker: Pubkey,
+    pub processed: u32,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub payer: Pubkey,
+    pub refund_account: Pubkey,
+    pub amount: u64,
+    pub refunded_at: i64,
+}
+
+#[event]
+pub struct TipPaid {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub agent: Pubkey,
+    pub billing_account: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ResultSubmitted {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub result_hash: [u8; 32],
+    pub result_uri: String,
+    pub result_signer: Pubkey,
+    pub submitted_at: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub bid: Pubkey,
+    pub agent: Pubkey,
+    pub price: u64,
+    pub eta: i64,
+}
+
+#[event]
+pub struct BidSelected {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub bid: Pubkey,
+    pub agent: Pubkey,
+    pub price: u64,
+    pub eta: i64,
+    pub selected_by: Pubkey,
+}
+
+#[event]
+pub struct BidClosed {
+    pub bid: Pubkey,
+    pub intent: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct BondPosted {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub agent: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct IntentTimedOut {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub flagged_by: Pubkey,
+    pub refund: u64,
+    pub bond_slashed: u64,
+    pub tip: u64,
+    pub timed_out_at: i64,
+}
+
+#[event]
+pub struct BondSettled {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub slashed: bool,
+}
+
+#[event]
+pub struct IntentCancelled {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub cancelled_by: Pubkey,
+    pub previous_status: u8,
+    pub refund: u64,
+    /// Paid to the provider for a late cancellation by the requester
+    pub penalty: u64,
+    pub cancelled_at: i64,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub dispute: Pubkey,
+    pub opener: Pubkey,
+    pub fee: u64,
+    pub reason_uri: String,
+    pub opened_at: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub dispute: Pubkey,
+    pub arbiter: Pubkey,
+    pub opener_prevailed: bool,
+    pub fee: u64,
+    pub arbiter_share: u64,
+    pub counterparty_share: u64,
+    pub resolved_at: i64,
+}
+
+#[event]
+pub struct InsuranceClaimFiled {
+    pub claim: Pubkey,
+    pub intent: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub reason_uri: String,
+    pub filed_at: i64,
+}
+
+#[event]
+pub struct InsuranceClaimResolved {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub claim: Pubkey,
+    pub arbiter: Pubkey,
+    pub claimant: Pubkey,
+    pub requested: u64,
+    pub payout: u64,
+    pub resolved_at: i64,
+}
+
+#[event]
+pub struct BookingCreated {
+    pub booking: Pubkey,
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    pub tokens_reserved: u64,
+    pub locked_price: u64,
+    pub deposit: u64,
+    pub start: Deadline,
+    pub end: Deadline,
+}
+
+#[event]
+pub struct BookingClosed {
+    pub booking: Pubkey,
+    pub tokens_used: u64,
+    pub forfeited_deposit: u64,
+}
+
+#[event]
+pub struct TrialStarted {
+    pub trial: Pubkey,
+    pub model_profile: Pubkey,
+    pub agent: Pubkey,
+    pub deposit: u64,
+    pub requests: u16,
+    pub expires: Deadline,
 }
 
-impl ModelProfile {
-    pub const MAX_SIZE: usize = 32 + 16 + (4 + 64) + (4 + 200) + 8 + 32 + 8 + 8 + 8 + 8 + 1;
+#[event]
+pub struct TrialSettled {
+    pub trial: Pubkey,
+    pub requests_used: u16,
+    pub refunded: u64,
+    pub forfeited: u64,
 }
 
-#[account]
-#[derive(Default)]
-pub struct AgentIntent {
-    pub from_agent: Pubkey,        // 32
-    pub to_agent: Pubkey,          // 32
-    pub nonce: u64,                // 8
-    pub status: u8,                // 1
-    pub payload_hash: [u8; 32],    // 32
-    pub payload_uri: String,       // 4 + 200
-    pub payment_amount: u64,       // 8
-    pub payment_mint: Pubkey,      // 32
-    pub result_hash: [u8; 32],     // 32
-    pub result_uri: String,        // 4 + 200
-    pub created_at: i64,           // 8
-    pub updated_at: i64,           // 8
-    pub bump: u8,                  // 1
+#[event]
+pub struct ReceiptTreeInitialized {
+    pub receipt_tree: Pubkey,
+    pub agent: Pubkey,
+    pub merkle_tree: Pubkey,
 }
 
-impl AgentIntent {
-    pub const MAX_SIZE: usize = 32 + 32 + 8 + 1 + 32 + (4 + 200) + 8 + 32 + 32 + (4 + 200) + 8 + 8 + 1;
+#[event]
+pub struct ReceiptSubmitted {
+    pub merkle_tree: Pubkey,
+    pub leaf_index: u64,
+    pub leaf: [u8; 32],
+    pub receipt: UsageReceipt,
 }
 
-// === Contexts ===
+#[event]
+pub struct RoyaltiesDeclared {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub schema_hash: [u8; 32],
+    pub splits: Vec<RoyaltySplit>,
+}
 
-#[derive(Accounts)]
-pub struct RegisterAgent<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + AgentIdentity::MAX_SIZE,
-        seeds = [b"agent", owner.key().as_ref()],
-        bump
-    )]
-    pub agent: Account<'info, AgentIdentity>,
+#[event]
+pub struct TeamDeclared {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub members: Vec<TeamShare>,
+    pub quorum: u8,
+}
 
-    #[account(mut)]
-    pub owner: Signer<'info>,
+#[event]
+pub struct TeamResultSubmitted {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub agent: Pubkey,
+    pub result: ContentCommitment,
+    pub result_uri: String,
+    pub completed: u8,
+    pub quorum: u8,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct TeamSettled {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub members_paid: u8,
+    pub paid: u64,
+    pub fee: u64,
+    pub refund: u64,
+    pub settled_at: i64,
 }
 
-#[derive(Accounts)]
-pub struct UpdateAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", owner.key().as_ref()],
-        bump = agent.bump,
-        has_one = owner_wallet @ ErrorCode::Unauthorized
-    )]
-    pub agent: Account<'info, AgentIdentity>,
+#[event]
+pub struct SlaSet {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub target: i64,
+    pub penalty_bps: u16,
+}
 
-    #[account(constraint = owner.key() == agent.owner_wallet @ ErrorCode::Unauthorized)]
-    pub owner: Signer<'info>,
+#[event]
+pub struct SlaPenaltyApplied {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub late_by: i64,
+    pub penalty: u64,
+    pub refund_account: Pubkey,
 }
 
-#[derive(Accounts)]
-#[instruction(profile_id: [u8; 16])]
-pub struct CreateModelProfile<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + ModelProfile::MAX_SIZE,
-        seeds = [b"model_profile", owner.key().as_ref(), &profile_id],
-        bump
-    )]
-    pub model_profile: Account<'info, ModelProfile>,
+#[event]
+pub struct PayloadSealed {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub sealed_to: [u8; 32],
+    pub ephemeral_pubkey: [u8; 32],
+    pub encrypted_key: [u8; 32],
+}
 
-    #[account(mut)]
-    pub owner: Signer<'info>,
+#[event]
+pub struct IntentRedelegated {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub to_agent: Pubkey,
+    pub delegate_agent: Pubkey,
+    pub delegate_share_bps: u16,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct SpendLimitUpdated {
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub daily_cap: u64,
 }
 
-#[derive(Accounts)]
-pub struct UpdateModelProfile<'info> {
-    #[account(
-        mut,
-        has_one = owner_wallet @ ErrorCode::Unauthorized
-    )]
-    pub model_profile: Account<'info, ModelProfile>,
+#[event]
+pub struct TransferDestinationsUpdated {
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub destinations: Vec<Pubkey>,
+}
 
-    #[account(constraint = owner.key() == model_profile.owner_wallet @ ErrorCode::Unauthorized)]
-    pub owner: Signer<'info>,
+#[event]
+pub struct BudgetUpdated {
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub daily_cap: u64,
+    pub total_cap: u64,
+    /// Vault balance after the update
+    pub balance: u64,
 }
 
-#[derive(Accounts)]
-#[instruction(nonce: u64)]
-pub struct CreateIntent<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + AgentIntent::MAX_SIZE,
-        seeds = [b"intent", from_agent.key().as_ref(), to_agent.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub intent: Account<'info, AgentIntent>,
+#[event]
+pub struct BudgetSpent {
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub intent: Pubkey,
+    /// Payment, tip and insurance premium drawn for the intent
+    pub amount: u64,
+    pub spent_today: u64,
+    pub total_spent: u64,
+}
 
-    #[account(
-        seeds = [b"agent", from_agent.owner_wallet.as_ref()],
-        bump = from_agent.bump
-    )]
-    pub from_agent: Account<'info, AgentIdentity>,
+#[event]
+pub struct BudgetClosed {
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub total_spent: u64,
+    /// Vault balance returned to the owner
+    pub refunded: u64,
+}
 
-    #[account(
-        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
-        bump = to_agent.bump
-    )]
-    pub to_agent: Account<'info, AgentIdentity>,
+#[event]
+pub struct TransferExecuted {
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub executed_at: i64,
+}
 
-    /// CHECK: Payment mint for the intent
-    pub payment_mint: AccountInfo<'info>,
+#[event]
+pub struct SwapExecuted {
+    pub agent: Pubkey,
+    pub swap_program: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub executed_at: i64,
+}
 
-    #[account(mut)]
-    pub from_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct GroupCreated {
+    pub group: Pubkey,
+    pub founder: Pubkey,
+    pub group_id: [u8; 16],
+}
 
-    #[account(mut)]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+/// `roles` of 0 with the member gone means it was removed
+#[event]
+pub struct GroupMemberUpdated {
+    pub group: Pubkey,
+    pub agent: Pubkey,
+    pub roles: u8,
+}
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+#[event]
+pub struct EarningsRoutingSet {
+    pub agent: Pubkey,
+    /// Default when routing is turned off
+    pub group: Pubkey,
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct GroupTreasurySpent {
+    pub group: Pubkey,
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
 }
 
-#[derive(Accounts)]
-pub struct UpdateIntentStatus<'info> {
-    #[account(mut)]
-    pub intent: Account<'info, AgentIntent>,
+#[event]
+pub struct BountyPosted {
+    pub bounty: Pubkey,
+    pub sponsor: Pubkey,
+    pub reviewer: Pubkey,
+    pub mint: Pubkey,
+    pub spec_hash: [u8; 32],
+    pub spec_uri: String,
+    pub amount: u64,
+    pub expires: Deadline,
+}
 
-    #[account(
-        seeds = [b"agent", to_agent.owner_wallet.as_ref()],
-        bump = to_agent.bump,
-        constraint = intent.to_agent == to_agent.key() @ ErrorCode::Unauthorized
-    )]
-    pub to_agent: Account<'info, AgentIdentity>,
+#[event]
+pub struct BountyAwarded {
+    pub bounty: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(constraint = owner.key() == to_agent.owner_wallet @ ErrorCode::Unauthorized)]
-    pub owner: Signer<'info>,
+#[event]
+pub struct BountyCancelled {
+    pub bounty: Pubkey,
+    /// Reviewer or sponsor
+    pub authority: Pubkey,
+    pub refunded: u64,
+}
 
-    #[account(mut)]
-    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+#[event]
+pub struct VoteApproved {
+    pub agent: Pubkey,
+    pub governance_program: Pubkey,
+    pub realm: Pubkey,
+    pub proposal: Pubkey,
+}
 
-    #[account(mut)]
-    pub billing_token_account: Option<Account<'info, TokenAccount>>,
+#[event]
+pub struct VoteApprovalRevoked {
+    pub agent: Pubkey,
+    pub realm: Pubkey,
+    pub proposal: Pubkey,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct VoteCast {
+    pub agent: Pubkey,
+    pub realm: Pubkey,
+    pub proposal: Pubkey,
+    pub vote_record: Pubkey,
+    pub vote: GovernanceVote,
+    pub cast_at: i64,
 }
 
-// === Events ===
+/// An intent funded against a provider's x402 quote
+#[event]
+pub struct X402PaymentSettled {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub from_agent: Pubkey,
+    pub to_agent: Pubkey,
+    pub resource_hash: [u8; 32],
+    pub payment_mint: Pubkey,
+    pub amount: u64,
+}
 
 #[event]
-pub struct AgentRegistered {
+pub struct NameBound {
     pub agent: Pubkey,
-    pub owner: Pubkey,
-    pub agent_wallet: Pubkey,
+    pub name_record: Pubkey,
+    pub name: String,
 }
 
 #[event]
-pub struct AgentUpdated {
+pub struct NameUnbound {
     pub agent: Pubkey,
-    pub updated_at: i64,
+    pub name_record: Pubkey,
+    pub name: String,
 }
 
 #[event]
-pub struct ModelProfileCreated {
-    pub profile: Pubkey,
-    pub owner: Pubkey,
-    pub label: String,
+pub struct IntentClosed {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    pub rent_payer: Pubkey,
+    /// Rent of the intent and its escrow token account
+    pub lamports: u64,
 }
 
 #[event]
-pub struct ModelProfileUpdated {
-    pub profile: Pubkey,
-    pub updated_at: i64,
+pub struct PayoutSwapSet {
+    pub intent: Pubkey,
+    pub sequence: u64,
+    /// Default when the payout goes back to the escrowed mint
+    pub payout_mint: Pubkey,
+    pub max_slippage_bps: u16,
 }
 
+/// Follows the `IntentCreated` of a new attempt at a failed intent
 #[event]
-pub struct IntentCreated {
+pub struct IntentRetried {
     pub intent: Pubkey,
+    pub sequence: u64,
+    pub previous_intent: Pubkey,
+    pub previous_sequence: u64,
+    pub attempt: u16,
     pub from_agent: Pubkey,
     pub to_agent: Pubkey,
-    pub payment_amount: u64,
+    /// Moved over from the failed intent's escrow
+    pub carried: u64,
+    /// Added from the payer's token account
+    pub topped_up: u64,
+    pub retried_at: i64,
 }
 
 #[event]
-pub struct IntentStatusUpdated {
-    pub intent: Pubkey,
-    pub status: u8,
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+/// Emitted for every account the program closes
+#[event]
+pub struct RentRefunded {
+    pub account: Pubkey,
+    pub recipient: Pubkey,
+    pub lamports: u64,
 }
 
 // === Errors ===
@@ -514,4 +12327,410 @@ pub enum ErrorCode {
     InsufficientPermissions,
     #[msg("Invalid intent status transition")]
     InvalidStatusTransition,
+    #[msg("Model profile account does not match the requested profile")]
+    InvalidModelProfile,
+    #[msg("Agent is not authorized to use this model profile")]
+    ProfileNotAuthorized,
+    #[msg("Invalid operating mode")]
+    InvalidOperatingMode,
+    #[msg("Protocol is draining; new intents are not accepted")]
+    ProtocolDraining,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Basis points value out of range")]
+    InvalidBasisPoints,
+    #[msg("Invalid protocol parameter value")]
+    InvalidParameter,
+    #[msg("Insured intents require the insurance pool for the payment mint")]
+    InsurancePoolRequired,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Snapshot the current profile before updating it")]
+    SnapshotRequired,
+    #[msg("Profile contents are already snapshotted")]
+    SnapshotUpToDate,
+    #[msg("Escrow and payout token accounts are required to move funds")]
+    EscrowAccountRequired,
+    #[msg("Payer did not consent to re-delegation")]
+    RedelegationNotAllowed,
+    #[msg("Intent has already been re-delegated")]
+    AlreadyRedelegated,
+    #[msg("Delegate must be a different agent")]
+    InvalidDelegate,
+    #[msg("Booking does not match this intent or is misconfigured")]
+    InvalidBooking,
+    #[msg("Intent is outside the booking window")]
+    OutsideBookingWindow,
+    #[msg("Booking has no reserved capacity left")]
+    BookingExhausted,
+    #[msg("Booking window has not ended")]
+    BookingStillActive,
+    #[msg("Penalty-free cancellation window has closed")]
+    CancellationWindowClosed,
+    #[msg("Swap route does not match the payout configuration")]
+    InvalidSwapRoute,
+    #[msg("Swap program is not allow-listed")]
+    SwapProgramNotAllowed,
+    #[msg("Swap output below the slippage bound")]
+    SlippageExceeded,
+    #[msg("Fee allowlist entry does not apply to this intent")]
+    InvalidFeeAllowlist,
+    #[msg("URI exceeds the configured maximum length")]
+    UriTooLong,
+    #[msg("Payout vault does not match or is misconfigured")]
+    InvalidPayoutVault,
+    #[msg("Payout sweep is not due yet")]
+    SweepNotDue,
+    #[msg("Escrow token account must be owned by the intent")]
+    InvalidEscrowAccount,
+    #[msg("Invalid encryption key")]
+    InvalidEncryptionKey,
+    #[msg("Previous encryption key is still in its overlap window")]
+    KeyRotationInProgress,
+    #[msg("Missing or invalid ed25519 signature")]
+    InvalidSignature,
+    #[msg("Signed intent has expired")]
+    SignedIntentExpired,
+    #[msg("Invalid release batch")]
+    InvalidReleaseBatch,
+    #[msg("Challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("Provider has no free capacity")]
+    ProviderAtCapacity,
+    #[msg("Trial does not match or is misconfigured")]
+    InvalidTrial,
+    #[msg("Trial has no free requests left")]
+    TrialExhausted,
+    #[msg("Trial period has not ended")]
+    TrialStillActive,
+    #[msg("Royalty splits are invalid or don't match the declaration")]
+    InvalidRoyaltySplits,
+    #[msg("Royalties can't be declared on auto-release intents")]
+    RoyaltiesUnsupported,
+    #[msg("Agent's daily spend limit for this mint is exhausted")]
+    SpendLimitExceeded,
+    #[msg("Destination is not on the agent's transfer allowlist")]
+    DestinationNotAllowed,
+    #[msg("Bounty does not match or is misconfigured")]
+    InvalidBounty,
+    #[msg("Bounty has not expired")]
+    BountyStillOpen,
+    #[msg("Governance accounts don't match the vote approval, the agent's owner or the realm")]
+    InvalidGovernanceAccounts,
+    #[msg("x402 quote has expired")]
+    X402QuoteExpired,
+    #[msg("Identity badge accounts are incomplete or don't match the agent")]
+    InvalidBadgeAccounts,
+    #[msg("Name is not a valid .sol label or doesn't match the name record")]
+    InvalidName,
+    #[msg("The owner doesn't hold this .sol domain")]
+    NameNotOwned,
+    #[msg("Intent has no insurance coverage left")]
+    NotInsured,
+    #[msg("Claim amount is zero or exceeds the intent's coverage")]
+    InvalidInsuranceClaim,
+    #[msg("Insurance pool can't cover the payout")]
+    InsurancePoolDepleted,
+    #[msg("Content commitment scheme is unknown")]
+    UnknownCommitmentScheme,
+    #[msg("Channel agents must be distinct and in ascending order")]
+    InvalidChannel,
+    #[msg("Payment is below the provider's minimum")]
+    PaymentBelowMinimum,
+    #[msg("Agent has opened too many intents in this window")]
+    IntentRateLimited,
+    #[msg("Accepting requires an acceptance bond")]
+    BondRequired,
+    #[msg("Intent hasn't passed its completion deadline")]
+    NotTimedOut,
+    #[msg("Acceptance bond hasn't been settled")]
+    BondOutstanding,
+    #[msg("Team members must be distinct, include the provider and split the whole payment")]
+    InvalidTeam,
+    #[msg("Not supported on team intents")]
+    TeamUnsupported,
+    #[msg("Not enough team members have submitted results")]
+    TeamQuorumNotReached,
+    #[msg("Bid or open intent is invalid")]
+    InvalidBid,
+    #[msg("Bidding on this intent has closed")]
+    BiddingClosed,
+    #[msg("Open intent has no selected bid yet")]
+    BidNotSelected,
+    #[msg("Account is already at the current layout version")]
+    AlreadyMigrated,
+    #[msg("Agent is already registered; pass RegisterOption::UPDATE_EXISTING to update it")]
+    AgentAlreadyRegistered,
+    #[msg("Delegation is invalid, expired or doesn't grant this permission")]
+    InvalidDelegation,
+    #[msg("Intent template is missing")]
+    InvalidTemplate,
+    #[msg("Agent is not a member of the group")]
+    NotGroupMember,
+    #[msg("Group has no free member slots")]
+    GroupFull,
+    #[msg("Group must keep at least one admin")]
+    GroupNeedsAdmin,
+    #[msg("Provider routes its earnings to a group treasury; pay into a token account the group owns")]
+    InvalidGroupPayout,
+    #[msg("Agent is frozen")]
+    AgentFrozen,
+    #[msg("Intent requires a result attested by the assignee's agent wallet")]
+    ResultNotAttested,
+    #[msg("Missing or mismatched agent stats account")]
+    InvalidAgentStats,
+    #[msg("Payment mint is not in the protocol's accepted mint registry")]
+    MintNotAccepted,
+    #[msg("Token account doesn't match the payment mint")]
+    PaymentMintMismatch,
+    #[msg("Bridge message is unverified, from an unregistered emitter or doesn't match the accounts")]
+    InvalidBridgeMessage,
+    #[msg("Bridge message is older than the last one applied")]
+    BridgeMessageReplayed,
+    #[msg("Budget vault, funding account or signer doesn't match the budget")]
+    InvalidBudget,
+    #[msg("Intent would exceed the agent's daily or total budget cap")]
+    BudgetExceeded,
+    #[msg("Schema doesn't match the intent or template, or its media type is invalid")]
+    InvalidSchema,
+    #[msg("Schema version is deprecated")]
+    DeprecatedSchema,
+    #[msg("Escrow still holds tokens")]
+    EscrowNotEmpty,
+    #[msg("Pinned profile version is cheaper than the profile's current price")]
+    StaleProfileVersion,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_code(err: Error) -> u32 {
+        match err {
+            Error::AnchorError(err) => err.error_code_number,
+            Error::ProgramError(err) => panic!("expected a program error code, got {err:?}"),
+        }
+    }
+
+    fn clock(slot: u64, unix_timestamp: i64) -> Clock {
+        Clock {
+            slot,
+            unix_timestamp,
+            ..Clock::default()
+        }
+    }
+
+    #[test]
+    fn deadlines_open_and_close_around_the_tolerance() {
+        let config = ProtocolConfig {
+            timestamp_tolerance: 30,
+            slot_tolerance: 5,
+            ..ProtocolConfig::default()
+        };
+
+        let at = Deadline::UnixTimestamp(1_000);
+        assert!(!at.reached(&clock(0, 969), &config));
+        assert!(at.reached(&clock(0, 970), &config));
+        assert!(!at.passed(&clock(0, 1_030), &config));
+        assert!(at.passed(&clock(0, 1_031), &config));
+
+        let at = Deadline::Slot(100);
+        assert!(!at.reached(&clock(94, i64::MAX), &config));
+        assert!(at.reached(&clock(95, 0), &config));
+        assert!(!at.passed(&clock(105, i64::MAX), &config));
+        assert!(at.passed(&clock(106, 0), &config));
+    }
+
+    #[test]
+    fn deadlines_saturate_at_the_clock_bounds() {
+        let config = ProtocolConfig {
+            timestamp_tolerance: 30,
+            slot_tolerance: 5,
+            ..ProtocolConfig::default()
+        };
+        assert!(Deadline::Slot(0).reached(&clock(0, 0), &config));
+        assert!(!Deadline::Slot(u64::MAX).passed(&clock(u64::MAX, 0), &config));
+        assert!(!Deadline::UnixTimestamp(i64::MAX).passed(&clock(0, i64::MAX), &config));
+    }
+
+    #[test]
+    fn key_sets_reject_duplicates_and_overflow() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut items = [Pubkey::default(); 2];
+        let mut count = 0;
+
+        set_insert(&mut items, &mut count, a).unwrap();
+        assert_eq!(
+            error_code(set_insert(&mut items, &mut count, a).unwrap_err()),
+            u32::from(ErrorCode::InvalidParameter)
+        );
+        set_insert(&mut items, &mut count, b).unwrap();
+        assert!(set_insert(&mut items, &mut count, c).is_err());
+        assert_eq!((items, count), ([a, b], 2));
+
+        set_remove(&mut items, &mut count, a).unwrap();
+        assert_eq!((items, count), ([b, Pubkey::default()], 1));
+        assert!(set_remove(&mut items, &mut count, a).is_err());
+        set_remove(&mut items, &mut count, b).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn payment_splits_add_up_to_the_payment() {
+        let mut intent = AgentIntent {
+            payment_amount: 1_001,
+            ..AgentIntent::default()
+        };
+        assert_eq!(split_payment(&intent, 0).unwrap(), (0, 0, 1_001));
+        assert_eq!(split_payment(&intent, 250).unwrap(), (25, 0, 976));
+
+        // The delegate's share comes out of what's left after the fee
+        intent.delegate_agent = Pubkey::new_unique();
+        intent.delegate_share_bps = 4_000;
+        let (fee, delegate, provider) = split_payment(&intent, 250).unwrap();
+        assert_eq!((fee, delegate, provider), (25, 390, 586));
+        assert_eq!(fee + delegate + provider, intent.payment_amount);
+
+        intent.delegate_share_bps = BPS_DENOMINATOR;
+        assert_eq!(split_payment(&intent, BPS_DENOMINATOR).unwrap(), (1_001, 0, 0));
+    }
+
+    #[test]
+    fn sla_penalty_accrues_per_hour_late_up_to_the_payment() {
+        let intent = AgentIntent {
+            payment_amount: 10_000,
+            accepted_at: 1_000,
+            sla_target: 3_600,
+            sla_penalty_bps: 1_000,
+            ..AgentIntent::default()
+        };
+        let deadline = 1_000 + 3_600;
+        assert_eq!(sla_penalty(&intent, deadline).unwrap(), 0);
+        // Pro rata within the hour
+        assert_eq!(sla_penalty(&intent, deadline + 1_800).unwrap(), 500);
+        assert_eq!(sla_penalty(&intent, deadline + 2 * 3_600).unwrap(), 2_000);
+        assert_eq!(sla_penalty(&intent, deadline + 100 * 3_600).unwrap(), 10_000);
+
+        // Nothing is owed without a penalty or before acceptance
+        let unpenalized = AgentIntent {
+            sla_penalty_bps: 0,
+            ..intent.clone()
+        };
+        assert_eq!(sla_penalty(&unpenalized, i64::MAX).unwrap(), 0);
+        let unaccepted = AgentIntent {
+            accepted_at: 0,
+            ..intent.clone()
+        };
+        assert_eq!(sla_penalty(&unaccepted, i64::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn budgets_enforce_the_daily_and_lifetime_caps() {
+        let mut budget = AgentBudget {
+            daily_cap: 100,
+            total_cap: 250,
+            ..AgentBudget::default()
+        };
+        let day = 1_000_000;
+
+        budget.record(60, day).unwrap();
+        budget.record(40, day + 10).unwrap();
+        assert_eq!(
+            error_code(budget.record(1, day + 20).unwrap_err()),
+            u32::from(ErrorCode::BudgetExceeded)
+        );
+        // A rejected spend doesn't count
+        assert_eq!((budget.spent_today, budget.total_spent), (100, 100));
+
+        // The day rolls over after a full window, the lifetime total doesn't
+        let day = day + SPEND_LIMIT_WINDOW;
+        budget.record(100, day).unwrap();
+        assert_eq!(budget.day_started_at, day);
+        let day = day + SPEND_LIMIT_WINDOW;
+        budget.record(50, day).unwrap();
+        assert!(budget.record(1, day).is_err());
+        assert_eq!((budget.spent_today, budget.total_spent), (50, 250));
+
+        // Zero caps don't limit, short of overflowing the tally
+        let mut uncapped = AgentBudget::default();
+        uncapped.record(u64::MAX, day).unwrap();
+        assert_eq!(
+            error_code(uncapped.record(1, day).unwrap_err()),
+            u32::from(ErrorCode::MathOverflow)
+        );
+    }
+
+    #[test]
+    fn agent_stats_fold_outcomes_and_saturate() {
+        let accepted = IntentStatus::Accepted as u8;
+        let completed = IntentStatus::Completed as u8;
+        let mut stats = AgentStats::default();
+        let mut intent = AgentIntent {
+            status: completed,
+            payment_amount: 500,
+            accepted_at: 100,
+            completed_at: 160,
+            ..AgentIntent::default()
+        };
+
+        stats.record_outcome(&intent, accepted, 160).unwrap();
+        assert_eq!((stats.completed, stats.volume, stats.average_completion_time()), (1, 500, 60));
+        // A dispute ruling against the provider undoes the completion
+        intent.status = IntentStatus::Failed as u8;
+        stats.record_outcome(&intent, completed, 200).unwrap();
+        assert_eq!((stats.completed, stats.failed, stats.volume), (0, 1, 0));
+        assert_eq!(stats.updated_at, 200);
+
+        stats.failed = u64::MAX;
+        stats.record_outcome(&intent, accepted, 300).unwrap();
+        assert_eq!(stats.failed, u64::MAX);
+        stats.completed = u64::MAX;
+        intent.status = completed;
+        stats.record_outcome(&intent, accepted, 300).unwrap();
+        assert_eq!(stats.completed, u64::MAX);
+    }
+
+    #[test]
+    fn intent_rate_limit_counts_per_bucket() {
+        let agent = Pubkey::new_unique();
+        let mut config = ProtocolConfig {
+            intent_rate_window: 10,
+            max_intents_per_window: 2,
+            ..ProtocolConfig::default()
+        };
+        let mut limit = IntentRateLimit::default();
+
+        limit.record(agent, 7, &clock(20, 0), &config).unwrap();
+        limit.record(agent, 7, &clock(29, 0), &config).unwrap();
+        assert_eq!(
+            error_code(limit.record(agent, 7, &clock(29, 0), &config).unwrap_err()),
+            u32::from(ErrorCode::IntentRateLimited)
+        );
+        limit.record(agent, 7, &clock(30, 0), &config).unwrap();
+        assert_eq!((limit.agent, limit.bump, limit.bucket, limit.count), (agent, 7, 3, 1));
+
+        // No limit, so the count saturates instead of overflowing
+        config.max_intents_per_window = 0;
+        limit.count = u16::MAX;
+        limit.record(agent, 7, &clock(30, 0), &config).unwrap();
+        assert_eq!(limit.count, u16::MAX);
+    }
+
+    #[test]
+    fn basis_points_round_down() {
+        assert_eq!(bps_of(999, 10).unwrap(), 0);
+        assert_eq!(bps_of(1_000, 10).unwrap(), 1);
+        assert_eq!(bps_of(u64::MAX, BPS_DENOMINATOR).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn migrations_start_from_the_stored_version() {
+        assert_eq!(migrated_from(3, 5).unwrap(), 3);
+        // Pre-versioning accounts hold string bytes where the version sits
+        assert_eq!(migrated_from(b'h', 5).unwrap(), 0);
+        assert_eq!(
+            error_code(migrated_from(5, 5).unwrap_err()),
+            u32::from(ErrorCode::AlreadyMigrated)
+        );
+    }
 }